@@ -23,6 +23,18 @@ fn main() {
 
     println!("cargo:rustc-env=GIT_REV={}", git_rev);
 
+    // versi migration terakhir (format versi diesel, eg: `20200501081500`),
+    // digunakan oleh readiness check untuk memastikan schema DB sudah up to date.
+    let latest_migration = fs::read_dir("migrations")
+        .expect("Cannot read migrations dir")
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| name.split('_').next().map(|v| v.replace('-', "")))
+        .filter(|version| version.chars().all(|c| c.is_ascii_digit()) && !version.is_empty())
+        .max()
+        .unwrap_or_default();
+    println!("cargo:rustc-env=PANDEMIA_LATEST_MIGRATION={}", latest_migration);
+
     if env::var("BUILD_FOR") == Ok("nightly".to_string()) {
         println!(
             "cargo:rustc-env=BUILD_INFO=ngihtly build {} @ {}",
@@ -208,7 +208,9 @@ class PandemiaBloc extends Bloc<PandemiaEvent, PandemiaState> {
       if (data["result"] != null) {
         if (data["result"]["new_update"] != null &&
             data["result"]["new_update"] != "") {
-          yield PandemiaNewUpdateAvailable(data["new_update"], data["notes"]);
+          yield PandemiaNewUpdateAvailable(
+              data["result"]["new_update"], data["result"]["notes"],
+              forceUpdate: data["result"]["force_update"] == true);
           yield PandemiaReady();
         }
       }
@@ -57,8 +57,10 @@ class LoadingSetting extends PandemiaState {
 class PandemiaNewUpdateAvailable extends PandemiaState {
   final String version;
   final String notes;
+  final bool forceUpdate;
 
-  PandemiaNewUpdateAvailable(this.version, this.notes);
+  PandemiaNewUpdateAvailable(this.version, this.notes,
+      {this.forceUpdate = false});
 
   @override
   String toString() => "PandemiaNewUpdateAvailable";
@@ -70,10 +70,14 @@ class HomeScreen extends StatelessWidget {
       pandemiaBloc.state.listen((PandemiaState state) {
         if (state is PandemiaNewUpdateAvailable) {
           _scaffoldKey.currentState.showSnackBar(SnackBar(
-            content: Text(
-                "Pandemia versi ${state.version} telah tersedia, segera lakukan update!"),
-            backgroundColor: Colors.blue,
+            content: Text(state.forceUpdate
+                ? "Versi aplikasi Anda sudah tidak didukung, mohon update ke Pandemia versi ${state.version}"
+                : "Pandemia versi ${state.version} telah tersedia, segera lakukan update!"),
+            backgroundColor: state.forceUpdate ? Colors.red : Colors.blue,
             behavior: SnackBarBehavior.fixed,
+            duration: state.forceUpdate
+                ? Duration(days: 1)
+                : Duration(milliseconds: 4000),
           ));
         }
       });
@@ -4,9 +4,24 @@ export default class ErrorCode {
   // Sukses atau tidak terjadi error.
   static NoError = 0;
 
+  // Object unik yang akan dibuat telah ada (kode lama, dipertahankan untuk kompatibilitas client).
+  static AlreadyExists = 304;
+
   // Unauthorized
   static Unauthorized = 3000;
 
+  // Akun membutuhkan kode verifikasi dua langkah (TOTP atau recovery code).
+  static TwoFactorRequired = 3001;
+
+  // Akun wajib mengaktifkan verifikasi dua langkah sebelum bisa melakukan operasi ini.
+  static TwoFactorSetupRequired = 3002;
+
+  // Akun telah diblokir.
+  static AccountBlocked = 3003;
+
+  // Tidak memiliki hak akses untuk resource yang dimaksud.
+  static AccessDenied = 3004;
+
   // Kegagalan yang berkaitan dengan proses serialize/deserialize data.
   static SerializeDeserializeError = 4001;
 
@@ -22,6 +37,87 @@ export default class ErrorCode {
   // Pengirim dan penerima alamatnya sama.
   static FromAndToTargetIsSame = 4005;
 
+  // Signature request tidak valid, kadaluarsa, atau nonce telah digunakan.
+  static InvalidSignature = 4006;
+
+  // Terlalu banyak percobaan, identitas sedang dikunci sementara.
+  static TooManyAttempts = 4007;
+
+  // Konfirmasi password tidak cocok.
+  static PasswordMismatch = 4010;
+
+  // Kode verifikasi tidak benar.
+  static InvalidVerificationCode = 4011;
+
+  // Public key tidak valid.
+  static InvalidPublicKey = 4012;
+
+  // Web token satgas tidak valid.
+  static InvalidWebToken = 4013;
+
+  // Kode area kota/kabupaten tidak dikenal.
+  static InvalidAreaCode = 4101;
+
+  // Kota/kabupaten tidak ditemukan.
+  static CityNotFound = 4102;
+
+  // Kecamatan tidak ditemukan.
+  static DistrictNotFound = 4103;
+
+  // Desa tidak ditemukan.
+  static VillageNotFound = 4104;
+
+  // Desa tidak terdaftar di kota milik pengguna.
+  static VillageNotInCity = 4105;
+
+  // Nama desa belum diset.
+  static VillageNotSet = 4106;
+
+  // Nama kecamatan belum diset.
+  static DistrictNotSet = 4107;
+
+  // Kota/kabupaten belum diset.
+  static CityNotSet = 4108;
+
+  // Pengguna tidak terdaftar pada area manapun.
+  static NoArea = 4109;
+
+  // Pengguna tidak terdaftar pada area yang dimaksud.
+  static NotInArea = 4110;
+
+  // Tidak memiliki akses untuk kota/kabupaten yang dimaksud.
+  static NoAccessToCity = 4111;
+
+  // Tidak memiliki akses untuk kecamatan yang dimaksud.
+  static NoAccessToDistrict = 4112;
+
+  // Pengguna tidak terdaftar sebagai satgas.
+  static NotSatgas = 4113;
+
+  // Pengguna sudah terdaftar sebagai satgas.
+  static AlreadySatgas = 4114;
+
+  // Jumlah satgas untuk desa telah mencapai batas.
+  static SatgasLimitReached = 4115;
+
+  // Pengguna tidak dapat menambahkan data.
+  static CannotAddData = 4116;
+
+  // Status tidak valid.
+  static InvalidStatus = 4117;
+
+  // Pendaftaran satgas menggunakan kode area telah dinonaktifkan.
+  static AreaCodeDisabled = 4118;
+
+  // Nomor telepon sudah terverifikasi.
+  static PhoneAlreadyVerified = 4201;
+
+  // Nomor telepon telah digunakan oleh akun lain.
+  static PhoneAlreadyUsed = 4202;
+
+  // Akun dengan nomor telepon ini tidak bisa digunakan.
+  static PhoneAccountUnusable = 4203;
+
   // Kegagalan yang tidak diketahui penyebabnya.
   static UnknownError = 5001;
 
@@ -32,4 +128,4 @@ export default class ErrorCode {
   // ketidakditemukannya record/data di dalam database.
   static DatabaseRecordNotFoundError = 6002;
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,195 @@
+//! Client Rust untuk Pandemia REST API.
+//!
+//! Crate ini hanya berisi transport-nya (url, access token, signed request
+//! dan decoding response), tipe request/response bersama ada di `pandemia-types`.
+//! Method typed untuk setiap endpoint digenerasikan oleh `#[api_group]` di sisi server
+//! sebagai extension dari [ApiClient], lihat `pandemia::api::client`. Contoh:
+//!
+//! ```rust,ignore
+//! let client = ApiClient::new("http://localhost:8080", "http://localhost:9090");
+//! let info: ApiResult<BuildInfo> = client.get(ApiAccess::Public, "/system/v1/info", false, &())?;
+//! ```
+
+#![deny(missing_docs)]
+
+#[macro_use]
+extern crate failure;
+#[macro_use]
+extern crate log;
+
+use ed25519_dalek::{ExpandedSecretKey, PublicKey};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode, Url};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub use pandemia_types::{signature, ApiAccess, ApiResult, FieldError};
+
+/// Error yang dikembalikan oleh [ApiClient].
+#[derive(Debug, Fail)]
+pub enum Error {
+    /// Request ditolak karena tidak terotorisasi (401/403).
+    #[fail(display = "Unauthorized")]
+    Unauthorized,
+
+    /// Endpoint atau resource tidak ditemukan (404).
+    #[fail(display = "Not found: {}", _0)]
+    NotFound(String),
+
+    /// Request tidak valid, berisi http status code dan body response.
+    #[fail(display = "Bad request ({}): {}", _0, _1)]
+    BadRequest(u16, String),
+
+    /// Server sedang sibuk (503).
+    #[fail(display = "Service busy")]
+    ServiceBusy,
+
+    /// Sign key tidak valid.
+    #[fail(display = "Invalid sign key: {}", _0)]
+    InvalidKey(String),
+
+    /// Error lain, eg: koneksi gagal, response tidak bisa di-decode.
+    #[fail(display = "{}", _0)]
+    Internal(String),
+}
+
+/// Result untuk operasi [ApiClient].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Client untuk mengakses Pandemia REST API.
+#[derive(Clone)]
+pub struct ApiClient {
+    client: Client,
+    public_url: String,
+    private_url: String,
+    access_token: Option<String>,
+    sign_key: Option<Vec<u8>>,
+}
+
+impl ApiClient {
+    /// Buat client baru, `public_url` dan `private_url` adalah base url
+    /// tempat masing-masing API di-serve (tanpa `/api`), eg: `http://localhost:8080`.
+    pub fn new(public_url: &str, private_url: &str) -> Self {
+        ApiClient {
+            client: Client::new(),
+            public_url: public_url.trim_end_matches('/').to_string(),
+            private_url: private_url.trim_end_matches('/').to_string(),
+            access_token: None,
+            sign_key: None,
+        }
+    }
+
+    /// Sertakan access token pada setiap request (header `X-Access-Token`).
+    pub fn with_access_token(mut self, token: &str) -> Self {
+        self.access_token = Some(token.to_string());
+        self
+    }
+
+    /// Tandatangani request ke endpoint `signed` menggunakan secret key (expanded ed25519, hex)
+    /// yang public key-nya telah didaftarkan via `/user/v1/me/key/register`.
+    pub fn with_sign_key(mut self, secret_key: &str) -> Result<Self> {
+        let bytes = hex::decode(secret_key).map_err(|e| Error::InvalidKey(e.to_string()))?;
+        ExpandedSecretKey::from_bytes(&bytes).map_err(|e| Error::InvalidKey(e.to_string()))?;
+        self.sign_key = Some(bytes);
+        Ok(self)
+    }
+
+    fn url(&self, access: ApiAccess, path: &str) -> String {
+        let base = match access {
+            ApiAccess::Public => &self.public_url,
+            ApiAccess::Private => &self.private_url,
+        };
+        format!("{}/api{}", base, path)
+    }
+
+    fn request(&self, method: Method, url: &str, signed: bool, body: &[u8]) -> Result<RequestBuilder> {
+        let mut builder = self.client.request(method.clone(), url);
+
+        if let Some(ref token) = self.access_token {
+            builder = builder.header("X-Access-Token", token.as_str());
+        }
+
+        if let (true, Some(secret_key)) = (signed, self.sign_key.as_ref()) {
+            let path = Url::parse(url)
+                .map(|u| u.path().to_string())
+                .map_err(internal_error)?;
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(internal_error)?
+                .as_secs() as i64;
+            let nonce: String = thread_rng().sample_iter(&Alphanumeric).take(32).collect();
+            let body_hash = hex::encode(Sha256::digest(body));
+            let message = signature::canonical_message(method.as_str(), &path, timestamp, &nonce, &body_hash);
+
+            let secret_key = ExpandedSecretKey::from_bytes(secret_key).map_err(internal_error)?;
+            let pub_key = PublicKey::from_expanded_secret(&secret_key);
+            let sig = secret_key.sign::<Sha512>(message.as_bytes(), &pub_key);
+
+            builder = builder
+                .header(signature::KEY_HEADER, hex::encode(pub_key.to_bytes()))
+                .header(signature::TIMESTAMP_HEADER, timestamp.to_string())
+                .header(signature::NONCE_HEADER, nonce)
+                .header(signature::SIGNATURE_HEADER, hex::encode(&sig.to_bytes()[..]));
+        }
+
+        Ok(builder)
+    }
+
+    /// Kirim request GET, `query` dikirim sebagai query string.
+    pub fn get<Q, R>(&self, access: ApiAccess, path: &str, signed: bool, query: &Q) -> Result<R>
+    where
+        Q: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let url = self.url(access, path);
+
+        trace!("GET {}", url);
+
+        let mut builder = self.request(Method::GET, &url, signed, b"")?;
+        if serde_json::to_value(query).map_err(internal_error)? != serde_json::Value::Null {
+            builder = builder.query(query);
+        }
+
+        Self::decode(builder.send().map_err(internal_error)?)
+    }
+
+    /// Kirim request POST, `query` dikirim sebagai body JSON.
+    pub fn post<Q, R>(&self, access: ApiAccess, path: &str, signed: bool, query: &Q) -> Result<R>
+    where
+        Q: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let url = self.url(access, path);
+        let body = serde_json::to_vec(query).map_err(internal_error)?;
+
+        trace!("POST {}", url);
+
+        let builder = self
+            .request(Method::POST, &url, signed, &body)?
+            .header("Content-Type", "application/json")
+            .body(body);
+
+        Self::decode(builder.send().map_err(internal_error)?)
+    }
+
+    fn decode<R: DeserializeOwned>(mut response: Response) -> Result<R> {
+        let body = response.text().map_err(internal_error)?;
+
+        trace!("Response status: {}", response.status());
+
+        match response.status() {
+            StatusCode::OK => serde_json::from_str(&body).map_err(internal_error),
+            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => Err(Error::Unauthorized),
+            StatusCode::NOT_FOUND => Err(Error::NotFound(body)),
+            StatusCode::SERVICE_UNAVAILABLE => Err(Error::ServiceBusy),
+            s if s.is_client_error() => Err(Error::BadRequest(s.as_u16(), body)),
+            s => Err(Error::Internal(format!("{}: {}", s, body))),
+        }
+    }
+}
+
+fn internal_error<E: std::fmt::Display>(e: E) -> Error {
+    Error::Internal(e.to_string())
+}
@@ -0,0 +1,110 @@
+//! Tipe-tipe request/response Pandemia REST API yang digunakan bersama
+//! oleh server (`pandemia`) dan client (`pandemia-client`).
+
+#![deny(missing_docs)]
+
+#[macro_use]
+extern crate serde_derive;
+
+use serde::Serialize;
+
+use std::fmt;
+
+pub mod signature;
+
+/// Jenis penanda akses API, kita bagikan menjadi 2 macam:
+///
+/// * Public
+/// * Private
+///
+/// Public adalah apabila kita ingin akses API-nya boleh digunakan oleh publik.
+/// Sementara Private adalah apabila kita ingin akses API-nya hanya untuk internal,
+/// nantinya masing-masing akses ini di-serve pada port yang berbeda
+/// sehingga perlu dilakukan settingan firewall oleh system administrator
+/// agar port untuk private API hanya boleh diakses dari jaringan internal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiAccess {
+    /// Penanda untuk akses publik
+    Public,
+
+    /// Penanda untuk akses privat
+    Private,
+}
+
+impl fmt::Display for ApiAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ApiAccess::Public => f.write_str("Public"),
+            ApiAccess::Private => f.write_str("Private"),
+        }
+    }
+}
+
+/// Struktur data ketika pemanggilan api sukses.
+#[derive(Serialize, Deserialize)]
+pub struct ApiResult<T> {
+    /// Error code untuk memberikan informasi hasil pengembalian,
+    /// apabila tidak ada error terjadi maka code harus berisi 0.
+    pub code: i32,
+
+    /// Status bisa berisi: "success" atau "error".
+    pub status: String,
+
+    /// Deskripsi error apabila terjadi error.
+    pub description: String,
+
+    /// Result data.
+    pub result: Option<T>,
+
+    /// Detail kesalahan per parameter apabila validasi parameter gagal.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl<T: Serialize> ApiResult<T> {
+    #[doc(hidden)]
+    pub fn new(code: i32, status: String, description: String, result: Option<T>) -> Self {
+        ApiResult {
+            code,
+            status,
+            description,
+            result,
+            fields: vec![],
+        }
+    }
+
+    /// Buat hasil sukses
+    pub fn success(result: T) -> Self {
+        Self::new(0, "success".to_owned(), "".to_owned(), Some(result))
+    }
+}
+
+impl ApiResult<()> {
+    /// Buat hasil error
+    pub fn error(code: i32, description: String) -> ApiResult<()> {
+        ApiResult {
+            code,
+            status: "error".to_owned(),
+            description,
+            result: None::<()>,
+            fields: vec![],
+        }
+    }
+
+    /// Sertakan detail kesalahan per parameter.
+    pub fn with_fields(mut self, fields: Vec<FieldError>) -> Self {
+        self.fields = fields;
+        self
+    }
+}
+
+/// Detail kesalahan per parameter yang dikirim ke client di [ApiResult].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    /// Nama field.
+    pub field: String,
+    /// Kode validasi, eg: `length`, `range`, `email`, `required`.
+    pub code: String,
+    /// Deskripsi kesalahan sesuai bahasa client.
+    pub message: String,
+}
@@ -0,0 +1,28 @@
+//! Protokol signed request, lihat `pandemia::api::signature` untuk verifikasinya.
+//!
+//! Client menandatangani canonical message berikut menggunakan ed25519:
+//!
+//! ```text
+//! <METHOD>\n<PATH>\n<TIMESTAMP>\n<NONCE>\n<SHA256-HEX(BODY)>
+//! ```
+
+/// Header berisi public key client.
+pub const KEY_HEADER: &str = "X-Sign-Key";
+/// Header berisi waktu request ditandatangani.
+pub const TIMESTAMP_HEADER: &str = "X-Sign-Timestamp";
+/// Header berisi nonce.
+pub const NONCE_HEADER: &str = "X-Sign-Nonce";
+/// Header berisi signature.
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Bentuk canonical message yang ditandatangani oleh client.
+pub fn canonical_message(method: &str, path: &str, timestamp: i64, nonce: &str, body_hash: &str) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        method.to_uppercase(),
+        path,
+        timestamp,
+        nonce,
+        body_hash
+    )
+}
@@ -36,8 +36,10 @@ mod dao;
 mod diagnostic_shim;
 mod meta;
 mod resolved_at_shim;
+mod schema;
 
 use diagnostic_shim::*;
+use heck::SnakeCase;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct ApiGroup {
@@ -62,6 +64,14 @@ struct ApiEndpoint {
     pub request_json: String,
     pub response_ok: String,
     pub accessors: Vec<String>,
+    #[serde(default)]
+    pub auth: String,
+    #[serde(default)]
+    pub signed: bool,
+    #[serde(default)]
+    pub request_type: String,
+    #[serde(default)]
+    pub response_type: String,
 }
 
 #[derive(Clone)]
@@ -121,7 +131,9 @@ fn get_lit_str(lit: &proc_macro2::Literal) -> String {
 fn gather_endpoint_info(stream: TokenStream, base: &str, group: &str) -> ApiEndpoint {
     let mut path = String::new();
     let mut accessor = String::new();
+    let mut auth = "required".to_string();
     let mut mutable = false;
+    let mut signed = false;
 
     let mut to_update = &mut path;
     let mut nicd = 0;
@@ -133,6 +145,9 @@ fn gather_endpoint_info(stream: TokenStream, base: &str, group: &str) -> ApiEndp
             TokenTree::Ident(ident) if ident.to_string() == "mutable" => {
                 mutable = true;
             }
+            TokenTree::Ident(ident) if ident.to_string() == "signed" => {
+                signed = true;
+            }
             TokenTree::Ident(ident) if ident.to_string() == "path" => {
                 to_update = &mut path;
                 nicd = 2;
@@ -141,6 +156,10 @@ fn gather_endpoint_info(stream: TokenStream, base: &str, group: &str) -> ApiEndp
                 to_update = &mut accessor;
                 nicd = 2;
             }
+            TokenTree::Ident(ident) if ident.to_string() == "auth" => {
+                to_update = &mut auth;
+                nicd = 2;
+            }
             TokenTree::Literal(lit) if nicd == 0 => {
                 *to_update = get_lit_str(lit);
             }
@@ -172,10 +191,176 @@ fn gather_endpoint_info(stream: TokenStream, base: &str, group: &str) -> ApiEndp
         },
         method_name: Default::default(),
         accessors: accessors,
+        auth,
+        signed,
         ..Default::default()
     }
 }
 
+/// Ambil tipe parameter `query` dari token parameter fungsi endpoint.
+fn parse_query_type(stream: TokenStream) -> Vec<TokenTree> {
+    let mut query_type: Vec<TokenTree> = vec![];
+    let mut in_query = false;
+    let mut begin_capture_query_type = false;
+    for inner in stream {
+        match inner {
+            TokenTree::Ident(ref ident) => {
+                if ident.to_string() == "query" {
+                    in_query = true;
+                } else if in_query {
+                    in_query = false;
+                    begin_capture_query_type = true;
+                    query_type.push(inner.clone());
+                } else if begin_capture_query_type {
+                    query_type.push(inner.clone());
+                }
+            }
+            TokenTree::Group(ref g) => {
+                if in_query && g.delimiter() == Delimiter::Parenthesis && !begin_capture_query_type {
+                    in_query = false;
+                    query_type.push(inner.clone());
+                } else if begin_capture_query_type {
+                    query_type.push(inner.clone());
+                }
+            }
+            TokenTree::Punct(ref punct) => {
+                if begin_capture_query_type {
+                    if punct.to_string() == "," {
+                        begin_capture_query_type = false;
+                    } else {
+                        query_type.push(inner.clone());
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    query_type
+}
+
+/// Signature fungsi endpoint: nama fungsi, tipe query dan tipe return-nya.
+struct EndpointSignature {
+    method_name: String,
+    query_type: TokenStream,
+    response_type: TokenStream,
+}
+
+fn is_punct(tt: &TokenTree, ch: char) -> bool {
+    match tt {
+        TokenTree::Punct(punct) => punct.as_char() == ch,
+        _ => false,
+    }
+}
+
+/// Kumpulkan signature semua fungsi di dalam body impl API group.
+fn gather_signatures(stream: TokenStream) -> Vec<EndpointSignature> {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut rv = vec![];
+    let mut i = 0;
+
+    while i + 2 < tokens.len() {
+        if tokens[i].to_string() != "fn" {
+            i += 1;
+            continue;
+        }
+
+        let query_type = match &tokens[i + 2] {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                parse_query_type(group.stream())
+            }
+            _ => vec![],
+        };
+
+        let mut j = i + 3;
+        let mut response_type = vec![];
+        if j + 1 < tokens.len() && is_punct(&tokens[j], '-') && is_punct(&tokens[j + 1], '>') {
+            j += 2;
+            while j < tokens.len() {
+                match &tokens[j] {
+                    TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => break,
+                    tt => response_type.push(tt.clone()),
+                }
+                j += 1;
+            }
+        }
+
+        rv.push(EndpointSignature {
+            method_name: tokens[i + 1].to_string(),
+            query_type: TokenStream::from_iter(query_type.into_iter()),
+            response_type: TokenStream::from_iter(response_type.into_iter()),
+        });
+
+        i = j;
+    }
+
+    rv
+}
+
+/// Kode untuk membuat schema dari tipe return endpoint,
+/// `ApiResult<T>` dibuatkan schema envelope dengan schema `T` sebagai `result`-nya.
+fn response_schema(response_type: &TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = response_type.clone().into_iter().collect();
+    let is_api_result = tokens.len() > 3
+        && tokens[0].to_string() == "ApiResult"
+        && is_punct(&tokens[1], '<')
+        && is_punct(&tokens[tokens.len() - 1], '>');
+
+    if is_api_result {
+        let inner = TokenStream::from_iter(tokens[2..tokens.len() - 1].iter().cloned());
+        quote! {
+            let result = (&&crate::api::openapi::SchemaProbe::<#inner>::new()).probe_schema(reg);
+            crate::api::openapi::api_result(reg, result)
+        }
+    } else if tokens.is_empty() {
+        quote! { serde_json::Value::Null }
+    } else {
+        quote! {
+            (&&crate::api::openapi::SchemaProbe::<#response_type>::new()).probe_schema(reg)
+        }
+    }
+}
+
+fn type_str(stream: &TokenStream) -> String {
+    stream.to_string().replace(" ", "")
+}
+
+/// Kode method client untuk endpoint, tipe query dan result-nya sama dengan signature endpoint.
+/// Bound-nya dibuat higher-ranked agar endpoint yang tipenya belum bisa di-(de)serialize
+/// tetap bisa dicompile, hanya saja method-nya tidak bisa dipanggil.
+fn client_method(aei: &ApiEndpoint, sig: &EndpointSignature, access: &Ident) -> TokenStream {
+    let method_name = Ident::new(&aei.method_name, Span::call_site());
+    let query_type = if sig.query_type.is_empty() {
+        quote! { () }
+    } else {
+        sig.query_type.clone()
+    };
+    let response_type = &sig.response_type;
+    let path = Literal::string(&aei.path);
+    let signed = aei.signed;
+    let call = if aei.method == "POST" {
+        quote! { post }
+    } else {
+        quote! { get }
+    };
+
+    let doc = if aei.desc.is_empty() {
+        format!("`{} {}`", aei.method, aei.path)
+    } else {
+        format!("{}\n\n`{} {}`", aei.desc, aei.method, aei.path)
+    };
+
+    quote! {
+        #[doc = #doc]
+        pub fn #method_name(&self, query: &#query_type) -> crate::api::client::Result<#response_type>
+        where
+            for<'q> &'q #query_type: serde::Serialize,
+            for<'de> #response_type: serde::Deserialize<'de>,
+        {
+            self.client.#call(crate::api::ApiAccess::#access, #path, #signed, &query)
+        }
+    }
+}
+
 impl ApiEndpoint {
     pub fn update(&mut self, right: &ApiEndpoint) {
         self.rel_path = right.path.clone();
@@ -184,6 +369,10 @@ impl ApiEndpoint {
         self.method = right.method.clone();
         self.method_name = right.method_name.clone();
         self.accessors = right.accessors.clone();
+        self.auth = right.auth.clone();
+        self.signed = right.signed;
+        self.request_type = right.request_type.clone();
+        self.response_type = right.response_type.clone();
     }
 }
 
@@ -342,8 +531,8 @@ pub fn api_group(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -
             elem: "Group".to_string(),
             group: group_name.clone(),
             title: group_name.clone(),
-            desc: api_doc,
-            accessors,
+            desc: api_doc.clone(),
+            accessors: accessors.clone(),
         }),
     );
 
@@ -501,6 +690,24 @@ pub fn api_group(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -
         new_items
     };
 
+    let signatures: Vec<EndpointSignature> = proc_macro2::TokenStream::from(item.clone())
+        .into_iter()
+        .filter_map(|item| match item {
+            TokenTree::Group(ref group) if group.delimiter() == Delimiter::Brace => {
+                Some(gather_signatures(group.stream()))
+            }
+            _ => None,
+        })
+        .flatten()
+        .collect();
+
+    for aei in api_endpoint_info.iter_mut() {
+        if let Some(sig) = signatures.iter().find(|s| s.method_name == aei.method_name) {
+            aei.request_type = type_str(&sig.query_type);
+            aei.response_type = type_str(&sig.response_type);
+        }
+    }
+
     for aei in &api_endpoint_info {
         merge_doc(&api_scope, &DocElem::Endpoint(aei.clone()));
     }
@@ -520,6 +727,50 @@ pub fn api_group(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -
             let path = Literal::string(&aei.path);
             let rel_path = Literal::string(&rel_path);
             let method_name = Ident::new(&aei.method_name, Span::call_site());
+
+            // metadata untuk dokumen OpenAPI
+            let sig = signatures.iter().find(|s| s.method_name == aei.method_name);
+            let query_type = sig.map(|s| s.query_type.clone()).unwrap_or_else(|| quote! { () });
+            let response = response_schema(
+                &sig.map(|s| s.response_type.clone())
+                    .unwrap_or_else(TokenStream::new),
+            );
+            let endpoint_accessors = if aei.accessors.is_empty() {
+                &accessors
+            } else {
+                &aei.accessors
+            };
+            let group = Literal::string(&aei.group);
+            let group_desc = Literal::string(&api_doc);
+            let method = Literal::string(&aei.method);
+            let method_name_str = Literal::string(&aei.method_name);
+            let desc = Literal::string(&aei.desc);
+            let auth = Literal::string(&aei.auth);
+            let signed = aei.signed;
+            sas.push(quote! {
+                sas.describe(crate::api::openapi::Operation {
+                    group: #group,
+                    group_desc: #group_desc,
+                    path: #path,
+                    method: #method,
+                    method_name: #method_name_str,
+                    desc: #desc,
+                    auth: #auth,
+                    accessors: &[#(#endpoint_accessors),*],
+                    signed: #signed,
+                    query: |reg: &mut crate::api::openapi::SchemaRegistry| {
+                        #[allow(unused_imports)]
+                        use crate::api::openapi::{ProbeFallback, ProbeSchema};
+                        (&&crate::api::openapi::SchemaProbe::<#query_type>::new()).probe_schema(reg)
+                    },
+                    response: |reg: &mut crate::api::openapi::SchemaRegistry| {
+                        #[allow(unused_imports)]
+                        use crate::api::openapi::{ProbeFallback, ProbeSchema};
+                        #response
+                    },
+                });
+            });
+
             sas.push(if aei.method == "POST" {
                 quote! {
                     debug!(concat!(#scope_name,"| + wiring endpoint POST `{}`"), #path);
@@ -533,6 +784,24 @@ pub fn api_group(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -
             });
         }
         let sases = TokenStream::from_iter(sas.into_iter());
+
+        // typed client untuk endpoint-endpoint di group ini
+        let (access, access_client) = if api_scope == "private" {
+            (quote! { Private }, quote! { PrivateClient })
+        } else {
+            (quote! { Public }, quote! { PublicClient })
+        };
+        let access_ident = Ident::new(&access.to_string(), Span::call_site());
+        let client_name = Ident::new(&format!("{}Client", struct_name), Span::call_site());
+        let client_accessor = Ident::new(&group_name.to_snake_case(), Span::call_site());
+        let client_doc = format!("Client untuk API group `{}` ({}).", group_name, api_scope);
+        let client_methods = api_endpoint_info.iter().filter_map(|aei| {
+            signatures
+                .iter()
+                .find(|s| s.method_name == aei.method_name && !s.response_type.is_empty())
+                .map(|sig| client_method(aei, sig, &access_ident))
+        });
+
         quote! {
             impl #struct_name {
                 #[doc(hidden)]
@@ -540,6 +809,23 @@ pub fn api_group(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -
                     #sases
                 }
             }
+
+            #[doc = #client_doc]
+            #[derive(Clone, Copy)]
+            pub struct #client_name<'a> {
+                client: &'a crate::api::client::ApiClient,
+            }
+
+            impl<'a> #client_name<'a> {
+                #(#client_methods)*
+            }
+
+            impl<'a> crate::api::client::#access_client<'a> {
+                #[doc = #client_doc]
+                pub fn #client_accessor(&self) -> #client_name<'a> {
+                    #client_name { client: self.0 }
+                }
+            }
         }
     };
 
@@ -562,6 +848,8 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
     let mut auth_str = "required".to_string();
     let mut func_name = "".to_string();
     let mut is_mutable = false;
+    let mut is_signed = false;
+    let mut is_two_factor_setup = false;
     let mut debug = false;
 
     let mut to_update = &mut path;
@@ -577,6 +865,12 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
             TokenTree::Ident(ident) if ident.to_string() == "mutable" => {
                 is_mutable = true;
             }
+            TokenTree::Ident(ident) if ident.to_string() == "signed" => {
+                is_signed = true;
+            }
+            TokenTree::Ident(ident) if ident.to_string() == "two_factor_setup" => {
+                is_two_factor_setup = true;
+            }
             TokenTree::Ident(ident) if ident.to_string() == "path" => {
                 to_update = &mut path;
                 nicd = 2;
@@ -605,6 +899,13 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
         ),
     }
 
+    if is_signed && (!is_mutable || auth == 0) {
+        panic!(
+            "API endpoint `{}`: `signed` hanya bisa digunakan pada endpoint mutable yang membutuhkan auth.",
+            path
+        );
+    }
+
     // dbg!((in_path, in_auth, auth_str, is_mutable));
 
     // println!("========= PATH: {} ============", path);
@@ -724,44 +1025,7 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
 
                     if group_cnt == 1 {
                         if let TokenTree::Group(ref group) = item {
-                            let mut in_query = false;
-                            let mut begin_capture_query_type = false;
-                            for inner in group.stream() {
-                                match inner {
-                                    TokenTree::Ident(ref ident) => {
-                                        if ident.to_string() == "query" {
-                                            in_query = true;
-                                        } else if in_query {
-                                            in_query = false;
-                                            begin_capture_query_type = true;
-                                            query_type.push(inner.clone());
-                                        } else if begin_capture_query_type {
-                                            query_type.push(inner.clone());
-                                        }
-                                    }
-                                    TokenTree::Group(ref g) => {
-                                        if in_query
-                                            && g.delimiter() == Delimiter::Parenthesis
-                                            && !begin_capture_query_type
-                                        {
-                                            in_query = false;
-                                            query_type.push(inner.clone());
-                                        } else if begin_capture_query_type {
-                                            query_type.push(inner.clone());
-                                        }
-                                    }
-                                    TokenTree::Punct(ref punct) => {
-                                        if begin_capture_query_type {
-                                            if punct.to_string() == "," {
-                                                begin_capture_query_type = false;
-                                            } else {
-                                                query_type.push(inner.clone());
-                                            }
-                                        }
-                                    }
-                                    _ => (),
-                                }
-                            }
+                            query_type = parse_query_type(group.stream());
                         }
 
                         if query_type.is_empty() {
@@ -828,6 +1092,36 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
                         .filter(|a| !a.is_empty())
                         .collect();
 
+                    // signed request hanya diverifikasi untuk accessor user,
+                    // `current_user` sudah di-unwrap apabila user satu-satunya accessor dan auth required.
+                    let signed_guard = if is_signed && accessors.iter().any(|a| a == "user") {
+                        if auth == 2 && accessors.len() == 1 {
+                            quote! {
+                                crate::api::signature::verify_request(state, &state.db()?, &current_user, req)?;
+                            }
+                        } else {
+                            quote! {
+                                if let Some(ref current_user) = current_user {
+                                    crate::api::signature::verify_request(state, &state.db()?, current_user, req)?;
+                                }
+                            }
+                        }
+                    } else {
+                        quote! {}
+                    };
+
+                    // admin yang wajib 2FA tapi belum mengaktifkannya hanya boleh mengakses
+                    // endpoint dengan atribut `two_factor_setup`.
+                    let two_factor_setup_guard = if is_two_factor_setup {
+                        quote! {}
+                    } else {
+                        quote! {
+                            if let Some(ref current_admin) = current_admin {
+                                crate::api::admin::require_two_factor_setup(state, current_admin)?;
+                            }
+                        }
+                    };
+
                     if auth != 0 {
                         // selain `none`
                         new_stream.push(quote! {
@@ -841,15 +1135,23 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
                                     "user" => {
                                         quote! {
                                             let current_user = if !accessor_loaded {
-                                                req.headers().get("X-Access-Token")
-                                                .and_then(|at| {
-                                                    let conn = state.db();
+                                                match req.headers().get("X-Access-Token") {
+                                                Some(at) => {
+                                                    // koneksi hanya dipegang selama guard, dilepas sebelum handler berjalan
+                                                    let conn = state.db()?;
                                                     let schema = crate::auth::AuthDao::new(&conn);
                                                     schema.get_access_token(at.to_str().unwrap()).ok()
                                                         .and_then(|at|{
                                                             if !at.expired(){
+                                                                if let Err(e) = schema.touch_session(&at, req.connection_info().remote().unwrap_or("")) {
+                                                                    error!("Cannot update session last used. {}", e);
+                                                                }
                                                                 let user_dao = crate::user_dao::UserDao::new(&conn);
                                                                 user_dao.get_by_id(at.user_id).ok()
+                                                                    .map(|user| {
+                                                                        crate::trace::set_actor("user", user.id);
+                                                                        user
+                                                                    })
                                                                     // .map_err(api::Error::from)
                                                             }else{
                                                                 // Err(api::Error::Expired("access token"))
@@ -857,7 +1159,9 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
                                                             }
                                                         })
                                                         // .map_err(|_| api::Error::Unauthorized)
-                                                })
+                                                }
+                                                None => None,
+                                                }
                                             } else { None };
                                             accessor_loaded = current_user.is_some();
                                         }
@@ -865,15 +1169,22 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
                                     "admin" => {
                                         quote! {
                                             let current_admin = if !accessor_loaded {
-                                                req.headers().get("X-Access-Token")
-                                                    .and_then(|at| {
-                                                        let conn = state.db();
+                                                match req.headers().get("X-Access-Token") {
+                                                    Some(at) => {
+                                                        let conn = state.db()?;
                                                         let schema = crate::auth::AuthDao::new(&conn);
                                                         schema.get_admin_access_token(at.to_str().unwrap()).ok()
                                                             .and_then(|at|{
                                                                 if !at.expired(){
+                                                                    if let Err(e) = schema.touch_admin_session(&at, req.connection_info().remote().unwrap_or("")) {
+                                                                        error!("Cannot update session last used. {}", e);
+                                                                    }
                                                                     let admin_dao = crate::admin_dao::AdminDao::new(&conn);
                                                                     admin_dao.get_by_id(at.admin_id).ok()
+                                                                        .map(|admin| {
+                                                                            crate::trace::set_actor("admin", admin.id);
+                                                                            admin
+                                                                        })
                                                                         // .map_err(api::Error::from)
                                                                 }else{
                                                                     // Err(api::Error::Expired("access token"))
@@ -881,9 +1192,12 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
                                                                 }
                                                             })
                                                             // .map_err(|_| api::Error::Unauthorized)
-                                                    })
+                                                    }
+                                                    None => None,
+                                                }
                                             } else { None };
                                             accessor_loaded = current_admin.is_some();
+                                            #two_factor_setup_guard
                                         }
                                     },
                                     x => panic!("Unknown accessor: {}", x)
@@ -956,6 +1270,8 @@ pub fn api_endpoint(attr: proc_macro::TokenStream, item: proc_macro::TokenStream
                         _ => (), // none
                     }
 
+                    new_stream.push(signed_guard);
+
                     new_stream.push(group.stream());
 
                     let group = Group::new(Delimiter::Brace, TokenStream::from_iter(new_stream.into_iter()));
@@ -983,6 +1299,11 @@ pub fn derive_dao(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     expand_proc_macro(input, dao::derive)
 }
 
+#[proc_macro_derive(ApiSchema, attributes(serde, validate))]
+pub fn derive_api_schema(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand_proc_macro(input, schema::derive)
+}
+
 fn expand_proc_macro<T: syn::parse::Parse>(
     input: proc_macro::TokenStream,
     f: fn(T) -> Result<proc_macro2::TokenStream, Diagnostic>,
@@ -119,6 +119,10 @@ impl MetaItem {
         }
     }
 
+    pub fn meta(&self) -> &syn::Meta {
+        &self.meta
+    }
+
     pub fn name(&self) -> syn::Ident {
         self.meta.name()
     }
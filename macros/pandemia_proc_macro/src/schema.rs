@@ -0,0 +1,270 @@
+//! Derive `ApiSchema`, membuat schema OpenAPI untuk struct/enum berdasarkan
+//! doc comment, atribut `serde` dan atribut `validate`-nya.
+
+use proc_macro2::{self, Span};
+use syn;
+
+use heck::{CamelCase, KebabCase, MixedCase, ShoutySnakeCase, SnakeCase};
+
+use crate::{diagnostic_shim::*, meta::*};
+
+type Literal2 = proc_macro2::Literal;
+
+fn doc_of(attrs: &[syn::Attribute]) -> String {
+    MetaItem::all_with_name(attrs, "doc")
+        .iter()
+        .filter_map(|m| m.str_value().ok())
+        .map(|a| a.trim().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Item di dalam `#[serde(..)]`, eg: `skip_serializing`, `rename = ".."`.
+fn serde_items(attrs: &[syn::Attribute]) -> Vec<MetaItem> {
+    MetaItem::all_with_name(attrs, "serde")
+        .iter()
+        .filter_map(|m| m.nested().ok())
+        .flat_map(|n| n)
+        .collect()
+}
+
+fn serde_flag(attrs: &[syn::Attribute], name: &str) -> bool {
+    serde_items(attrs).iter().any(|m| m.name() == name)
+}
+
+fn serde_str(attrs: &[syn::Attribute], name: &str) -> Option<String> {
+    serde_items(attrs)
+        .iter()
+        .find(|m| m.name() == name)
+        .and_then(|m| m.str_value().ok())
+}
+
+fn rename_all(name: &str, rule: Option<&String>) -> String {
+    match rule.map(String::as_str) {
+        Some("lowercase") => name.to_lowercase(),
+        Some("UPPERCASE") => name.to_uppercase(),
+        Some("snake_case") => name.to_snake_case(),
+        Some("camelCase") => name.to_mixed_case(),
+        Some("PascalCase") => name.to_camel_case(),
+        Some("SCREAMING_SNAKE_CASE") => name.to_shouty_snake_case(),
+        Some("kebab-case") => name.to_kebab_case(),
+        _ => name.to_string(),
+    }
+}
+
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .iter()
+            .last()
+            .map(|s| s.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+fn lit_f64(lit: &syn::Lit) -> Option<f64> {
+    match lit {
+        syn::Lit::Int(i) => Some(i.value() as f64),
+        syn::Lit::Float(f) => Some(f.value()),
+        syn::Lit::Str(s) => s.value().parse().ok(),
+        _ => None,
+    }
+}
+
+fn nested_f64(item: &MetaItem, name: &str) -> Option<f64> {
+    item.nested()
+        .ok()
+        .and_then(|mut n| n.find(|m| m.name() == name))
+        .and_then(|m| match m.meta() {
+            syn::Meta::NameValue(nv) => lit_f64(&nv.lit),
+            _ => None,
+        })
+}
+
+fn opt_tokens<T: quote::ToTokens>(value: Option<T>) -> proc_macro2::TokenStream {
+    match value {
+        Some(v) => quote! { Some(#v) },
+        None => quote! { None },
+    }
+}
+
+/// Batasan dari atribut `#[validate(..)]` pada field.
+fn constraints(attrs: &[syn::Attribute]) -> Vec<proc_macro2::TokenStream> {
+    let items: Vec<MetaItem> = MetaItem::all_with_name(attrs, "validate")
+        .iter()
+        .filter_map(|m| m.nested().ok())
+        .flat_map(|n| n)
+        .collect();
+
+    let mut rv = vec![];
+
+    for item in items {
+        match item.name().to_string().as_str() {
+            "length" => {
+                let equal = nested_f64(&item, "equal").map(|a| a as u64);
+                let min = opt_tokens(nested_f64(&item, "min").map(|a| a as u64).or(equal));
+                let max = opt_tokens(nested_f64(&item, "max").map(|a| a as u64).or(equal));
+                rv.push(quote! { schema = crate::api::openapi::length(schema, #min, #max); });
+            }
+            "range" => {
+                let min = opt_tokens(nested_f64(&item, "min"));
+                let max = opt_tokens(nested_f64(&item, "max"));
+                rv.push(quote! { schema = crate::api::openapi::range(schema, #min, #max); });
+            }
+            "email" | "url" | "phone" => {
+                let format = match item.name().to_string().as_str() {
+                    "url" => "uri".to_string(),
+                    x => x.to_string(),
+                };
+                rv.push(quote! {
+                    schema = crate::api::openapi::annotate(schema, "format", serde_json::Value::from(#format));
+                });
+            }
+            _ => (),
+        }
+    }
+
+    rv
+}
+
+fn probe(ty: &syn::Type) -> proc_macro2::TokenStream {
+    quote! {
+        (&&crate::api::openapi::SchemaProbe::<#ty>::new()).probe_schema(reg)
+    }
+}
+
+fn struct_schema(
+    item: &syn::DeriveInput,
+    data: &syn::DataStruct,
+) -> Result<proc_macro2::TokenStream, Diagnostic> {
+    let desc = doc_of(&item.attrs);
+    let rule = serde_str(&item.attrs, "rename_all");
+
+    let fields = match data.fields {
+        syn::Fields::Named(ref fields) => fields,
+        syn::Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+            // newtype, schema mengikuti tipe di dalamnya
+            let schema = probe(&fields.unnamed[0].ty);
+            return Ok(quote! { #schema });
+        }
+        _ => return Ok(quote! { serde_json::Value::Object(Default::default()) }),
+    };
+
+    let mut props = vec![];
+
+    for field in fields.named.iter() {
+        if serde_flag(&field.attrs, "skip") || serde_flag(&field.attrs, "skip_serializing") {
+            continue;
+        }
+
+        let ident = match field.ident {
+            Some(ref ident) => ident.to_string(),
+            None => continue,
+        };
+        let name = serde_str(&field.attrs, "rename").unwrap_or_else(|| rename_all(&ident, rule.as_ref()));
+        let required = !is_option(&field.ty)
+            && !serde_flag(&field.attrs, "default")
+            && !serde_flag(&field.attrs, "skip_serializing_if");
+        let field_desc = doc_of(&field.attrs);
+        let schema = probe(&field.ty);
+        let constraints = constraints(&field.attrs);
+
+        props.push(quote! {
+            {
+                let mut schema = #schema;
+                #(#constraints)*
+                obj.field(#name, #required, #field_desc, schema);
+            }
+        });
+    }
+
+    Ok(quote! {
+        let mut obj = crate::api::openapi::ObjectSchema::new(#desc);
+        #(#props)*
+        obj.build()
+    })
+}
+
+fn enum_schema(
+    item: &syn::DeriveInput,
+    data: &syn::DataEnum,
+) -> Result<proc_macro2::TokenStream, Diagnostic> {
+    let desc = doc_of(&item.attrs);
+    let rule = serde_str(&item.attrs, "rename_all");
+
+    // hanya enum unit yang diserialisasi sebagai string yang didukung
+    if data.variants.iter().any(|v| match v.fields {
+        syn::Fields::Unit => false,
+        _ => true,
+    }) {
+        return Ok(quote! { serde_json::Value::Object(Default::default()) });
+    }
+
+    let names: Vec<String> = data
+        .variants
+        .iter()
+        .filter(|v| !serde_flag(&v.attrs, "skip") && !serde_flag(&v.attrs, "skip_serializing"))
+        .map(|v| {
+            serde_str(&v.attrs, "rename").unwrap_or_else(|| rename_all(&v.ident.to_string(), rule.as_ref()))
+        })
+        .collect();
+
+    let desc = if desc.is_empty() {
+        quote! {}
+    } else {
+        quote! { schema["description"] = serde_json::Value::from(#desc); }
+    };
+
+    Ok(quote! {
+        let mut schema = serde_json::Value::Object(Default::default());
+        schema["type"] = serde_json::Value::from("string");
+        schema["enum"] = serde_json::Value::from(vec![#(#names),*]);
+        #desc
+        schema
+    })
+}
+
+pub fn derive(item: syn::DeriveInput) -> Result<proc_macro2::TokenStream, Diagnostic> {
+    let name = &item.ident;
+
+    let body = match item.data {
+        syn::Data::Struct(ref data) => struct_schema(&item, data)?,
+        syn::Data::Enum(ref data) => enum_schema(&item, data)?,
+        syn::Data::Union(_) => return Err(Span::call_site().error("ApiSchema tidak mendukung union")),
+    };
+
+    // tipe generic di-inline, karena nama schema-nya bergantung pada parameter tipenya
+    let schema_path = if item.generics.type_params().next().is_none() {
+        let name_lit = Literal2::string(&name.to_string());
+        quote! {
+            fn schema_path() -> Option<&'static str> {
+                Some(concat!(module_path!(), "::", #name_lit))
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let mut generics = item.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(crate::api::openapi::ApiSchema));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics crate::api::openapi::ApiSchema for #name #ty_generics #where_clause {
+            #schema_path
+
+            #[allow(unused_imports, unused_mut, unused_variables)]
+            fn schema(reg: &mut crate::api::openapi::SchemaRegistry) -> serde_json::Value {
+                use crate::api::openapi::{ProbeFallback, ProbeSchema};
+                #body
+            }
+        }
+    })
+}
@@ -0,0 +1,16 @@
+CREATE TABLE boundaries (
+  id BIGSERIAL PRIMARY KEY NOT NULL,
+  kind SMALLINT NOT NULL, -- 4 = city, 5 = district, 6 = village (lihat `LocKind`)
+  ref_id BIGINT NOT NULL, -- id dari cities/districts/villages sesuai `kind`
+  "name" TEXT NOT NULL,
+  geometry TEXT NOT NULL, -- GeoJSON geometry (Polygon atau MultiPolygon)
+  min_lat DOUBLE PRECISION NOT NULL,
+  min_lng DOUBLE PRECISION NOT NULL,
+  max_lat DOUBLE PRECISION NOT NULL,
+  max_lng DOUBLE PRECISION NOT NULL,
+  meta TEXT[] NOT NULL DEFAULT '{}',
+  ts TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX idx_boundaries_kind_ref_id ON boundaries(kind, ref_id);
+CREATE INDEX idx_boundaries_bbox ON boundaries(kind, min_lat, max_lat, min_lng, max_lng);
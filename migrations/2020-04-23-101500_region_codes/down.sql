@@ -0,0 +1,7 @@
+DROP INDEX idx_villages_code;
+DROP INDEX idx_districts_code;
+DROP INDEX idx_cities_code;
+
+ALTER TABLE villages DROP COLUMN code;
+ALTER TABLE districts DROP COLUMN code;
+ALTER TABLE cities DROP COLUMN code;
@@ -0,0 +1,8 @@
+-- Kode wilayah resmi Kemendagri, eg: 33.07 (kab), 33.07.01 (kec), 33.07.01.2001 (desa)
+ALTER TABLE cities ADD COLUMN code TEXT NOT NULL DEFAULT '';
+ALTER TABLE districts ADD COLUMN code TEXT NOT NULL DEFAULT '';
+ALTER TABLE villages ADD COLUMN code TEXT NOT NULL DEFAULT '';
+
+CREATE UNIQUE INDEX idx_cities_code ON cities(code) WHERE code <> '';
+CREATE UNIQUE INDEX idx_districts_code ON districts(code) WHERE code <> '';
+CREATE UNIQUE INDEX idx_villages_code ON villages(code) WHERE code <> '';
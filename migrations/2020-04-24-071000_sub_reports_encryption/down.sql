@@ -0,0 +1,8 @@
+DROP INDEX idx_sub_reports_residence_address_bidx;
+DROP INDEX idx_sub_reports_age_bidx;
+DROP INDEX idx_sub_reports_full_name_bidx;
+
+ALTER TABLE sub_reports DROP COLUMN residence_address_bidx;
+ALTER TABLE sub_reports DROP COLUMN age_bidx;
+ALTER TABLE sub_reports DROP COLUMN full_name_bidx;
+ALTER TABLE sub_reports DROP COLUMN pii;
@@ -0,0 +1,11 @@
+-- Data pribadi (full_name, age, residence_address, notes) disimpan terenkripsi di kolom `pii`,
+-- kolom plaintext dikosongkan setelah data dienkripsi.
+ALTER TABLE sub_reports ADD COLUMN pii TEXT NOT NULL DEFAULT '';
+-- Blind index untuk pencarian, full_name di-index per kata.
+ALTER TABLE sub_reports ADD COLUMN full_name_bidx TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE sub_reports ADD COLUMN age_bidx TEXT NOT NULL DEFAULT '';
+ALTER TABLE sub_reports ADD COLUMN residence_address_bidx TEXT NOT NULL DEFAULT '';
+
+CREATE INDEX idx_sub_reports_full_name_bidx ON sub_reports USING GIN (full_name_bidx);
+CREATE INDEX idx_sub_reports_age_bidx ON sub_reports (age_bidx);
+CREATE INDEX idx_sub_reports_residence_address_bidx ON sub_reports (residence_address_bidx);
@@ -0,0 +1,7 @@
+DROP INDEX idx_user_settings_ts;
+DROP INDEX idx_user_connect_latest_loc_ts;
+DROP INDEX idx_sub_reports_last_updated;
+
+ALTER TABLE user_settings DROP COLUMN ts;
+ALTER TABLE user_connect DROP COLUMN latest_loc_ts;
+ALTER TABLE sub_reports DROP COLUMN last_updated;
@@ -0,0 +1,9 @@
+-- Timestamp untuk keperluan retensi data, lihat `monitor::RetentionMonitor`.
+ALTER TABLE sub_reports ADD COLUMN last_updated TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+UPDATE sub_reports SET last_updated = ts;
+ALTER TABLE user_connect ADD COLUMN latest_loc_ts TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE user_settings ADD COLUMN ts TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+CREATE INDEX idx_sub_reports_last_updated ON sub_reports (last_updated);
+CREATE INDEX idx_user_connect_latest_loc_ts ON user_connect (latest_loc_ts);
+CREATE INDEX idx_user_settings_ts ON user_settings (ts);
@@ -0,0 +1,21 @@
+DROP INDEX idx_admin_access_tokens_refresh_token;
+DROP INDEX idx_admin_access_tokens_admin_id;
+ALTER TABLE admin_access_tokens DROP COLUMN refresh_valid_thru;
+ALTER TABLE admin_access_tokens DROP COLUMN refresh_token;
+ALTER TABLE admin_access_tokens DROP COLUMN last_used;
+ALTER TABLE admin_access_tokens DROP COLUMN ip_address;
+ALTER TABLE admin_access_tokens DROP COLUMN platform;
+ALTER TABLE admin_access_tokens DROP COLUMN name;
+ALTER TABLE admin_access_tokens DROP COLUMN device_id;
+ALTER TABLE admin_access_tokens DROP COLUMN id;
+
+DROP INDEX idx_access_tokens_refresh_token;
+DROP INDEX idx_access_tokens_user_id;
+ALTER TABLE access_tokens DROP COLUMN refresh_valid_thru;
+ALTER TABLE access_tokens DROP COLUMN refresh_token;
+ALTER TABLE access_tokens DROP COLUMN last_used;
+ALTER TABLE access_tokens DROP COLUMN ip_address;
+ALTER TABLE access_tokens DROP COLUMN platform;
+ALTER TABLE access_tokens DROP COLUMN name;
+ALTER TABLE access_tokens DROP COLUMN device_id;
+ALTER TABLE access_tokens DROP COLUMN id;
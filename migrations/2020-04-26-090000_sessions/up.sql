@@ -0,0 +1,24 @@
+-- Informasi sesi per device & refresh token untuk access token user dan admin.
+ALTER TABLE access_tokens ADD COLUMN id BIGSERIAL NOT NULL UNIQUE;
+ALTER TABLE access_tokens ADD COLUMN device_id TEXT NOT NULL DEFAULT '';
+ALTER TABLE access_tokens ADD COLUMN name TEXT NOT NULL DEFAULT '';
+ALTER TABLE access_tokens ADD COLUMN platform TEXT NOT NULL DEFAULT '';
+ALTER TABLE access_tokens ADD COLUMN ip_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE access_tokens ADD COLUMN last_used TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE access_tokens ADD COLUMN refresh_token TEXT NOT NULL DEFAULT '';
+ALTER TABLE access_tokens ADD COLUMN refresh_valid_thru TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+CREATE INDEX idx_access_tokens_user_id ON access_tokens (user_id);
+CREATE UNIQUE INDEX idx_access_tokens_refresh_token ON access_tokens (refresh_token) WHERE refresh_token <> '';
+
+ALTER TABLE admin_access_tokens ADD COLUMN id BIGSERIAL NOT NULL UNIQUE;
+ALTER TABLE admin_access_tokens ADD COLUMN device_id TEXT NOT NULL DEFAULT '';
+ALTER TABLE admin_access_tokens ADD COLUMN name TEXT NOT NULL DEFAULT '';
+ALTER TABLE admin_access_tokens ADD COLUMN platform TEXT NOT NULL DEFAULT '';
+ALTER TABLE admin_access_tokens ADD COLUMN ip_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE admin_access_tokens ADD COLUMN last_used TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE admin_access_tokens ADD COLUMN refresh_token TEXT NOT NULL DEFAULT '';
+ALTER TABLE admin_access_tokens ADD COLUMN refresh_valid_thru TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+CREATE INDEX idx_admin_access_tokens_admin_id ON admin_access_tokens (admin_id);
+CREATE UNIQUE INDEX idx_admin_access_tokens_refresh_token ON admin_access_tokens (refresh_token) WHERE refresh_token <> '';
@@ -0,0 +1 @@
+DROP TABLE satgas_invitations;
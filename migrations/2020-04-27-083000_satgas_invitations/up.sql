@@ -0,0 +1,24 @@
+-- Undangan satgas per orang, menggantikan pendaftaran menggunakan area code kota.
+CREATE TABLE satgas_invitations (
+    id BIGSERIAL PRIMARY KEY,
+    token TEXT NOT NULL UNIQUE,
+    city_id BIGINT NOT NULL REFERENCES cities (id) ON DELETE CASCADE,
+    district_id BIGINT NOT NULL REFERENCES districts (id) ON DELETE CASCADE,
+    village_id BIGINT NOT NULL REFERENCES villages (id) ON DELETE CASCADE,
+    village_name TEXT NOT NULL,
+    district_name TEXT NOT NULL,
+    is_medic BOOLEAN NOT NULL DEFAULT FALSE,
+    -- nama & no telp orang yang diundang, hanya sebagai catatan untuk admin
+    invitee_name TEXT NOT NULL DEFAULT '',
+    invitee_phone TEXT NOT NULL DEFAULT '',
+    creator_id BIGINT NOT NULL,
+    creator_name TEXT NOT NULL,
+    accepted_by BIGINT NOT NULL DEFAULT 0,
+    accepted_by_name TEXT NOT NULL DEFAULT '',
+    accepted_at TIMESTAMP,
+    revoked BOOLEAN NOT NULL DEFAULT FALSE,
+    expiration TIMESTAMP NOT NULL,
+    ts TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_satgas_invitations_city_id ON satgas_invitations (city_id);
@@ -0,0 +1 @@
+DROP TABLE admin_two_factors;
@@ -0,0 +1,14 @@
+-- TOTP (autentikasi dua langkah) untuk akun admin.
+CREATE TABLE admin_two_factors (
+    id BIGSERIAL PRIMARY KEY,
+    admin_id BIGINT NOT NULL UNIQUE REFERENCES admins (id) ON DELETE CASCADE,
+    -- secret TOTP dalam format base32
+    secret TEXT NOT NULL,
+    -- false selama enrollment belum dikonfirmasi menggunakan kode dari authenticator
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    -- hash sha256 dari recovery codes yang belum digunakan
+    recovery_codes TEXT[] NOT NULL DEFAULT '{}',
+    -- periode terakhir kode TOTP digunakan, untuk mencegah replay
+    last_step BIGINT NOT NULL DEFAULT 0,
+    ts TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,3 @@
+DROP TABLE request_nonces;
+DROP INDEX idx_user_keys_user_id_pub_key;
+ALTER TABLE user_keys DROP COLUMN client_key;
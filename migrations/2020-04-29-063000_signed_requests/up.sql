@@ -0,0 +1,13 @@
+-- key ed25519 yang didaftarkan oleh client (secret key hanya ada di perangkat client),
+-- berbeda dengan key yang digenerasikan oleh server ketika akun dibuat.
+ALTER TABLE user_keys ADD COLUMN client_key BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX idx_user_keys_user_id_pub_key ON user_keys (user_id, pub_key);
+
+-- nonce dari signed request yang sudah digunakan, untuk mencegah replay.
+CREATE TABLE request_nonces (
+    user_id BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    nonce TEXT NOT NULL,
+    ts TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, nonce)
+);
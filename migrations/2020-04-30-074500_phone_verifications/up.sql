@@ -0,0 +1,14 @@
+-- Verifikasi nomor telepon menggunakan OTP (SMS), digunakan untuk meng-upgrade
+-- akun device (anonim) menjadi akun dengan nomor telepon terverifikasi.
+CREATE TABLE phone_verifications (
+    id BIGSERIAL PRIMARY KEY,
+    -- hanya ada satu verifikasi yang berjalan per akun
+    user_id BIGINT NOT NULL UNIQUE REFERENCES users (id) ON DELETE CASCADE,
+    phone_num VARCHAR NOT NULL,
+    -- hash sha256 dari kode OTP
+    code_hash TEXT NOT NULL,
+    -- jumlah percobaan verifikasi yang gagal
+    attempts INT NOT NULL DEFAULT 0,
+    expiration TIMESTAMP NOT NULL,
+    ts TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,16 @@
+-- Registry rilis aplikasi mobile per platform, digunakan oleh endpoint check_version
+-- untuk memberitahu (atau memaksa) client yang sudah usang agar melakukan update.
+CREATE TABLE app_releases (
+    id BIGSERIAL PRIMARY KEY,
+    -- eg: android, ios
+    platform VARCHAR NOT NULL,
+    version VARCHAR NOT NULL,
+    -- client dengan versi di bawah ini wajib update
+    min_supported_version VARCHAR NOT NULL,
+    notes TEXT NOT NULL DEFAULT '',
+    download_url TEXT NOT NULL DEFAULT '',
+    -- paksa client yang lebih lama untuk update ke rilis ini
+    force_update BOOLEAN NOT NULL DEFAULT FALSE,
+    ts TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (platform, version)
+);
@@ -0,0 +1,29 @@
+-- Job terjadwal (lihat `src/scheduler`), jadwal berikutnya disimpan di sini
+-- sehingga tetap konsisten walaupun server di-restart atau berjalan lebih dari satu instance.
+CREATE TABLE scheduled_jobs (
+    id BIGSERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    -- eg: `0 3 * * *` atau `@every 30m`
+    schedule VARCHAR NOT NULL,
+    last_run TIMESTAMP,
+    -- NULL apabila belum pernah jalan
+    last_success BOOLEAN,
+    last_error TEXT,
+    next_run TIMESTAMP,
+    ts TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Riwayat jalannya job.
+CREATE TABLE job_runs (
+    id BIGSERIAL PRIMARY KEY,
+    job_id BIGINT NOT NULL REFERENCES scheduled_jobs (id) ON DELETE CASCADE,
+    started TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- NULL selama job masih berjalan
+    finished TIMESTAMP,
+    success BOOLEAN,
+    error TEXT,
+    -- dijalankan manual via API, bukan oleh jadwal
+    manual BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX job_runs_job_id ON job_runs (job_id, id DESC);
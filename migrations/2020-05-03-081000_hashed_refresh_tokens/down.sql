@@ -0,0 +1,3 @@
+-- Hash tidak bisa dikembalikan, refresh token yang ada tidak berlaku lagi.
+UPDATE access_tokens SET refresh_token = '';
+UPDATE admin_access_tokens SET refresh_token = '';
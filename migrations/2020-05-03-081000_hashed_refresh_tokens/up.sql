@@ -0,0 +1,5 @@
+-- Refresh token disimpan dalam bentuk hash SHA-256 (hex), sama seperti kode OTP.
+UPDATE access_tokens SET refresh_token = encode(sha256(convert_to(refresh_token, 'UTF8')), 'hex')
+    WHERE refresh_token <> '';
+UPDATE admin_access_tokens SET refresh_token = encode(sha256(convert_to(refresh_token, 'UTF8')), 'hex')
+    WHERE refresh_token <> '';
@@ -0,0 +1,6 @@
+DROP INDEX idx_user_connect_latest_village_id;
+DROP INDEX idx_user_connect_latest_district_id;
+
+ALTER TABLE user_connect DROP COLUMN latest_village_id;
+ALTER TABLE user_connect DROP COLUMN latest_district_id;
+ALTER TABLE user_connect DROP COLUMN latest_city_id;
@@ -0,0 +1,8 @@
+-- ID wilayah hasil resolve batas wilayah (boundaries) untuk target push notif,
+-- NULL apabila lokasi device tidak berada di wilayah manapun.
+ALTER TABLE user_connect ADD COLUMN latest_city_id BIGINT;
+ALTER TABLE user_connect ADD COLUMN latest_district_id BIGINT;
+ALTER TABLE user_connect ADD COLUMN latest_village_id BIGINT;
+
+CREATE INDEX idx_user_connect_latest_district_id ON user_connect (latest_district_id);
+CREATE INDEX idx_user_connect_latest_village_id ON user_connect (latest_village_id);
@@ -5,6 +5,8 @@ use chrono::prelude::*;
 use diesel::prelude::*;
 
 use crate::{
+    auth::AuthDao,
+    crypto::PasshashScheme,
     error::{Error as PdmError, ErrorCode},
     models::{Admin, ResetPasswordAdmin},
     result::Result,
@@ -21,6 +23,20 @@ struct NewAdmin<'a> {
     pub meta: &'a Vec<String>,
 }
 
+#[derive(Insertable)]
+#[table_name = "admins"]
+struct NewSuperAdmin<'a> {
+    pub id: ID,
+    pub name: &'a str,
+    pub email: &'a str,
+    pub phone_num: &'a str,
+    pub meta: &'a Vec<String>,
+    pub active: bool,
+}
+
+/// ID super admin, dibuat oleh migration awal.
+pub const SUPER_ADMIN_ID: ID = 1;
+
 #[doc(hidden)]
 #[derive(Insertable, AsChangeset)]
 #[table_name = "reset_password_admins"]
@@ -56,6 +72,7 @@ impl<'a> AdminDao<'a> {
         phone_num: &'a str,
         password: &'a str,
         meta: &'a Vec<String>,
+        scheme: PasshashScheme,
     ) -> Result<Admin> {
         self.db.build_transaction().read_write().run::<_, _, _>(|| {
             let admin: Admin = diesel::insert_into(admins::table)
@@ -68,13 +85,13 @@ impl<'a> AdminDao<'a> {
                 .get_result(self.db)?;
 
             // tambahkan password baru
-            let passhash = &crate::crypto::get_passhash(password);
+            let passhash = &scheme.hash(password);
             diesel::insert_into(admin_passhash::table)
                 .values(&NewAdminPasshash {
                     admin_id: admin.id,
                     passhash,
                     deprecated: false,
-                    ver: 1,
+                    ver: scheme.ver(),
                 })
                 .execute(self.db)?;
 
@@ -82,6 +99,45 @@ impl<'a> AdminDao<'a> {
         })
     }
 
+    /// Buat atau reset super admin beserta password-nya,
+    /// semua sesi super admin yang masih aktif akan dihapus.
+    pub fn reset_super_admin(
+        &self,
+        name: &str,
+        email: &str,
+        phone_num: &str,
+        password: &str,
+        scheme: PasshashScheme,
+    ) -> Result<Admin> {
+        use crate::schema::admins::dsl;
+
+        self.db.build_transaction().read_write().run(|| {
+            let admin: Admin = diesel::insert_into(admins::table)
+                .values(&NewSuperAdmin {
+                    id: SUPER_ADMIN_ID,
+                    name,
+                    email,
+                    phone_num,
+                    meta: &vec![],
+                    active: true,
+                })
+                .on_conflict(dsl::id)
+                .do_update()
+                .set((
+                    dsl::name.eq(name),
+                    dsl::email.eq(email),
+                    dsl::phone_num.eq(phone_num),
+                    dsl::active.eq(true),
+                ))
+                .get_result(self.db)?;
+
+            self.set_password(admin.id, password, scheme)?;
+            AuthDao::new(self.db).clear_access_token_by_admin_id(admin.id)?;
+
+            Ok(admin)
+        })
+    }
+
     /// Mendapatkan admin berdasarkan emailnya.
     pub fn get_by_email(&self, email: &str) -> Result<Admin> {
         use crate::schema::admins::dsl;
@@ -91,20 +147,18 @@ impl<'a> AdminDao<'a> {
             .map_err(From::from)
     }
 
-    /// Request token untuk reset password.
-    pub fn reset_password(&self, admin_id: ID, name: String, email: String) -> Result<()> {
+    /// Request token untuk reset password, token dikembalikan agar bisa dikirim ke admin.
+    pub fn reset_password(&self, admin_id: ID) -> Result<String> {
         use crate::schema::reset_password_admins::dsl;
 
         let expiration = Some(util::now() + chrono::Duration::days(1));
-        let token = &token::generate_token();
+        let token = token::generate_token();
         let new_entry = NewResetPasswordKey {
             admin_id,
-            token,
+            token: &token,
             expiration,
         };
 
-        debug!("reset password token: {}", token);
-
         diesel::insert_into(reset_password_admins::table)
             .values(&new_entry)
             .on_conflict(dsl::admin_id)
@@ -112,7 +166,9 @@ impl<'a> AdminDao<'a> {
             .set(&new_entry)
             .execute(self.db)?;
 
-        Ok(())
+        debug!("reset password token generated for admin {}", admin_id);
+
+        Ok(token)
     }
 
     /// Verifikasi token untuk reset password.
@@ -149,14 +205,14 @@ impl<'a> AdminDao<'a> {
         Ok(())
     }
 
-    /// Setting admin's password
-    pub fn set_password(&self, admin_id: ID, password: &str) -> Result<()> {
+    /// Setting admin's password, di-hash menggunakan skema `scheme`.
+    pub fn set_password(&self, admin_id: ID, password: &str, scheme: PasshashScheme) -> Result<()> {
         use crate::schema::admin_passhash::dsl;
 
         let _ = self.get_by_id(admin_id)?;
 
         self.db.build_transaction().read_write().run(|| {
-            let passhash = &crate::crypto::get_passhash(password);
+            let passhash = &scheme.hash(password);
 
             // dipresiasi password lama
             diesel::update(
@@ -171,7 +227,7 @@ impl<'a> AdminDao<'a> {
                     admin_id,
                     passhash,
                     deprecated: false,
-                    ver: 1,
+                    ver: scheme.ver(),
                 })
                 .execute(self.db)?;
 
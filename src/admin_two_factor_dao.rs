@@ -0,0 +1,309 @@
+//! Dao implementation for AdminTwoFactor
+//!
+//! Autentikasi dua langkah (TOTP) untuk admin. Admin melakukan enrollment dengan men-scan secret
+//! ke aplikasi authenticator lalu mengkonfirmasi menggunakan kode pertama, setelah itu
+//! recovery codes diberikan sekali untuk digunakan apabila perangkat hilang.
+//!
+//! Secret TOTP disimpan terenkripsi menggunakan `security.data_key`.
+
+use diesel::prelude::*;
+
+use crate::{
+    config::TwoFactorConfig,
+    crypto::{self, DataKey},
+    models::{Admin, AdminTwoFactor},
+    result::Result,
+    schema::admin_two_factors,
+    token, totp, util, ID,
+};
+
+/// Jumlah recovery code yang diberikan ketika enrollment.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Insertable)]
+#[table_name = "admin_two_factors"]
+struct NewAdminTwoFactor<'a> {
+    pub admin_id: ID,
+    pub secret: &'a str,
+}
+
+/// Kebijakan kapan admin wajib menggunakan autentikasi dua langkah.
+///
+/// Diatur melalui konfigurasi `two_factor.required_accesses` (env var `ADMIN_2FA_REQUIRED_ACCESSES`
+/// dipisah koma), admin yang memiliki salah satu akses tersebut wajib mengaktifkan 2FA.
+/// Gunakan `*` untuk mewajibkan semua admin.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TwoFactorPolicy {
+    /// Wajib untuk semua admin.
+    pub all: bool,
+    /// Daftar akses yang dianggap sensitif.
+    pub accesses: Vec<String>,
+}
+
+impl TwoFactorPolicy {
+    /// Kebijakan dari konfigurasi.
+    pub fn from_config(config: &TwoFactorConfig) -> Self {
+        Self::new(&config.required_accesses)
+    }
+
+    fn new(accesses: &[String]) -> Self {
+        let items: Vec<String> = accesses
+            .iter()
+            .map(|a| a.trim().trim_start_matches("access.").to_string())
+            .filter(|a| !a.is_empty())
+            .collect();
+        Self {
+            all: items.iter().any(|a| a == "*"),
+            accesses: items.into_iter().filter(|a| a != "*").collect(),
+        }
+    }
+
+    /// Check apakah admin wajib menggunakan 2FA.
+    pub fn requires(&self, admin: &Admin) -> bool {
+        self.all || self.accesses.iter().any(|a| admin.has_access(a))
+    }
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    crypto::sha256_hash(normalize_recovery_code(code).as_bytes()).to_hex()
+}
+
+fn encrypt_secret(secret: &str) -> Result<String> {
+    Ok(crypto::data_key()?.encrypt(secret.as_bytes()))
+}
+
+/// Dekripsi secret TOTP, secret lama yang masih plaintext (base32) dikembalikan apa adanya.
+fn decrypt_secret(secret: &str) -> Result<String> {
+    if !DataKey::is_encrypted(secret) {
+        return Ok(secret.to_owned());
+    }
+    match crypto::data_key()?
+        .decrypt(secret)
+        .and_then(|a| String::from_utf8(a).ok())
+    {
+        Some(secret) => Ok(secret),
+        None => fail!("Cannot decrypt two factor secret"),
+    }
+}
+
+/// Generate recovery codes baru, mengembalikan (plain codes, hashes).
+fn generate_recovery_codes() -> (Vec<String>, Vec<String>) {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code = hex::encode(token::rand_bytes(5));
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect();
+    let hashes = codes.iter().map(|a| hash_recovery_code(a)).collect();
+    (codes, hashes)
+}
+
+/// Data Access Object for AdminTwoFactor
+#[derive(Dao)]
+#[table_name = "admin_two_factors"]
+pub struct AdminTwoFactorDao<'a> {
+    db: &'a PgConnection,
+}
+
+impl<'a> AdminTwoFactorDao<'a> {
+    /// Get konfigurasi 2FA milik admin.
+    pub fn get_by_admin_id(&self, admin_id: ID) -> Result<Option<AdminTwoFactor>> {
+        use crate::schema::admin_two_factors::dsl;
+
+        dsl::admin_two_factors
+            .filter(dsl::admin_id.eq(admin_id))
+            .first(self.db)
+            .optional()
+            .map_err(From::from)
+    }
+
+    /// Check apakah admin telah mengaktifkan 2FA.
+    pub fn is_enabled(&self, admin_id: ID) -> Result<bool> {
+        Ok(self
+            .get_by_admin_id(admin_id)?
+            .map(|a| a.enabled)
+            .unwrap_or(false))
+    }
+
+    /// Mulai enrollment, mengembalikan secret dalam format base32.
+    /// Enrollment sebelumnya yang belum dikonfirmasi akan diganti.
+    pub fn enroll(&self, admin_id: ID) -> Result<String> {
+        use crate::schema::admin_two_factors::dsl;
+
+        if self.is_enabled(admin_id)? {
+            return Err(param_err!("Two factor authentication already enabled"));
+        }
+
+        let secret = totp::generate_secret();
+        let encrypted = encrypt_secret(&secret)?;
+
+        self.db.build_transaction().read_write().run::<_, _, _>(|| {
+            diesel::delete(dsl::admin_two_factors.filter(dsl::admin_id.eq(admin_id))).execute(self.db)?;
+
+            diesel::insert_into(admin_two_factors::table)
+                .values(&NewAdminTwoFactor {
+                    admin_id,
+                    secret: &encrypted,
+                })
+                .execute(self.db)?;
+
+            Ok(())
+        })?;
+
+        Ok(secret)
+    }
+
+    /// Konfirmasi enrollment menggunakan kode dari authenticator,
+    /// mengembalikan recovery codes yang hanya ditampilkan sekali ini.
+    pub fn confirm(&self, admin_id: ID, code: &str) -> Result<Vec<String>> {
+        use crate::schema::admin_two_factors::dsl;
+
+        let tf = match self.get_by_admin_id(admin_id)? {
+            Some(tf) if !tf.enabled => tf,
+            Some(_) => return Err(param_err!("Two factor authentication already enabled")),
+            None => return Err(param_err!("No pending two factor enrollment")),
+        };
+
+        let secret = decrypt_secret(&tf.secret)?;
+        let step = match totp::verify(&secret, code, util::current_time().as_secs(), 0) {
+            Some(step) => step,
+            None => return Err(param_err!("Invalid verification code")),
+        };
+
+        let (codes, hashes) = generate_recovery_codes();
+
+        diesel::update(dsl::admin_two_factors.filter(dsl::id.eq(tf.id)))
+            .set((
+                dsl::enabled.eq(true),
+                dsl::last_step.eq(step),
+                dsl::recovery_codes.eq(hashes),
+            ))
+            .execute(self.db)?;
+
+        Ok(codes)
+    }
+
+    /// Verifikasi kode TOTP atau recovery code,
+    /// recovery code yang berhasil digunakan tidak bisa dipakai lagi.
+    pub fn verify(&self, admin_id: ID, code: &str) -> Result<bool> {
+        use crate::schema::admin_two_factors::dsl;
+
+        let tf = match self.get_by_admin_id(admin_id)? {
+            Some(tf) if tf.enabled => tf,
+            _ => return Ok(false),
+        };
+
+        let secret = decrypt_secret(&tf.secret)?;
+        if let Some(step) = totp::verify(&secret, code, util::current_time().as_secs(), tf.last_step) {
+            let updated =
+                diesel::update(dsl::admin_two_factors.filter(dsl::id.eq(tf.id).and(dsl::last_step.lt(step))))
+                    .set(dsl::last_step.eq(step))
+                    .execute(self.db)?;
+
+            // enkripsi secret lama yang masih tersimpan plaintext
+            if !DataKey::is_encrypted(&tf.secret) {
+                diesel::update(dsl::admin_two_factors.filter(dsl::id.eq(tf.id)))
+                    .set(dsl::secret.eq(encrypt_secret(&secret)?))
+                    .execute(self.db)?;
+            }
+
+            return Ok(updated > 0);
+        }
+
+        let hash = hash_recovery_code(code);
+        if !tf.recovery_codes.contains(&hash) {
+            return Ok(false);
+        }
+
+        let remaining: Vec<String> = tf.recovery_codes.into_iter().filter(|a| a != &hash).collect();
+        let updated = diesel::update(
+            dsl::admin_two_factors.filter(
+                dsl::id
+                    .eq(tf.id)
+                    .and(dsl::recovery_codes.contains(vec![hash.to_owned()])),
+            ),
+        )
+        .set(dsl::recovery_codes.eq(remaining))
+        .execute(self.db)?;
+
+        if updated > 0 {
+            warn!("admin {} used a recovery code", admin_id);
+        }
+
+        Ok(updated > 0)
+    }
+
+    /// Ganti semua recovery codes dengan yang baru.
+    pub fn regenerate_recovery_codes(&self, admin_id: ID) -> Result<Vec<String>> {
+        use crate::schema::admin_two_factors::dsl;
+
+        let (codes, hashes) = generate_recovery_codes();
+
+        let updated = diesel::update(
+            dsl::admin_two_factors.filter(dsl::admin_id.eq(admin_id).and(dsl::enabled.eq(true))),
+        )
+        .set(dsl::recovery_codes.eq(hashes))
+        .execute(self.db)?;
+
+        if updated == 0 {
+            return Err(param_err!("Two factor authentication not enabled"));
+        }
+
+        Ok(codes)
+    }
+
+    /// Nonaktifkan 2FA milik admin.
+    pub fn disable(&self, admin_id: ID) -> Result<()> {
+        use crate::schema::admin_two_factors::dsl;
+
+        diesel::delete(dsl::admin_two_factors.filter(dsl::admin_id.eq(admin_id))).execute(self.db)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_from_config() {
+        let policy = |value: &str| {
+            let mut config = crate::config::Config::default();
+            config
+                .apply_vars(|key| match key {
+                    "ADMIN_2FA_REQUIRED_ACCESSES" => Some(value.to_string()),
+                    _ => None,
+                })
+                .unwrap();
+            TwoFactorPolicy::from_config(&config.two_factor)
+        };
+        assert_eq!(policy(""), TwoFactorPolicy::default());
+        assert_eq!(
+            policy("satgas, access.update_village_data,,"),
+            TwoFactorPolicy {
+                all: false,
+                accesses: vec!["satgas".to_string(), "update_village_data".to_string()],
+            }
+        );
+        assert!(policy("*").all);
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let (codes, hashes) = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(codes[0].len(), 11);
+        assert_eq!(
+            hash_recovery_code(&codes[0].to_uppercase().replace("-", " ")),
+            hashes[0]
+        );
+    }
+}
@@ -5,21 +5,29 @@ use diesel::prelude::*;
 use validator::Validate;
 
 use crate::{
+    admin_two_factor_dao::TwoFactorPolicy,
     api,
     api::types::*,
     api::{
-        error::{param_error, unauthorized},
-        ApiResult,
+        error::{coded_error, param_error, throttle, unauthorized},
+        ApiResult, AppState, Error as ApiError,
     },
-    dao::AdminDao,
-    error::Error,
+    auth::PasswordPolicy,
+    crypto::PasshashScheme,
+    dao::{AdminDao, AdminTwoFactorDao, Logs},
+    error::{Error, ErrorCode},
+    mailer::{self, AccountChange, Message},
     models,
     prelude::*,
-    ID,
+    rate_limit::{self, Endpoint, Identity, LockedIdentity, Subject},
+    totp, ID,
 };
 
+/// Header berisi kode TOTP atau recovery code untuk step-up verification.
+pub const TWO_FACTOR_HEADER: &str = "X-Totp-Code";
+
 /// New Admin query
-#[derive(Serialize, Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct NewAdmin {
     #[validate(length(
         min = 3,
@@ -48,7 +56,7 @@ pub struct NewAdmin {
 }
 
 /// Activate Admin query
-#[derive(Serialize, Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct ActivateAdmin {
     #[validate(phone(message = "Invalid phone number: {}"))]
     pub phone_num: String,
@@ -58,25 +66,111 @@ pub struct ActivateAdmin {
     pub token: String,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct UpdatePassword {
     pub id: ID,
     pub password: String,
     pub password_confm: String,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct UpdateAccesses {
     pub id: ID,
     pub accesses: Vec<String>,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct UpdateMeta {
     pub id: ID,
     pub meta: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct TwoFactorCode {
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct UnlockIdentity {
+    pub endpoint: Endpoint,
+    pub subject: Subject,
+    pub value: String,
+}
+
+/// Status autentikasi dua langkah milik current admin.
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    /// Apakah admin wajib mengaktifkan 2FA menurut kebijakan.
+    pub required: bool,
+}
+
+/// Secret untuk didaftarkan ke aplikasi authenticator.
+#[derive(Serialize, Deserialize, PartialEq, ApiSchema)]
+pub struct TwoFactorEnrollment {
+    pub secret: String,
+    /// URI `otpauth://` untuk ditampilkan sebagai QR code.
+    pub uri: String,
+}
+
+/// Step-up verification untuk endpoint sensitif,
+/// admin yang telah mengaktifkan 2FA wajib menyertakan kode di header `X-Totp-Code`.
+/// Kode yang salah dihitung oleh rate limiter per akun admin.
+pub(crate) fn require_two_factor(
+    state: &AppState,
+    conn: &PgConnection,
+    admin: &models::Admin,
+    req: &api::HttpRequest,
+) -> api::Result<()> {
+    let dao = AdminTwoFactorDao::new(conn);
+
+    if !dao.is_enabled(admin.id)? {
+        if TwoFactorPolicy::from_config(&state.config().two_factor).requires(admin) {
+            return coded_error(ErrorCode::TwoFactorSetupRequired);
+        }
+        return Ok(());
+    }
+
+    let code = req
+        .headers()
+        .get(TWO_FACTOR_HEADER)
+        .and_then(|a| a.to_str().ok())
+        .unwrap_or("");
+
+    if code.is_empty() {
+        return coded_error(ErrorCode::TwoFactorRequired);
+    }
+
+    let identities = [Identity::account(&admin.email)];
+    throttle(Endpoint::AdminTwoFactor, &identities)?;
+
+    if !dao.verify(admin.id, code)? {
+        warn!("admin `{}` failed two factor verification", admin.id);
+        rate_limit::record(&state.config().rate_limit, Endpoint::AdminTwoFactor, &identities);
+        return Err(ApiError::Unauthorized);
+    }
+
+    rate_limit::reset(Endpoint::AdminTwoFactor, &identities[0]);
+
+    Ok(())
+}
+
+/// Admin yang wajib menggunakan 2FA menurut kebijakan namun belum mengaktifkannya hanya boleh
+/// mengakses endpoint enrollment (endpoint dengan atribut `two_factor_setup`),
+/// dipanggil oleh guard `#[api_endpoint]` untuk accessor admin.
+pub(crate) fn require_two_factor_setup(state: &AppState, admin: &models::Admin) -> api::Result<()> {
+    if !TwoFactorPolicy::from_config(&state.config().two_factor).requires(admin) {
+        return Ok(());
+    }
+
+    let conn = state.db()?;
+    if !AdminTwoFactorDao::new(&conn).is_enabled(admin.id)? {
+        return coded_error(ErrorCode::TwoFactorSetupRequired);
+    }
+
+    Ok(())
+}
+
 /// Holder untuk implementasi API endpoint publik untuk admin.
 pub struct PublicApi;
 
@@ -87,13 +181,16 @@ impl PublicApi {
     pub fn add_admin(query: NewAdmin) -> ApiResult<models::Admin> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AdminDao::new(&conn);
 
         if query.password != query.confirm_password {
-            return param_error("Confirmation password didn't match");
+            return coded_error(ErrorCode::PasswordMismatch);
         }
 
+        PasswordPolicy::from_config(&state.config().password)
+            .check(&query.password, &[&query.name, &query.email])?;
+
         if current_admin.id != 1 {
             return unauthorized();
         }
@@ -107,27 +204,39 @@ impl PublicApi {
         labels.sort();
         labels.dedup();
 
-        dao.create(
+        let admin = dao.create(
             &query.name,
             &query.email,
             &query.phone_num,
             &query.password,
             &labels,
-        )
-        .map_err(From::from)
-        .map(ApiResult::success)
+            PasshashScheme::current(&state.config().password),
+        )?;
+
+        mailer::send(
+            &admin.email,
+            &Message::AdminInvitation {
+                name: &admin.name,
+                email: &admin.email,
+                inviter: &current_admin.name,
+            },
+        );
+
+        Ok(ApiResult::success(admin))
     }
 
     /// Update accesses.
     #[api_endpoint(path = "/update_accesses", auth = "required", mutable, accessor = "admin")]
     pub fn update_accesses(query: UpdateAccesses) -> ApiResult<()> {
         use crate::schema::admins::{self, dsl};
-        let conn = state.db();
+        let conn = state.db()?;
 
         if current_admin.id != 1 {
             return unauthorized();
         }
 
+        require_two_factor(state, &conn, &current_admin, req)?;
+
         let admin = AdminDao::new(&conn).get_by_id(query.id)?;
 
         let mut meta = admin.meta.clone();
@@ -143,6 +252,14 @@ impl PublicApi {
             .execute(&conn)
             .map_err(Error::from)?;
 
+        mailer::send(
+            &admin.email,
+            &Message::AccountChanged {
+                name: &admin.name,
+                change: AccountChange::AccessesChanged,
+            },
+        );
+
         Ok(ApiResult::success(()))
     }
 
@@ -150,7 +267,7 @@ impl PublicApi {
     #[api_endpoint(path = "/update_meta", auth = "required", mutable, accessor = "admin")]
     pub fn update_meta(query: UpdateMeta) -> ApiResult<()> {
         use crate::schema::admins::{self, dsl};
-        let conn = state.db();
+        let conn = state.db()?;
 
         if current_admin.id != 1 {
             return unauthorized();
@@ -171,7 +288,7 @@ impl PublicApi {
     pub fn list_admin(query: QueryEntries) -> ApiResult<EntriesResult<Admin>> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AdminDao::new(&conn);
 
         if current_admin.id > 1 {
@@ -193,7 +310,7 @@ impl PublicApi {
     /// Mendapatkan jumlah admin secara keseluruhan.
     #[api_endpoint(path = "/count", auth = "required")]
     pub fn admin_count(state: &AppState, query: ()) -> ApiResult<i64> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AdminDao::new(&conn);
 
         dao.count().map(ApiResult::success).map_err(From::from)
@@ -202,7 +319,7 @@ impl PublicApi {
     /// Mendapatkan data admin berdasarkan ID.
     #[api_endpoint(path = "/detail", auth = "required", accessor = "admin")]
     pub fn admin_detail(query: IdQuery) -> ApiResult<Admin> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AdminDao::new(&conn);
 
         dao.get_by_id(query.id)
@@ -213,9 +330,11 @@ impl PublicApi {
     /// Delete admin.
     #[api_endpoint(path = "/delete", auth = "required", mutable = "true")]
     pub fn delete_admin(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AdminDao::new(&conn);
 
+        require_two_factor(state, &conn, &current_admin, req)?;
+
         dao.delete_by_id(query.id)?;
 
         Ok(ApiResult::success(()))
@@ -232,11 +351,20 @@ impl PublicApi {
     pub fn reset_password(query: ResetPassword) -> ApiResult<()> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AdminDao::new(&conn);
         let admin = dao.get_by_email(&query.email)?;
 
-        dao.reset_password(admin.id, admin.name, admin.email)?;
+        let token = dao.reset_password(admin.id)?;
+
+        mailer::send(
+            &admin.email,
+            &Message::ResetPassword {
+                name: &admin.name,
+                email: &admin.email,
+                token: &token,
+            },
+        );
 
         Ok(ApiResult::success(()))
     }
@@ -246,7 +374,7 @@ impl PublicApi {
     pub fn reset_password_verify(query: ResetPassword) -> ApiResult<()> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AdminDao::new(&conn);
         let admin = dao.get_by_email(&query.email)?;
 
@@ -264,15 +392,29 @@ impl PublicApi {
     pub fn set_new_password(query: ResetPassword) -> ApiResult<()> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AdminDao::new(&conn);
         let admin = dao.get_by_email(&query.email)?;
 
         match (query.token, query.password) {
             (Some(token), Some(password)) => {
                 dao.verify_reset_password(admin.id, &token)?;
-                dao.set_password(admin.id, &password)?;
+                PasswordPolicy::from_config(&state.config().password)
+                    .check(&password, &[&admin.name, &admin.email])?;
+                dao.set_password(
+                    admin.id,
+                    &password,
+                    PasshashScheme::current(&state.config().password),
+                )?;
                 dao.remove_reset_password(admin.id)?;
+
+                mailer::send(
+                    &admin.email,
+                    &Message::AccountChanged {
+                        name: &admin.name,
+                        change: AccountChange::PasswordChanged,
+                    },
+                );
             }
             _ => param_error("Parameter token or password can't be empty.")?,
         }
@@ -283,7 +425,7 @@ impl PublicApi {
     /// Update password.
     #[api_endpoint(path = "/update_password", auth = "required", mutable, accessor = "admin")]
     pub fn update_password(query: UpdatePassword) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
 
         let dao = AdminDao::new(&conn);
 
@@ -291,7 +433,167 @@ impl PublicApi {
             return unauthorized();
         }
 
-        dao.set_password(query.id, &query.password)?;
+        if query.password != query.password_confm {
+            return coded_error(ErrorCode::PasswordMismatch);
+        }
+
+        let admin = dao.get_by_id(query.id)?;
+
+        PasswordPolicy::from_config(&state.config().password)
+            .check(&query.password, &[&admin.name, &admin.email])?;
+
+        dao.set_password(
+            admin.id,
+            &query.password,
+            PasshashScheme::current(&state.config().password),
+        )?;
+
+        mailer::send(
+            &admin.email,
+            &Message::AccountChanged {
+                name: &admin.name,
+                change: AccountChange::PasswordChanged,
+            },
+        );
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Mendapatkan status autentikasi dua langkah current admin.
+    #[api_endpoint(path = "/two_factor/status", auth = "required", two_factor_setup)]
+    pub fn two_factor_status(query: ()) -> ApiResult<TwoFactorStatus> {
+        let conn = state.db()?;
+
+        Ok(ApiResult::success(TwoFactorStatus {
+            enabled: AdminTwoFactorDao::new(&conn).is_enabled(current_admin.id)?,
+            required: TwoFactorPolicy::from_config(&state.config().two_factor).requires(&current_admin),
+        }))
+    }
+
+    /// Mulai enrollment autentikasi dua langkah,
+    /// 2FA baru aktif setelah dikonfirmasi via `/two_factor/confirm`.
+    #[api_endpoint(path = "/two_factor/enroll", auth = "required", mutable, two_factor_setup)]
+    pub fn enroll_two_factor(query: ()) -> ApiResult<TwoFactorEnrollment> {
+        let conn = state.db()?;
+
+        let secret = AdminTwoFactorDao::new(&conn).enroll(current_admin.id)?;
+        let uri = totp::provisioning_uri(&secret, &current_admin.email, "Pandemia");
+
+        Ok(ApiResult::success(TwoFactorEnrollment { secret, uri }))
+    }
+
+    /// Konfirmasi enrollment menggunakan kode dari aplikasi authenticator,
+    /// mengembalikan recovery codes yang hanya ditampilkan sekali.
+    #[api_endpoint(path = "/two_factor/confirm", auth = "required", mutable, two_factor_setup)]
+    pub fn confirm_two_factor(query: TwoFactorCode) -> ApiResult<Vec<String>> {
+        let conn = state.db()?;
+
+        let codes = AdminTwoFactorDao::new(&conn).confirm(current_admin.id, &query.code)?;
+
+        Logs::new(&conn).write(
+            &format!("{} enabled two factor authentication", current_admin.name),
+            current_admin.id,
+        );
+
+        Ok(ApiResult::success(codes))
+    }
+
+    /// Generate ulang recovery codes, recovery codes lama tidak berlaku lagi.
+    #[api_endpoint(path = "/two_factor/recovery_codes", auth = "required", mutable)]
+    pub fn regenerate_recovery_codes(query: ()) -> ApiResult<Vec<String>> {
+        let conn = state.db()?;
+
+        require_two_factor(state, &conn, &current_admin, req)?;
+
+        let codes = AdminTwoFactorDao::new(&conn).regenerate_recovery_codes(current_admin.id)?;
+
+        Logs::new(&conn).write(
+            &format!("{} regenerated two factor recovery codes", current_admin.name),
+            current_admin.id,
+        );
+
+        Ok(ApiResult::success(codes))
+    }
+
+    /// Nonaktifkan autentikasi dua langkah current admin,
+    /// tidak bisa dilakukan apabila 2FA diwajibkan oleh kebijakan.
+    #[api_endpoint(path = "/two_factor/disable", auth = "required", mutable)]
+    pub fn disable_two_factor(query: ()) -> ApiResult<()> {
+        let conn = state.db()?;
+
+        if TwoFactorPolicy::from_config(&state.config().two_factor).requires(&current_admin) {
+            return param_error("Two factor authentication is required for this account");
+        }
+
+        require_two_factor(state, &conn, &current_admin, req)?;
+
+        AdminTwoFactorDao::new(&conn).disable(current_admin.id)?;
+
+        Logs::new(&conn).write(
+            &format!("{} disabled two factor authentication", current_admin.name),
+            current_admin.id,
+        );
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Reset autentikasi dua langkah milik admin lain yang kehilangan perangkat
+    /// dan recovery codes-nya, hanya bisa dilakukan oleh super admin.
+    #[api_endpoint(path = "/two_factor/reset", auth = "required", mutable)]
+    pub fn reset_two_factor(query: IdQuery) -> ApiResult<()> {
+        let conn = state.db()?;
+
+        if current_admin.id != 1 {
+            return unauthorized();
+        }
+
+        require_two_factor(state, &conn, &current_admin, req)?;
+
+        let admin = AdminDao::new(&conn).get_by_id(query.id)?;
+
+        AdminTwoFactorDao::new(&conn).disable(admin.id)?;
+
+        Logs::new(&conn).write(
+            &format!(
+                "{} reset two factor authentication of {}",
+                current_admin.name, admin.name
+            ),
+            current_admin.id,
+        );
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Daftar identitas (IP, akun, device) yang sedang dikunci oleh rate limiter.
+    #[api_endpoint(path = "/rate_limit/locked", auth = "required")]
+    pub fn locked_identities(query: ()) -> ApiResult<Vec<LockedIdentity>> {
+        if current_admin.id != 1 {
+            return unauthorized();
+        }
+
+        Ok(ApiResult::success(rate_limit::locked()))
+    }
+
+    /// Buka kunci identitas yang dikunci oleh rate limiter.
+    #[api_endpoint(path = "/rate_limit/unlock", auth = "required", mutable)]
+    pub fn unlock_identity(query: UnlockIdentity) -> ApiResult<()> {
+        let conn = state.db()?;
+
+        if current_admin.id != 1 {
+            return unauthorized();
+        }
+
+        if !rate_limit::unlock(query.endpoint, &Identity::new(query.subject, &query.value)) {
+            return coded_error(ErrorCode::DatabaseRecordNotFoundError);
+        }
+
+        Logs::new(&conn).write(
+            &format!(
+                "{} unlocked {:?} `{}` on {}",
+                current_admin.name, query.subject, query.value, query.endpoint
+            ),
+            current_admin.id,
+        );
 
         Ok(ApiResult::success(()))
     }
@@ -32,7 +32,7 @@ use crate::{
 
 use std::collections::HashMap;
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct AreaQuery {
     pub province: String,
     pub city: String,
@@ -43,7 +43,7 @@ pub struct AreaQuery {
     pub limit: i64,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct QueryReportNotes {
     pub province: String,
     pub city: String,
@@ -54,13 +54,13 @@ pub struct QueryReportNotes {
     pub limit: i64,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct GetTotal {
     pub province: String,
     pub city: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct TotalResult {
     pub odp: i32,
     pub pdp: i32,
@@ -83,7 +83,7 @@ struct Entry {
     deaths: i64,
 }
 
-#[derive(QueryableByName, Serialize, Debug)]
+#[derive(QueryableByName, Serialize, Debug, ApiSchema)]
 pub struct TravelerData {
     #[sql_type = "Text"]
     pub loc_path: String,
@@ -101,7 +101,7 @@ pub struct TravelerData {
     pub pptb: i64,
 }
 
-#[derive(QueryableByName, Serialize, Debug)]
+#[derive(QueryableByName, Serialize, Debug, ApiSchema)]
 pub struct GeneralData {
     #[sql_type = "Text"]
     pub loc_path: String,
@@ -134,7 +134,7 @@ impl PublicApi {
     #[api_endpoint(path = "/area", auth = "none")]
     pub fn search_area(query: AreaQuery) -> ApiResult<EntriesResult<VillageData>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
 
         // // get area code from province & city
         // let area_code: String = get_area_code(&normalize(&query.province), &normalize(&query.city), &conn)?;
@@ -155,7 +155,7 @@ impl PublicApi {
     #[api_endpoint(path = "/report_notes", auth = "none")]
     pub fn list_report_notes(query: QueryReportNotes) -> ApiResult<EntriesResult<ReportNote>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = ReportNoteDao::new(&conn);
 
         // let area_code: String = get_area_code(&normalize(&query.province), &normalize(&query.city), &conn)?;
@@ -187,7 +187,7 @@ impl PublicApi {
     #[api_endpoint(path = "/total", auth = "none")]
     pub fn get_total_data(query: GetTotal) -> ApiResult<TotalResult> {
         use crate::schema::village_data::{self, dsl};
-        let conn = state.db();
+        let conn = state.db()?;
         // let area_code: String = get_area_code(&normalize(&query.province), &normalize(&query.city), &conn)?;
 
         // let city = CityDao::new(&conn)
@@ -227,7 +227,7 @@ impl PublicApi {
     #[api_endpoint(path = "/district_data", auth = "none")]
     pub fn get_districts_data(query: AreaQuery) -> ApiResult<EntriesResult<DistrictData>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = DistrictDataDao::new(&conn);
 
         let city = get_city(&query.province, &query.city, &conn)?;
@@ -247,7 +247,7 @@ impl PublicApi {
     #[api_endpoint(path = "/trend/general", auth = "none")]
     pub fn get_general_trend_data(query: AreaQuery) -> ApiResult<TrendData> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
 
         let city = get_city(&query.province, &query.city, &conn)?;
 
@@ -350,7 +350,7 @@ impl PublicApi {
     #[api_endpoint(path = "/trend/traveler", auth = "none")]
     pub fn get_traveler_trend_data(query: AreaQuery) -> ApiResult<TrendData> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
 
         let city = get_city(&query.province, &query.city, &conn)?;
 
@@ -432,7 +432,7 @@ impl PublicApi {
     #[api_endpoint(path = "/data/location_address", auth = "none")]
     pub fn get_location_address(query: ()) -> ApiResult<Vec<IdAddress>> {
         use crate::schema::cities::{self, dsl};
-        let conn = state.db();
+        let conn = state.db()?;
 
         let cities: Vec<(i64, String, String)> = {
             cities::table
@@ -483,7 +483,7 @@ impl PublicApi {
     pub fn get_district_data(query: AreaQuery) -> ApiResult<Vec<IdAddress>> {
         use crate::schema::districts::{self, dsl};
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = DistrictDao::new(&conn);
 
         let city = get_city(&query.province, &query.city, &conn)?;
@@ -520,7 +520,7 @@ impl PublicApi {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct IdAddress {
     pub id: i64,
     pub name: String,
@@ -529,13 +529,13 @@ pub struct IdAddress {
     pub path: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct Serie {
     pub name: String,
     pub data: Vec<i64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct TrendData {
     pub cats: Vec<String>,
     pub series: Vec<Serie>,
@@ -0,0 +1,113 @@
+//! Koleksi query yang digunakan untuk operasi pada rest API Boundary
+#![allow(missing_docs)]
+
+use actix_web::{HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use validator::Validate;
+
+use crate::{
+    api,
+    api::types::*,
+    api::{error::param_error, ApiResult, Error as ApiError, HttpRequest as ApiHttpRequest},
+    auth,
+    boundary_dao::ImportResult,
+    dao::BoundaryDao,
+    error::{Error, ErrorCode},
+    prelude::*,
+    types::LocKind,
+    ID,
+};
+
+use std::fs;
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct ResolveQuery {
+    #[validate(range(min = -90.0, max = 90.0))]
+    pub latitude: f64,
+    #[validate(range(min = -180.0, max = 180.0))]
+    pub longitude: f64,
+}
+
+/// Hasil resolusi koordinat menjadi wilayah administratif.
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct ResolvedLocation {
+    pub city_id: Option<ID>,
+    pub city: Option<String>,
+    pub province: Option<String>,
+    pub district_id: Option<ID>,
+    pub district: Option<String>,
+    pub village_id: Option<ID>,
+    pub village: Option<String>,
+    pub loc_path: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct ImportBoundary {
+    /// Jenis wilayah: `city`, `district`, atau `village`.
+    pub kind: String,
+    /// Path ke file GeoJSON (FeatureCollection) di server.
+    #[validate(length(min = 1, max = 1000))]
+    pub path: String,
+}
+
+/// Holder untuk implementasi API endpoint publik untuk Boundary.
+pub struct PublicApi;
+
+#[api_group("Boundary", "public", base = "/boundary/v1")]
+impl PublicApi {
+    /// Resolve titik latitude/longitude menjadi desa, kecamatan dan kota/kab
+    /// berdasarkan batas wilayah yang telah di-import.
+    #[api_endpoint(path = "/resolve", auth = "none")]
+    pub fn resolve(query: ResolveQuery) -> ApiResult<ResolvedLocation> {
+        query.validate()?;
+        let conn = state.db()?;
+
+        let area = BoundaryDao::new(&conn).resolve(query.latitude, query.longitude)?;
+
+        Ok(ApiResult::success(ResolvedLocation {
+            city_id: area.city.as_ref().map(|a| a.id),
+            city: area.city.as_ref().map(|a| a.name.to_owned()),
+            province: area.city.as_ref().map(|a| a.province.to_owned()),
+            district_id: area.district.as_ref().map(|a| a.id),
+            district: area.district.as_ref().map(|a| a.name.to_owned()),
+            village_id: area.village.as_ref().map(|a| a.id),
+            village: area.village.as_ref().map(|a| a.name.to_owned()),
+            loc_path: area.loc_path(),
+        }))
+    }
+}
+
+/// Holder untuk implementasi API endpoint privat untuk Boundary.
+pub struct PrivateApi;
+
+#[api_group("Boundary", "private", base = "/boundary/v1")]
+impl PrivateApi {
+    /// Import batas wilayah dari file GeoJSON.
+    #[api_endpoint(path = "/import", auth = "none", mutable)]
+    pub fn import_boundaries(query: ImportBoundary) -> ApiResult<ImportResult> {
+        query.validate()?;
+
+        let kind = match query.kind.as_str() {
+            "city" => LocKind::City,
+            "district" => LocKind::District,
+            "village" => LocKind::Village,
+            _ => return param_error("Invalid kind, expected city, district or village"),
+        };
+
+        let data: JsonValue = serde_json::from_str(&fs::read_to_string(&query.path)?)
+            .map_err(|e| ApiError::BadRequest(ErrorCode::SerializeDeserializeError as i32, e.to_string()))?;
+
+        let conn = state.db()?;
+        let result = BoundaryDao::new(&conn).import_geojson(kind, &data)?;
+
+        info!(
+            "boundaries imported from {}: {} imported, {} skipped",
+            query.path,
+            result.imported,
+            result.skipped.len()
+        );
+
+        Ok(ApiResult::success(result))
+    }
+}
@@ -16,11 +16,13 @@ use crate::{
     error::{Error, ErrorCode},
     models,
     prelude::*,
-    util, ID,
+    region_import, util, ID,
 };
 
+use std::fs;
+
 /// New City query
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct NewCity {
     pub name: String,
     pub province: String,
@@ -28,6 +30,14 @@ pub struct NewCity {
     pub area_code: String,
 }
 
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct ImportRegions {
+    /// Path ke file kode wilayah (CSV `kode,nama`) di server.
+    #[validate(length(min = 1, max = 1000))]
+    pub path: String,
+    pub dry_run: Option<bool>,
+}
+
 /// Holder untuk implementasi API endpoint publik untuk city.
 pub struct PublicApi;
 
@@ -36,7 +46,7 @@ impl PublicApi {
     /// Rest API endpoint untuk menambahkan city baru.
     #[api_endpoint(path = "/add", mutable, auth = "required")]
     pub fn add_city(query: NewCity) -> ApiResult<models::City> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = CityDao::new(&conn);
 
         if current_admin.has_access("update_city") {
@@ -58,7 +68,7 @@ impl PublicApi {
     // /// Mendapatkan daftar city
     // #[api_endpoint(path = "/list", auth = "required")]
     // pub fn list_city(query: QueryEntries) -> ApiResult<EntriesResult<models::City>> {
-    //     let conn = state.db();
+    //     let conn = state.db()?;
     //     let dao = CityDao::new(&conn);
 
     //     let entries = dao.get_citys(query.offset, query.limit)?;
@@ -71,7 +81,7 @@ impl PublicApi {
     #[api_endpoint(path = "/search", auth = "required")]
     pub fn search_city(query: QueryEntries) -> ApiResult<EntriesResult<models::City>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = CityDao::new(&conn);
 
         let sresult = dao.search(
@@ -89,7 +99,7 @@ impl PublicApi {
     /// Mendapatkan jumlah city secara keseluruhan.
     #[api_endpoint(path = "/count", auth = "required")]
     pub fn city_count(state: &AppState, query: ()) -> ApiResult<i64> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = CityDao::new(&conn);
 
         dao.count().map(ApiResult::success).map_err(From::from)
@@ -98,7 +108,7 @@ impl PublicApi {
     /// Mendapatkan data city berdasarkan ID.
     #[api_endpoint(path = "/detail", auth = "required")]
     pub fn city_detail(query: IdQuery) -> ApiResult<models::City> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = CityDao::new(&conn);
 
         dao.get_by_id(query.id)
@@ -109,7 +119,7 @@ impl PublicApi {
     /// Delete city.
     #[api_endpoint(path = "/delete", auth = "required", mutable = "true")]
     pub fn delete_city(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = CityDao::new(&conn);
 
         dao.delete_by_id(query.id)?;
@@ -122,4 +132,18 @@ impl PublicApi {
 pub struct PrivateApi;
 
 #[api_group("City", "private", base = "/city/v1")]
-impl PrivateApi {}
+impl PrivateApi {
+    /// Import kode wilayah resmi (Kemendagri) dari file lokal di server,
+    /// gunakan `dry_run` untuk melihat laporan perbedaan nama tanpa menyimpan perubahan.
+    #[api_endpoint(path = "/import_regions", auth = "none", mutable)]
+    pub fn import_regions(query: ImportRegions) -> ApiResult<region_import::ImportReport> {
+        query.validate()?;
+
+        let content = fs::read_to_string(&query.path)?;
+
+        let conn = state.db()?;
+        let report = region_import::import_regions(&content, query.dry_run.unwrap_or(false), &conn)?;
+
+        Ok(ApiResult::success(report))
+    }
+}
@@ -0,0 +1,47 @@
+//! Typed client untuk Pandemia REST API.
+//!
+//! Transport-nya ada di crate `pandemia-client` (`libs/pandemia-client-rs`), di sini
+//! method untuk setiap endpoint digenerasikan oleh `#[api_group]` dari signature
+//! fungsi endpoint-nya, sehingga tipe query dan result-nya selalu sama dengan
+//! yang digunakan oleh server. Contoh:
+//!
+//! ```rust,ignore
+//! use pandemia::api::client::{ApiClient, TypedClient};
+//!
+//! let client = ApiClient::new("http://localhost:8080", "http://localhost:9090");
+//! let info = client.public().system().info(&())?;
+//! ```
+//!
+//! Method endpoint hanya bisa dipanggil apabila tipe query-nya mengimplementasikan
+//! `Serialize` dan tipe result-nya mengimplementasikan `Deserialize`.
+
+pub use pandemia_client::{ApiClient, Error, Result};
+
+/// Akses ke API group typed dari [ApiClient].
+pub trait TypedClient {
+    /// Akses endpoint-endpoint public.
+    fn public(&self) -> PublicClient<'_>;
+
+    /// Akses endpoint-endpoint private.
+    fn private(&self) -> PrivateClient<'_>;
+}
+
+impl TypedClient for ApiClient {
+    fn public(&self) -> PublicClient<'_> {
+        PublicClient(self)
+    }
+
+    fn private(&self) -> PrivateClient<'_> {
+        PrivateClient(self)
+    }
+}
+
+/// Akses ke API group public, method untuk setiap group
+/// digenerasikan oleh `#[api_group]`, eg: `client.public().user()`.
+#[derive(Clone, Copy)]
+pub struct PublicClient<'a>(pub(crate) &'a ApiClient);
+
+/// Akses ke API group private, method untuk setiap group
+/// digenerasikan oleh `#[api_group]`, eg: `client.private().user()`.
+#[derive(Clone, Copy)]
+pub struct PrivateClient<'a>(pub(crate) &'a ApiClient);
@@ -20,7 +20,7 @@ use crate::{
 };
 
 /// New District query
-#[derive(Serialize, Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct NewDistrict {
     #[validate(length(max = 100))]
     pub name: String,
@@ -37,7 +37,7 @@ pub struct NewDistrict {
     pub longitude: Option<f64>,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct SearchDistrict {
     pub scope: Option<String>,
     pub query: Option<String>,
@@ -57,7 +57,7 @@ impl PublicApi {
     pub fn add_district(query: NewDistrict) -> ApiResult<models::District> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
 
         if !current_admin.has_access("districts") {
             return unauthorized();
@@ -91,7 +91,7 @@ impl PublicApi {
     /// Mendapatkan daftar district
     #[api_endpoint(path = "/list", auth = "required", accessor = "admin")]
     pub fn list_district(query: QueryEntries) -> ApiResult<EntriesResult<District>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = DistrictDao::new(&conn);
 
         let entries = dao.get_districts(query.offset, query.limit)?;
@@ -107,7 +107,7 @@ impl PublicApi {
     #[api_endpoint(path = "/search", auth = "required", accessor = "admin")]
     pub fn search_districts(query: SearchDistrict) -> ApiResult<EntriesResult<District>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = DistrictDao::new(&conn);
 
         let (province, city) = match query.scope {
@@ -149,7 +149,7 @@ impl PublicApi {
     /// Mendapatkan jumlah district secara keseluruhan.
     #[api_endpoint(path = "/count", auth = "required")]
     pub fn district_count(state: &AppState, query: ()) -> ApiResult<i64> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = DistrictDao::new(&conn);
 
         dao.count().map(ApiResult::success).map_err(From::from)
@@ -158,7 +158,7 @@ impl PublicApi {
     /// Mendapatkan data district berdasarkan ID.
     #[api_endpoint(path = "/detail", auth = "required")]
     pub fn district_detail(query: IdQuery) -> ApiResult<models::District> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = DistrictDao::new(&conn);
 
         dao.get_by_id(query.id)
@@ -169,7 +169,7 @@ impl PublicApi {
     /// Delete district.
     #[api_endpoint(path = "/delete", auth = "required", mutable = "true")]
     pub fn delete_district(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = DistrictDao::new(&conn);
 
         dao.delete_by_id(query.id)?;
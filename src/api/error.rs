@@ -4,12 +4,21 @@
 
 //! The set of errors for the API module.
 
+use actix_web::http::header;
 use validator::{ValidationErrors, ValidationErrorsKind};
 
-use crate::{api::ApiResult, error::Error as PandemiaError, error::ErrorCode};
+use crate::{api::ApiResult, error::Error as PandemiaError, error::ErrorCode, i18n::Lang, rate_limit};
+
+pub use pandemia_types::FieldError;
 
 use failure;
-use std::io;
+use std::{collections::BTreeMap, io};
+
+/// Kode error untuk [Error::Expired].
+///
+/// Nilai ini sama dengan [ErrorCode::SerializeDeserializeError] karena sudah digunakan
+/// oleh client sejak sebelum katalog error ada, sehingga tidak masuk katalog [ErrorCode].
+pub const EXPIRED_CODE: i32 = 4001;
 
 /// List of possible API errors.
 #[derive(Fail, Debug)]
@@ -42,7 +51,7 @@ pub enum Error {
     AlreadyExists,
 
     /// Error yang muncul ketika suatu object telah habis masa berlakunya
-    /// pada saat transaksi misalnya.
+    /// pada saat transaksi misalnya, kodenya [EXPIRED_CODE].
     #[fail(display = "{} expired", _0)]
     Expired(&'static str),
 
@@ -54,6 +63,157 @@ pub enum Error {
     /// authentication credentials.
     #[fail(display = "Unauthorized")]
     Unauthorized,
+
+    /// Error yang terdaftar di katalog error, deskripsinya diambil dari [ErrorCode::message]
+    /// sesuai bahasa client, argumen digunakan untuk mengisi `{}` pada deskripsi.
+    #[fail(display = "error code {:?}", _0)]
+    Catalogue(ErrorCode, Vec<String>),
+
+    /// Satu atau lebih parameter tidak lolos validasi.
+    #[fail(display = "Invalid parameter: {:?}", _0)]
+    InvalidFields(Vec<InvalidField>),
+}
+
+/// Parameter yang tidak lolos validasi,
+/// deskripsinya dibuat ketika response dikirim sesuai bahasa client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidField {
+    /// Nama field, untuk nested struct dipisahkan titik, eg: `location.lat`.
+    pub field: String,
+    /// Kode validasi, eg: `length`, `range`, `email`, `required`.
+    pub code: String,
+    /// Parameter validasi, eg: `min`, `max`, `value`.
+    pub params: BTreeMap<String, String>,
+    /// Pesan custom dari atribut `#[validate(.., message = "..")]`.
+    pub message: Option<String>,
+}
+
+impl InvalidField {
+    /// Buat invalid field baru.
+    pub fn new(field: &str, code: &str) -> Self {
+        InvalidField {
+            field: field.to_owned(),
+            code: code.to_owned(),
+            params: BTreeMap::new(),
+            message: None,
+        }
+    }
+
+    /// Deskripsi kesalahan dalam bahasa `lang`.
+    pub fn describe(&self, lang: Lang) -> String {
+        let f = &self.field;
+        let min = self.params.get("min");
+        let max = self.params.get("max");
+
+        if let Some(msg) = &self.message {
+            return msg.replace("{}", self.params.get("value").map(|a| a.as_str()).unwrap_or(""));
+        }
+
+        match (self.code.as_str(), min, max, lang) {
+            ("length", Some(min), Some(max), Lang::Id) => {
+                format!("`{}` harus terdiri dari {} sampai {} karakter", f, min, max)
+            }
+            ("length", Some(min), Some(max), Lang::En) => {
+                format!("`{}` must be between {} and {} characters", f, min, max)
+            }
+            ("length", Some(min), None, Lang::Id) => format!("`{}` minimal {} karakter", f, min),
+            ("length", Some(min), None, Lang::En) => format!("`{}` must be at least {} characters", f, min),
+            ("length", None, Some(max), Lang::Id) => format!("`{}` maksimal {} karakter", f, max),
+            ("length", None, Some(max), Lang::En) => format!("`{}` must be at most {} characters", f, max),
+            ("range", Some(min), Some(max), Lang::Id) => {
+                format!("`{}` harus bernilai antara {} dan {}", f, min, max)
+            }
+            ("range", Some(min), Some(max), Lang::En) => {
+                format!("`{}` must be between {} and {}", f, min, max)
+            }
+            ("range", Some(min), None, Lang::Id) => format!("`{}` tidak boleh kurang dari {}", f, min),
+            ("range", Some(min), None, Lang::En) => format!("`{}` must not be less than {}", f, min),
+            ("range", None, Some(max), Lang::Id) => format!("`{}` tidak boleh lebih dari {}", f, max),
+            ("range", None, Some(max), Lang::En) => format!("`{}` must not be greater than {}", f, max),
+            ("email", _, _, Lang::Id) => format!("`{}` bukan alamat email yang valid", f),
+            ("email", _, _, Lang::En) => format!("`{}` is not a valid email address", f),
+            ("phone", _, _, Lang::Id) => format!("`{}` bukan nomor telepon yang valid", f),
+            ("phone", _, _, Lang::En) => format!("`{}` is not a valid phone number", f),
+            ("required", _, _, Lang::Id) => format!("Parameter `{}` tidak ada", f),
+            ("required", _, _, Lang::En) => format!("No `{}` parameter", f),
+            (_, _, _, Lang::Id) => format!("`{}` tidak valid", f),
+            (_, _, _, Lang::En) => format!("`{}` is invalid", f),
+        }
+    }
+}
+
+impl Error {
+    /// Kode error yang dikirim ke client.
+    pub fn code(&self) -> i32 {
+        match self {
+            Error::Io(_) => ErrorCode::UnknownError as i32,
+            Error::BadRequest(code, _)
+            | Error::NotFound(code, _)
+            | Error::InternalError(code, _)
+            | Error::InvalidParameter(code, _)
+            | Error::CustomError(code, _) => *code,
+            Error::AlreadyExists => ErrorCode::AlreadyExists as i32,
+            Error::Expired(_) => EXPIRED_CODE,
+            Error::Unauthorized => ErrorCode::Unauthorized as i32,
+            Error::Catalogue(code, _) => *code as i32,
+            Error::InvalidFields(_) => ErrorCode::InvalidParameter as i32,
+        }
+    }
+
+    /// Deskripsi error dalam bahasa `lang`,
+    /// error dengan deskripsi custom dikembalikan apa adanya.
+    pub fn describe(&self, lang: Lang) -> String {
+        match self {
+            Error::BadRequest(_, d)
+            | Error::NotFound(_, d)
+            | Error::InvalidParameter(_, d)
+            | Error::CustomError(_, d) => d.to_owned(),
+            Error::InternalError(_, err) => err.to_string(),
+            Error::Io(err) => err.to_string(),
+            Error::AlreadyExists => ErrorCode::AlreadyExists.describe::<&str>(lang, &[]),
+            Error::Expired(d) => match lang {
+                Lang::Id => format!("{} telah kadaluarsa", d),
+                Lang::En => format!("{} expired", d),
+            },
+            Error::Unauthorized => ErrorCode::Unauthorized.describe::<&str>(lang, &[]),
+            Error::Catalogue(code, args) => code.describe(lang, args),
+            Error::InvalidFields(fields) => fields
+                .first()
+                .map(|f| f.describe(lang))
+                .unwrap_or_else(|| ErrorCode::InvalidParameter.describe::<&str>(lang, &[])),
+        }
+    }
+
+    /// Buat response error dengan deskripsi dalam bahasa `lang`.
+    pub fn to_response(&self, lang: Lang) -> HttpResponse {
+        let mut result = ApiResult::error(self.code(), self.describe(lang));
+
+        if let Error::InvalidFields(fields) = self {
+            result = result.with_fields(
+                fields
+                    .iter()
+                    .map(|f| FieldError {
+                        field: f.field.to_owned(),
+                        code: f.code.to_owned(),
+                        message: f.describe(lang),
+                    })
+                    .collect(),
+            );
+        }
+
+        let mut resp = match self {
+            Error::Io(_) => HttpResponse::InternalServerError(),
+            Error::Catalogue(ErrorCode::ServiceBusy, _) => HttpResponse::ServiceUnavailable(),
+            _ => HttpResponse::Ok(),
+        };
+        resp.header(header::CONTENT_LANGUAGE, lang.code()).json(result)
+    }
+}
+
+impl From<ErrorCode> for Error {
+    fn from(code: ErrorCode) -> Self {
+        Error::Catalogue(code, vec![])
+    }
 }
 
 impl From<io::Error> for Error {
@@ -79,27 +239,49 @@ impl From<hex::FromHexError> for Error {
 
 impl From<validator::ValidationErrors> for Error {
     fn from(e: validator::ValidationErrors) -> Self {
-        // dbg!(&e);
-        for (k, v) in e.errors() {
-            // dbg!(&v);
-            match v {
-                ValidationErrorsKind::Field(fields) => {
-                    let field = fields.last().expect("Empty error fields");
-                    let param_value = field
-                        .params
-                        .get("value")
-                        .map(|a| format!("{}", a))
-                        .unwrap_or("".to_string());
-                    let message = match &field.message {
-                        Some(msg) => msg.to_string().replace("{}", &param_value),
-                        _ => format!("Invalid {}: {}", field.code, param_value),
-                    };
-                    return Error::InvalidParameter(ErrorCode::InvalidParameter as i32, message);
+        let mut fields = vec![];
+        collect_invalid_fields("", &e, &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+        Error::InvalidFields(fields)
+    }
+}
+
+fn collect_invalid_fields(prefix: &str, errors: &ValidationErrors, out: &mut Vec<InvalidField>) {
+    for (name, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match kind {
+            ValidationErrorsKind::Field(errs) => {
+                for err in errs {
+                    let mut field = InvalidField::new(&path, &err.code);
+                    field.message = err.message.as_ref().map(|m| m.to_string());
+                    for (k, v) in &err.params {
+                        // batas range disimpan sebagai float oleh validator, eg: `1.0`
+                        let v = match v {
+                            serde_json::Value::String(s) => s.to_owned(),
+                            serde_json::Value::Number(n) => match n.as_f64() {
+                                Some(f) if f.fract() == 0.0 && !n.is_u64() && !n.is_i64() => {
+                                    format!("{}", f as i64)
+                                }
+                                _ => n.to_string(),
+                            },
+                            v => v.to_string(),
+                        };
+                        field.params.insert(k.to_string(), v);
+                    }
+                    out.push(field);
+                }
+            }
+            ValidationErrorsKind::Struct(errs) => collect_invalid_fields(&path, errs, out),
+            ValidationErrorsKind::List(items) => {
+                for (i, errs) in items {
+                    collect_invalid_fields(&format!("{}[{}]", path, i), errs, out);
                 }
-                _ => (),
             }
         }
-        Error::InvalidParameter(ErrorCode::InvalidParameter as i32, "Invalid parameter".to_owned())
     }
 }
 
@@ -114,13 +296,12 @@ impl From<PandemiaError> for Error {
                     DatabaseErrorKind::UniqueViolation | DatabaseErrorKind::ForeignKeyViolation => {
                         Error::AlreadyExists
                     }
-                    _ => Error::CustomError(ErrorCode::DatabaseError as i32, "Internal error".to_owned()),
+                    _ => ErrorCode::DatabaseError.into(),
                 }
             }
-            PandemiaError::Storage(diesel::result::Error::NotFound) => Error::NotFound(
-                ErrorCode::DatabaseRecordNotFoundError as i32,
-                "Not found".to_owned(),
-            ),
+            PandemiaError::Storage(diesel::result::Error::NotFound) => {
+                ErrorCode::DatabaseRecordNotFoundError.into()
+            }
             PandemiaError::Unauthorized => Error::Unauthorized,
             PandemiaError::InvalidParameter(msg) => {
                 Error::InvalidParameter(ErrorCode::InvalidParameter as i32, e.to_string())
@@ -131,34 +312,43 @@ impl From<PandemiaError> for Error {
     }
 }
 
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
 
 impl ResponseError for Error {
     fn error_response(&self) -> HttpResponse {
-        match self {
-            Error::BadRequest(code, err) => HttpResponse::Ok().json(ApiResult::error(*code, err.to_owned())),
-            Error::InternalError(code, err) => {
-                HttpResponse::Ok().json(ApiResult::error(*code, err.to_string()))
-            }
-            Error::Io(err) => HttpResponse::InternalServerError()
-                .json(ApiResult::error(ErrorCode::UnknownError as i32, err.to_string())),
-            Error::NotFound(code, err) => HttpResponse::Ok().json(ApiResult::error(*code, err.to_string())),
-            Error::InvalidParameter(code, d) => {
-                HttpResponse::Ok().json(ApiResult::error(*code, d.to_owned()))
-            }
-            Error::AlreadyExists => {
-                HttpResponse::Ok().json(ApiResult::error(304, "Already exists".to_owned()))
-            }
-            Error::CustomError(code, d) => HttpResponse::Ok().json(ApiResult::error(*code, d.to_owned())),
-            Error::Unauthorized => {
-                // HttpResponse::Unauthorized().finish()
-                HttpResponse::Ok().json(ApiResult::error(
-                    ErrorCode::Unauthorized as i32,
-                    "Unauthorized".to_owned(),
-                ))
-            }
-            Error::Expired(d) => HttpResponse::Ok().json(ApiResult::error(4001, format!("{}", self))),
-        }
+        self.to_response(Lang::default())
+    }
+}
+
+/// Error API beserta bahasa yang diminta oleh client.
+#[derive(Fail, Debug)]
+#[fail(display = "{}", _0)]
+pub struct Localized(#[cause] Error, Lang);
+
+impl ResponseError for Localized {
+    fn error_response(&self) -> HttpResponse {
+        self.0.to_response(self.1)
+    }
+}
+
+/// Ubah error menjadi actix error yang deskripsinya menggunakan bahasa `lang`.
+pub fn localize(err: Error, lang: Lang) -> actix_web::Error {
+    Localized(err, lang).into()
+}
+
+/// Bahasa yang diminta client melalui header `Accept-Language`,
+/// apabila tidak ada bahasa yang didukung menggunakan bahasa Indonesia.
+pub fn request_lang<S>(req: &HttpRequest<S>) -> Lang {
+    req.headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|a| a.to_str().ok())
+        .and_then(Lang::from_accept_language)
+        .unwrap_or_default()
+}
+
+impl From<diesel::result::Error> for Error {
+    fn from(e: diesel::result::Error) -> Self {
+        PandemiaError::from(e).into()
     }
 }
 
@@ -202,3 +392,80 @@ pub fn unauthorized<T>() -> Result<T, Error> {
     Err(Error::Unauthorized)?;
     panic!("Unhandled error");
 }
+
+/// Build error dari katalog error.
+pub fn coded_error<T>(code: ErrorCode) -> Result<T, Error> {
+    Err(code.into())
+}
+
+/// Tolak request apabila salah satu identitas sedang dikunci oleh rate limiter.
+pub fn throttle(endpoint: rate_limit::Endpoint, identities: &[rate_limit::Identity]) -> Result<(), Error> {
+    match rate_limit::check(endpoint, identities) {
+        Some(secs) => Err(Error::Catalogue(
+            ErrorCode::TooManyAttempts,
+            vec![secs.to_string()],
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Query {
+        #[validate(length(min = 2, max = 64))]
+        name: String,
+        #[validate(range(min = 1, max = 100))]
+        limit: i64,
+    }
+
+    #[test]
+    fn test_validation_field_errors() {
+        let query = Query {
+            name: "a".to_string(),
+            limit: 0,
+        };
+        let err: Error = query.validate().unwrap_err().into();
+
+        assert_eq!(err.code(), ErrorCode::InvalidParameter as i32);
+        match &err {
+            Error::InvalidFields(fields) => {
+                let names: Vec<&str> = fields.iter().map(|f| f.field.as_str()).collect();
+                assert_eq!(names, vec!["limit", "name"]);
+                assert_eq!(
+                    fields[1].describe(Lang::Id),
+                    "`name` harus terdiri dari 2 sampai 64 karakter"
+                );
+            }
+            _ => panic!("expected invalid fields error"),
+        }
+        assert_eq!(err.describe(Lang::En), "`limit` must be between 1 and 100");
+        assert_eq!(err.describe(Lang::Id), "`limit` harus bernilai antara 1 dan 100");
+    }
+
+    #[test]
+    fn test_catalogue_description() {
+        let err = Error::Catalogue(ErrorCode::TooManyAttempts, vec!["30".to_string()]);
+        assert_eq!(err.code(), 4007);
+        assert_eq!(
+            err.describe(Lang::En),
+            "Too many attempts, please try again in 30 seconds"
+        );
+        assert_eq!(
+            err.describe(Lang::Id),
+            "Terlalu banyak percobaan, silahkan coba lagi dalam 30 detik"
+        );
+        assert_eq!(Error::Unauthorized.describe(Lang::En), "Unauthorized");
+    }
+
+    #[test]
+    fn test_legacy_codes() {
+        // kode yang sudah digunakan client sebelum ada katalog error tidak boleh berubah
+        assert_eq!(Error::AlreadyExists.code(), 304);
+        assert_eq!(Error::Expired("token").code(), 4001);
+        assert_eq!(Error::Expired("token").describe(Lang::En), "token expired");
+    }
+}
@@ -0,0 +1,119 @@
+//! Thread pool untuk menjalankan handler endpoint di luar worker thread actix,
+//! handler berisi query Diesel yang blocking sehingga query yang lambat
+//! (eg: analytic) tidak membuat seluruh API macet.
+//!
+//! Ukuran pool diatur melalui konfigurasi `api.blocking_threads` & `api.max_pending`,
+//! request di atas `api.max_pending` langsung ditolak dengan error `ServiceBusy`.
+//!
+//! Handler dijalankan dengan trace context dari request (lihat [crate::trace])
+//! sehingga request id ikut tercatat di log & event yang dipicu oleh handler.
+
+use futures::future::{self, Either, Future};
+use futures_cpupool::{Builder, CpuPool};
+
+use crate::{
+    api::{Error, Result},
+    config::Config,
+    error::ErrorCode,
+    trace::{self, Context},
+    util,
+};
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+/// Blocking pool beserta batas handler yang boleh berjalan/mengantri.
+struct Executor {
+    pool: CpuPool,
+    max_pending: usize,
+}
+
+impl Executor {
+    fn new(config: &Config) -> Self {
+        Self {
+            pool: Builder::new()
+                .pool_size(config.api.blocking_threads(&config.database))
+                .name_prefix("api-blocking-")
+                .create(),
+            max_pending: config.api.max_pending(&config.database),
+        }
+    }
+}
+
+lazy_static! {
+    static ref CURRENT: RwLock<Option<Arc<Executor>>> = RwLock::new(None);
+}
+
+static PENDING: AtomicUsize = AtomicUsize::new(0);
+
+/// Buat blocking pool sesuai konfigurasi `config`, dipanggil ketika startup
+/// (atau oleh test yang membutuhkan konfigurasi khusus). Handler yang sedang
+/// berjalan di pool sebelumnya tetap diselesaikan.
+pub fn init(config: &Config) {
+    *CURRENT.write().unwrap() = Some(Arc::new(Executor::new(config)));
+}
+
+/// Blocking pool yang dibuat oleh [init].
+fn get() -> Arc<Executor> {
+    CURRENT
+        .read()
+        .unwrap()
+        .clone()
+        .expect("Executor not initialized, call executor::init first")
+}
+
+/// Menandai satu handler yang sedang berjalan/mengantri,
+/// dilepas ketika handler selesai (termasuk apabila panic).
+struct Pending;
+
+impl Pending {
+    fn acquire(max_pending: usize) -> Option<Pending> {
+        if PENDING.fetch_add(1, Ordering::SeqCst) >= max_pending {
+            PENDING.fetch_sub(1, Ordering::SeqCst);
+            None
+        } else {
+            Some(Pending)
+        }
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        PENDING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Jumlah handler yang sedang berjalan/mengantri.
+pub(crate) fn pending() -> usize {
+    PENDING.load(Ordering::SeqCst)
+}
+
+/// Tunggu sampai semua handler selesai, digunakan ketika shutdown.
+pub(crate) fn wait_idle(timeout: Duration) -> bool {
+    util::wait_until(timeout, || pending() == 0)
+}
+
+/// Jalankan `f` di blocking pool dengan trace context `context`,
+/// hasilnya bisa di-chain di worker thread actix.
+pub(crate) fn spawn<F, R>(context: Option<Context>, f: F) -> impl Future<Item = R, Error = Error>
+where
+    F: FnOnce() -> Result<R> + Send + 'static,
+    R: Send + 'static,
+{
+    let executor = get();
+    match Pending::acquire(executor.max_pending) {
+        Some(pending) => Either::A(executor.pool.spawn_fn(move || {
+            let _pending = pending;
+            trace::scope(context, f)
+        })),
+        None => {
+            warn!("blocking pool is full, rejecting request");
+            Either::B(future::err(Error::from(ErrorCode::ServiceBusy)))
+        }
+    }
+}
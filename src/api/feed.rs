@@ -25,7 +25,7 @@ use crate::{
 //     pub loc: String,
 // }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct FeedQuery {
     #[validate(length(min = 0, max = 500))]
     pub loc: Option<String>,
@@ -48,7 +48,7 @@ impl PublicApi {
     #[api_endpoint(path = "/query", auth = "none")]
     pub fn query_feed(query: FeedQuery) -> ApiResult<EntriesResult<models::Feed>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = FeedDao::new(&conn);
 
         let entries = dao.search(
@@ -66,7 +66,7 @@ impl PublicApi {
     /// Mendapatkan jumlah feed secara keseluruhan.
     #[api_endpoint(path = "/count", auth = "required")]
     pub fn feed_count(state: &AppState, query: ()) -> ApiResult<i64> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = FeedDao::new(&conn);
 
         dao.count().map(ApiResult::success).map_err(From::from)
@@ -75,7 +75,7 @@ impl PublicApi {
     /// Mendapatkan data feed berdasarkan ID.
     #[api_endpoint(path = "/detail", auth = "required")]
     pub fn feed_detail(query: IdQuery) -> ApiResult<models::Feed> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = FeedDao::new(&conn);
 
         dao.get_by_id(query.id)
@@ -92,7 +92,7 @@ impl PrivateApi {
     // /// Rest API endpoint untuk menambahkan feed baru.
     // #[api_endpoint(path = "/add", mutable, auth = "required")]
     // pub fn add_feed(query: NewFeed) -> ApiResult<models::Feed> {
-    //     let conn = state.db();
+    //     let conn = state.db()?;
     //     let dao = FeedDao::new(&conn);
 
     //     // @TODO(*): Add parameter checking here
@@ -105,7 +105,7 @@ impl PrivateApi {
     /// Delete feed.
     #[api_endpoint(path = "/delete", auth = "required", mutable = "true")]
     pub fn delete_feed(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = FeedDao::new(&conn);
 
         dao.delete_by_id(query.id)?;
@@ -0,0 +1,271 @@
+//! Koleksi query yang digunakan untuk operasi pada rest API undangan satgas.
+//!
+//! Undangan dibuat oleh admin kota/kab untuk satu orang, terikat pada desa dan peran tertentu
+//! (medis atau bukan), hanya bisa digunakan sekali dan memiliki batas waktu.
+#![allow(missing_docs)]
+
+use actix_web::{HttpRequest, HttpResponse};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    api,
+    api::types::*,
+    api::{
+        error::{coded_error, unauthorized},
+        user::{register_satgas, SatgasProfile},
+        ApiResult, Error as ApiError, HttpRequest as ApiHttpRequest,
+    },
+    auth,
+    config::MailConfig,
+    dao::{CityDao, Logs, SatgasInvitationDao, VillageDao},
+    error::ErrorCode,
+    mailer,
+    models::{self, SatgasInvitation},
+    prelude::*,
+    satgas_invitation_dao::InvitationState,
+    ID,
+};
+
+/// Masa berlaku default undangan dalam satuan hari.
+const DEFAULT_VALID_DAYS: i64 = 7;
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct AddInvitation {
+    pub village_id: ID,
+    pub is_medic: bool,
+    #[validate(length(min = 2, max = 64))]
+    pub invitee_name: String,
+    #[validate(length(max = 15))]
+    pub invitee_phone: Option<String>,
+    #[validate(range(min = 1, max = 30))]
+    pub valid_days: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct ListInvitations {
+    pub query: Option<String>,
+    /// Filter status: `pending`, `accepted`, `revoked`, `expired`, kosong untuk semua.
+    pub status: Option<String>,
+    #[validate(range(min = 0, max = 1_000_000))]
+    pub offset: i64,
+    #[validate(range(min = 1, max = 1000))]
+    pub limit: i64,
+}
+
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct TokenQuery {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct AcceptInvitation {
+    pub token: String,
+    #[validate(length(min = 2, max = 64))]
+    pub full_name: String,
+    #[validate(length(min = 2, max = 50))]
+    pub email: Option<String>,
+    #[validate(length(min = 2, max = 15))]
+    pub phone_num: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Undangan yang baru dibuat beserta link untuk dibagikan (bisa juga dijadikan QR code).
+#[derive(Serialize, Deserialize, PartialEq, ApiSchema)]
+pub struct CreatedInvitation {
+    pub invitation: SatgasInvitation,
+    /// Link undangan, hanya ada apabila `PANDEMIA_WEB_URL` diset.
+    pub link: Option<String>,
+}
+
+/// Informasi publik dari sebuah undangan, tidak berisi data pembuat maupun yang diundang.
+#[derive(Serialize, ApiSchema)]
+pub struct InvitationInfo {
+    pub village_id: ID,
+    pub village_name: String,
+    pub district_name: String,
+    pub city_name: String,
+    pub province: String,
+    pub is_medic: bool,
+    pub status: &'static str,
+    pub expiration: NaiveDateTime,
+}
+
+/// Check apakah admin boleh mengelola undangan untuk kota/kab tertentu.
+fn can_manage(admin: &models::Admin, city_id: ID) -> bool {
+    admin.is_super_admin() || (admin.has_access("satgas") && admin.get_city_id() == Some(city_id))
+}
+
+fn invitation_link(config: &MailConfig, token: &str) -> Option<String> {
+    mailer::web_url(config).map(|url| format!("{}/satgas/join?token={}", url, token))
+}
+
+/// Holder untuk implementasi API endpoint publik untuk undangan satgas.
+pub struct PublicApi;
+
+#[api_group("Invitation", "public", base = "/invitation/v1")]
+impl PublicApi {
+    /// Buat undangan satgas baru untuk satu desa.
+    #[api_endpoint(path = "/add", auth = "required", mutable, accessor = "admin")]
+    pub fn add_invitation(query: AddInvitation) -> ApiResult<CreatedInvitation> {
+        query.validate()?;
+        let conn = state.db()?;
+
+        let village = VillageDao::new(&conn).get_by_id(query.village_id)?;
+
+        if !can_manage(&current_admin, village.city_id) {
+            return unauthorized();
+        }
+
+        let invitation = SatgasInvitationDao::new(&conn).create(
+            &village,
+            query.is_medic,
+            &query.invitee_name,
+            query.invitee_phone.as_ref().map(|a| a.as_str()).unwrap_or(""),
+            current_admin.id,
+            &current_admin.name,
+            query.valid_days.unwrap_or(DEFAULT_VALID_DAYS),
+        )?;
+
+        Logs::new(&conn).write(
+            &format!(
+                "{} invited {} as satgas{} for village {}, district {}",
+                current_admin.name,
+                invitation.invitee_name,
+                if invitation.is_medic { " (medic)" } else { "" },
+                invitation.village_name,
+                invitation.district_name
+            ),
+            current_admin.id,
+        );
+
+        let link = invitation_link(&state.config().mail, &invitation.token);
+
+        Ok(ApiResult::success(CreatedInvitation { invitation, link }))
+    }
+
+    /// Daftar undangan satgas pada kota/kab admin.
+    #[api_endpoint(path = "/list", auth = "required", accessor = "admin")]
+    pub fn list_invitations(query: ListInvitations) -> ApiResult<EntriesResult<SatgasInvitation>> {
+        query.validate()?;
+        let conn = state.db()?;
+
+        let city_id = if current_admin.is_super_admin() {
+            None
+        } else {
+            match current_admin.get_city_id() {
+                Some(city_id) if current_admin.has_access("satgas") => Some(city_id),
+                _ => return unauthorized(),
+            }
+        };
+
+        let status = InvitationState::from(query.status.as_ref().map(|a| a.as_str()).unwrap_or(""));
+
+        let sresult = SatgasInvitationDao::new(&conn).search(
+            city_id,
+            status,
+            query.query.as_ref().map(|a| a.as_str()).unwrap_or(""),
+            query.offset,
+            query.limit,
+        )?;
+
+        Ok(ApiResult::success(sresult))
+    }
+
+    /// Batalkan undangan yang belum diterima.
+    #[api_endpoint(path = "/revoke", auth = "required", mutable, accessor = "admin")]
+    pub fn revoke_invitation(query: IdQuery) -> ApiResult<()> {
+        let conn = state.db()?;
+        let dao = SatgasInvitationDao::new(&conn);
+
+        let invitation = dao.get_by_id(query.id)?;
+
+        if !can_manage(&current_admin, invitation.city_id) {
+            return unauthorized();
+        }
+
+        dao.revoke(invitation.id)?;
+
+        Logs::new(&conn).write(
+            &format!(
+                "{} revoked satgas invitation for {} ({})",
+                current_admin.name, invitation.invitee_name, invitation.village_name
+            ),
+            current_admin.id,
+        );
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Mendapatkan informasi undangan berdasarkan token,
+    /// digunakan untuk menampilkan halaman konfirmasi sebelum undangan diterima.
+    #[api_endpoint(path = "/info", auth = "none")]
+    pub fn invitation_info(query: TokenQuery) -> ApiResult<InvitationInfo> {
+        let conn = state.db()?;
+
+        let invitation = SatgasInvitationDao::new(&conn).get_by_token(&query.token)?;
+        let city = CityDao::new(&conn).get_by_id(invitation.city_id)?;
+
+        Ok(ApiResult::success(InvitationInfo {
+            village_id: invitation.village_id,
+            status: invitation.status(),
+            village_name: invitation.village_name,
+            district_name: invitation.district_name,
+            city_name: city.name,
+            province: city.province,
+            is_medic: invitation.is_medic,
+            expiration: invitation.expiration,
+        }))
+    }
+
+    /// Terima undangan dan daftarkan current user sebagai satgas
+    /// pada desa dan peran yang tercantum di undangan.
+    #[api_endpoint(path = "/accept", auth = "required", mutable)]
+    pub fn accept_invitation(query: AcceptInvitation) -> ApiResult<()> {
+        query.validate()?;
+        let conn = state.db()?;
+        let dao = SatgasInvitationDao::new(&conn);
+
+        if current_user.is_satgas() {
+            return coded_error(ErrorCode::AlreadySatgas);
+        }
+
+        let invitation = conn.build_transaction().read_write().run::<_, ApiError, _>(|| {
+            let invitation = dao.accept(&query.token, &current_user)?;
+            let village = VillageDao::new(&conn).get_by_id(invitation.village_id)?;
+            let city = CityDao::new(&conn).get_by_id(invitation.city_id)?;
+
+            register_satgas(
+                &conn,
+                state.geocoder(),
+                current_user.id,
+                &SatgasProfile {
+                    full_name: &query.full_name,
+                    email: &query.email,
+                    phone_num: &query.phone_num,
+                    latitude: query.latitude,
+                    longitude: query.longitude,
+                    is_medic: invitation.is_medic,
+                },
+                &city,
+                &village,
+                &[format!("invitation_id={}", invitation.id)],
+            )?;
+
+            Ok(invitation)
+        })?;
+
+        Logs::new(&conn).write(
+            &format!(
+                "{} accepted satgas invitation from {} for village {}, district {}",
+                query.full_name, invitation.creator_name, invitation.village_name, invitation.district_name
+            ),
+            current_user.id,
+        );
+
+        Ok(ApiResult::success(()))
+    }
+}
@@ -22,7 +22,7 @@ use crate::{
     ID,
 };
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct SearchArea {
     pub longitude: f64,
     pub latitude: f64,
@@ -41,7 +41,7 @@ impl PublicApi {
 
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
 
         let sql_text = format!(
             r#"u.id, u.full_name, s.s_key, s.s_value, uc.latest_loc_long, uc.latest_loc_lat FROM users as u INNER JOIN user_connect AS uc ON u.id=uc.user_id 
@@ -163,7 +163,7 @@ impl PublicApi {
     // /// Rest API endpoint untuk menambahkan map_area baru.
     // #[api_endpoint(path = "/add", mutable, auth = "required")]
     // pub fn add_map_area(query: NewMapArea) -> ApiResult<models::MapArea> {
-    //     let conn = state.db();
+    //     let conn = state.db()?;
     //     let dao = MapAreaDao::new(&conn);
 
     //     // @TODO(*): Add parameter checking here
@@ -179,7 +179,7 @@ impl PublicApi {
     // /// Mendapatkan daftar map_area
     // #[api_endpoint(path = "/list", auth = "required")]
     // pub fn list_map_area(query: QueryEntries) -> ApiResult<EntriesResult<models::MapArea>> {
-    //     let conn = state.db();
+    //     let conn = state.db()?;
     //     let dao = MapAreaDao::new(&conn);
 
     //     let entries = dao.get_map_areas(query.offset, query.limit)?;
@@ -191,7 +191,7 @@ impl PublicApi {
     // /// Mendapatkan jumlah map_area secara keseluruhan.
     // #[api_endpoint(path = "/count", auth = "required")]
     // pub fn map_area_count(state: &AppState, query: ()) -> ApiResult<i64> {
-    //     let conn = state.db();
+    //     let conn = state.db()?;
     //     let dao = MapAreaDao::new(&conn);
 
     //     dao.count().map(ApiResult::success).map_err(From::from)
@@ -200,7 +200,7 @@ impl PublicApi {
     // /// Mendapatkan data map_area berdasarkan ID.
     // #[api_endpoint(path = "/detail", auth = "required")]
     // pub fn map_area_detail(query: IdQuery) -> ApiResult<models::MapArea> {
-    //     let conn = state.db();
+    //     let conn = state.db()?;
     //     let dao = MapAreaDao::new(&conn);
 
     //     dao.get_by_id(query.id)
@@ -211,7 +211,7 @@ impl PublicApi {
     // /// Delete map_area.
     // #[api_endpoint(path = "/delete", auth = "required", mutable="true")]
     // pub fn delete_map_area(query: IdQuery) -> ApiResult<()> {
-    //    let conn = state.db();
+    //    let conn = state.db()?;
     //    let dao = MapAreaDao::new(&conn);
 
     //    dao.delete_by_id(query.id)?;
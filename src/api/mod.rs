@@ -13,26 +13,48 @@ use futures::future::{Future, IntoFuture};
 use regex::Regex;
 
 pub(crate) mod error;
+pub mod executor;
+pub(crate) mod signature;
 mod with;
 
 pub mod admin;
 pub mod analytic;
+pub mod boundary;
 pub mod cities;
+pub mod client;
 pub mod district;
 pub mod feed;
+pub mod invitation;
 pub mod map_area;
+pub mod openapi;
 pub mod pandemia;
 mod parsed_query;
+mod request;
+mod request_id;
+mod shutdown;
 pub mod types;
 pub mod user;
 pub mod village;
 
 use self::with::{Immutable, ImmutableReq, Mutable, MutableReq, NamedWith, With};
-pub use self::{error::Error, with::Result};
+pub use pandemia_types::{ApiAccess, ApiResult};
+
+pub use self::{
+    error::{Error, FieldError},
+    request::{client_ip, ConnectionInfo, HttpRequest},
+    with::Result,
+};
 pub use crate::{auth, error::ErrorCode, user_dao};
 
 use crate::eventstream::{self, Event};
-use crate::{db, service::Service};
+use crate::{
+    config::Config,
+    db,
+    geolocator::{self, Geocoder},
+    health, metrics,
+    service::Service,
+    trace,
+};
 
 use std::{
     collections::BTreeMap,
@@ -41,85 +63,11 @@ use std::{
     marker::PhantomData,
     sync::{mpsc, Arc},
     thread,
+    time::{Duration, Instant},
 };
 
-/// Jenis penanda akses API, kita bagikan menjadi 2 macam:
-///
-/// * Public
-/// * Private
-///
-/// Public adalah apabila kita ingin akses API-nya boleh digunakan oleh publik.
-/// Sementara Private adalah apabila kita ingin akses API-nya hanya untuk internal,
-/// nantinya masing-masing akses ini di-serve pada port yang berbeda
-/// sehingga perlu dilakukan settingan firewall oleh system administrator
-/// agar port untuk private API hanya boleh diakses dari jaringan internal.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ApiAccess {
-    /// Penanda untuk akses publik
-    Public,
-
-    /// Penanda untuk akses privat
-    Private,
-}
-
-impl fmt::Display for ApiAccess {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            ApiAccess::Public => f.write_str("Public"),
-            ApiAccess::Private => f.write_str("Private"),
-        }
-    }
-}
-
 use serde::{de::DeserializeOwned, Serialize};
 
-/// Struktur data ketika pemanggilan api sukses.
-#[derive(Serialize, Deserialize)]
-pub struct ApiResult<T> {
-    /// Error code untuk memberikan informasi hasil pengembalian,
-    /// apabila tidak ada error terjadi maka code harus berisi 0.
-    pub code: i32,
-
-    /// Status bisa berisi: "success" atau "error".
-    pub status: String,
-
-    /// Deskripsi error apabila terjadi error.
-    pub description: String,
-
-    /// Result data.
-    pub result: Option<T>,
-}
-
-impl<T: Serialize> ApiResult<T> {
-    #[doc(hidden)]
-    pub fn new(code: i32, status: String, description: String, result: Option<T>) -> Self {
-        ApiResult {
-            code,
-            status,
-            description,
-            result,
-        }
-    }
-
-    /// Buat hasil sukses
-    pub fn success(result: T) -> Self {
-        Self::new(0, "success".to_owned(), "".to_owned(), Some(result))
-    }
-}
-
-impl ApiResult<()> {
-    /// Buat hasil error
-    pub fn error(code: i32, description: String) -> ApiResult<()> {
-        // Self::new(code, "error".to_owned(), description, None::<_>)
-        ApiResult {
-            code,
-            status: "error".to_owned(),
-            description,
-            result: None::<()>,
-        }
-    }
-}
-
 /// Defines an object that could be used as an API backend.
 ///
 /// This trait is used to implement an API backend for Exonum.
@@ -164,10 +112,10 @@ pub trait ServiceApiBackend: Sized {
 
 /// Type alias for the concrete `actix-web` HTTP response.
 pub type FutureResponse = actix_web::FutureResponse<HttpResponse, actix_web::Error>;
-/// Type alias for the concrete `actix-web` HTTP request.
-pub type HttpRequest = actix_web::HttpRequest<AppState>;
+/// Type alias for the concrete `actix-web` HTTP request, digunakan oleh raw handler.
+pub type RawRequest = actix_web::HttpRequest<AppState>;
 /// Type alias for the inner `actix-web` HTTP requests handler.
-pub type RawHandler = dyn Fn(HttpRequest) -> FutureResponse + 'static + Send + Sync;
+pub type RawHandler = dyn Fn(RawRequest) -> FutureResponse + 'static + Send + Sync;
 /// Type alias for the `actix-web::App` with the `AppState`.
 pub type App = actix_web::App<AppState>;
 /// Type alias for actix `Scope` with `AppState`.
@@ -221,29 +169,50 @@ impl ServiceApiBackend for ApiBuilder {
         for handler in self.handlers.clone() {
             let inner = handler.inner;
             output = output.route(&handler.name, handler.method.clone(), move |request| {
-                inner(request)
+                observed(&inner, request)
             });
         }
         output
     }
 }
 
+// Jalankan handler sambil mencatat jumlah request & latency-nya ke [metrics].
+fn observed(inner: &RawHandler, request: RawRequest) -> FutureResponse {
+    // path endpoint sama dengan path di `#[api_endpoint]`, eg: `/user/v1/me/info`
+    let endpoint = request.path().trim_start_matches("/api").to_owned();
+    let method = request.method().clone();
+    let start = Instant::now();
+    let future = inner(request).then(move |result| {
+        let status = match result {
+            Ok(ref resp) => resp.status(),
+            Err(ref e) => e.as_response_error().error_response().status(),
+        };
+        metrics::observe_request(&endpoint, method.as_str(), status.as_u16(), start.elapsed());
+        result
+    });
+    Box::new(future)
+}
+
 impl<Q, I, F> From<NamedWith<Q, I, Result<I>, F, Immutable>> for RequestHandler
 where
     F: for<'r> Fn(&'r AppState, Q) -> Result<I> + 'static + Send + Sync + Clone,
-    Q: DeserializeOwned + 'static,
-    I: Serialize + 'static,
+    Q: DeserializeOwned + Send + 'static,
+    I: Serialize + Send + 'static,
 {
     fn from(f: NamedWith<Q, I, Result<I>, F, Immutable>) -> Self {
         let handler = f.inner.handler;
-        let index = move |request: HttpRequest| -> FutureResponse {
-            let context = request.state();
+        let index = move |request: RawRequest| -> FutureResponse {
+            let handler = handler.clone();
+            let context = request.state().clone();
+            let lang = error::request_lang(&request);
+            let trace = request_id::context(&request);
             let future = Query::from_request(&request, &Default::default())
                 .map(|query: Query<Q>| query.into_inner())
                 .or_else(map_error)
-                .and_then(|query| handler(context, query).map_err(From::from))
-                .and_then(|value| Ok(map_ok(value, &request)))
-                .into_future();
+                .into_future()
+                .and_then(move |query| executor::spawn(trace, move || handler(&context, query)))
+                .map(move |value| map_ok(value, &request))
+                .map_err(move |e| error::localize(e, lang));
             Box::new(future)
         };
 
@@ -258,19 +227,24 @@ where
 impl<Q, I, F> From<NamedWith<Q, I, Result<I>, F, ImmutableReq>> for RequestHandler
 where
     F: for<'r> Fn(&'r AppState, Q, &HttpRequest) -> Result<I> + 'static + Send + Sync + Clone,
-    Q: DeserializeOwned + 'static,
-    I: Serialize + 'static,
+    Q: DeserializeOwned + Send + 'static,
+    I: Serialize + Send + 'static,
 {
     fn from(f: NamedWith<Q, I, Result<I>, F, ImmutableReq>) -> Self {
         let handler = f.inner.handler;
-        let index = move |request: HttpRequest| -> FutureResponse {
-            let context = request.state();
+        let index = move |request: RawRequest| -> FutureResponse {
+            let handler = handler.clone();
+            let context = request.state().clone();
+            let lang = error::request_lang(&request);
+            let trace = request_id::context(&request);
+            let req = HttpRequest::new(&request, None);
             let future = Query::from_request(&request, &Default::default())
                 .map(|query: Query<Q>| query.into_inner())
                 .or_else(map_error)
-                .and_then(|query| handler(context, query, &request).map_err(From::from))
-                .and_then(|value| Ok(map_ok(value, &request)))
-                .into_future();
+                .into_future()
+                .and_then(move |query| executor::spawn(trace, move || handler(&context, query, &req)))
+                .map(move |value| map_ok(value, &request))
+                .map_err(move |e| error::localize(e, lang));
             Box::new(future)
         };
 
@@ -284,7 +258,7 @@ where
 
 // Me-mapping pengembalian `Ok(())` menjadi format [ApiResult].
 #[inline]
-fn map_ok<I: Serialize>(value: I, request: &HttpRequest) -> HttpResponse {
+fn map_ok<I: Serialize>(value: I, request: &RawRequest) -> HttpResponse {
     let headers = request.headers();
     match serde_json::to_string(&value) {
         Ok(body) => {
@@ -311,9 +285,9 @@ fn map_ok<I: Serialize>(value: I, request: &HttpRequest) -> HttpResponse {
 
 // Me-mapping pengembalian error ketika parsing query agar bisa ditampilkan ke client.
 #[inline]
-fn map_error<I: DeserializeOwned + 'static, E>(e: E) -> ::std::result::Result<I, actix_web::Error>
+fn map_error<I: DeserializeOwned + 'static, E>(e: E) -> Result<I>
 where
-    E: Into<actix_web::error::Error> + fmt::Display,
+    E: fmt::Display,
 {
     // @TODO(*): Regex ini mungkin perlu dibuat lazy_static?
     let re = Regex::new(r"missing field `(.*?)`").unwrap();
@@ -321,38 +295,34 @@ where
     debug!("err_desc: {}", err_desc);
     let mut iter = re.captures_iter(&err_desc);
     if let Some(field) = iter.next() {
-        Err(actix_web::Error::from(Error::InvalidParameter(
-            ErrorCode::InvalidParameter as i32,
-            format!("No `{}` parameter", &field[1]),
-        )))
+        Err(Error::InvalidFields(vec![error::InvalidField::new(
+            &field[1], "required",
+        )]))
     } else {
-        Err(actix_web::Error::from(Error::InvalidParameter(
-            ErrorCode::SerializeDeserializeError as i32,
-            "Invalid parameter data".to_owned(),
-        )))
+        Err(ErrorCode::SerializeDeserializeError.into())
     }
 }
 
 impl<Q, I, F> From<NamedWith<Q, I, Result<I>, F, Mutable>> for RequestHandler
 where
     F: for<'r> Fn(&'r mut AppState, Q) -> Result<I> + 'static + Send + Sync + Clone,
-    Q: DeserializeOwned + 'static,
-    I: Serialize + PartialEq + 'static,
+    Q: DeserializeOwned + Send + 'static,
+    I: Serialize + PartialEq + Send + 'static,
 {
     fn from(f: NamedWith<Q, I, Result<I>, F, Mutable>) -> Self {
         let handler = f.inner.handler;
-        let index = move |request: HttpRequest| -> FutureResponse {
+        let index = move |request: RawRequest| -> FutureResponse {
             let handler = handler.clone();
             let mut context = request.state().clone();
+            let lang = error::request_lang(&request);
+            let trace = request_id::context(&request);
             request
                 .json()
                 // .from_err()
                 .or_else(map_error)
-                .and_then(move |query: Q| {
-                    handler(&mut context, query)
-                        .map(|v| map_ok(v, &request))
-                        .map_err(From::from)
-                })
+                .and_then(move |query: Q| executor::spawn(trace, move || handler(&mut context, query)))
+                .map(move |v| map_ok(v, &request))
+                .map_err(move |e| error::localize(e, lang))
                 .responder()
         };
 
@@ -367,22 +337,31 @@ where
 impl<Q, I, F> From<NamedWith<Q, I, Result<I>, F, MutableReq>> for RequestHandler
 where
     F: for<'r> Fn(&'r mut AppState, Q, &HttpRequest) -> Result<I> + 'static + Send + Sync + Clone,
-    Q: DeserializeOwned + 'static,
-    I: Serialize + 'static,
+    Q: DeserializeOwned + Send + 'static,
+    I: Serialize + Send + 'static,
 {
     fn from(f: NamedWith<Q, I, Result<I>, F, MutableReq>) -> Self {
         let handler = f.inner.handler;
-        let index = move |request: HttpRequest| -> FutureResponse {
+        let index = move |request: RawRequest| -> FutureResponse {
             let handler = handler.clone();
             let mut context = request.state().clone();
+            let lang = error::request_lang(&request);
+            let trace = request_id::context(&request);
 
+            // body dibaca manual agar hash-nya bisa digunakan untuk verifikasi signed request
             request
-                .json()
-                .or_else(map_error)
-                .and_then(move |query: Q| {
-                    handler(&mut context, query, &request)
-                        .map(|v| map_ok(v, &request))
-                        .map_err(From::from)
+                .body()
+                .map_err(actix_web::Error::from)
+                .and_then(move |body| {
+                    let req = HttpRequest::new(&request, Some(signature::BodyHash::of(&body)));
+                    serde_json::from_slice(&body)
+                        .or_else(map_error)
+                        .into_future()
+                        .and_then(move |query: Q| {
+                            executor::spawn(trace, move || handler(&mut context, query, &req))
+                        })
+                        .map(move |v| map_ok(v, &request))
+                        .map_err(move |e| error::localize(e, lang))
                 })
                 .responder()
         };
@@ -403,6 +382,7 @@ pub type ResourceFunc = Arc<Box<dyn Fn(Scope) -> Scope + Sync + Send + 'static>>
 pub struct ServiceApiScope {
     pub(crate) actix_backend: ApiBuilder,
     pub(crate) resources: Vec<ResourceFunc>,
+    pub(crate) operations: Vec<openapi::Operation>,
 }
 
 impl ServiceApiScope {
@@ -510,6 +490,13 @@ impl ServiceApiScope {
         self
     }
 
+    /// Tambahkan metadata endpoint untuk dokumen OpenAPI,
+    /// dipanggil oleh wiring yang dibuat macro `api_group`.
+    pub fn describe(&mut self, operation: openapi::Operation) -> &mut Self {
+        self.operations.push(operation);
+        self
+    }
+
     /// Returns a mutable reference to the underlying web backend.
     pub fn web_backend(&mut self) -> &mut ApiBuilder {
         &mut self.actix_backend
@@ -539,6 +526,13 @@ impl ServiceApiBuilder {
     pub fn private_scope(&mut self) -> &mut ServiceApiScope {
         &mut self.private_scope
     }
+
+    fn scope(&self, access: ApiAccess) -> &ServiceApiScope {
+        match access {
+            ApiAccess::Public => &self.public_scope,
+            ApiAccess::Private => &self.private_scope,
+        }
+    }
 }
 
 /// API Aggregator digunakan untuk meng-aggregate requirements untuk keperluan
@@ -573,7 +567,39 @@ impl ApiAggregator {
             (prefix, builder)
         }));
 
-        Self { inner }
+        let mut agg = Self { inner };
+        agg.wire_openapi();
+        agg
+    }
+
+    /// Dokumen OpenAPI untuk semua endpoint dengan akses `access`.
+    pub fn openapi(&self, access: ApiAccess) -> serde_json::Value {
+        openapi::document(access, self.inner.values().map(|builder| builder.scope(access)))
+    }
+
+    /// Serve dokumen OpenAPI pada `/system/v1/openapi.json` untuk masing-masing akses.
+    fn wire_openapi(&mut self) {
+        for &access in &[ApiAccess::Public, ApiAccess::Private] {
+            let doc = Arc::new(self.openapi(access).to_string());
+            let builder = self
+                .inner
+                .entry("system".to_owned())
+                .or_insert_with(ServiceApiBuilder::new);
+            let scope = match access {
+                ApiAccess::Public => builder.public_scope(),
+                ApiAccess::Private => builder.private_scope(),
+            };
+            scope.with_scope(move |scope| {
+                let doc = doc.clone();
+                scope.resource("v1/openapi.json", move |r| {
+                    r.method(actix_web::http::Method::GET).f(move |_| {
+                        HttpResponse::Ok()
+                            .content_type("application/json")
+                            .body(doc.as_str().to_owned())
+                    })
+                })
+            });
+        }
     }
 
     #[inline]
@@ -638,32 +664,116 @@ use db::{DbConn, DbConnMan};
 #[derive(Clone)]
 pub struct AppState {
     db: DbConnMan,
+    config: Arc<Config>,
+    geocoder: Arc<dyn Geocoder>,
 }
 
 impl AppState {
-    #[doc(hidden)]
-    pub fn new() -> AppState {
-        AppState { db: db::clone() }
+    /// Buat state dengan konfigurasi `config`, DB pool & geocoder yang digunakan
+    /// adalah yang sudah dipasang ketika startup (lihat [crate::init]).
+    pub fn with_config(config: Arc<Config>) -> AppState {
+        AppState {
+            db: db::clone(),
+            geocoder: geolocator::get(),
+            config,
+        }
     }
 
-    /// Get Backend DB connection
-    pub fn db(&self) -> DbConn {
-        self.db
-            .get()
-            .expect("cannot get DB connection from the r2d2 pool")
+    /// Ganti geocoder yang digunakan oleh handler, berguna untuk test yang
+    /// tidak ingin bergantung pada layanan eksternal.
+    pub fn with_geocoder(mut self, geocoder: Box<dyn Geocoder>) -> AppState {
+        self.geocoder = Arc::from(geocoder);
+        self
+    }
+
+    /// Konfigurasi yang digunakan.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Geocoder yang digunakan, lihat [crate::geolocator].
+    pub fn geocoder(&self) -> &dyn Geocoder {
+        self.geocoder.as_ref()
+    }
+
+    /// Get Backend DB connection,
+    /// mengembalikan error `ServiceBusy` apabila pool habis sampai timeout.
+    pub fn db(&self) -> Result<DbConn> {
+        self.db.get().map_err(|e| {
+            error!("cannot get DB connection from the r2d2 pool: {}", e);
+            ErrorCode::ServiceBusy.into()
+        })
     }
 }
 
 #[doc(hidden)]
-pub fn create_app(agg: &ApiAggregator, access: ApiAccess) -> App {
-    let state = AppState::new();
+pub fn create_app(agg: &ApiAggregator, access: ApiAccess, config: Arc<Config>) -> App {
+    let state = AppState::with_config(config);
     let mut app = App::with_state(state)
-        .middleware(middleware::DefaultHeaders::new().header("Access-Control-Allow-Origin", "*"))
+        .middleware(request_id::RequestId)
+        .middleware(
+            middleware::DefaultHeaders::new()
+                .header("Access-Control-Allow-Origin", "*")
+                .header("Access-Control-Expose-Headers", trace::REQUEST_ID_HEADER),
+        )
         .middleware(Cors::default());
     app = app.scope("api", |scope: Scope| agg.extend(access, scope));
+    app = wire_health(app, access);
+    if access == ApiAccess::Private {
+        app = app.resource("/metrics", |r| {
+            r.method(actix_web::http::Method::GET).f(|_| {
+                HttpResponse::Ok()
+                    .content_type(metrics::content_type())
+                    .body(metrics::render())
+            })
+        });
+    }
     app
 }
 
+// Endpoint health check untuk orchestrator, lihat [health].
+fn wire_health(app: App, access: ApiAccess) -> App {
+    use actix_web::http::Method;
+
+    let app = app
+        .resource("/health", |r| {
+            r.method(Method::GET)
+                .f(|_| HttpResponse::Ok().json(json!({ "status": "ok" })))
+        })
+        .resource("/ready", |r| {
+            r.method(Method::GET).f(|req| -> FutureResponse {
+                let lang = error::request_lang(req);
+                let future = executor::spawn(request_id::context(req), || Ok(health::readiness()))
+                    .map(|readiness| {
+                        let mut resp = if readiness.ready {
+                            HttpResponse::Ok()
+                        } else {
+                            HttpResponse::ServiceUnavailable()
+                        };
+                        resp.json(readiness)
+                    })
+                    .map_err(move |e| error::localize(e, lang));
+                Box::new(future)
+            })
+        });
+
+    if access != ApiAccess::Private {
+        return app;
+    }
+
+    app.resource("/status", |r| {
+        r.method(Method::GET).f(|req| -> FutureResponse {
+            let lang = error::request_lang(req);
+            let config = req.state().config.clone();
+            let trace = request_id::context(req);
+            let future = executor::spawn(trace, move || health::status(&config).map_err(Error::from))
+                .map(|status| HttpResponse::Ok().json(status))
+                .map_err(move |e| error::localize(e, lang));
+            Box::new(future)
+        })
+    })
+}
+
 /// Stelan untuk server API yang akan dijalankan
 /// ini memungkinkan kita menjalankan server untuk setiap akses pada listen address yang berbeda.
 /// Sebagai contoh rest API untuk public ada di port 8000 dan untuk private ada di 9000.
@@ -690,21 +800,29 @@ impl ApiServer {
 pub struct ServiceApiConfig {
     /// Daftar server API yang akan dijalankan.
     pub api_servers: Vec<ApiServer>,
+    /// Konfigurasi aplikasi yang diteruskan ke setiap handler melalui [AppState].
+    pub config: Arc<Config>,
 }
 
 impl ServiceApiConfig {
     #[doc(hidden)]
-    pub fn new(api_servers: Vec<ApiServer>) -> Self {
-        ServiceApiConfig { api_servers }
+    pub fn new(api_servers: Vec<ApiServer>, config: Arc<Config>) -> Self {
+        ServiceApiConfig { api_servers, config }
     }
 }
 
 /// Start API server berdasarkan konfigurasi yang ada.
-pub fn start(agg: ApiAggregator, config: ServiceApiConfig) {
+///
+/// Berjalan sampai menerima signal SIGTERM/SIGINT, kemudian melakukan graceful shutdown
+/// (lihat `api/shutdown.rs`). Mengembalikan error apabila server gagal dijalankan
+/// atau masih ada handler/event yang belum selesai diproses sampai `api.shutdown_timeout`.
+pub fn start(agg: ApiAggregator, config: ServiceApiConfig) -> ::std::result::Result<(), failure::Error> {
     let (system_tx, system_rx) = mpsc::channel();
     let (api_runtime_tx, api_runtime_rx) = mpsc::channel();
 
     let api_servers = config.api_servers.clone();
+    let app_config = config.config.clone();
+    let shutdown_timeout = app_config.api.shutdown_timeout;
 
     let system_thread = thread::spawn(move || -> ::std::result::Result<(), failure::Error> {
         let system = System::new("http-server");
@@ -713,17 +831,24 @@ pub fn start(agg: ApiAggregator, config: ServiceApiConfig) {
             let access = api_server.access;
             let listen_address = api_server.listen_address.clone();
             let agg = agg.clone();
+            let app_config = app_config.clone();
 
             println!("{} rest API serving at {}", access, listen_address);
-            HttpServer::new(move || create_app(&agg, access))
+            HttpServer::new(move || create_app(&agg, access, app_config.clone()))
+                .disable_signals()
+                .shutdown_timeout(shutdown_timeout.min(u64::from(u16::max_value())) as u16)
                 .bind(listen_address)
                 .map(|server| server.start())
         });
 
         system_tx.send(System::current())?;
+        let mut servers = vec![];
         for handler in api_handlers {
-            api_runtime_tx.send(handler?)?;
+            let server = handler?;
+            servers.push(server.clone());
+            api_runtime_tx.send(server)?;
         }
+        shutdown::Shutdown::start(servers);
 
         trace!("starting server...");
 
@@ -756,8 +881,16 @@ pub fn start(agg: ApiAggregator, config: ServiceApiConfig) {
     }
 
     if let Err(er) = system_thread.join().unwrap() {
-        eprintln!("ERROR: Cannot start server. {}", er);
+        bail!("Cannot start server. {}", er);
+    }
+
+    if !shutdown::drain(Duration::from_secs(shutdown_timeout)) {
+        bail!(
+            "Shutdown timed out after {}s, some handlers or events were not finished",
+            shutdown_timeout
+        );
     }
 
     println!("done.");
+    Ok(())
 }
@@ -0,0 +1,624 @@
+//! Pembuatan dokumen OpenAPI 3 dari metadata endpoint yang dikumpulkan oleh macro
+//! `api_group`/`api_endpoint`.
+//!
+//! Schema request dan response diambil dari implementasi [ApiSchema] pada tipe query
+//! dan result masing-masing endpoint, implementasinya bisa dibuat menggunakan
+//! `#[derive(ApiSchema)]`. Tipe yang tidak mengimplementasikan [ApiSchema] tetap
+//! didokumentasikan namun dengan schema bebas (`{}`).
+//!
+//! Dokumen di-serve pada endpoint `/api/system/v1/openapi.json` di server public
+//! maupun private, masing-masing hanya berisi endpoint sesuai aksesnya.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::api::{signature, ApiAccess, FieldError, ServiceApiScope};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    marker::PhantomData,
+};
+
+/// Versi spesifikasi OpenAPI yang dihasilkan.
+pub const OPENAPI_VERSION: &str = "3.0.3";
+
+/// Nama security scheme untuk header `X-Access-Token`.
+const ACCESS_TOKEN_SCHEME: &str = "accessToken";
+
+const SCHEMA_REF_PREFIX: &str = "#/components/schemas/";
+
+/// Tipe yang bisa dideskripsikan sebagai JSON schema pada dokumen OpenAPI.
+pub trait ApiSchema {
+    /// Path lengkap tipe (eg: `pandemia::models::User`), apabila ada schema-nya
+    /// didaftarkan pada `components/schemas`, `None` untuk tipe yang schema-nya di-inline.
+    fn schema_path() -> Option<&'static str> {
+        None
+    }
+
+    /// Buat schema untuk tipe ini, `null` apabila tipe tidak memiliki representasi,
+    /// eg: `()`.
+    fn schema(reg: &mut SchemaRegistry) -> JsonValue;
+}
+
+/// Kumpulan schema yang akan ditulis pada `components/schemas`.
+#[derive(Default)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<String, JsonValue>,
+    names: BTreeMap<&'static str, String>,
+}
+
+impl SchemaRegistry {
+    /// Buat registry kosong.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dapatkan schema untuk tipe `T`, tipe yang memiliki path didaftarkan
+    /// dan dikembalikan sebagai `$ref`.
+    pub fn schema_of<T: ApiSchema + ?Sized>(&mut self) -> JsonValue {
+        let path = match T::schema_path() {
+            Some(path) => path,
+            None => return T::schema(self),
+        };
+
+        if let Some(name) = self.names.get(path) {
+            return schema_ref(name);
+        }
+
+        let name = self.name_for(path);
+        self.names.insert(path, name.clone());
+        // placeholder agar tipe rekursif tidak didaftarkan berulang-ulang
+        self.schemas.insert(name.clone(), json!({}));
+        let schema = T::schema(self);
+        self.schemas.insert(name.clone(), schema);
+
+        schema_ref(&name)
+    }
+
+    /// Nama schema dari path tipe, menggunakan nama tipe apabila belum dipakai,
+    /// selain itu menggunakan path modulnya, eg: `api.types.User`.
+    fn name_for(&self, path: &str) -> String {
+        let name = path.rsplit("::").next().unwrap_or(path);
+        if !self.schemas.contains_key(name) {
+            return name.to_string();
+        }
+        path.split("::").skip(1).collect::<Vec<_>>().join(".")
+    }
+
+    /// Resolve `$ref` menjadi schema yang terdaftar.
+    pub fn resolve<'a>(&'a self, schema: &'a JsonValue) -> &'a JsonValue {
+        schema
+            .get("$ref")
+            .and_then(JsonValue::as_str)
+            .and_then(|r| self.schemas.get(r.trim_start_matches(SCHEMA_REF_PREFIX)))
+            .unwrap_or(schema)
+    }
+
+    /// Semua schema yang terdaftar.
+    pub fn into_schemas(self) -> BTreeMap<String, JsonValue> {
+        self.schemas
+    }
+}
+
+fn schema_ref(name: &str) -> JsonValue {
+    json!({ "$ref": format!("{}{}", SCHEMA_REF_PREFIX, name) })
+}
+
+/// Tambahkan keyword pada schema, schema `$ref` dibungkus menggunakan `allOf`
+/// karena keyword lain di samping `$ref` diabaikan pada OpenAPI 3.0.
+pub fn annotate(schema: JsonValue, key: &str, value: JsonValue) -> JsonValue {
+    let mut schema = match schema {
+        JsonValue::Object(ref obj) if obj.contains_key("$ref") => json!({ "allOf": [schema] }),
+        JsonValue::Object(_) => schema,
+        _ => json!({}),
+    };
+    schema[key] = value;
+    schema
+}
+
+/// Batasan panjang dari `#[validate(length(..))]`, untuk string maupun array.
+pub fn length(schema: JsonValue, min: Option<u64>, max: Option<u64>) -> JsonValue {
+    let (min_key, max_key) = if schema.get("type") == Some(&json!("array")) {
+        ("minItems", "maxItems")
+    } else {
+        ("minLength", "maxLength")
+    };
+    let schema = match min {
+        Some(min) => annotate(schema, min_key, json!(min)),
+        None => schema,
+    };
+    match max {
+        Some(max) => annotate(schema, max_key, json!(max)),
+        None => schema,
+    }
+}
+
+/// Batasan nilai dari `#[validate(range(..))]`.
+pub fn range(schema: JsonValue, min: Option<f64>, max: Option<f64>) -> JsonValue {
+    let schema = match min {
+        Some(min) => annotate(schema, "minimum", json!(min)),
+        None => schema,
+    };
+    match max {
+        Some(max) => annotate(schema, "maximum", json!(max)),
+        None => schema,
+    }
+}
+
+/// Builder schema object, digunakan oleh `#[derive(ApiSchema)]`.
+#[doc(hidden)]
+pub struct ObjectSchema {
+    description: String,
+    properties: JsonMap<String, JsonValue>,
+    required: Vec<JsonValue>,
+}
+
+impl ObjectSchema {
+    #[doc(hidden)]
+    pub fn new(description: &str) -> Self {
+        Self {
+            description: description.to_owned(),
+            properties: JsonMap::new(),
+            required: vec![],
+        }
+    }
+
+    /// Tambahkan property.
+    pub fn field(&mut self, name: &str, required: bool, description: &str, schema: JsonValue) -> &mut Self {
+        let schema = if description.is_empty() {
+            schema
+        } else {
+            annotate(schema, "description", json!(description))
+        };
+        if required {
+            self.required.push(json!(name));
+        }
+        self.properties.insert(name.to_owned(), schema);
+        self
+    }
+
+    /// Selesaikan schema.
+    pub fn build(self) -> JsonValue {
+        let mut rv = json!({
+            "type": "object",
+            "properties": self.properties,
+        });
+        if !self.required.is_empty() {
+            rv["required"] = JsonValue::Array(self.required);
+        }
+        if !self.description.is_empty() {
+            rv["description"] = json!(self.description);
+        }
+        rv
+    }
+}
+
+/// Penanda tipe untuk memilih schema secara statis, lihat [ProbeSchema].
+#[doc(hidden)]
+pub struct SchemaProbe<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized> SchemaProbe<T> {
+    #[doc(hidden)]
+    pub fn new() -> Self {
+        SchemaProbe(PhantomData)
+    }
+}
+
+/// Dipanggil melalui `(&&SchemaProbe::<T>::new()).probe_schema(reg)`,
+/// menggunakan schema dari [ApiSchema] apabila `T` mengimplementasikannya
+/// dan jatuh ke [ProbeFallback] apabila tidak.
+#[doc(hidden)]
+pub trait ProbeSchema {
+    fn probe_schema(&self, reg: &mut SchemaRegistry) -> JsonValue;
+}
+
+impl<T: ApiSchema + ?Sized> ProbeSchema for &SchemaProbe<T> {
+    fn probe_schema(&self, reg: &mut SchemaRegistry) -> JsonValue {
+        reg.schema_of::<T>()
+    }
+}
+
+#[doc(hidden)]
+pub trait ProbeFallback {
+    fn probe_schema(&self, reg: &mut SchemaRegistry) -> JsonValue;
+}
+
+impl<T: ?Sized> ProbeFallback for SchemaProbe<T> {
+    fn probe_schema(&self, _reg: &mut SchemaRegistry) -> JsonValue {
+        json!({})
+    }
+}
+
+macro_rules! impl_api_schema {
+    ($($ty:ty => $schema:tt),* $(,)*) => {
+        $(
+            impl ApiSchema for $ty {
+                fn schema(_reg: &mut SchemaRegistry) -> JsonValue {
+                    json!($schema)
+                }
+            }
+        )*
+    };
+}
+
+impl_api_schema! {
+    bool => { "type": "boolean" },
+    i8 => { "type": "integer", "format": "int32" },
+    i16 => { "type": "integer", "format": "int32" },
+    i32 => { "type": "integer", "format": "int32" },
+    i64 => { "type": "integer", "format": "int64" },
+    isize => { "type": "integer", "format": "int64" },
+    u8 => { "type": "integer", "format": "int32", "minimum": 0 },
+    u16 => { "type": "integer", "format": "int32", "minimum": 0 },
+    u32 => { "type": "integer", "format": "int64", "minimum": 0 },
+    u64 => { "type": "integer", "format": "int64", "minimum": 0 },
+    usize => { "type": "integer", "format": "int64", "minimum": 0 },
+    f32 => { "type": "number", "format": "float" },
+    f64 => { "type": "number", "format": "double" },
+    char => { "type": "string", "minLength": 1, "maxLength": 1 },
+    str => { "type": "string" },
+    String => { "type": "string" },
+    NaiveDateTime => { "type": "string", "format": "date-time" },
+    NaiveDate => { "type": "string", "format": "date" },
+    JsonValue => {},
+    JsonMap<String, JsonValue> => { "type": "object" },
+}
+
+impl ApiSchema for () {
+    fn schema(_reg: &mut SchemaRegistry) -> JsonValue {
+        JsonValue::Null
+    }
+}
+
+impl<T: ApiSchema + ?Sized> ApiSchema for &T {
+    fn schema(reg: &mut SchemaRegistry) -> JsonValue {
+        reg.schema_of::<T>()
+    }
+}
+
+impl<T: ApiSchema + ?Sized> ApiSchema for Box<T> {
+    fn schema(reg: &mut SchemaRegistry) -> JsonValue {
+        reg.schema_of::<T>()
+    }
+}
+
+impl<T: ApiSchema> ApiSchema for Option<T> {
+    fn schema(reg: &mut SchemaRegistry) -> JsonValue {
+        annotate(reg.schema_of::<T>(), "nullable", json!(true))
+    }
+}
+
+impl<T: ApiSchema> ApiSchema for Vec<T> {
+    fn schema(reg: &mut SchemaRegistry) -> JsonValue {
+        json!({ "type": "array", "items": reg.schema_of::<T>() })
+    }
+}
+
+impl<T: ApiSchema> ApiSchema for [T] {
+    fn schema(reg: &mut SchemaRegistry) -> JsonValue {
+        json!({ "type": "array", "items": reg.schema_of::<T>() })
+    }
+}
+
+impl<K, V: ApiSchema> ApiSchema for BTreeMap<K, V> {
+    fn schema(reg: &mut SchemaRegistry) -> JsonValue {
+        json!({ "type": "object", "additionalProperties": reg.schema_of::<V>() })
+    }
+}
+
+impl<K, V: ApiSchema, S> ApiSchema for HashMap<K, V, S> {
+    fn schema(reg: &mut SchemaRegistry) -> JsonValue {
+        json!({ "type": "object", "additionalProperties": reg.schema_of::<V>() })
+    }
+}
+
+// `FieldError` didefinisikan di `pandemia-types` sehingga schema-nya tidak bisa di-derive.
+impl ApiSchema for FieldError {
+    fn schema_path() -> Option<&'static str> {
+        Some("pandemia::api::error::FieldError")
+    }
+
+    fn schema(reg: &mut SchemaRegistry) -> JsonValue {
+        let mut schema = ObjectSchema::new("Detail kesalahan per parameter.");
+        schema
+            .field("field", true, "Nama field.", reg.schema_of::<String>())
+            .field(
+                "code",
+                true,
+                "Kode validasi, eg: `length`, `range`, `email`, `required`.",
+                reg.schema_of::<String>(),
+            )
+            .field(
+                "message",
+                true,
+                "Deskripsi kesalahan sesuai bahasa client.",
+                reg.schema_of::<String>(),
+            );
+        schema.build()
+    }
+}
+
+/// Schema untuk [ApiResult](crate::api::ApiResult) yang membungkus `result`.
+pub fn api_result(reg: &mut SchemaRegistry, result: JsonValue) -> JsonValue {
+    let result = match result {
+        JsonValue::Null => json!({ "nullable": true }),
+        result => annotate(result, "nullable", json!(true)),
+    };
+
+    json!({
+        "type": "object",
+        "required": ["code", "status", "description"],
+        "properties": {
+            "code": {
+                "type": "integer",
+                "format": "int32",
+                "description": "0 apabila sukses, selain itu berisi kode error dari katalog error."
+            },
+            "status": { "type": "string", "enum": ["success", "error"] },
+            "description": { "type": "string", "description": "Deskripsi error apabila terjadi error." },
+            "result": result,
+            "fields": {
+                "type": "array",
+                "items": reg.schema_of::<crate::api::FieldError>(),
+                "description": "Detail kesalahan per parameter apabila validasi parameter gagal."
+            }
+        }
+    })
+}
+
+/// Metadata endpoint untuk dokumen OpenAPI, dibuat oleh macro `api_group`
+/// pada saat wiring endpoint.
+#[derive(Clone)]
+pub struct Operation {
+    /// Nama API group, digunakan sebagai tag.
+    pub group: &'static str,
+    /// Deskripsi API group.
+    pub group_desc: &'static str,
+    /// Path lengkap endpoint, eg: `/system/v1/info`.
+    pub path: &'static str,
+    /// HTTP method, `GET` atau `POST`.
+    pub method: &'static str,
+    /// Nama fungsi handler.
+    pub method_name: &'static str,
+    /// Doc comment endpoint.
+    pub desc: &'static str,
+    /// Kebutuhan auth: `none`, `optional` atau `required`.
+    pub auth: &'static str,
+    /// Accessor yang bisa mengakses endpoint, eg: `user`, `admin`.
+    pub accessors: &'static [&'static str],
+    /// Endpoint memverifikasi signed request.
+    pub signed: bool,
+    /// Schema tipe query.
+    pub query: fn(&mut SchemaRegistry) -> JsonValue,
+    /// Schema tipe result.
+    pub response: fn(&mut SchemaRegistry) -> JsonValue,
+}
+
+impl Operation {
+    fn build(&self, operation_id: String, reg: &mut SchemaRegistry) -> JsonValue {
+        let mut rv = JsonMap::new();
+
+        rv.insert("tags".to_string(), json!([self.group]));
+        rv.insert("operationId".to_string(), json!(operation_id));
+        if !self.desc.is_empty() {
+            rv.insert(
+                "summary".to_string(),
+                json!(self.desc.lines().next().unwrap_or("").trim()),
+            );
+            rv.insert("description".to_string(), json!(self.desc));
+        }
+
+        let mut parameters = vec![];
+        let query = (self.query)(reg);
+
+        if self.method == "GET" {
+            let query = reg.resolve(&query);
+            let required = query
+                .get("required")
+                .and_then(JsonValue::as_array)
+                .cloned()
+                .unwrap_or_default();
+            if let Some(props) = query.get("properties").and_then(JsonValue::as_object) {
+                for (name, schema) in props {
+                    parameters.push(json!({
+                        "name": name,
+                        "in": "query",
+                        "required": required.contains(&json!(name)),
+                        "schema": schema,
+                    }));
+                }
+            }
+        } else if !query.is_null() {
+            rv.insert(
+                "requestBody".to_string(),
+                json!({
+                    "required": true,
+                    "content": { "application/json": { "schema": query } }
+                }),
+            );
+        }
+
+        if self.signed {
+            for header in &[
+                signature::KEY_HEADER,
+                signature::TIMESTAMP_HEADER,
+                signature::NONCE_HEADER,
+                signature::SIGNATURE_HEADER,
+            ] {
+                parameters.push(json!({
+                    "name": header,
+                    "in": "header",
+                    "required": false,
+                    "schema": { "type": "string" },
+                }));
+            }
+        }
+
+        if !parameters.is_empty() {
+            rv.insert("parameters".to_string(), JsonValue::Array(parameters));
+        }
+
+        let response = (self.response)(reg);
+        let ok = if response.is_null() {
+            json!({ "description": "OK" })
+        } else {
+            json!({
+                "description": "OK",
+                "content": { "application/json": { "schema": response } }
+            })
+        };
+        rv.insert("responses".to_string(), json!({ "200": ok }));
+
+        let security = match self.auth {
+            "required" => json!([{ ACCESS_TOKEN_SCHEME: [] }]),
+            "optional" => json!([{ ACCESS_TOKEN_SCHEME: [] }, {}]),
+            _ => json!([]),
+        };
+        rv.insert("security".to_string(), security);
+        rv.insert("x-auth".to_string(), json!(self.auth));
+        if self.auth != "none" {
+            rv.insert("x-accessors".to_string(), json!(self.accessors));
+        }
+        if self.signed {
+            rv.insert("x-signed".to_string(), json!(true));
+        }
+
+        JsonValue::Object(rv)
+    }
+}
+
+/// Buat dokumen OpenAPI dari semua endpoint pada scope-scope yang diberikan.
+pub fn document<'a, I>(access: ApiAccess, scopes: I) -> JsonValue
+where
+    I: IntoIterator<Item = &'a ServiceApiScope>,
+{
+    let mut reg = SchemaRegistry::new();
+    let mut paths = JsonMap::new();
+    let mut tags: Vec<JsonValue> = vec![];
+    let mut operation_ids: Vec<String> = vec![];
+
+    for op in scopes.into_iter().flat_map(|scope| scope.operations.iter()) {
+        if !tags.iter().any(|t| t["name"] == op.group) {
+            tags.push(json!({ "name": op.group, "description": op.group_desc }));
+        }
+
+        let mut operation_id = op.method_name.to_string();
+        if operation_ids.contains(&operation_id) {
+            operation_id = format!("{}_{}", op.group.to_lowercase().replace(' ', "_"), op.method_name);
+        }
+        operation_ids.push(operation_id.clone());
+
+        let item = paths
+            .entry(op.path.to_string())
+            .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+        item[op.method.to_lowercase()] = op.build(operation_id, &mut reg);
+    }
+
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": format!("Pandemia {} API", access),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": "/api" }],
+        "tags": tags,
+        "paths": paths,
+        "components": {
+            "schemas": reg.into_schemas(),
+            "securitySchemes": {
+                ACCESS_TOKEN_SCHEME: {
+                    "type": "apiKey",
+                    "in": "header",
+                    "name": "X-Access-Token",
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Contoh query.
+    #[derive(ApiSchema)]
+    #[allow(dead_code)]
+    struct ItemQuery {
+        /// ID item.
+        id: i64,
+        #[validate(length(min = 1, max = 20))]
+        name: Option<String>,
+    }
+
+    #[derive(ApiSchema)]
+    #[allow(dead_code)]
+    struct Item {
+        id: i64,
+        tags: Vec<String>,
+        #[serde(skip_serializing)]
+        secret: String,
+        #[serde(rename = "ts")]
+        created: NaiveDateTime,
+    }
+
+    struct NoSchema;
+
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn test_schema_registry() {
+        let mut reg = SchemaRegistry::new();
+
+        assert_eq!(
+            reg.schema_of::<Item>(),
+            json!({ "$ref": "#/components/schemas/Item" })
+        );
+        assert_eq!(
+            reg.schema_of::<Vec<Item>>(),
+            json!({ "type": "array", "items": { "$ref": "#/components/schemas/Item" } })
+        );
+        assert_eq!(
+            (&&SchemaProbe::<NoSchema>::new()).probe_schema(&mut reg),
+            json!({})
+        );
+        assert_eq!(
+            (&&SchemaProbe::<Option<i32>>::new()).probe_schema(&mut reg),
+            json!({ "type": "integer", "format": "int32", "nullable": true })
+        );
+
+        let query = reg.schema_of::<ItemQuery>();
+        assert_eq!(
+            reg.resolve(&query),
+            &json!({
+                "type": "object",
+                "description": "Contoh query.",
+                "required": ["id"],
+                "properties": {
+                    "id": { "type": "integer", "format": "int64", "description": "ID item." },
+                    "name": { "type": "string", "nullable": true, "minLength": 1, "maxLength": 20 },
+                }
+            })
+        );
+
+        let schemas = reg.into_schemas();
+        assert_eq!(
+            schemas["Item"],
+            json!({
+                "type": "object",
+                "required": ["id", "tags", "ts"],
+                "properties": {
+                    "id": { "type": "integer", "format": "int64" },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "ts": { "type": "string", "format": "date-time" },
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_schema_name_collision() {
+        let mut reg = SchemaRegistry::new();
+        reg.schemas.insert("User".to_string(), json!({}));
+        assert_eq!(reg.name_for("pandemia::api::types::User"), "api.types.User");
+        assert_eq!(reg.name_for("pandemia::models::Admin"), "Admin");
+    }
+}
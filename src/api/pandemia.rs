@@ -19,8 +19,8 @@ use crate::{
     api::{error::*, parsed_query::*, ApiResult, Error as ApiError, Error::*, HttpRequest as ApiHttpRequest},
     auth,
     dao::{
-        CityDao, DistrictDao, DistrictDataDao, Logs, RecordDao, ReportNoteDao, SubReportDao, VillageDao,
-        VillageDataDao,
+        BoundaryDao, CityDao, DistrictDao, DistrictDataDao, Logs, RecordDao, ReportNoteDao, SubReportDao,
+        VillageDao, VillageDataDao,
     },
     district_data_dao::{NewDistrictData, UpdateDistrictData},
     error::{self, Error, ErrorCode},
@@ -36,7 +36,7 @@ use crate::{
     ID,
 };
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct RecordUpdate {
     #[validate(range(min = 1, max = 9999999999))]
     pub id: i64,
@@ -62,19 +62,19 @@ pub struct RecordUpdate {
     pub loc_path: String,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct UpdateRecords {
     records: Vec<RecordUpdate>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct InfoLocation {
     pub name: String,
     pub latest_record: models::Record,
     pub history: Vec<models::Record>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct SubReportQuery {
     pub offset: i64,
     pub limit: i64,
@@ -83,7 +83,7 @@ pub struct SubReportQuery {
     pub city_id: Option<ID>,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct AddRecord {
     #[validate(length(min = 2, max = 1000))]
     pub loc: String,
@@ -119,7 +119,7 @@ pub struct AddRecord {
     pub loc_path: String,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct AddSubReport {
     #[validate(length(min = 1, max = 50, message = "Nama wajib diisi"))]
     pub full_name: String,
@@ -141,9 +141,11 @@ pub struct AddSubReport {
     #[validate(length(max = 100))]
     pub district_name: Option<String>,
     pub village_id: Option<ID>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct UpdateSubReport {
     pub id: ID,
     #[validate(length(min = 1, max = 50))]
@@ -163,7 +165,7 @@ pub struct UpdateSubReport {
     pub add_info: Option<Vec<String>>,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct UpdateReportNoteStatus {
     pub id: ID,
     pub state: Vec<String>,
@@ -178,7 +180,7 @@ macro_rules! has_label {
     };
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ApiSchema)]
 pub struct SubReportCount {
     // #[sql_type = "BigInt"]
     pub odp: i64,
@@ -205,7 +207,7 @@ impl PublicApi {
     #[api_endpoint(path = "/add_record", auth = "required", mutable, accessor = "admin")]
     pub fn add_record(query: AddRecord) -> ApiResult<models::Record> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = RecordDao::new(&conn);
 
         if !current_admin.has_access("records") {
@@ -252,10 +254,16 @@ impl PublicApi {
     }
 
     /// Add Sub Report.
-    #[api_endpoint(path = "/sub_report/add", auth = "required", accessor = "user,admin", mutable)]
+    #[api_endpoint(
+        path = "/sub_report/add",
+        auth = "required",
+        accessor = "user,admin",
+        mutable,
+        signed
+    )]
     pub fn add_sub_report(query: AddSubReport) -> ApiResult<models::SubReport> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = SubReportDao::new(&conn);
 
         let mut city_id = 0;
@@ -268,7 +276,7 @@ impl PublicApi {
 
         if let Some(current_user) = current_user.as_ref() {
             if !current_user.is_satgas() {
-                return param_error("Anda tidak dapat menambahkan data");
+                return coded_error(ErrorCode::CannotAddData);
             }
 
             if current_user.is_blocked() || current_user.is_deleted() {
@@ -281,7 +289,7 @@ impl PublicApi {
             // };
             city_id = match current_user.get_city_id() {
                 Some(a) => a,
-                None => return param_error("Anda tidak terdaftar sebagai satgas"),
+                None => return coded_error(ErrorCode::NotSatgas),
             };
 
             // village_id = current_user
@@ -324,39 +332,40 @@ impl PublicApi {
                 };
             }
 
-            if query.village_name.is_none() {
-                return param_error("Nama desa belum diset");
+            // apabila koordinat diberikan, tentukan desa berdasarkan batas wilayah
+            if village_id == 0 {
+                if let (Some(lat), Some(lng)) = (query.latitude, query.longitude) {
+                    if let Some(village) = BoundaryDao::new(&conn).resolve(lat, lng)?.village {
+                        village_id = village.id;
+                    }
+                }
+            }
+
+            if village_id == 0 && query.village_name.is_none() {
+                return coded_error(ErrorCode::VillageNotSet);
             }
 
             if city_id == 0 && village_id == 0 {
-                return param_error("Anda tidak terdaftar pada kota yang dimaksud");
+                return coded_error(ErrorCode::NotInArea);
             }
 
-            let _village_name = query.village_name.as_ref().unwrap();
-
             let village = if village_id != 0 {
-                VillageDao::new(&conn).get_by_id(village_id).map_err(|e| {
-                    ApiError::BadRequest(48232, format!("Tidak ada desa dengan id {}", village_id))
-                })?
+                VillageDao::new(&conn)
+                    .get_by_id(village_id)
+                    .map_err(|_| Catalogue(ErrorCode::VillageNotFound, vec![village_id.to_string()]))?
             } else {
                 if query.district_name.is_none() || query.district_name == Some("".to_string()) {
-                    return param_error("Nama kecamatan belum diset");
+                    return coded_error(ErrorCode::DistrictNotSet);
                 }
                 let _district_name = query.district_name.as_ref().unwrap();
+                let _village_name = query.village_name.as_ref().unwrap();
 
                 let district = DistrictDao::new(&conn)
                     .get_by_name(city_id, _district_name)
-                    .map_err(|_| {
-                        ApiError::NotFound(404, format!("Tidak ada kecamatan dengan nama {}", _district_name))
-                    })?;
+                    .map_err(|_| Catalogue(ErrorCode::DistrictNotFound, vec![_district_name.to_owned()]))?;
                 VillageDao::new(&conn)
                     .get_by_name_id(city_id, district.id, _village_name)
-                    .map_err(|e| {
-                        ApiError::BadRequest(
-                            48231,
-                            format!("Desa {} tidak terdaftar di dalam kota Anda", village_name),
-                        )
-                    })?
+                    .map_err(|_| Catalogue(ErrorCode::VillageNotInCity, vec![_village_name.to_owned()]))?
             };
 
             village_id = village.id;
@@ -377,13 +386,13 @@ impl PublicApi {
         }
 
         if village_name.is_empty() {
-            return param_error("Nama desa masih kosong");
+            return coded_error(ErrorCode::VillageNotSet);
         }
         if district_name.is_empty() {
-            return param_error("Nama kecamatan masih kosong");
+            return coded_error(ErrorCode::DistrictNotSet);
         }
         if city_id == 0 {
-            return param_error("Gagal memproses kota");
+            return coded_error(ErrorCode::CityNotSet);
         }
 
         let mut healthy: HealthyKind = HealthyKind::Health;
@@ -544,7 +553,7 @@ impl PublicApi {
         accessor = "user,admin"
     )]
     pub fn delete_sub_report(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = SubReportDao::new(&conn);
 
         let sr = dao.get_by_id(query.id)?;
@@ -682,7 +691,7 @@ impl PublicApi {
     )]
     pub fn update_sub_report(query: UpdateSubReport) -> ApiResult<SubReport> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = SubReportDao::new(&conn);
 
         let mut current_user_id = 0;
@@ -695,7 +704,7 @@ impl PublicApi {
 
         if let Some(current_user) = current_user {
             if !current_user.is_satgas() {
-                return param_error("Anda tidak dapat menambahkan data")?;
+                return coded_error(ErrorCode::CannotAddData);
             }
             if current_user.is_blocked() || current_user.is_deleted() {
                 return unauthorized();
@@ -705,11 +714,11 @@ impl PublicApi {
             //     a => a,
             // };
             city_id = match current_user.get_city_id() {
-                None => return param_error("Anda tidak terdaftar pada area manapun (no city_id)"),
+                None => return coded_error(ErrorCode::NoArea),
                 Some(a) => a,
             };
             village_id = match current_user.get_village_id() {
-                None => return param_error("Anda tidak terdaftar pada area manapun (no village_id)"),
+                None => return coded_error(ErrorCode::NoArea),
                 Some(a) => a,
             };
 
@@ -727,7 +736,7 @@ impl PublicApi {
         if let Some(current_admin) = current_admin {
             if current_admin.id != 1 {
                 city_id = match current_admin.get_city_id() {
-                    None => return param_error("Anda tidak terdaftar pada area ini"),
+                    None => return coded_error(ErrorCode::NotInArea),
                     Some(a) => a,
                 };
 
@@ -956,9 +965,10 @@ impl PublicApi {
     }
 
     /// Search for sub_report
+    /// Pencarian nama cocok per kata secara utuh (bukan substring), umur & alamat harus sama persis.
     #[api_endpoint(path = "/sub_report/search", auth = "required", accessor = "user,admin")]
     pub fn search_sub_reports(query: SubReportQuery) -> ApiResult<EntriesResult<SubReport>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = SubReportDao::new(&conn);
 
         // let area_code = match current_user.get_area_code() {
@@ -985,14 +995,14 @@ impl PublicApi {
                 current_user.get_village_id(),
             ) {
                 (Some(a), Some(b), Some(c)) => (Some(a), Some(b), Some(c)),
-                _ => return param_error("Anda tidak terdaftar pada area manapun (no city_id)"),
+                _ => return coded_error(ErrorCode::NoArea),
             }
         } else if current_admin.as_ref().map(|a| a.id == 1).unwrap_or(false) {
             // root admin tak perlu dibatasi city
             (None, None, None)
         } else if let Some(current_admin) = current_admin.as_ref() {
             match current_admin.get_city_id().or_else(|| query.city_id) {
-                None => return param_error("Anda tidak terdaftar pada area manapun (no city_id)"),
+                None => return coded_error(ErrorCode::NoArea),
                 Some(city_id) => {
                     let city = CityDao::new(&conn).get_by_id(city_id)?;
                     let district = match parq
@@ -1001,10 +1011,7 @@ impl PublicApi {
                     {
                         Some(district) => {
                             if district.city_id != city_id {
-                                return bad_request(&format!(
-                                    "Anda tidak memiliki akses untuk kecamatan {}",
-                                    district.name
-                                ));
+                                return Err(Catalogue(ErrorCode::NoAccessToDistrict, vec![district.name]));
                             }
                             Some(district)
                         }
@@ -1075,8 +1082,16 @@ impl PublicApi {
             query.limit,
         )?;
 
+        let reader_id = current_user
+            .as_ref()
+            .map(|a| a.id)
+            .or_else(|| current_admin.as_ref().map(|a| a.id))
+            .unwrap_or(0);
+
+        let entries = dao.reveal(result.entries, reader_id, &current_user_name)?;
+
         Ok(ApiResult::success(EntriesResult {
-            entries: result.entries.into_iter().map(|a| a.to_api_type(&conn)).collect(),
+            entries: entries.into_iter().map(|a| a.to_api_type(&conn)).collect(),
             count: result.count,
         }))
     }
@@ -1084,11 +1099,11 @@ impl PublicApi {
     /// Get sub report all status count for current user village access.
     #[api_endpoint(path = "/sub_report/count", auth = "required")]
     pub fn get_sub_report_count(query: ()) -> ApiResult<JsonMap<String, JsonValue>> {
-        let conn = state.db();
+        let conn = state.db()?;
 
         let village_id = current_user
             .get_village_id()
-            .ok_or(param_err!("You have no accesss to this resource"))?;
+            .ok_or(ApiError::from(ErrorCode::AccessDenied))?;
 
         let result: Vec<CountByStatus> = sql_query(&format!(
             "select status, COUNT(*) from sub_reports WHERE village_id={} GROUP BY status",
@@ -1110,7 +1125,7 @@ impl PublicApi {
     /// Get location stats data (single mode).
     #[api_endpoint(path = "/info_location", auth = "none")]
     pub fn get_info_location(query: LocationQuery) -> ApiResult<Option<models::Record>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = RecordDao::new(&conn);
         // let locs: Vec<String> = vec![query.loc.to_owned()];
 
@@ -1127,7 +1142,7 @@ impl PublicApi {
     /// Get per location stats data, use comma for multiple locations.
     #[api_endpoint(path = "/info_locations", auth = "none")]
     pub fn get_info_locations(query: LocationQuery) -> ApiResult<Vec<InfoLocation>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = RecordDao::new(&conn);
 
         let mut locs: Vec<String> = {
@@ -1187,7 +1202,7 @@ impl PublicApi {
     /// Get latest data record search/query by location.
     #[api_endpoint(path = "/search_records", auth = "required", accessor = "admin")]
     pub fn search_records(query: QueryEntries) -> ApiResult<EntriesResult<Record>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = RecordDao::new(&conn);
 
         let result = dao.search(&query.query.unwrap_or("".to_string()), query.offset, query.limit)?;
@@ -1204,7 +1219,7 @@ impl PublicApi {
         use crate::schema::records::{self, dsl};
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
 
         let locs = query
             .records
@@ -1280,7 +1295,7 @@ impl PublicApi {
     /// Delete record by id
     #[api_endpoint(path = "/delete_record", auth = "required", mutable, accessor = "admin")]
     pub fn delete_record(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = RecordDao::new(&conn);
         let rec = dao.get_by_id(query.id)?;
         dao.delete_by_id(rec.id)?;
@@ -1296,7 +1311,7 @@ impl PublicApi {
     /// Search for journal_logs
     #[api_endpoint(path = "/journal/search", auth = "required", accessor = "admin")]
     pub fn search_journal_logs(query: QueryEntries) -> ApiResult<EntriesResult<models::Log>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = Logs::new(&conn);
 
         let rv = dao.search(&query.query.unwrap_or("".to_string()), query.offset, query.limit)?;
@@ -1311,7 +1326,7 @@ impl PublicApi {
     #[api_endpoint(path = "/report_note/add", auth = "required", mutable)]
     pub fn add_report_note(query: AddReportNote) -> ApiResult<ReportNote> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = ReportNoteDao::new(&conn);
 
         if current_user.is_blocked() {
@@ -1354,7 +1369,7 @@ impl PublicApi {
     /// Delete report note.
     #[api_endpoint(path = "/report_note/delete", auth = "required", mutable, accessor = "admin")]
     pub fn delete_report_note(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = ReportNoteDao::new(&conn);
 
         if !current_admin.has_access("report_notes") {
@@ -1384,7 +1399,7 @@ impl PublicApi {
     pub fn update_state_report_note(query: UpdateReportNoteStatus) -> ApiResult<()> {
         use crate::schema::report_notes::{self, dsl};
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = ReportNoteDao::new(&conn);
 
         if !current_admin.has_access("report_notes") {
@@ -1416,7 +1431,7 @@ impl PublicApi {
     #[api_endpoint(path = "/report_note/search", auth = "required", accessor = "admin")]
     pub fn search_report_notes(query: SearchNotes) -> ApiResult<EntriesResult<ReportNote>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = ReportNoteDao::new(&conn);
 
         let city_id = current_admin.get_city_id();
@@ -1447,7 +1462,7 @@ impl PublicApi {
     }
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct SearchNotes {
     pub query: Option<String>,
     // pub meta_contains: String,
@@ -1458,14 +1473,14 @@ pub struct SearchNotes {
     pub limit: i64,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct AddReportNote {
     pub title: Option<String>,
     pub notes: String,
 }
 
 use crate::{
-    event_handler::FCM,
+    event_handler,
     push_notif_handler::{FCMHandler, FCMPayloadData},
     types::NotifKind,
     util,
@@ -1473,10 +1488,12 @@ use crate::{
 
 use std::thread;
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct TestPushNotifQuery {
     pub loc: String,
     pub loc_kind: i16,
+    /// ID kecamatan/desa untuk `loc_kind` kecamatan (5) atau desa (6).
+    pub loc_id: Option<ID>,
 }
 
 /// Holder untuk implementasi API endpoint privat.
@@ -1487,13 +1504,15 @@ impl PrivateApi {
     /// Test push notif functionality, only for internal testing purposes.
     #[api_endpoint(path = "/test/push_notif", auth = "none", mutable)]
     pub fn test_push_notif(query: TestPushNotifQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
+        let fcm = event_handler::fcm()?;
         let _ = thread::spawn(move || {
-            if let Err(e) = FCM.push(
+            if let Err(e) = fcm.push(
                 "fcm",
                 &FCMPayloadData {
                     receiver_loc: &query.loc,
                     receiver_loc_kind: query.loc_kind.into(),
+                    receiver_loc_id: query.loc_id,
                     target_id: 0,
                     kind: NotifKind::NewCases,
                     title: "Test",
@@ -0,0 +1,187 @@
+//! Data request yang bisa diakses oleh handler endpoint.
+//!
+//! Berbeda dengan `actix_web::HttpRequest`, object ini bisa dikirim ke thread lain
+//! sehingga handler bisa dijalankan di blocking pool (lihat `executor`).
+
+use actix_web::http::{HeaderMap, Method};
+
+use crate::api::{signature::BodyHash, RawRequest};
+
+use std::net::{IpAddr, SocketAddr};
+
+/// Informasi koneksi dari request.
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    host: String,
+    remote: Option<String>,
+}
+
+impl ConnectionInfo {
+    /// Host yang diminta oleh client.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Alamat IP client (tanpa port), header `Forwarded`/`X-Forwarded-For` hanya
+    /// diperhitungkan apabila request datang dari `api.trusted_proxies`.
+    pub fn remote(&self) -> Option<&str> {
+        self.remote.as_ref().map(String::as_str)
+    }
+}
+
+/// Request yang sedang diproses oleh handler endpoint.
+#[derive(Debug)]
+pub struct HttpRequest {
+    method: Method,
+    path: String,
+    headers: HeaderMap,
+    connection_info: ConnectionInfo,
+    body_hash: Option<BodyHash>,
+}
+
+impl HttpRequest {
+    pub(crate) fn new(req: &RawRequest, body_hash: Option<BodyHash>) -> Self {
+        let info = req.connection_info();
+        HttpRequest {
+            method: req.method().clone(),
+            path: req.path().to_string(),
+            headers: req.headers().clone(),
+            connection_info: ConnectionInfo {
+                host: info.host().to_string(),
+                remote: remote_ip(req),
+            },
+            body_hash,
+        }
+    }
+
+    /// HTTP method.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Path dari URL request.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Header request.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Informasi koneksi.
+    pub fn connection_info(&self) -> &ConnectionInfo {
+        &self.connection_info
+    }
+
+    /// Hash dari body request, hanya ada untuk endpoint mutable.
+    pub(crate) fn body_hash(&self) -> Option<&BodyHash> {
+        self.body_hash.as_ref()
+    }
+}
+
+/// Alamat IP client dari request, string kosong apabila tidak diketahui.
+/// Gunakan ini untuk rate limit, log dan sesi agar alamat yang dipakai selalu sama.
+pub fn client_ip(req: &HttpRequest) -> &str {
+    req.connection_info().remote().unwrap_or("")
+}
+
+/// Alamat IP dari `addr`, port, bracket IPv6 dan quote dibuang.
+fn parse_ip(addr: &str) -> Option<IpAddr> {
+    let addr = addr.trim().trim_matches('"');
+    addr.parse::<IpAddr>()
+        .or_else(|_| addr.parse::<SocketAddr>().map(|a| a.ip()))
+        .or_else(|_| {
+            addr.trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+        })
+        .ok()
+}
+
+/// Daftar alamat dari header `Forwarded` (parameter `for`) atau `X-Forwarded-For`,
+/// urut dari client hingga proxy terakhir.
+fn forwarded_for(headers: &HeaderMap) -> Vec<String> {
+    let header = |name: &str| headers.get(name).and_then(|a| a.to_str().ok());
+
+    if let Some(forwarded) = header("Forwarded") {
+        return forwarded
+            .split(',')
+            .filter_map(|a| {
+                a.split(';')
+                    .map(|a| a.trim())
+                    .find(|a| a.len() > 4 && a[..4].eq_ignore_ascii_case("for="))
+                    .map(|a| a[4..].to_string())
+            })
+            .collect();
+    }
+
+    header("X-Forwarded-For")
+        .map(|a| a.split(',').map(|a| a.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Tentukan alamat client dari rantai `forwarded` + `peer`, dibaca dari belakang selama
+/// alamat tersebut adalah proxy yang dipercaya.
+fn resolve_client_ip(peer: Option<IpAddr>, forwarded: &[String], trusted: &[IpAddr]) -> Option<IpAddr> {
+    let mut rv = peer?;
+    for addr in forwarded.iter().rev() {
+        if !trusted.contains(&rv) {
+            break;
+        }
+        match parse_ip(addr) {
+            Some(ip) => rv = ip,
+            None => break,
+        }
+    }
+    Some(rv)
+}
+
+fn remote_ip(req: &RawRequest) -> Option<String> {
+    let trusted: Vec<IpAddr> = req
+        .state()
+        .config()
+        .api
+        .trusted_proxies
+        .iter()
+        .filter_map(|a| parse_ip(a))
+        .collect();
+    let forwarded = if trusted.is_empty() {
+        vec![]
+    } else {
+        forwarded_for(req.headers())
+    };
+    resolve_client_ip(req.peer_addr().map(|a| a.ip()), &forwarded, &trusted).map(|a| a.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_client_ip() {
+        let ip = |a: &str| a.parse::<IpAddr>().unwrap();
+        let chain = |a: &[&str]| a.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+
+        // header dari client langsung diabaikan
+        assert_eq!(
+            resolve_client_ip(Some(ip("1.2.3.4")), &chain(&["5.6.7.8"]), &trusted),
+            Some(ip("1.2.3.4"))
+        );
+        // melalui proxy, alamat palsu di awal header diabaikan
+        assert_eq!(
+            resolve_client_ip(
+                Some(ip("10.0.0.1")),
+                &chain(&["6.6.6.6", "1.2.3.4:5678", "10.0.0.2"]),
+                &trusted
+            ),
+            Some(ip("1.2.3.4"))
+        );
+        assert_eq!(
+            resolve_client_ip(Some(ip("10.0.0.1")), &chain(&["\"[2001:db8::1]:80\""]), &trusted),
+            Some(ip("2001:db8::1"))
+        );
+        assert_eq!(resolve_client_ip(None, &chain(&["1.2.3.4"]), &trusted), None);
+    }
+}
@@ -0,0 +1,76 @@
+//! Middleware untuk memberikan request id pada setiap request, lihat [crate::trace].
+//!
+//! Request id dari header `X-Request-Id` digunakan apabila valid (eg: dari reverse proxy),
+//! selain itu dibuat baru. Request id dikembalikan di header response yang sama.
+
+use actix_web::{
+    http::header::{HeaderName, HeaderValue},
+    middleware::{Finished, Middleware, Response, Started},
+    HttpResponse,
+};
+
+use crate::{
+    api::{AppState, RawRequest},
+    trace::{self, Context},
+};
+
+use std::time::Instant;
+
+/// Trace context & waktu mulai request, disimpan di extensions request.
+struct Traced {
+    context: Context,
+    start: Instant,
+}
+
+/// Middleware request id.
+pub(crate) struct RequestId;
+
+impl Middleware<AppState> for RequestId {
+    fn start(&self, req: &RawRequest) -> actix_web::Result<Started> {
+        let request_id = req
+            .headers()
+            .get(trace::REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| trace::is_valid_id(id))
+            .map(str::to_string)
+            .unwrap_or_else(trace::generate_id);
+        let context = Context::new(request_id, req.path());
+        req.extensions_mut().insert(Traced {
+            context,
+            start: Instant::now(),
+        });
+        Ok(Started::Done)
+    }
+
+    fn response(&self, req: &RawRequest, mut resp: HttpResponse) -> actix_web::Result<Response> {
+        if let Some(context) = context(req) {
+            if let Ok(value) = HeaderValue::from_str(&context.request_id) {
+                resp.headers_mut()
+                    .insert(HeaderName::from_static("x-request-id"), value);
+            }
+        }
+        Ok(Response::Done(resp))
+    }
+
+    fn finish(&self, req: &RawRequest, resp: &HttpResponse) -> Finished {
+        if let Some(traced) = req.extensions().get::<Traced>() {
+            trace::scope(Some(traced.context.clone()), || {
+                info!(
+                    "{} {} -> {} in {:?}",
+                    req.method(),
+                    req.path(),
+                    resp.status().as_u16(),
+                    traced.start.elapsed()
+                )
+            });
+        }
+        Finished::Done
+    }
+}
+
+/// Trace context dari request, untuk diteruskan ke thread yang menjalankan handler.
+pub(crate) fn context(req: &RawRequest) -> Option<Context> {
+    req.extensions()
+        .get::<Traced>()
+        .map(|traced| traced.context.clone())
+}
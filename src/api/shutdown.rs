@@ -0,0 +1,100 @@
+//! Graceful shutdown ketika menerima SIGTERM/SIGINT/SIGQUIT:
+//!
+//! 1. Server HTTP berhenti menerima koneksi baru dan menunggu request yang sedang berjalan
+//!    (maksimal `api.shutdown_timeout` detik), kemudian actix system dihentikan.
+//! 2. Monitor dihentikan, pengecekan yang sedang berjalan ditunggu sampai selesai.
+//! 3. Menunggu handler di blocking pool dan antrian event di `eventstream` (feed, push notif)
+//!    selesai diproses.
+//!
+//! Signal kedua selama proses shutdown akan langsung menghentikan actix system.
+
+use actix_web::{
+    actix::{
+        actors::signal::{ProcessSignals, Signal, SignalType, Subscribe},
+        Actor, Addr, Arbiter, Context, Handler, System, SystemService,
+    },
+    server::{Server, StopServer},
+};
+use futures::future::{self, Future};
+
+use crate::{api::executor, eventstream, monitor};
+
+use std::time::Duration;
+
+/// Actor penerima signal dari OS.
+pub(crate) struct Shutdown {
+    servers: Vec<Addr<Server>>,
+    stopping: bool,
+}
+
+impl Shutdown {
+    /// Mulai menerima signal untuk menghentikan `servers`.
+    pub(crate) fn start(servers: Vec<Addr<Server>>) {
+        let addr = Shutdown {
+            servers,
+            stopping: false,
+        }
+        .start();
+        ProcessSignals::from_registry().do_send(Subscribe(addr.recipient()));
+    }
+}
+
+impl Actor for Shutdown {
+    type Context = Context<Self>;
+}
+
+impl Handler<Signal> for Shutdown {
+    type Result = ();
+
+    fn handle(&mut self, msg: Signal, _ctx: &mut Self::Context) {
+        let name = match msg.0 {
+            SignalType::Int => "SIGINT",
+            SignalType::Term => "SIGTERM",
+            SignalType::Quit => "SIGQUIT",
+            _ => return,
+        };
+
+        if self.stopping {
+            warn!("{} received while shutting down, forcing stop", name);
+            System::current().stop_with_code(1);
+            return;
+        }
+        self.stopping = true;
+
+        info!("{} received, stopping API servers...", name);
+        let stops = self
+            .servers
+            .iter()
+            .map(|server| server.send(StopServer { graceful: true }))
+            .collect::<Vec<_>>();
+
+        Arbiter::spawn(future::join_all(stops).then(|result| {
+            if let Err(e) = result {
+                warn!("cannot stop API server gracefully: {}", e);
+            }
+            System::current().stop();
+            Ok(())
+        }));
+    }
+}
+
+/// Hentikan monitor lalu tunggu handler & event yang tersisa selesai diproses,
+/// mengembalikan `false` apabila masih ada yang belum selesai sampai `timeout`.
+pub(crate) fn drain(timeout: Duration) -> bool {
+    info!("stopping monitors...");
+    monitor::stop_monitors();
+
+    info!("waiting for {} pending handlers...", executor::pending());
+    let handlers = executor::wait_idle(timeout);
+    if !handlers {
+        warn!("{} handlers still running", executor::pending());
+    }
+
+    info!("waiting for {} pending events...", eventstream::pending());
+    let events = eventstream::wait_idle(timeout);
+    if !events {
+        warn!("{} events still pending", eventstream::pending());
+    }
+
+    handlers && events
+}
@@ -0,0 +1,148 @@
+//! Verifikasi signed request menggunakan ed25519 key yang didaftarkan oleh client.
+//!
+//! Endpoint yang ditandai `signed` pada `api_endpoint` akan memverifikasi header berikut:
+//!
+//! * `X-Sign-Key` - public key client (hex), didaftarkan via `/user/v1/me/key/register`.
+//! * `X-Sign-Timestamp` - waktu UNIX dalam detik.
+//! * `X-Sign-Nonce` - string acak unik per request (8-64 karakter).
+//! * `X-Signature` - signature ed25519 (hex) dari canonical message:
+//!
+//! ```text
+//! <METHOD>\n<PATH>\n<TIMESTAMP>\n<NONCE>\n<SHA256-HEX(BODY)>
+//! ```
+//!
+//! Signing bersifat opt-in: request tanpa signature tetap diterima kecuali user telah
+//! mendaftarkan client key atau konfigurasi `security.require_signed_requests`
+//! (env `REQUIRE_SIGNED_REQUESTS=true`) diaktifkan.
+
+use diesel::pg::PgConnection;
+
+use crate::{
+    api::{self, AppState, Error as ApiError},
+    crypto,
+    error::ErrorCode,
+    models::User,
+    user_dao::UserDao,
+    util,
+};
+
+pub use pandemia_types::signature::{
+    canonical_message, KEY_HEADER, NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+
+/// Selisih waktu maksimal (dalam detik) antara client dan server.
+const MAX_CLOCK_SKEW: i64 = 300;
+
+/// Hash sha256 (hex) dari body request, disimpan oleh handler mutable
+/// karena body sudah tidak bisa dibaca lagi setelah di-deserialize.
+#[derive(Debug)]
+pub(crate) struct BodyHash(pub String);
+
+impl BodyHash {
+    pub(crate) fn of(body: &[u8]) -> Self {
+        BodyHash(crypto::sha256_hash(body).to_hex())
+    }
+}
+
+fn header<'a>(req: &'a api::HttpRequest, name: &str) -> Option<&'a str> {
+    req.headers()
+        .get(name)
+        .and_then(|a| a.to_str().ok())
+        .map(|a| a.trim())
+        .filter(|a| !a.is_empty())
+}
+
+fn invalid(msg: &str) -> ApiError {
+    ApiError::BadRequest(ErrorCode::InvalidSignature as i32, msg.to_string())
+}
+
+/// Verifikasi signature request dari user, dipanggil oleh guard yang digenerasikan `api_endpoint`.
+pub(crate) fn verify_request(
+    state: &AppState,
+    conn: &PgConnection,
+    user: &User,
+    req: &api::HttpRequest,
+) -> api::Result<()> {
+    let dao = UserDao::new(conn);
+
+    let signature = match header(req, SIGNATURE_HEADER) {
+        Some(signature) => signature,
+        None => {
+            if state.config().security.require_signed_requests || dao.has_client_key(user.id)? {
+                return Err(ApiError::BadRequest(
+                    ErrorCode::MessageHasNoSign as i32,
+                    "Request must be signed".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+    };
+
+    let pub_key = header(req, KEY_HEADER).ok_or_else(|| invalid("No sign key"))?;
+    let nonce = header(req, NONCE_HEADER).ok_or_else(|| invalid("No sign nonce"))?;
+    let timestamp = header(req, TIMESTAMP_HEADER)
+        .and_then(|a| a.parse::<i64>().ok())
+        .ok_or_else(|| invalid("Invalid sign timestamp"))?;
+
+    if nonce.len() < 8 || nonce.len() > 64 {
+        return Err(invalid("Invalid sign nonce"));
+    }
+
+    let now = util::current_time().as_secs() as i64;
+    if (now - timestamp).abs() > MAX_CLOCK_SKEW {
+        return Err(invalid("Signature expired"));
+    }
+
+    if dao.get_client_key(user.id, &pub_key.to_lowercase())?.is_none() {
+        return Err(invalid("Unknown sign key"));
+    }
+
+    let body_hash = req
+        .body_hash()
+        .map(|a| a.0.clone())
+        .unwrap_or_else(|| BodyHash::of(b"").0);
+
+    let message = canonical_message(req.method().as_str(), req.path(), timestamp, nonce, &body_hash);
+
+    let valid = match (hex::decode(signature), hex::decode(pub_key)) {
+        (Ok(signature), Ok(pub_key)) => crypto::verify_signature(message.as_bytes(), &signature, &pub_key),
+        _ => false,
+    };
+
+    if !valid {
+        warn!("user `{}` sent request with invalid signature", user.id);
+        return Err(invalid("Invalid signature"));
+    }
+
+    let expired_before = util::now() - chrono::Duration::seconds(MAX_CLOCK_SKEW * 2);
+    if !dao.use_request_nonce(user.id, nonce, expired_before)? {
+        warn!("user `{}` replayed signed request nonce `{}`", user.id, nonce);
+        return Err(invalid("Nonce already used"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_message() {
+        let body_hash = BodyHash::of(b"{\"id\":1}").0;
+        assert_eq!(
+            canonical_message(
+                "post",
+                "/village/v1/commit",
+                1_588_000_000,
+                "abcdefgh",
+                &body_hash
+            ),
+            format!("POST\n/village/v1/commit\n1588000000\nabcdefgh\n{}", body_hash)
+        );
+        assert_eq!(
+            BodyHash::of(b"").0,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
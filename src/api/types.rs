@@ -29,13 +29,13 @@ pub trait ToApiType<T> {
     // }
 }
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default, ApiSchema)]
 pub struct EntriesResult<T> {
     pub entries: Vec<T>,
     pub count: i64,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct QueryEntries {
     pub query: Option<String>,
     #[validate(range(min = 0, max = 1_000_000))]
@@ -44,12 +44,12 @@ pub struct QueryEntries {
     pub limit: i64,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct IdQuery {
     pub id: ID,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct ResetPassword {
     #[validate(email(message = "Email not valid, please enter valid email address"))]
     pub email: String,
@@ -58,7 +58,7 @@ pub struct ResetPassword {
     pub password: Option<String>,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct LocationQuery {
     #[deprecated(since = "0.2.10", note = "use loc_path instead")]
     #[validate(length(max = 100))]
@@ -69,7 +69,7 @@ pub struct LocationQuery {
     pub with_history: Option<bool>,
 }
 
-#[derive(Serialize, Validate)]
+#[derive(Serialize, Validate, ApiSchema)]
 pub struct LocationInfoResult {
     pub name: String,
     pub odp: i32,
@@ -79,7 +79,7 @@ pub struct LocationInfoResult {
     pub recovered: i32,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct UserConnect {
     #[validate(length(min = 1, message = "Device id can't be empty"))]
     pub device_id: String,
@@ -92,7 +92,7 @@ pub struct UserConnect {
     pub loc_name_full: String,
 }
 
-#[derive(Validate, Serialize, Deserialize)]
+#[derive(Validate, Serialize, Deserialize, ApiSchema)]
 pub struct UpdateLocation {
     #[validate(length(min = 1, max = 1000, message = "Device id can't be empty"))]
     pub device_id: String,
@@ -100,16 +100,19 @@ pub struct UpdateLocation {
     pub loc_name: String,
     #[validate(length(min = 0, max = 1000, message = "Location full name can't be empty"))]
     pub loc_name_full: String,
+    /// Apabila diset maka lokasi akan di-resolve dari batas wilayah.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct PandemicInfoDetail {
     pub total_cases: i32,
     pub total_deaths: i32,
     pub total_recovered: i32,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct OccupationInfoDetail {
     pub vac_total: i32,
     pub used_total: i32,
@@ -117,7 +120,7 @@ pub struct OccupationInfoDetail {
     pub last_updated: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct MapMarker {
     pub longitude: f64,
     pub latitude: f64,
@@ -128,7 +131,7 @@ pub struct MapMarker {
     pub occupation_detail: Option<OccupationInfoDetail>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct Record {
     pub id: ID,
     pub loc: String,
@@ -185,7 +188,7 @@ impl ToApiType<Record> for models::Record {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct Admin {
     pub id: ID,
     pub name: String,
@@ -230,7 +233,7 @@ impl ToApiType<Admin> for models::Admin {
 }
 
 /// Bentuk model akun di dalam database.
-#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, ApiSchema)]
 pub struct User {
     /// ID dari akun.
     pub id: i64,
@@ -311,7 +314,7 @@ impl From<models::User> for ApiResult<User> {
 }
 
 /// Bentuk model akun di dalam database.
-#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, ApiSchema)]
 pub struct Satgas {
     /// ID dari akun.
     pub id: i64,
@@ -395,7 +398,7 @@ impl ToApiType<Satgas> for models::User {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct ReportNote {
     pub id: ID,
     pub title: String,
@@ -433,7 +436,7 @@ impl ToApiType<ReportNote> for models::ReportNote {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct VillageData {
     pub id: ID,
     pub village_id: ID,
@@ -517,7 +520,7 @@ impl ToApiType<VillageData> for models::VillageData {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct DistrictData {
     pub id: ID,
     pub district_id: ID,
@@ -552,7 +555,7 @@ impl From<(models::DistrictData, models::District)> for DistrictData {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct SubReport {
     pub id: ID,
     pub creator_id: ID,
@@ -611,7 +614,7 @@ impl ToApiType<SubReport> for models::SubReport {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct District {
     pub id: ID,
     pub name: String,
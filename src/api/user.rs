@@ -8,39 +8,46 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use validator::Validate;
 
-use crate::crypto::{self, PublicKey, SecretKey, Signature};
+use crate::crypto::{self, PasshashScheme, PublicKey, SecretKey, Signature};
 
 use crate::{
     api,
     api::types::*,
     api::{
-        error::{bad_request, param_error, unauthorized},
+        admin::require_two_factor,
+        error::{coded_error, throttle, unauthorized},
         parsed_query::*,
         ApiResult, Error as ApiError, HttpRequest as ApiHttpRequest,
     },
     auth,
-    dao::{AuthDao, CityDao, VillageDao},
+    dao::{AuthDao, CityDao, Logs, PhoneVerificationDao, VillageDao},
     error::{Error, ErrorCode},
-    geolocator, models,
+    geolocator::Geocoder,
+    models,
+    phone_verification_dao::OTP_VALIDITY_SECS,
     prelude::*,
+    rate_limit, sms,
     types::AccountKind,
     util, ID,
 };
 
-#[derive(Deserialize, Validate)]
+/// Jumlah maksimal satgas untuk setiap desa.
+const MAX_SATGAS_PER_VILLAGE: i64 = 2;
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct SetUserSetting {
     pub key: String,
     pub value: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct UpdatePassword {
     pub old_password: String,
     pub new_password: String,
     pub verif_new_password: String,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct UpdateUser {
     #[validate(length(min = 2, max = 64))]
     pub full_name: String,
@@ -64,7 +71,38 @@ pub struct UpdateUser {
     pub is_medic: bool,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct ClientKey {
+    /// Public key ed25519 dalam format hex.
+    pub pub_key: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct RequestPhoneOtp {
+    #[validate(phone(message = "Invalid phone number: {}"))]
+    pub phone_num: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct VerifyPhone {
+    #[validate(phone(message = "Invalid phone number: {}"))]
+    pub phone_num: String,
+    #[validate(length(min = 4, max = 10))]
+    pub code: String,
+    /// Nama lengkap, hanya digunakan apabila akun di-upgrade (bukan digabung).
+    #[validate(length(min = 2, max = 100))]
+    pub full_name: Option<String>,
+}
+
+/// Hasil verifikasi nomor telepon.
+#[derive(Serialize, Deserialize, PartialEq, ApiSchema)]
+pub struct PhoneVerified {
+    pub user: User,
+    /// `true` apabila akun device digabungkan ke akun yang sudah ada dengan nomor yang sama.
+    pub merged: bool,
+}
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct UpdateAccesses {
     pub id: ID,
     pub accesses: Vec<String>,
@@ -82,7 +120,7 @@ impl PublicApi {
     // /// aktifasi menggunakan endpoint `/user/activate`.
     // #[api_endpoint(path = "/user/register", mutable, auth = "none")]
     // pub fn register_user(query: RegisterUser) -> ApiResult<String> {
-    //     let conn = state.db();
+    //     let conn = state.db()?;
     //     let schema = UserDao::new(&conn);
 
     //     schema
@@ -95,7 +133,7 @@ impl PublicApi {
     // /// Ini nantinya dijadikan link yang akan dikirimkan ke email pendaftar.
     // #[api_endpoint(path = "/user/activate", auth = "none", mutable)]
     // pub fn activate_user(query: ActivateUser) -> ApiResult<types::User> {
-    //     let conn = state.db();
+    //     let conn = state.db()?;
     //     let schema = UserDao::new(&conn);
     //     let user = schema.activate_registered_user(query.token)?;
     //     schema.set_password(user.id, &query.password)?;
@@ -111,95 +149,52 @@ impl PublicApi {
         Ok(ApiResult::success(current_user.into()))
     }
 
-    /// Update current user.
+    /// Update current user dan daftarkan sebagai satgas menggunakan kode area kota.
+    ///
+    /// Deprecated: gunakan undangan satgas `/invitation/v1/accept`,
+    /// hanya aktif apabila `security.allow_area_code` diset (masa transisi aplikasi lama).
     #[api_endpoint(path = "/me/update", auth = "required", mutable)]
     pub fn update_current_user(query: UpdateUser) -> ApiResult<()> {
+        if !state.config().security.allow_area_code {
+            return coded_error(ErrorCode::AreaCodeDisabled);
+        }
         query.validate()?;
-        let conn = state.db();
-        let dao = UserDao::new(&conn);
+        let conn = state.db()?;
 
         let city = CityDao::new(&conn).get_by_area_code(&query.area_code)?;
 
         if city.is_none() {
-            return param_error("Kode area tidak benar, mohon periksa kembali.");
+            return coded_error(ErrorCode::InvalidAreaCode);
         }
         let city = city.unwrap();
 
-        let loc_info = match geolocator::ll_to_address(query.latitude, query.longitude, &conn) {
-            Ok(loc_info) => Some(loc_info),
-            Err(e) => {
-                error!("Cannot get geo locator. {}", e);
-                None
-            }
-        };
-
         // get village id
         let village = match VillageDao::new(&conn).get_by_name_str(&city.province, &city.name, &query.village)
         {
             Ok(a) => a,
             Err(_) => {
-                return param_error(&format!(
-                    "Tidak dapat menemukan data untuk desa {}",
-                    query.village
+                return Err(ApiError::Catalogue(
+                    ErrorCode::VillageNotFound,
+                    vec![query.village.to_owned()],
                 ))
             }
         };
 
-        // check maks 2 satgas per daerah
-        {
-            use crate::schema::users::{self, dsl};
-            let village = format!("village_id={}", village.id);
-            if users::table
-                .filter(dsl::meta.contains(&vec![":satgas:", &village]))
-                .select(diesel::dsl::count(dsl::id))
-                .first::<i64>(&conn)
-                .map_err(Error::from)?
-                > 1
-            {
-                return bad_request("Maksimal 2 satgas per desa");
-            }
-        }
-
-        let mut meta: Vec<String> = Vec::new();
-
-        // daftarkan sebagai satgas dan set metadata-nya
-        meta.push(":satgas:".to_string());
-        meta.push(format!("village={}", query.village));
-        meta.push(format!("village_id={}", village.id));
-        meta.push(format!("district_id={}", village.district_id));
-        meta.push(format!("district={}", village.district_name));
-        meta.push(format!("area_code={}", city.area_code));
-        meta.push(format!("city_name={}", city.name));
-        meta.push(format!("city_id={}", city.id));
-        meta.push(format!("province_name={}", city.province));
-        meta.push(format!("address_by_area_code={}/{}", city.province, city.name));
-        meta.push("access.data".to_string());
-        meta.push("access.data_person".to_string());
-        if query.is_medic {
-            meta.push(":medic:".to_string());
-            meta.push("access.village_data".to_string());
-        }
-
-        if let Some(loc_info) = loc_info {
-            meta.push(format!(
-                "address={}/{}/{}/{}/{}/{}",
-                loc_info.country_code,
-                loc_info.province,
-                loc_info.city.unwrap_or("?".to_string()),
-                loc_info.district.unwrap_or("?".to_string()),
-                loc_info.subdistrict.unwrap_or("?".to_string()),
-                loc_info.label
-            ));
-        }
-
-        dao.update_user_info(
+        register_satgas(
+            &conn,
+            state.geocoder(),
             current_user.id,
-            &query.full_name,
-            &query.email,
-            &query.phone_num,
-            query.latitude,
-            query.longitude,
-            meta.iter().map(|a| a.as_str()).collect::<Vec<&str>>(),
+            &SatgasProfile {
+                full_name: &query.full_name,
+                email: &query.email,
+                phone_num: &query.phone_num,
+                latitude: query.latitude,
+                longitude: query.longitude,
+                is_medic: query.is_medic,
+            },
+            &city,
+            &village,
+            &[format!("area_code={}", city.area_code)],
         )?;
         Ok(ApiResult::success(()))
     }
@@ -207,21 +202,22 @@ impl PublicApi {
     /// Update password.
     #[api_endpoint(path = "/update_password", auth = "required", mutable, accessor=["user", "admin"])]
     pub fn update_password(query: UpdatePassword) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
-        if query.new_password.len() < 6 {
-            param_error("New password too short, please use min 6 characters long")?;
-        }
-
         if query.new_password != query.verif_new_password {
-            param_error("Password verification didn't match")?;
+            coded_error(ErrorCode::PasswordMismatch)?;
         }
 
+        auth::PasswordPolicy::from_config(&state.config().password).check(
+            &query.new_password,
+            &[&current_user.full_name, &current_user.email],
+        )?;
+
         let auth_dao = auth::AuthDao::new(&conn);
 
-        let user_passhash = auth_dao.get_passhash(AccountKind::User, current_user.id)?;
-        if !crypto::password_match(&query.old_password, &user_passhash) {
+        let scheme = PasshashScheme::current(&state.config().password);
+        if !auth_dao.verify_password(AccountKind::User, current_user.id, &query.old_password, scheme)? {
             warn!(
                 "user `{}` try to update password using wrong password",
                 &current_user.id
@@ -229,7 +225,7 @@ impl PublicApi {
             Err(ApiError::Unauthorized)?
         }
 
-        dao.set_password(current_user.id, &query.new_password)?;
+        dao.set_password(current_user.id, &query.new_password, scheme)?;
 
         Ok(ApiResult::success(()))
     }
@@ -237,7 +233,7 @@ impl PublicApi {
     /// Mendapatkan data user berdasarkan ID.
     #[api_endpoint(path = "/detail", auth = "required", accessor = "admin")]
     pub fn user_detail(query: IdQuery) -> ApiResult<User> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         let is_super_admin = current_admin.id == 1;
@@ -256,7 +252,7 @@ impl PublicApi {
     /// Mendapatkan data user berdasarkan ID.
     #[api_endpoint(path = "/satgas/detail", auth = "required", accessor = "admin")]
     pub fn satgas_detail(query: IdQuery) -> ApiResult<Satgas> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         dao.get_by_id(query.id)
@@ -268,7 +264,7 @@ impl PublicApi {
     #[api_endpoint(path = "/update_accesses", auth = "required", mutable, accessor = "admin")]
     pub fn update_accesses(query: UpdateAccesses) -> ApiResult<()> {
         use crate::schema::users::{self, dsl};
-        let conn = state.db();
+        let conn = state.db()?;
 
         if current_admin.id != 1 {
             return unauthorized();
@@ -295,7 +291,7 @@ impl PublicApi {
     /// Delete satgas.
     #[api_endpoint(path = "/satgas/delete", auth = "required", mutable, accessor = "admin")]
     pub fn delete_satgas(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
         let user = dao.get_by_id(query.id)?;
         if current_admin.id != 1 {
@@ -307,6 +303,8 @@ impl PublicApi {
             }
         }
 
+        require_two_factor(state, &conn, &current_admin, req)?;
+
         dao.mark_deleted(user.id)?;
 
         // clear up user's access token
@@ -318,7 +316,7 @@ impl PublicApi {
     /// Delete satgas.
     #[api_endpoint(path = "/satgas/block", auth = "required", mutable, accessor = "admin")]
     pub fn block_satgas(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
         let user = dao.get_by_id(query.id)?;
         if current_admin.id != 1 {
@@ -338,7 +336,7 @@ impl PublicApi {
     /// Delete satgas.
     #[api_endpoint(path = "/satgas/unblock", auth = "required", mutable, accessor = "admin")]
     pub fn unblock_satgas(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
         let user = dao.get_by_id(query.id)?;
         if current_admin.id != 1 {
@@ -355,13 +353,142 @@ impl PublicApi {
         Ok(ApiResult::success(()))
     }
 
+    /// Kirim kode OTP via SMS untuk verifikasi nomor telepon,
+    /// digunakan untuk meng-upgrade akun device menjadi akun dengan nomor telepon terverifikasi.
+    #[api_endpoint(path = "/me/phone/request_otp", auth = "required", mutable)]
+    pub fn request_phone_otp(query: RequestPhoneOtp) -> ApiResult<()> {
+        query.validate()?;
+
+        let conn = state.db()?;
+
+        if current_user.is_phone_verified() && current_user.phone_num == query.phone_num {
+            return coded_error(ErrorCode::PhoneAlreadyVerified);
+        }
+
+        let identities = [
+            rate_limit::Identity::ip(api::client_ip(req)),
+            rate_limit::Identity::account(&query.phone_num),
+        ];
+        throttle(rate_limit::Endpoint::PhoneOtp, &identities)?;
+        rate_limit::record(
+            &state.config().rate_limit,
+            rate_limit::Endpoint::PhoneOtp,
+            &identities,
+        );
+
+        let code = PhoneVerificationDao::new(&conn).create(current_user.id, &query.phone_num)?;
+
+        sms::send(
+            &query.phone_num,
+            &format!(
+                "Kode verifikasi Pandemia: {}. Berlaku {} menit, jangan berikan kode ini kepada siapapun.",
+                code,
+                OTP_VALIDITY_SECS / 60
+            ),
+        );
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Verifikasi nomor telepon menggunakan kode OTP.
+    /// Apabila nomor telah digunakan oleh akun lain, akun device ini digabungkan ke akun tersebut
+    /// (settings, device, data gejala dan sesi dipindahkan), sehingga beberapa device
+    /// bisa menggunakan satu akun yang sama.
+    #[api_endpoint(path = "/me/phone/verify", auth = "required", mutable)]
+    pub fn verify_phone(query: VerifyPhone) -> ApiResult<PhoneVerified> {
+        query.validate()?;
+
+        let conn = state.db()?;
+        let dao = UserDao::new(&conn);
+
+        if !PhoneVerificationDao::new(&conn).verify(current_user.id, &query.phone_num, &query.code)? {
+            return coded_error(ErrorCode::InvalidVerificationCode);
+        }
+
+        let existing = match dao.get_by_phone_num(&query.phone_num) {
+            Ok(user) => Some(user),
+            Err(Error::Storage(diesel::result::Error::NotFound)) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let (user, merged) = match existing {
+            Some(target) if target.id != current_user.id => {
+                if target.is_blocked() || target.is_deleted() {
+                    return coded_error(ErrorCode::PhoneAccountUnusable);
+                }
+                if !current_user.is_device_account() {
+                    return coded_error(ErrorCode::PhoneAlreadyUsed);
+                }
+
+                // merge dan set nomor telepon harus atomic, agar akun device tidak
+                // tergabung tanpa nomor telepon terverifikasi apabila salah satunya gagal
+                let user = conn.build_transaction().read_write().run::<_, Error, _>(|| {
+                    dao.merge_into(&current_user, &target)?;
+                    dao.set_verified_phone(&target, &query.phone_num, None)
+                })?;
+
+                Logs::new(&conn).write(
+                    &format!("device account {} merged into {}", current_user.id, target.id),
+                    target.id,
+                );
+
+                (user, true)
+            }
+            _ => {
+                let user = dao.set_verified_phone(
+                    &current_user,
+                    &query.phone_num,
+                    query.full_name.as_ref().map(|a| a.trim()),
+                )?;
+                (user, false)
+            }
+        };
+
+        Ok(ApiResult::success(PhoneVerified {
+            user: user.into(),
+            merged,
+        }))
+    }
+
+    /// Daftarkan public key client untuk signed request.
+    /// Setelah ada key terdaftar, request ke endpoint `signed` wajib ditandatangani,
+    /// mendaftarkan key tambahan harus ditandatangani menggunakan key yang sudah ada.
+    #[api_endpoint(path = "/me/key/register", auth = "required", mutable, signed)]
+    pub fn register_client_key(query: ClientKey) -> ApiResult<()> {
+        let conn = state.db()?;
+
+        let pub_key = hex::decode(query.pub_key.trim())
+            .ok()
+            .filter(|a| crypto::is_valid_public_key(a))
+            .and_then(|a| PublicKey::from_slice(&a));
+
+        let pub_key = match pub_key {
+            Some(pub_key) => pub_key,
+            None => return coded_error(ErrorCode::InvalidPublicKey),
+        };
+
+        UserDao::new(&conn).register_client_key(current_user.id, &pub_key)?;
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Nonaktifkan public key client.
+    #[api_endpoint(path = "/me/key/revoke", auth = "required", mutable, signed)]
+    pub fn revoke_client_key(query: ClientKey) -> ApiResult<()> {
+        let conn = state.db()?;
+
+        UserDao::new(&conn).revoke_client_key(current_user.id, &query.pub_key.trim().to_lowercase())?;
+
+        Ok(ApiResult::success(()))
+    }
+
     /// Register and connect current account to event push notif (FCM).
     /// Parameter `app_id` adalah app id dari client app.
     #[api_endpoint(path = "/me/connect/create", auth = "required", mutable)]
     pub fn connect_create(query: UserConnect) -> ApiResult<()> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         dao.create_user_connect(
@@ -381,7 +508,7 @@ impl PublicApi {
     pub fn connect_remove(query: UserConnect) -> ApiResult<()> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         dao.remove_user_connect(&query.device_id, &query.provider_name, &query.app_id)?;
@@ -391,21 +518,31 @@ impl PublicApi {
     /// Update latest location
     #[api_endpoint(path = "/me/update_loc", auth = "required", mutable)]
     pub fn update_location(query: UpdateLocation) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
-        dao.update_user_location(
-            &current_user,
-            &query.device_id,
-            &query.loc_name,
-            &query.loc_name_full,
-        )?;
+        match (query.latitude, query.longitude) {
+            (Some(latitude), Some(longitude)) => dao.update_user_location_ll(
+                &current_user,
+                &query.device_id,
+                latitude,
+                longitude,
+                &query.loc_name,
+                &query.loc_name_full,
+            )?,
+            _ => dao.update_user_location(
+                &current_user,
+                &query.device_id,
+                &query.loc_name,
+                &query.loc_name_full,
+            )?,
+        }
         Ok(ApiResult::success(()))
     }
 
     /// Mendapatkan data akun.
     #[api_endpoint(path = "/user/info", accessor = "admin", auth = "required")]
     pub fn user_info(query: IdQuery) -> ApiResult<db::User> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         dao.get_by_id(query.id)
@@ -417,7 +554,7 @@ impl PublicApi {
     #[api_endpoint(path = "/update_setting", auth = "required", mutable)]
     pub fn update_setting(query: SetUserSetting) -> ApiResult<()> {
         use crate::schema::users::{self, dsl};
-        let conn = state.db();
+        let conn = state.db()?;
 
         current_user.set_setting(&query.key, &query.value, &conn)?;
 
@@ -427,7 +564,7 @@ impl PublicApi {
     /// Get user settings.
     #[api_endpoint(path = "/settings", auth = "required")]
     pub fn get_settings(query: ()) -> ApiResult<Vec<models::UserSetting>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let user_settings = current_user.get_settings(&conn)?;
 
         Ok(ApiResult::success(user_settings))
@@ -436,7 +573,7 @@ impl PublicApi {
     /// Listing user
     #[api_endpoint(path = "/users", auth = "required", accessor = "admin")]
     pub fn list_user(query: QueryEntries) -> ApiResult<EntriesResult<User>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         let entries = dao.get_users(query.offset, query.limit)?;
@@ -451,7 +588,7 @@ impl PublicApi {
     /// Mencari akun berdasarkan kata kunci.
     #[api_endpoint(path = "/search", auth = "required", accessor = "admin")]
     pub fn search_users(query: QueryEntries) -> ApiResult<EntriesResult<User>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         if query.query.is_none() {
@@ -471,7 +608,7 @@ impl PublicApi {
     /// Mencari akun satgas berdasarkan kata kunci.
     #[api_endpoint(path = "/satgas/search", auth = "required", accessor = "admin")]
     pub fn satgas_search(query: QueryEntries) -> ApiResult<EntriesResult<Satgas>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         let keyword = query.query.unwrap_or("".to_string());
@@ -522,7 +659,7 @@ impl PrivateApi {
     /// Listing user
     #[api_endpoint(path = "/users", auth = "none")]
     pub fn list_user(query: QueryEntries) -> ApiResult<EntriesResult<db::User>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         let entries = dao.get_users(query.offset, query.limit)?;
@@ -534,7 +671,7 @@ impl PrivateApi {
     /// Mencari akun berdasarkan kata kunci.
     #[api_endpoint(path = "/search", auth = "none")]
     pub fn search_users(query: QueryEntries) -> ApiResult<EntriesResult<db::User>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         if query.query.is_none() {
@@ -551,7 +688,7 @@ impl PrivateApi {
     /// Mendapatkan jumlah akun secara keseluruhan.
     #[api_endpoint(path = "/user/count")]
     pub fn user_count(state: &AppState, query: ()) -> ApiResult<i64> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         dao.count().map(ApiResult::success).map_err(From::from)
@@ -560,7 +697,7 @@ impl PrivateApi {
     /// Mendapatkan data akun.
     #[api_endpoint(path = "/user/info", auth = "required")]
     pub fn user_info(query: IdQuery) -> ApiResult<db::User> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = UserDao::new(&conn);
 
         dao.get_by_id(query.id)
@@ -568,3 +705,95 @@ impl PrivateApi {
             .map_err(From::from)
     }
 }
+
+/// Data profil yang diisi oleh user ketika mendaftar sebagai satgas.
+pub(crate) struct SatgasProfile<'a> {
+    pub full_name: &'a str,
+    pub email: &'a Option<String>,
+    pub phone_num: &'a str,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub is_medic: bool,
+}
+
+/// Daftarkan user sebagai satgas untuk desa tertentu dan set metadata-nya,
+/// digunakan oleh alur kode area maupun alur undangan.
+pub(crate) fn register_satgas(
+    conn: &PgConnection,
+    geocoder: &dyn Geocoder,
+    user_id: ID,
+    profile: &SatgasProfile,
+    city: &models::City,
+    village: &models::Village,
+    extra_meta: &[String],
+) -> api::Result<()> {
+    // check maks satgas per daerah
+    {
+        use crate::schema::users::{self, dsl};
+        let village = format!("village_id={}", village.id);
+        if users::table
+            .filter(dsl::meta.contains(&vec![":satgas:", &village]))
+            .select(diesel::dsl::count(dsl::id))
+            .first::<i64>(conn)
+            .map_err(Error::from)?
+            >= MAX_SATGAS_PER_VILLAGE
+        {
+            return Err(ApiError::Catalogue(
+                ErrorCode::SatgasLimitReached,
+                vec![MAX_SATGAS_PER_VILLAGE.to_string()],
+            ));
+        }
+    }
+
+    let loc_info = match geocoder.ll_to_address(profile.latitude, profile.longitude, conn) {
+        Ok(loc_info) => Some(loc_info),
+        Err(e) => {
+            error!("Cannot get geo locator. {}", e);
+            None
+        }
+    };
+
+    let mut meta: Vec<String> = Vec::new();
+
+    // daftarkan sebagai satgas dan set metadata-nya
+    meta.push(":satgas:".to_string());
+    meta.push(format!("village={}", village.name));
+    meta.push(format!("village_id={}", village.id));
+    meta.push(format!("district_id={}", village.district_id));
+    meta.push(format!("district={}", village.district_name));
+    meta.push(format!("city_name={}", city.name));
+    meta.push(format!("city_id={}", city.id));
+    meta.push(format!("province_name={}", city.province));
+    meta.push(format!("address_by_area_code={}/{}", city.province, city.name));
+    meta.push("access.data".to_string());
+    meta.push("access.data_person".to_string());
+    if profile.is_medic {
+        meta.push(":medic:".to_string());
+        meta.push("access.village_data".to_string());
+    }
+    meta.extend(extra_meta.iter().cloned());
+
+    if let Some(loc_info) = loc_info {
+        meta.push(format!(
+            "address={}/{}/{}/{}/{}/{}",
+            loc_info.country_code,
+            loc_info.province,
+            loc_info.city.unwrap_or("?".to_string()),
+            loc_info.district.unwrap_or("?".to_string()),
+            loc_info.subdistrict.unwrap_or("?".to_string()),
+            loc_info.label
+        ));
+    }
+
+    UserDao::new(conn).update_user_info(
+        user_id,
+        profile.full_name,
+        profile.email,
+        profile.phone_num,
+        profile.latitude,
+        profile.longitude,
+        meta.iter().map(|a| a.as_str()).collect::<Vec<&str>>(),
+    )?;
+
+    Ok(())
+}
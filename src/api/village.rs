@@ -27,12 +27,12 @@ use crate::{
 };
 
 /// New Village query
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct NewVillage {
     pub name: String,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct AddVillage {
     #[validate(length(min = 2, max = 1000))]
     pub name: String,
@@ -46,7 +46,7 @@ pub struct AddVillage {
     pub longitude: String,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct RecordUpdate {
     #[validate(range(min = 1, max = 9999999999))]
     pub id: i64,
@@ -70,20 +70,20 @@ pub struct RecordUpdate {
     // pub last_updated: NaiveDateTime,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct AddVillageData {
     pub village_id: i64,
     pub record: RecordUpdate,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct CommitData {
     province: Option<String>,
     city: Option<String>,
     records: Vec<RecordUpdate>,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct VillageSearch {
     pub query: Option<String>,
     /// Scope is location path (loc_path), eg: /ID/Jawa Tengah/Wonosobo
@@ -103,23 +103,16 @@ impl PublicApi {
     #[api_endpoint(path = "/add", auth = "required", mutable, accessor = "admin")]
     pub fn add_village(query: AddVillage) -> ApiResult<models::Village> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDao::new(&conn);
 
         let city = CityDao::new(&conn)
             .get_by_name(&query.province, &query.city)
-            .map_err(|_| {
-                ApiError::BadRequest(83913, format!("Tidak ada kota/kab dengan nama {}", query.city))
-            })?;
+            .map_err(|_| ApiError::Catalogue(ErrorCode::CityNotFound, vec![query.city.to_owned()]))?;
 
         let district = DistrictDao::new(&conn)
             .get_by_name(city.id, &query.district)
-            .map_err(|_| {
-                ApiError::InvalidParameter(
-                    404,
-                    format!("Tidak dapat menemukan kecamatan dengan nama {}", query.district),
-                )
-            })?;
+            .map_err(|_| ApiError::Catalogue(ErrorCode::DistrictNotFound, vec![query.district.to_owned()]))?;
 
         let village = dao.create(
             &query.name,
@@ -139,7 +132,7 @@ impl PublicApi {
     #[api_endpoint(path = "/search", auth = "optional")]
     pub fn search_villages(query: VillageSearch) -> ApiResult<EntriesResult<models::Village>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDao::new(&conn);
 
         // let parq = parse_query(&query.query);
@@ -162,7 +155,7 @@ impl PublicApi {
     /// Delete village.
     #[api_endpoint(path = "/delete", auth = "required", mutable, accessor = "admin")]
     pub fn delete_village(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
 
         let dao = VillageDao::new(&conn);
 
@@ -175,7 +168,7 @@ impl PublicApi {
     #[api_endpoint(path = "/village_data/search", auth = "none")]
     pub fn search_village_data(query: QueryEntries) -> ApiResult<EntriesResult<VillageData>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDataDao::new(&conn);
 
         let parq = match query.query.as_ref() {
@@ -208,7 +201,7 @@ impl PublicApi {
     // /// Rest API endpoint untuk menambahkan village baru.
     // #[api_endpoint(path = "/add", mutable, auth = "required")]
     // pub fn add_village(query: NewVillage) -> ApiResult<models::Village> {
-    //     let conn = state.db();
+    //     let conn = state.db()?;
     //     let dao = VillageDao::new(&conn);
 
     //     // @TODO(*): Add parameter checking here
@@ -231,7 +224,7 @@ impl PublicApi {
     pub fn add_village_data(query: AddVillageData) -> ApiResult<VillageData> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDataDao::new(&conn);
 
         // check apakah data untuk village_id ini sudah ada belum
@@ -247,7 +240,7 @@ impl PublicApi {
         if let Some(current_admin) = current_admin {
             if !current_admin.has_access("update_village_data") {
                 if current_admin.get_city_id().unwrap_or(0) != village.city_id {
-                    return param_error("Anda tidak memiliki akses untuk kab/kota ini");
+                    return coded_error(ErrorCode::NoAccessToCity);
                 }
             }
             last_updated_by_id = current_admin.id;
@@ -327,7 +320,7 @@ impl PublicApi {
         accessor = "admin,user"
     )]
     pub fn delete_village_data(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDataDao::new(&conn);
 
         let d = dao.get_by_id(query.id)?;
@@ -360,7 +353,7 @@ impl PublicApi {
     /// Mendapatkan data village berdasarkan ID.
     #[api_endpoint(path = "/detail", auth = "required")]
     pub fn village_detail(query: IdQuery) -> ApiResult<models::Village> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDao::new(&conn);
 
         dao.get_by_id(query.id)
@@ -369,12 +362,12 @@ impl PublicApi {
     }
 
     /// Update multiple records at once.
-    #[api_endpoint(path = "/commit", auth = "required", mutable, accessor = "admin,user")]
+    #[api_endpoint(path = "/commit", auth = "required", mutable, accessor = "admin,user", signed)]
     pub fn commit(query: CommitData) -> ApiResult<()> {
         use crate::schema::village_data::{self, dsl};
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
 
         let locs = query
             .records
@@ -523,7 +516,7 @@ impl PublicApi {
     #[api_endpoint(path = "/village_address", auth = "required", accessor = "user,admin")]
     pub fn search_village_address(query: VillageSearch) -> ApiResult<EntriesResult<VillageAddress>> {
         query.validate()?;
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDao::new(&conn);
 
         let sresult = dao.search(
@@ -547,7 +540,7 @@ impl PublicApi {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct VillageAddress {
     pub village_id: i64,
     pub address: String,
@@ -561,7 +554,7 @@ impl PrivateApi {
     /// Mendapatkan daftar village
     #[api_endpoint(path = "/list", auth = "required")]
     pub fn list_village(query: QueryEntries) -> ApiResult<EntriesResult<models::Village>> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDao::new(&conn);
 
         let entries = dao.get_villages(query.offset, query.limit)?;
@@ -573,7 +566,7 @@ impl PrivateApi {
     /// Mendapatkan jumlah village secara keseluruhan.
     #[api_endpoint(path = "/count", auth = "required")]
     pub fn village_count(state: &AppState, query: ()) -> ApiResult<i64> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDao::new(&conn);
 
         dao.count().map(ApiResult::success).map_err(From::from)
@@ -582,7 +575,7 @@ impl PrivateApi {
     /// Delete village.
     #[api_endpoint(path = "/delete", auth = "required", mutable = "true")]
     pub fn delete_village(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = VillageDao::new(&conn);
 
         dao.delete_by_id(query.id)?;
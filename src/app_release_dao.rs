@@ -0,0 +1,156 @@
+//! Dao implementation for AppRelease
+//!
+//! Registry rilis aplikasi mobile per platform, digunakan untuk mengecek
+//! apakah client perlu (atau wajib) melakukan update.
+
+use diesel::prelude::*;
+
+use crate::{models::AppRelease, result::Result, schema::app_releases, util, ID};
+
+use std::cmp::Ordering;
+
+#[derive(Insertable)]
+#[table_name = "app_releases"]
+struct NewAppRelease<'a> {
+    pub platform: &'a str,
+    pub version: &'a str,
+    pub min_supported_version: &'a str,
+    pub notes: &'a str,
+    pub download_url: &'a str,
+    pub force_update: bool,
+}
+
+/// Hasil pengecekan versi client terhadap rilis yang terdaftar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateCheck<'a> {
+    /// Rilis terbaru untuk platform client.
+    pub latest: &'a AppRelease,
+    /// Client wajib update, karena versinya di bawah versi minimal yang didukung
+    /// atau ada rilis yang lebih baru yang ditandai force update.
+    pub force: bool,
+}
+
+/// Cari update untuk client dengan versi `version`,
+/// `None` apabila client telah menggunakan versi terbaru atau versinya tidak valid.
+pub fn check_update<'a>(releases: &'a [AppRelease], version: &str) -> Option<UpdateCheck<'a>> {
+    let current = util::parse_version(version)?;
+
+    let newer: Vec<&AppRelease> = releases
+        .iter()
+        .filter(|r| {
+            util::parse_version(&r.version)
+                .map(|v| v > current)
+                .unwrap_or(false)
+        })
+        .collect();
+
+    let latest = newer
+        .iter()
+        .cloned()
+        .max_by(|a, b| util::compare_version(&a.version, &b.version).unwrap_or(Ordering::Equal))?;
+
+    let below_min = util::parse_version(&latest.min_supported_version)
+        .map(|min| current < min)
+        .unwrap_or(false);
+
+    Some(UpdateCheck {
+        latest,
+        force: below_min || newer.iter().any(|r| r.force_update),
+    })
+}
+
+/// Data Access Object for AppRelease
+#[derive(Dao)]
+#[table_name = "app_releases"]
+pub struct AppReleaseDao<'a> {
+    db: &'a PgConnection,
+}
+
+impl<'a> AppReleaseDao<'a> {
+    /// Daftarkan rilis baru untuk platform.
+    pub fn create(
+        &self,
+        platform: &str,
+        version: &str,
+        min_supported_version: &str,
+        notes: &str,
+        download_url: &str,
+        force_update: bool,
+    ) -> Result<AppRelease> {
+        match util::compare_version(min_supported_version, version) {
+            None => return Err(param_err!("Invalid version format, expected eg: 1.2.3")),
+            Some(Ordering::Greater) => {
+                return Err(param_err!(
+                    "Minimum supported version is greater than the version"
+                ))
+            }
+            _ => (),
+        }
+
+        diesel::insert_into(app_releases::table)
+            .values(&NewAppRelease {
+                platform: &platform.trim().to_lowercase(),
+                version: version.trim(),
+                min_supported_version: min_supported_version.trim(),
+                notes,
+                download_url,
+                force_update,
+            })
+            .get_result(self.db)
+            .map_err(From::from)
+    }
+
+    /// Mendapatkan semua rilis untuk platform, diurutkan dari yang terbaru didaftarkan.
+    pub fn get_by_platform(&self, platform: &str) -> Result<Vec<AppRelease>> {
+        use crate::schema::app_releases::dsl;
+
+        dsl::app_releases
+            .filter(dsl::platform.eq(platform.trim().to_lowercase()))
+            .order(dsl::ts.desc())
+            .load(self.db)
+            .map_err(From::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(version: &str, min: &str, force_update: bool) -> AppRelease {
+        AppRelease {
+            id: 0,
+            platform: "android".to_string(),
+            version: version.to_string(),
+            min_supported_version: min.to_string(),
+            notes: "".to_string(),
+            download_url: "".to_string(),
+            force_update,
+            ts: util::now(),
+        }
+    }
+
+    #[test]
+    fn test_check_update() {
+        let releases = vec![
+            release("1.0.0", "1.0.0", false),
+            release("1.1.0", "1.0.0", true),
+            release("1.2.0", "1.0.0", false),
+        ];
+
+        assert_eq!(check_update(&releases, "1.2.0"), None);
+        assert_eq!(check_update(&releases, "invalid"), None);
+
+        let update = check_update(&releases, "1.1.0").expect("update");
+        assert_eq!(update.latest.version, "1.2.0");
+        assert!(!update.force);
+
+        // melewatkan rilis 1.1.0 yang ditandai force update
+        let update = check_update(&releases, "1.0.0").expect("update");
+        assert_eq!(update.latest.version, "1.2.0");
+        assert!(update.force);
+
+        let releases = vec![release("2.0.0", "1.5.0", false)];
+        assert!(check_update(&releases, "1.4.9").expect("update").force);
+        assert!(!check_update(&releases, "1.5.0").expect("update").force);
+    }
+}
@@ -6,6 +6,8 @@ use chrono::{Duration, NaiveDateTime};
 use diesel::{pg::PgConnection, prelude::*};
 
 use crate::{
+    config::PasswordConfig,
+    crypto::{self, PasshashScheme},
     error::{Error as PandemiaError, ErrorCode},
     models::AdminAccessToken,
     models::{AccessToken, User},
@@ -13,9 +15,20 @@ use crate::{
     schema::{access_tokens, admin_access_tokens},
     token,
     types::AccountKind,
-    util, ID,
+    util,
+    valid::Refreshable,
+    ID,
 };
 
+/// Masa berlaku access token.
+pub const ACCESS_TOKEN_VALID_DAYS: i64 = 7;
+/// Masa berlaku refresh token, diperpanjang setiap kali digunakan.
+pub const REFRESH_TOKEN_VALID_DAYS: i64 = 60;
+/// Sesi yang tidak digunakan lebih dari ini dianggap expired.
+pub const SESSION_IDLE_DAYS: i64 = 30;
+/// Interval minimum untuk update `last_used`, supaya tidak menulis ke DB di setiap request.
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
 #[doc(hidden)]
 #[derive(Insertable)]
 #[table_name = "access_tokens"]
@@ -24,6 +37,12 @@ pub struct NewAccessToken<'a> {
     pub user_id: ID,
     pub created: NaiveDateTime,
     pub valid_thru: NaiveDateTime,
+    pub device_id: &'a str,
+    pub platform: &'a str,
+    pub ip_address: &'a str,
+    pub last_used: NaiveDateTime,
+    pub refresh_token: &'a str,
+    pub refresh_valid_thru: NaiveDateTime,
 }
 
 #[doc(hidden)]
@@ -34,6 +53,98 @@ pub struct NewAdminAccessToken<'a> {
     pub admin_id: ID,
     pub created: NaiveDateTime,
     pub valid_thru: NaiveDateTime,
+    pub device_id: &'a str,
+    pub platform: &'a str,
+    pub ip_address: &'a str,
+    pub last_used: NaiveDateTime,
+    pub refresh_token: &'a str,
+    pub refresh_valid_thru: NaiveDateTime,
+}
+
+/// Informasi device/client dari sebuah sesi.
+#[derive(Default)]
+pub struct SessionInfo<'a> {
+    /// ID device (khusus untuk mobile app).
+    pub device_id: &'a str,
+    /// Platform atau user agent dari client.
+    pub platform: &'a str,
+    /// Alamat IP client.
+    pub ip_address: &'a str,
+}
+
+/// Kebijakan password, diatur melalui konfigurasi `password`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordPolicy {
+    /// Panjang minimal password.
+    pub min_length: usize,
+    /// Wajib mengandung huruf dan angka.
+    pub require_mixed: bool,
+    /// Wajib mengandung simbol.
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self::from_config(&PasswordConfig::default())
+    }
+}
+
+impl PasswordPolicy {
+    /// Kebijakan dari konfigurasi.
+    pub fn from_config(config: &PasswordConfig) -> Self {
+        Self {
+            min_length: config.min_length,
+            require_mixed: config.require_mixed,
+            require_symbol: config.require_symbol,
+        }
+    }
+
+    /// Periksa password terhadap kebijakan, `personal` berisi data akun (nama, email)
+    /// yang tidak boleh digunakan sebagai password.
+    pub fn check(&self, password: &str, personal: &[&str]) -> Result<()> {
+        if password.chars().count() < self.min_length {
+            return Err(param_err!(format!(
+                "Password too short, please use min {} characters long",
+                self.min_length
+            )));
+        }
+        if password.len() > 500 {
+            return Err(param_err!("Password too long"));
+        }
+        if self.require_mixed
+            && !(password.chars().any(|c| c.is_alphabetic()) && password.chars().any(|c| c.is_numeric()))
+        {
+            return Err(param_err!("Password must contain both letters and numbers"));
+        }
+        if self.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+            return Err(param_err!("Password must contain at least one symbol"));
+        }
+        let lower = password.to_lowercase();
+        if personal
+            .iter()
+            .map(|a| a.trim().to_lowercase())
+            .flat_map(|a| {
+                // untuk email, bagian sebelum `@` juga diperiksa
+                let local = a.split('@').next().unwrap_or("").to_string();
+                vec![a, local]
+            })
+            .filter(|a| a.len() >= 3)
+            .any(|a| lower.contains(&a))
+        {
+            return Err(param_err!("Password must not contain your name or email"));
+        }
+        Ok(())
+    }
+}
+
+/// Refresh token disimpan di DB dalam bentuk hash, plaintext hanya diberikan ke client.
+fn hash_refresh_token(refresh_token: &str) -> String {
+    crypto::sha256_hash(refresh_token.trim().as_bytes()).to_hex()
+}
+
+fn days_from(now: NaiveDateTime, days: i64) -> NaiveDateTime {
+    now.checked_add_signed(Duration::days(days))
+        .expect("cannot assign valid_thru time")
 }
 
 /// Untuk mengoperasikan skema data di database
@@ -78,50 +189,273 @@ impl<'a> AuthDao<'a> {
 
     /// Generate access token, this write access token into database.
     pub fn generate_access_token(&self, user_id: ID) -> Result<AccessToken> {
+        self.create_session(user_id, &SessionInfo::default())
+    }
+
+    /// Generate admin access token
+    pub fn generate_admin_access_token(&self, admin_id: ID) -> Result<AccessToken> {
+        self.create_admin_session(admin_id, &SessionInfo::default())
+    }
+
+    /// Buat sesi baru untuk user, menghasilkan access token beserta refresh token-nya.
+    pub fn create_session(&self, user_id: ID, info: &SessionInfo) -> Result<AccessToken> {
         use crate::schema::access_tokens::{self, dsl};
 
-        let now = chrono::Utc::now().naive_utc();
+        let now = util::now();
 
-        // hapus token lama kalau ada
-        // diesel::delete(dsl::access_tokens.filter(dsl::user_id.eq(user_id))).execute(self.db)?;
+        // satu device hanya memiliki satu sesi aktif
+        if !info.device_id.is_empty() {
+            diesel::delete(
+                dsl::access_tokens.filter(dsl::user_id.eq(user_id).and(dsl::device_id.eq(info.device_id))),
+            )
+            .execute(self.db)?;
+        }
 
-        let token = NewAccessToken {
-            token: &token::generate_access_token(),
-            user_id,
-            created: now,
-            valid_thru: now
-                .checked_add_signed(Duration::days(7))
-                .expect("cannot assign valid_thru time"),
-        };
+        let refresh_token = token::generate_access_token();
 
-        diesel::insert_into(access_tokens::table)
-            .values(&token)
-            .get_result(self.db)
-            .map_err(From::from)
+        let mut session: AccessToken = diesel::insert_into(access_tokens::table)
+            .values(&NewAccessToken {
+                token: &token::generate_access_token(),
+                user_id,
+                created: now,
+                valid_thru: days_from(now, ACCESS_TOKEN_VALID_DAYS),
+                device_id: info.device_id,
+                platform: info.platform,
+                ip_address: info.ip_address,
+                last_used: now,
+                refresh_token: &hash_refresh_token(&refresh_token),
+                refresh_valid_thru: days_from(now, REFRESH_TOKEN_VALID_DAYS),
+            })
+            .get_result(self.db)?;
+
+        session.refresh_token = refresh_token;
+        Ok(session)
     }
 
-    /// Generate admin access token
-    pub fn generate_admin_access_token(&self, admin_id: ID) -> Result<AccessToken> {
+    /// Buat sesi baru untuk admin.
+    pub fn create_admin_session(&self, admin_id: ID, info: &SessionInfo) -> Result<AccessToken> {
         use crate::schema::admin_access_tokens::{self, dsl};
 
-        let now = chrono::Utc::now().naive_utc();
+        let now = util::now();
 
-        // hapus token lama kalau ada
-        // diesel::delete(dsl::admin_access_tokens.filter(dsl::admin_id.eq(admin_id))).execute(self.db)?;
+        let refresh_token = token::generate_access_token();
 
-        let token = NewAdminAccessToken {
-            token: &token::generate_access_token(),
-            admin_id,
-            created: now,
-            valid_thru: now
-                .checked_add_signed(Duration::days(7))
-                .expect("cannot assign valid_thru time"),
-        };
+        let mut session: AccessToken = diesel::insert_into(admin_access_tokens::table)
+            .values(&NewAdminAccessToken {
+                token: &token::generate_access_token(),
+                admin_id,
+                created: now,
+                valid_thru: days_from(now, ACCESS_TOKEN_VALID_DAYS),
+                device_id: info.device_id,
+                platform: info.platform,
+                ip_address: info.ip_address,
+                last_used: now,
+                refresh_token: &hash_refresh_token(&refresh_token),
+                refresh_valid_thru: days_from(now, REFRESH_TOKEN_VALID_DAYS),
+            })
+            .get_result(self.db)?;
 
-        diesel::insert_into(admin_access_tokens::table)
-            .values(&token)
-            .get_result(self.db)
-            .map_err(From::from)
+        session.refresh_token = refresh_token;
+        Ok(session)
+    }
+
+    /// Perbarui access token menggunakan refresh token,
+    /// access token & refresh token lama tidak berlaku lagi (rotasi).
+    ///
+    /// Rotasi dilakukan dalam satu query `UPDATE ... WHERE refresh_token = <lama>`,
+    /// sehingga refresh token yang sama tidak bisa digunakan dua kali secara bersamaan.
+    pub fn refresh_session(&self, refresh_token: &str, ip_address: &str) -> Result<AccessToken> {
+        use crate::schema::access_tokens::dsl;
+
+        let now = util::now();
+        let old_hash = hash_refresh_token(refresh_token);
+        let new_refresh_token = token::generate_access_token();
+
+        let session: Option<AccessToken> = diesel::update(
+            dsl::access_tokens.filter(
+                dsl::refresh_token
+                    .eq(&old_hash)
+                    .and(dsl::refresh_valid_thru.ge(now))
+                    .and(dsl::last_used.ge(days_from(now, -SESSION_IDLE_DAYS))),
+            ),
+        )
+        .set((
+            dsl::token.eq(token::generate_access_token()),
+            dsl::valid_thru.eq(days_from(now, ACCESS_TOKEN_VALID_DAYS)),
+            dsl::refresh_token.eq(hash_refresh_token(&new_refresh_token)),
+            dsl::refresh_valid_thru.eq(days_from(now, REFRESH_TOKEN_VALID_DAYS)),
+            dsl::ip_address.eq(ip_address),
+            dsl::last_used.eq(now),
+        ))
+        .get_result(self.db)
+        .optional()?;
+
+        match session {
+            Some(mut session) => {
+                session.refresh_token = new_refresh_token;
+                Ok(session)
+            }
+            None => {
+                // refresh token tidak dikenal atau sudah expired, hapus sesi yang sudah expired
+                diesel::delete(dsl::access_tokens.filter(dsl::refresh_token.eq(&old_hash)))
+                    .execute(self.db)?;
+                Err(PandemiaError::Unauthorized)
+            }
+        }
+    }
+
+    /// Perbarui access token admin menggunakan refresh token.
+    pub fn refresh_admin_session(&self, refresh_token: &str, ip_address: &str) -> Result<AccessToken> {
+        use crate::schema::admin_access_tokens::dsl;
+
+        let now = util::now();
+        let old_hash = hash_refresh_token(refresh_token);
+        let new_refresh_token = token::generate_access_token();
+
+        let session: Option<AccessToken> = diesel::update(
+            dsl::admin_access_tokens.filter(
+                dsl::refresh_token
+                    .eq(&old_hash)
+                    .and(dsl::refresh_valid_thru.ge(now))
+                    .and(dsl::last_used.ge(days_from(now, -SESSION_IDLE_DAYS))),
+            ),
+        )
+        .set((
+            dsl::token.eq(token::generate_access_token()),
+            dsl::valid_thru.eq(days_from(now, ACCESS_TOKEN_VALID_DAYS)),
+            dsl::refresh_token.eq(hash_refresh_token(&new_refresh_token)),
+            dsl::refresh_valid_thru.eq(days_from(now, REFRESH_TOKEN_VALID_DAYS)),
+            dsl::ip_address.eq(ip_address),
+            dsl::last_used.eq(now),
+        ))
+        .get_result(self.db)
+        .optional()?;
+
+        match session {
+            Some(mut session) => {
+                session.refresh_token = new_refresh_token;
+                Ok(session)
+            }
+            None => {
+                diesel::delete(dsl::admin_access_tokens.filter(dsl::refresh_token.eq(&old_hash)))
+                    .execute(self.db)?;
+                Err(PandemiaError::Unauthorized)
+            }
+        }
+    }
+
+    /// Catat waktu terakhir sesi digunakan.
+    pub fn touch_session(&self, access_token: &AccessToken, ip_address: &str) -> Result<()> {
+        use crate::schema::access_tokens::dsl;
+
+        if (util::now() - access_token.last_used).num_seconds() < TOUCH_INTERVAL_SECS {
+            return Ok(());
+        }
+
+        diesel::update(dsl::access_tokens.filter(dsl::id.eq(access_token.id)))
+            .set((dsl::last_used.eq(util::now()), dsl::ip_address.eq(ip_address)))
+            .execute(self.db)?;
+
+        Ok(())
+    }
+
+    /// Catat waktu terakhir sesi admin digunakan.
+    pub fn touch_admin_session(&self, access_token: &AdminAccessToken, ip_address: &str) -> Result<()> {
+        use crate::schema::admin_access_tokens::dsl;
+
+        if (util::now() - access_token.last_used).num_seconds() < TOUCH_INTERVAL_SECS {
+            return Ok(());
+        }
+
+        diesel::update(dsl::admin_access_tokens.filter(dsl::id.eq(access_token.id)))
+            .set((dsl::last_used.eq(util::now()), dsl::ip_address.eq(ip_address)))
+            .execute(self.db)?;
+
+        Ok(())
+    }
+
+    /// Mendapatkan daftar sesi aktif milik user.
+    pub fn get_sessions(&self, user_id: ID) -> Result<Vec<AccessToken>> {
+        use crate::schema::access_tokens::dsl;
+
+        let sessions: Vec<AccessToken> = dsl::access_tokens
+            .filter(dsl::user_id.eq(user_id))
+            .order(dsl::last_used.desc())
+            .load(self.db)?;
+
+        Ok(sessions.into_iter().filter(|a| !a.refresh_expired()).collect())
+    }
+
+    /// Mendapatkan daftar sesi aktif milik admin.
+    pub fn get_admin_sessions(&self, admin_id: ID) -> Result<Vec<AdminAccessToken>> {
+        use crate::schema::admin_access_tokens::dsl;
+
+        let sessions: Vec<AdminAccessToken> = dsl::admin_access_tokens
+            .filter(dsl::admin_id.eq(admin_id))
+            .order(dsl::last_used.desc())
+            .load(self.db)?;
+
+        Ok(sessions.into_iter().filter(|a| !a.refresh_expired()).collect())
+    }
+
+    /// Beri nama pada sesi milik user.
+    pub fn rename_session(&self, user_id: ID, session_id: ID, name: &str) -> Result<()> {
+        use crate::schema::access_tokens::dsl;
+
+        let updated =
+            diesel::update(dsl::access_tokens.filter(dsl::id.eq(session_id).and(dsl::user_id.eq(user_id))))
+                .set(dsl::name.eq(name))
+                .execute(self.db)?;
+
+        if updated == 0 {
+            return Err(PandemiaError::NotFound("Session not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Beri nama pada sesi milik admin.
+    pub fn rename_admin_session(&self, admin_id: ID, session_id: ID, name: &str) -> Result<()> {
+        use crate::schema::admin_access_tokens::dsl;
+
+        let updated = diesel::update(
+            dsl::admin_access_tokens.filter(dsl::id.eq(session_id).and(dsl::admin_id.eq(admin_id))),
+        )
+        .set(dsl::name.eq(name))
+        .execute(self.db)?;
+
+        if updated == 0 {
+            return Err(PandemiaError::NotFound("Session not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Cabut (hapus) satu sesi milik user.
+    pub fn revoke_session(&self, user_id: ID, session_id: ID) -> Result<()> {
+        use crate::schema::access_tokens::dsl;
+
+        let deleted =
+            diesel::delete(dsl::access_tokens.filter(dsl::id.eq(session_id).and(dsl::user_id.eq(user_id))))
+                .execute(self.db)?;
+
+        if deleted == 0 {
+            return Err(PandemiaError::NotFound("Session not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Cabut (hapus) satu sesi milik admin.
+    pub fn revoke_admin_session(&self, admin_id: ID, session_id: ID) -> Result<()> {
+        use crate::schema::admin_access_tokens::dsl;
+
+        let deleted = diesel::delete(
+            dsl::admin_access_tokens.filter(dsl::id.eq(session_id).and(dsl::admin_id.eq(admin_id))),
+        )
+        .execute(self.db)?;
+
+        if deleted == 0 {
+            return Err(PandemiaError::NotFound("Session not found".to_string()));
+        }
+        Ok(())
     }
 
     /// Mendapatkan passhash
@@ -150,6 +484,68 @@ impl<'a> AuthDao<'a> {
         }
     }
 
+    /// Verifikasi password akun berdasarkan versi skema hash yang tersimpan.
+    /// Apabila cocok dan hash masih menggunakan skema lain, password di-hash ulang
+    /// menggunakan skema `scheme` (skema terbaru) secara transparan.
+    pub fn verify_password(
+        &self,
+        kind: AccountKind,
+        id: ID,
+        password: &str,
+        scheme: PasshashScheme,
+    ) -> Result<bool> {
+        let (passhash, ver): (String, i32) = match kind {
+            AccountKind::User => {
+                use crate::schema::user_passhash::dsl;
+                dsl::user_passhash
+                    .filter(dsl::user_id.eq(id).and(dsl::deprecated.eq(false)))
+                    .select((dsl::passhash, dsl::ver))
+                    .get_result(self.db)?
+            }
+            AccountKind::Admin => {
+                use crate::schema::admin_passhash::dsl;
+                dsl::admin_passhash
+                    .filter(dsl::admin_id.eq(id).and(dsl::deprecated.eq(false)))
+                    .select((dsl::passhash, dsl::ver))
+                    .get_result(self.db)?
+            }
+        };
+
+        if !crypto::password_match_ver(password, &passhash, ver) {
+            return Ok(false);
+        }
+
+        if scheme.ver() != ver {
+            debug!(
+                "rehash password of account {} from ver {} to {}",
+                id,
+                ver,
+                scheme.ver()
+            );
+            let new_passhash = scheme.hash(password);
+            match kind {
+                AccountKind::User => {
+                    use crate::schema::user_passhash::dsl;
+                    diesel::update(
+                        dsl::user_passhash.filter(dsl::user_id.eq(id).and(dsl::deprecated.eq(false))),
+                    )
+                    .set((dsl::passhash.eq(&new_passhash), dsl::ver.eq(scheme.ver())))
+                    .execute(self.db)?;
+                }
+                AccountKind::Admin => {
+                    use crate::schema::admin_passhash::dsl;
+                    diesel::update(
+                        dsl::admin_passhash.filter(dsl::admin_id.eq(id).and(dsl::deprecated.eq(false))),
+                    )
+                    .set((dsl::passhash.eq(&new_passhash), dsl::ver.eq(scheme.ver())))
+                    .execute(self.db)?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
     /// Periksa apakah akun terhubung dengan spesifik passhash.
     /// Mengembalikan true apabila valid (ada).
     pub fn valid_passhash(&self, user_id: ID, passhash: &str) -> bool {
@@ -182,4 +578,48 @@ impl<'a> AuthDao<'a> {
         diesel::delete(dsl::admin_access_tokens.filter(dsl::admin_id.eq(admin_id))).execute(self.db)?;
         Ok(())
     }
+
+    /// Hapus access token user & admin yang sudah expired dan tidak bisa di-refresh lagi,
+    /// mengembalikan jumlah token yang dihapus.
+    pub fn purge_expired_tokens(&self) -> Result<usize> {
+        use crate::schema::{access_tokens::dsl as at, admin_access_tokens::dsl as aat};
+
+        let now = util::now();
+        let users = diesel::delete(
+            at::access_tokens.filter(at::valid_thru.lt(now).and(at::refresh_valid_thru.lt(now))),
+        )
+        .execute(self.db)?;
+        let admins = diesel::delete(
+            aat::admin_access_tokens.filter(aat::valid_thru.lt(now).and(aat::refresh_valid_thru.lt(now))),
+        )
+        .execute(self.db)?;
+
+        Ok(users + admins)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_policy() {
+        let policy = PasswordPolicy::default();
+        let personal = ["Robin", "robin.h@pandemia.net"];
+
+        assert!(policy.check("a1b2c3d4", &personal).is_ok());
+        assert!(policy.check("a1b2c3", &personal).is_err());
+        assert!(policy.check("abcdefgh", &personal).is_err());
+        assert!(policy.check("12345678", &personal).is_err());
+        assert!(policy.check("xrobin2020", &personal).is_err());
+        assert!(policy.check("Robin.H123", &personal).is_err());
+
+        let policy = PasswordPolicy {
+            min_length: 6,
+            require_mixed: false,
+            require_symbol: true,
+        };
+        assert!(policy.check("abcdef", &[]).is_err());
+        assert!(policy.check("abc#ef", &[]).is_ok());
+    }
 }
@@ -1,17 +1,65 @@
 #[macro_use]
 extern crate log;
 extern crate dotenv;
-extern crate env_logger;
 
-use pandemia::monitor;
+use pandemia::auth::PasswordPolicy;
+use pandemia::config::{self, Config};
+use pandemia::crypto::PasshashScheme;
+use pandemia::dao::{AdminDao, AuthDao, DistrictDataDao, RecordDao, SubReportDao};
+use pandemia::monitor::{self, DataMonitor};
 use pandemia::prelude::*;
 use pandemia::service::load_services;
+use pandemia::{admin_dao, event_handler, eventstream, metrics, region_import, trace};
 
-use std::env;
+use diesel::pg::PgConnection;
+
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+    ops::Deref,
+    process,
+    sync::Arc,
+    time::Duration,
+};
+
+const USAGE: &str = r#"Usage: pandemia_server [COMMAND]
+
+Tanpa COMMAND server API & monitor akan dijalankan.
+
+Commands:
+    import-regions <FILE> [--dry-run]      Buat/update kota, kecamatan & desa dari file CSV
+    encrypt-sub-reports                    Enkripsi data sub report yang masih plaintext
+    super-admin [--name N] [--email E] [--phone P]
+                                           Buat/reset super admin, password dibaca dari env
+                                           PANDEMIA_ADMIN_PASSWORD atau stdin
+    scrape <SOURCE>                        Ambil data sekali dari SOURCE:
+                                           worldometers, kawalcorona, jatengprov
+    recalculate-district-data              Hitung ulang semua district_data dari village_data
+    rebuild-map-markers                    Bangun ulang map marker dari record terbaru
+    purge-tokens                           Hapus access token yang sudah expired
+    help                                   Tampilkan bantuan ini
+"#;
 
 fn main() {
     dotenv::dotenv().ok();
-    env_logger::init();
+
+    let config = config::init(Config::load().unwrap_or_else(|e| {
+        eprintln!("Invalid configuration: {}", e);
+        process::exit(1);
+    }));
+    trace::init_logger(&config.log);
+
+    let args: Vec<String> = env::args().collect();
+    if args.len() > 1 {
+        run_command(&config, &args[1..]);
+        return;
+    }
+
+    // data pribadi sub report tidak bisa disimpan tanpa data key
+    if config.security.data_key.is_none() {
+        eprintln!("Invalid configuration: security.data_key (PANDEMIA_DATA_KEY) must be set");
+        process::exit(1);
+    }
 
     println!(
         r#"
@@ -37,19 +85,196 @@ ____________    __      ____________,
 
     trace!("starting up...");
 
+    pandemia::init(&config);
+
     let services = load_services();
 
-    let public_listening_address =
-        env::var("PANDEMIA_PUBLIC_LISTENING").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
-    let private_listening_address =
-        env::var("PANDEMIA_PRIVATE_LISTENING").unwrap_or_else(|_| "127.0.0.1:9090".to_string());
+    let api_config = ServiceApiConfig::new(
+        vec![
+            ApiServer::new(ApiAccess::Public, config.api.public_listening.clone()),
+            ApiServer::new(ApiAccess::Private, config.api.private_listening.clone()),
+        ],
+        config.clone(),
+    );
+
+    monitor::start_monitors(&config);
+
+    if let Err(e) = api::start(ApiAggregator::new(services), api_config) {
+        eprintln!("ERROR: {}", e);
+        process::exit(1);
+    }
+}
+
+fn connect(config: &Arc<Config>) -> impl Deref<Target = PgConnection> {
+    pandemia::init(config);
+    AppState::with_config(config.clone()).db().unwrap_or_else(|e| {
+        eprintln!("Cannot connect to database: {}", e);
+        process::exit(1);
+    })
+}
+
+/// Ambil nilai dari opsi `--name value`.
+fn flag<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+fn read_password() -> String {
+    if let Ok(password) = env::var("PANDEMIA_ADMIN_PASSWORD") {
+        return password;
+    }
+    print!("Password: ");
+    io::stdout().flush().ok();
+    let mut password = String::new();
+    io::stdin().lock().read_line(&mut password).unwrap_or_else(|e| {
+        eprintln!("Cannot read password: {}", e);
+        process::exit(1);
+    });
+    password.trim_end_matches(|c| c == '\r' || c == '\n').to_string()
+}
+
+fn run_command(config: &Arc<Config>, args: &[String]) {
+    match args[0].as_str() {
+        "import-regions" => {
+            let path = match args.get(1) {
+                Some(path) => path,
+                None => {
+                    eprintln!("Usage: pandemia_server import-regions <FILE> [--dry-run]");
+                    process::exit(1);
+                }
+            };
+            let dry_run = args.iter().any(|a| a == "--dry-run");
+
+            let content = fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("Cannot read {}: {}", path, e);
+                process::exit(1);
+            });
 
-    let config = ServiceApiConfig::new(vec![
-        ApiServer::new(ApiAccess::Public, public_listening_address),
-        ApiServer::new(ApiAccess::Private, private_listening_address),
-    ]);
+            let conn = connect(config);
+            match region_import::import_regions(&content, dry_run, &conn) {
+                Ok(report) => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report).expect("Cannot serialize report")
+                ),
+                Err(e) => {
+                    eprintln!("Import failed: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "encrypt-sub-reports" => {
+            let conn = connect(config);
+            match SubReportDao::new(&conn).encrypt_legacy() {
+                Ok(count) => println!("{} sub reports encrypted", count),
+                Err(e) => {
+                    eprintln!("Encryption failed: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "super-admin" => {
+            let conn = connect(config);
+            let current = AdminDao::new(&conn).get_by_id(admin_dao::SUPER_ADMIN_ID).ok();
+            let name = flag(args, "--name")
+                .map(str::to_string)
+                .or_else(|| current.as_ref().map(|a| a.name.clone()))
+                .unwrap_or_else(|| "Admin".to_string());
+            let email = flag(args, "--email")
+                .map(str::to_string)
+                .or_else(|| current.as_ref().map(|a| a.email.clone()))
+                .unwrap_or_else(|| "admin@pandemia.net".to_string());
+            let phone_num = flag(args, "--phone")
+                .map(str::to_string)
+                .or_else(|| current.as_ref().map(|a| a.phone_num.clone()))
+                .unwrap_or_else(|| "+62456".to_string());
 
-    monitor::start_monitors();
+            let password = read_password();
+            let policy = PasswordPolicy::from_config(&config.password);
+            if let Err(e) = policy.check(&password, &[&name, &email]) {
+                eprintln!("Invalid password: {}", e);
+                process::exit(1);
+            }
 
-    api::start(ApiAggregator::new(services), config);
+            let scheme = PasshashScheme::current(&config.password);
+            match AdminDao::new(&conn).reset_super_admin(&name, &email, &phone_num, &password, scheme) {
+                Ok(admin) => println!("Super admin {} <{}> updated", admin.name, admin.email),
+                Err(e) => {
+                    eprintln!("Cannot update super admin: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "scrape" => {
+            let source = args.get(1).map(String::as_str).unwrap_or_default();
+            let conn = connect(config);
+            let result = match source {
+                "worldometers" => DataMonitor::check_worldometers(&conn),
+                "kawalcorona" => DataMonitor::check_indonesian_provinces(&conn),
+                "jatengprov" => DataMonitor::get_jatengprov(&conn),
+                _ => {
+                    eprintln!("Usage: pandemia_server scrape <worldometers|kawalcorona|jatengprov>");
+                    process::exit(1);
+                }
+            };
+            if let Err(e) = metrics::scraped(source, result) {
+                eprintln!("Scrape {} failed: {}", source, e);
+                process::exit(1);
+            }
+            // tunggu event `NewRecordUpdate` (map marker, push notif, dll) selesai diproses
+            if !eventstream::wait_idle(Duration::from_secs(60)) {
+                warn!("{} events still pending", eventstream::pending());
+            }
+            println!("Scrape {} done", source);
+        }
+        "recalculate-district-data" => {
+            let conn = connect(config);
+            // updater 0 = system
+            match DistrictDataDao::new(&conn).recalculate_all(0) {
+                Ok(count) => println!("{} districts recalculated", count),
+                Err(e) => {
+                    eprintln!("Recalculation failed: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "rebuild-map-markers" => {
+            let conn = connect(config);
+            let records = RecordDao::new(&conn).get_all_latest().unwrap_or_else(|e| {
+                eprintln!("Cannot get records: {}", e);
+                process::exit(1);
+            });
+            let mut failed = 0;
+            for record in &records {
+                if let Err(e) = event_handler::update_map_marker(record, &conn) {
+                    eprintln!("Cannot update map marker for {}: {}", record.loc, e);
+                    failed += 1;
+                }
+            }
+            println!(
+                "{} map markers rebuilt, {} failed",
+                records.len() - failed,
+                failed
+            );
+            if failed > 0 {
+                process::exit(1);
+            }
+        }
+        "purge-tokens" => {
+            let conn = connect(config);
+            match AuthDao::new(&conn).purge_expired_tokens() {
+                Ok(count) => println!("{} expired tokens purged", count),
+                Err(e) => {
+                    eprintln!("Purge failed: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "help" | "--help" | "-h" => print!("{}", USAGE),
+        x => {
+            eprintln!("Unknown command: {}\n\n{}", x, USAGE);
+            process::exit(1);
+        }
+    }
 }
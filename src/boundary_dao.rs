@@ -0,0 +1,426 @@
+//! Dao implementation for Boundary
+//!
+//! Boundary adalah batas wilayah administratif (kota/kab, kecamatan, desa)
+//! dalam bentuk polygon, digunakan untuk me-resolve koordinat latitude/longitude
+//! menjadi desa/kecamatan/kota di sisi server tanpa bergantung pada
+//! penamaan dari geocoder eksternal.
+
+use chrono::prelude::*;
+use diesel::prelude::*;
+use serde_json::Value as JsonValue;
+
+use crate::{
+    dao::{CityDao, DistrictDao, VillageDao},
+    models::{Boundary, City, District, Village},
+    result::Result,
+    schema::boundaries,
+    types::LocKind,
+    util, ID,
+};
+
+#[derive(Insertable, AsChangeset)]
+#[table_name = "boundaries"]
+struct NewBoundary<'a> {
+    pub kind: i16,
+    pub ref_id: ID,
+    pub name: &'a str,
+    pub geometry: &'a str,
+    pub min_lat: f64,
+    pub min_lng: f64,
+    pub max_lat: f64,
+    pub max_lng: f64,
+    pub meta: &'a Vec<&'a str>,
+}
+
+/// Titik dalam urutan GeoJSON yaitu (longitude, latitude).
+type Point = (f64, f64);
+
+/// Geometry polygon hasil parsing dari GeoJSON,
+/// mendukung tipe `Polygon` dan `MultiPolygon`.
+#[derive(Debug)]
+pub struct Geometry {
+    /// Daftar polygon, masing-masing berisi ring luar diikuti ring lubang (holes).
+    polygons: Vec<Vec<Vec<Point>>>,
+}
+
+impl Geometry {
+    /// Parse geometry dari GeoJSON object (Geometry atau Feature).
+    pub fn from_geojson(value: &JsonValue) -> Result<Self> {
+        let value = if value["type"] == "Feature" {
+            &value["geometry"]
+        } else {
+            value
+        };
+
+        let polygons = match value["type"].as_str() {
+            Some("Polygon") => vec![parse_polygon(&value["coordinates"])?],
+            Some("MultiPolygon") => match value["coordinates"].as_array() {
+                Some(polys) => polys.iter().map(parse_polygon).collect::<Result<Vec<_>>>()?,
+                None => fail!("Invalid MultiPolygon coordinates"),
+            },
+            Some(t) => fail!(format!("Unsupported geometry type: {}", t)),
+            None => fail!("No geometry type"),
+        };
+
+        if polygons.is_empty() {
+            fail!("Empty geometry");
+        }
+
+        Ok(Geometry { polygons })
+    }
+
+    /// Bounding box dari geometry dalam bentuk (min_lat, min_lng, max_lat, max_lng).
+    pub fn bbox(&self) -> (f64, f64, f64, f64) {
+        let mut min_lat = std::f64::MAX;
+        let mut min_lng = std::f64::MAX;
+        let mut max_lat = std::f64::MIN;
+        let mut max_lng = std::f64::MIN;
+        for ring in self.polygons.iter().filter_map(|p| p.first()) {
+            for &(lng, lat) in ring {
+                min_lat = min_lat.min(lat);
+                min_lng = min_lng.min(lng);
+                max_lat = max_lat.max(lat);
+                max_lng = max_lng.max(lng);
+            }
+        }
+        (min_lat, min_lng, max_lat, max_lng)
+    }
+
+    /// Periksa apakah titik lat/long berada di dalam geometry ini.
+    pub fn contains(&self, lat: f64, lng: f64) -> bool {
+        self.polygons.iter().any(|rings| {
+            let mut rings = rings.iter();
+            match rings.next() {
+                Some(outer) => {
+                    ring_contains(outer, lng, lat) && !rings.any(|hole| ring_contains(hole, lng, lat))
+                }
+                None => false,
+            }
+        })
+    }
+}
+
+fn parse_polygon(value: &JsonValue) -> Result<Vec<Vec<Point>>> {
+    let rings = match value.as_array() {
+        Some(rings) => rings,
+        None => fail!("Invalid polygon coordinates"),
+    };
+    let mut rv = Vec::with_capacity(rings.len());
+    for ring in rings {
+        let points = match ring.as_array() {
+            Some(points) => points,
+            None => fail!("Invalid polygon ring"),
+        };
+        let mut ring_points = Vec::with_capacity(points.len());
+        for point in points {
+            match (point[0].as_f64(), point[1].as_f64()) {
+                (Some(lng), Some(lat)) => ring_points.push((lng, lat)),
+                _ => fail!("Invalid point coordinate"),
+            }
+        }
+        if ring_points.len() < 3 {
+            fail!("Polygon ring must have at least 3 points");
+        }
+        rv.push(ring_points);
+    }
+    Ok(rv)
+}
+
+/// Ray casting point-in-polygon.
+fn ring_contains(ring: &[Point], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut j = ring.len() - 1;
+    for i in 0..ring.len() {
+        let (xi, yi) = ring[i];
+        let (xj, yj) = ring[j];
+        if ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi) + xi) {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Hasil resolusi koordinat menjadi wilayah administratif.
+#[derive(Default)]
+pub struct ResolvedArea {
+    /// Kota/kabupaten.
+    pub city: Option<City>,
+    /// Kecamatan.
+    pub district: Option<District>,
+    /// Desa.
+    pub village: Option<Village>,
+}
+
+impl ResolvedArea {
+    /// Apakah tidak ada wilayah yang berhasil di-resolve.
+    pub fn is_empty(&self) -> bool {
+        self.city.is_none()
+    }
+
+    /// Nama kota/kabupaten, digunakan sebagai `latest_loc` agar sama dengan
+    /// nama lokasi yang dikirim oleh client, lokasi lengkap ada di [ResolvedArea::loc_path].
+    pub fn loc_name(&self) -> &str {
+        self.city.as_ref().map(|a| a.name.as_str()).unwrap_or("")
+    }
+
+    /// Location path lengkap, eg: `Indonesia/Jawa Tengah/Wonosobo/Kertek/Kapencar`.
+    pub fn loc_path(&self) -> String {
+        let city = match self.city.as_ref() {
+            Some(city) => city,
+            None => return "".to_string(),
+        };
+        let country = if city.country_code == "ID" {
+            "Indonesia"
+        } else {
+            city.country_code.as_str()
+        };
+        let mut path = vec![country, city.province.as_str(), city.name.as_str()];
+        if let Some(district) = self.district.as_ref() {
+            path.push(district.name.as_str());
+            if let Some(village) = self.village.as_ref() {
+                path.push(village.name.as_str());
+            }
+        }
+        path.join("/")
+    }
+}
+
+/// Hasil import boundary dari GeoJSON.
+#[derive(Serialize, Deserialize, PartialEq, ApiSchema)]
+pub struct ImportResult {
+    /// Jumlah feature yang berhasil di-import.
+    pub imported: usize,
+    /// Daftar feature yang dilewati beserta alasannya.
+    pub skipped: Vec<String>,
+}
+
+/// Data Access Object for Boundary
+#[derive(Dao)]
+#[table_name = "boundaries"]
+pub struct BoundaryDao<'a> {
+    db: &'a PgConnection,
+}
+
+impl<'a> BoundaryDao<'a> {
+    /// Simpan boundary, apabila sudah ada untuk kind & ref_id yang sama maka
+    /// geometry-nya akan diperbaharui.
+    pub fn upsert(&self, kind: LocKind, ref_id: ID, name: &str, geometry: &JsonValue) -> Result<Boundary> {
+        use crate::schema::boundaries::{self, dsl};
+
+        let (min_lat, min_lng, max_lat, max_lng) = Geometry::from_geojson(geometry)?.bbox();
+        let geometry = geometry.to_string();
+
+        let new_boundary = NewBoundary {
+            kind: kind as i16,
+            ref_id,
+            name,
+            geometry: &geometry,
+            min_lat,
+            min_lng,
+            max_lat,
+            max_lng,
+            meta: &vec![],
+        };
+
+        self.db
+            .build_transaction()
+            .read_write()
+            .run::<_, crate::error::Error, _>(|| {
+                let existing = dsl::boundaries
+                    .filter(dsl::kind.eq(kind as i16).and(dsl::ref_id.eq(ref_id)))
+                    .select(dsl::id)
+                    .first::<ID>(self.db)
+                    .optional()?;
+
+                match existing {
+                    Some(id) => diesel::update(dsl::boundaries.filter(dsl::id.eq(id)))
+                        .set((&new_boundary, dsl::ts.eq(util::now())))
+                        .get_result(self.db)
+                        .map_err(From::from),
+                    None => diesel::insert_into(boundaries::table)
+                        .values(&new_boundary)
+                        .get_result(self.db)
+                        .map_err(From::from),
+                }
+            })
+    }
+
+    /// Mencari boundary dengan jenis `kind` yang mengandung titik lat/long.
+    pub fn find_containing(&self, kind: LocKind, lat: f64, lng: f64) -> Result<Option<Boundary>> {
+        use crate::schema::boundaries::{self, dsl};
+
+        // prefilter menggunakan bounding box, baru kemudian diperiksa polygon-nya.
+        let candidates = dsl::boundaries
+            .filter(
+                dsl::kind
+                    .eq(kind as i16)
+                    .and(dsl::min_lat.le(lat))
+                    .and(dsl::max_lat.ge(lat))
+                    .and(dsl::min_lng.le(lng))
+                    .and(dsl::max_lng.ge(lng)),
+            )
+            .load::<Boundary>(self.db)?;
+
+        for boundary in candidates {
+            let geometry: JsonValue = serde_json::from_str(&boundary.geometry)?;
+            match Geometry::from_geojson(&geometry) {
+                Ok(geometry) => {
+                    if geometry.contains(lat, lng) {
+                        return Ok(Some(boundary));
+                    }
+                }
+                Err(e) => error!("Invalid geometry for boundary {}: {}", boundary.id, e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Resolve koordinat lat/long menjadi desa, kecamatan, dan kota/kab.
+    /// Pencarian dimulai dari level paling spesifik (desa).
+    pub fn resolve(&self, lat: f64, lng: f64) -> Result<ResolvedArea> {
+        let mut area = ResolvedArea::default();
+
+        if let Some(b) = self.find_containing(LocKind::Village, lat, lng)? {
+            let village = VillageDao::new(self.db).get_by_id(b.ref_id)?;
+            area.district = DistrictDao::new(self.db).get_by_id(village.district_id).ok();
+            area.city = CityDao::new(self.db).get_by_id(village.city_id).ok();
+            area.village = Some(village);
+        } else if let Some(b) = self.find_containing(LocKind::District, lat, lng)? {
+            let district = DistrictDao::new(self.db).get_by_id(b.ref_id)?;
+            area.city = CityDao::new(self.db).get_by_id(district.city_id).ok();
+            area.district = Some(district);
+        } else if let Some(b) = self.find_containing(LocKind::City, lat, lng)? {
+            area.city = CityDao::new(self.db).get_by_id(b.ref_id).ok();
+        }
+
+        Ok(area)
+    }
+
+    /// Mendapatkan ID kecamatan/desa dari location path record,
+    /// eg: `/Indonesia/Jawa Tengah/Wonosobo/Kertek` atau `/Indonesia/Jawa Tengah/Wonosobo/Kertek/Kapencar`.
+    pub fn area_id_by_path(&self, kind: LocKind, loc_path: &str) -> Result<Option<ID>> {
+        let parts: Vec<&str> = loc_path.trim_matches('/').split('/').collect();
+
+        match (kind, parts.as_slice()) {
+            (LocKind::District, [_, province, city, district]) => {
+                let city = CityDao::new(self.db).get_by_name(province, city)?;
+                Ok(Some(DistrictDao::new(self.db).get_by_name(city.id, district)?.id))
+            }
+            (LocKind::Village, [_, province, city, district, village]) => {
+                let city = CityDao::new(self.db).get_by_name(province, city)?;
+                let district = DistrictDao::new(self.db).get_by_name(city.id, district)?;
+                Ok(Some(
+                    VillageDao::new(self.db)
+                        .get_by_name_id(city.id, district.id, village)?
+                        .id,
+                ))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Import boundary dari GeoJSON FeatureCollection.
+    ///
+    /// Setiap feature harus memiliki properties `ref_id` atau nama wilayah
+    /// (`province`, `city`, `district`, `village`) sesuai `kind` yang di-import.
+    pub fn import_geojson(&self, kind: LocKind, collection: &JsonValue) -> Result<ImportResult> {
+        let features = match collection["features"].as_array() {
+            Some(features) => features,
+            None => fail!("Not a GeoJSON FeatureCollection"),
+        };
+
+        let mut result = ImportResult {
+            imported: 0,
+            skipped: vec![],
+        };
+
+        for (i, feature) in features.iter().enumerate() {
+            let props = &feature["properties"];
+            match self.find_ref(kind, props) {
+                Ok((ref_id, name)) => match self.upsert(kind, ref_id, &name, &feature["geometry"]) {
+                    Ok(_) => result.imported += 1,
+                    Err(e) => result.skipped.push(format!("feature #{} ({}): {}", i, name, e)),
+                },
+                Err(e) => result.skipped.push(format!("feature #{}: {}", i, e)),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Mendapatkan ref_id dan nama wilayah dari properties GeoJSON feature.
+    fn find_ref(&self, kind: LocKind, props: &JsonValue) -> Result<(ID, String)> {
+        let prop = |key: &str| props[key].as_str().unwrap_or("").trim().to_string();
+
+        if let Some(ref_id) = props["ref_id"].as_i64() {
+            let name = match kind {
+                LocKind::City => CityDao::new(self.db).get_by_id(ref_id)?.name,
+                LocKind::District => DistrictDao::new(self.db).get_by_id(ref_id)?.name,
+                LocKind::Village => VillageDao::new(self.db).get_by_id(ref_id)?.name,
+                _ => fail!("Unsupported boundary kind"),
+            };
+            return Ok((ref_id, name));
+        }
+
+        let city = CityDao::new(self.db)
+            .get_by_name(&prop("province"), &prop("city"))
+            .map_err(|_| param_err!(format!("city `{}` not found", prop("city"))))?;
+
+        if let LocKind::City = kind {
+            return Ok((city.id, city.name));
+        }
+
+        let district = DistrictDao::new(self.db)
+            .get_by_name(city.id, &prop("district"))
+            .map_err(|_| param_err!(format!("district `{}` not found", prop("district"))))?;
+
+        match kind {
+            LocKind::District => Ok((district.id, district.name)),
+            LocKind::Village => {
+                let village = VillageDao::new(self.db)
+                    .get_by_name_id(city.id, district.id, &prop("village"))
+                    .map_err(|_| param_err!(format!("village `{}` not found", prop("village"))))?;
+                Ok((village.id, village.name))
+            }
+            _ => fail!("Unsupported boundary kind"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> JsonValue {
+        json!({
+            "type": "Polygon",
+            "coordinates": [
+                [[109.0, -7.5], [110.0, -7.5], [110.0, -7.0], [109.0, -7.0], [109.0, -7.5]],
+                [[109.4, -7.3], [109.6, -7.3], [109.6, -7.2], [109.4, -7.2], [109.4, -7.3]]
+            ]
+        })
+    }
+
+    #[test]
+    fn test_geometry_contains() {
+        let geometry = Geometry::from_geojson(&square()).unwrap();
+        assert!(geometry.contains(-7.4, 109.2));
+        assert!(!geometry.contains(-7.25, 109.5)); // di dalam hole
+        assert!(!geometry.contains(-6.9, 109.5));
+        assert!(!geometry.contains(-7.4, 110.1));
+    }
+
+    #[test]
+    fn test_geometry_bbox() {
+        let geometry = Geometry::from_geojson(&square()).unwrap();
+        assert_eq!(geometry.bbox(), (-7.5, 109.0, -7.0, 110.0));
+    }
+
+    #[test]
+    fn test_geometry_invalid() {
+        assert!(Geometry::from_geojson(&json!({"type": "Point", "coordinates": [109.0, -7.0]})).is_err());
+        assert!(Geometry::from_geojson(&json!({"type": "Polygon", "coordinates": [[[1.0, 1.0]]]})).is_err());
+    }
+}
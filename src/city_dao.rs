@@ -69,6 +69,27 @@ impl<'a> CityDao<'a> {
             .map_err(From::from)
     }
 
+    /// Mendapatkan city berdasarkan kode wilayah resmi (Kemendagri).
+    pub fn get_by_code(&self, code: &str) -> Result<Option<City>> {
+        use crate::schema::cities::{self, dsl};
+
+        dsl::cities
+            .filter(dsl::code.eq(code))
+            .first(self.db)
+            .optional()
+            .map_err(From::from)
+    }
+
+    /// Set kode wilayah resmi untuk city.
+    pub fn set_code(&self, id: ID, code: &str) -> Result<()> {
+        use crate::schema::cities::{self, dsl};
+
+        diesel::update(dsl::cities.filter(dsl::id.eq(id)))
+            .set(dsl::code.eq(code))
+            .execute(self.db)?;
+        Ok(())
+    }
+
     /// Search for specific cities
     pub fn search(&self, query: &str, offset: i64, limit: i64) -> Result<EntriesResult<City>> {
         use crate::schema::cities::{self, dsl};
@@ -0,0 +1,752 @@
+//! Konfigurasi Pandemia.
+//!
+//! Konfigurasi dibaca dari file TOML (path dari env `PANDEMIA_CONFIG`, atau `pandemia.toml`
+//! apabila ada), kemudian setiap nilai bisa di-override menggunakan env var, lihat [ENV_VARS].
+//! Konfigurasi di-load sekali ketika startup menggunakan [init], contoh file konfigurasi
+//! bisa dilihat di `pandemia.toml.example`.
+
+use crate::{
+    crypto::{PasshashScheme, DATA_KEY_LENGTH},
+    rate_limit::{Endpoint, Rule},
+    scheduler::Schedule,
+};
+
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs, io,
+    net::IpAddr,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+/// Path default file konfigurasi.
+pub const DEFAULT_PATH: &str = "pandemia.toml";
+
+/// Daftar env var yang bisa digunakan untuk meng-override konfigurasi dari file.
+pub const ENV_VARS: &[(&str, &str)] = &[
+    ("DATABASE_URL", "database.url"),
+    ("DB_POOL_SIZE", "database.pool_size"),
+    ("DB_POOL_TIMEOUT", "database.pool_timeout"),
+    ("PANDEMIA_PUBLIC_LISTENING", "api.public_listening"),
+    ("PANDEMIA_PRIVATE_LISTENING", "api.private_listening"),
+    ("API_BLOCKING_THREADS", "api.blocking_threads"),
+    ("API_MAX_PENDING", "api.max_pending"),
+    ("API_SHUTDOWN_TIMEOUT", "api.shutdown_timeout"),
+    ("API_TRUSTED_PROXIES", "api.trusted_proxies"),
+    ("FCM_SERVER_KEY", "fcm.server_key"),
+    ("GEOLOCATOR_API_KEY", "geolocator.api_key"),
+    ("DATA_MONITOR_INTERVAL", "monitor.data_interval"),
+    ("RETENTION_MONITOR_INTERVAL", "monitor.retention_interval"),
+    ("RECORD_STALE_HOURS", "monitor.record_stale_hours"),
+    ("LOG_FORMAT", "log.format"),
+    ("PASSWORD_MIN_LENGTH", "password.min_length"),
+    ("PASSWORD_REQUIRE_MIXED", "password.require_mixed"),
+    ("PASSWORD_REQUIRE_SYMBOL", "password.require_symbol"),
+    ("PASSWORD_HASH_VERSION", "password.hash_version"),
+    ("ADMIN_2FA_REQUIRED_ACCESSES", "two_factor.required_accesses"),
+    ("PANDEMIA_DATA_KEY", "security.data_key"),
+    ("REQUIRE_SIGNED_REQUESTS", "security.require_signed_requests"),
+    ("SATGAS_ALLOW_AREA_CODE", "security.allow_area_code"),
+    ("RETENTION_SUB_REPORT_DAYS", "retention.sub_report_days"),
+    ("RETENTION_DEVICE_LOCATION_DAYS", "retention.device_location_days"),
+    ("RETENTION_SYMPTOM_DAYS", "retention.symptom_days"),
+    ("SMTP_HOST", "mail.smtp_host"),
+    ("SMTP_USERNAME", "mail.smtp_username"),
+    ("SMTP_PASSWORD", "mail.smtp_password"),
+    ("MAIL_DIR", "mail.dir"),
+    ("MAIL_FROM", "mail.from"),
+    ("MAIL_LANG", "mail.lang"),
+    ("PANDEMIA_WEB_URL", "mail.web_url"),
+    ("SMS_API_URL", "sms.api_url"),
+    ("SMS_API_KEY", "sms.api_key"),
+    ("RATE_LIMIT_ADMIN_AUTHORIZE", "rate_limit.rules.admin_authorize"),
+    ("RATE_LIMIT_SATGAS_AUTHORIZE", "rate_limit.rules.satgas_authorize"),
+    ("RATE_LIMIT_DEVICE_AUTHORIZE", "rate_limit.rules.device_authorize"),
+    ("RATE_LIMIT_PHONE_OTP", "rate_limit.rules.phone_otp"),
+    ("RATE_LIMIT_ADMIN_TWO_FACTOR", "rate_limit.rules.admin_two_factor"),
+];
+
+/// Error ketika me-load konfigurasi.
+#[derive(Fail, Debug)]
+pub enum ConfigError {
+    /// File konfigurasi tidak bisa dibaca.
+    #[fail(display = "cannot read config file `{}`: {}", _0, _1)]
+    Read(String, #[cause] io::Error),
+
+    /// Format file konfigurasi tidak valid.
+    #[fail(display = "invalid config file `{}`: {}", _0, _1)]
+    Parse(String, #[cause] toml::de::Error),
+
+    /// Nilai konfigurasi tidak valid, param: 1: nama konfigurasi, 2: deskripsi.
+    #[fail(display = "invalid `{}`: {}", _0, _1)]
+    Invalid(&'static str, String),
+}
+
+/// Konfigurasi utama.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Konfigurasi database.
+    pub database: DatabaseConfig,
+    /// Konfigurasi server API.
+    pub api: ApiConfig,
+    /// Konfigurasi push notif FCM.
+    pub fcm: FcmConfig,
+    /// Konfigurasi geocoder.
+    pub geolocator: GeolocatorConfig,
+    /// Konfigurasi monitor.
+    pub monitor: MonitorConfig,
+    /// Konfigurasi scheduler.
+    pub scheduler: SchedulerConfig,
+    /// Konfigurasi log.
+    pub log: LogConfig,
+    /// Kebijakan password.
+    pub password: PasswordConfig,
+    /// Kebijakan autentikasi dua langkah admin.
+    pub two_factor: TwoFactorConfig,
+    /// Konfigurasi keamanan data.
+    pub security: SecurityConfig,
+    /// Kebijakan retensi data.
+    pub retention: RetentionConfig,
+    /// Konfigurasi pengiriman email.
+    pub mail: MailConfig,
+    /// Konfigurasi pengiriman SMS.
+    pub sms: SmsConfig,
+    /// Konfigurasi rate limiter.
+    pub rate_limit: RateLimitConfig,
+}
+
+/// Konfigurasi database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// URL koneksi PostgreSQL.
+    pub url: String,
+    /// Jumlah koneksi maksimal di dalam pool.
+    pub pool_size: u32,
+    /// Lama waktu menunggu koneksi yang tersedia di pool (detik) sebelum dianggap sibuk.
+    pub pool_timeout: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            pool_size: 10,
+            pool_timeout: 5,
+        }
+    }
+}
+
+/// Konfigurasi server API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// Listen address untuk API public.
+    pub public_listening: String,
+    /// Listen address untuk API private.
+    pub private_listening: String,
+    /// Jumlah thread di blocking pool, default sama dengan `database.pool_size`.
+    pub blocking_threads: Option<usize>,
+    /// Jumlah handler maksimal yang sedang berjalan/mengantri,
+    /// default `blocking_threads * 64`.
+    pub max_pending: Option<usize>,
+    /// Lama waktu maksimal (detik) menunggu request, handler & event yang sedang diproses
+    /// selesai ketika server dimatikan.
+    pub shutdown_timeout: u64,
+    /// Alamat IP reverse proxy yang dipercaya, header `Forwarded`/`X-Forwarded-For`
+    /// hanya digunakan apabila request datang dari alamat ini.
+    /// Via env var ditulis dipisah koma.
+    pub trusted_proxies: Vec<String>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            public_listening: "0.0.0.0:8080".to_string(),
+            private_listening: "127.0.0.1:9090".to_string(),
+            blocking_threads: None,
+            max_pending: None,
+            shutdown_timeout: 30,
+            trusted_proxies: vec![],
+        }
+    }
+}
+
+impl ApiConfig {
+    /// Jumlah thread di blocking pool.
+    pub fn blocking_threads(&self, db: &DatabaseConfig) -> usize {
+        self.blocking_threads.unwrap_or(db.pool_size as usize)
+    }
+
+    /// Jumlah handler maksimal yang sedang berjalan/mengantri.
+    pub fn max_pending(&self, db: &DatabaseConfig) -> usize {
+        self.max_pending.unwrap_or_else(|| self.blocking_threads(db) * 64)
+    }
+}
+
+/// Konfigurasi push notif FCM.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FcmConfig {
+    /// FCM server key, kosongkan untuk menonaktifkan push notif.
+    pub server_key: String,
+}
+
+/// Konfigurasi geocoder.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeolocatorConfig {
+    /// HERE API key, apabila tidak diset geocoding hanya menggunakan data lokal (gazetteer).
+    pub api_key: Option<String>,
+}
+
+/// Konfigurasi monitor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitorConfig {
+    /// Interval `DataMonitor` mengambil data dari sumber remote (detik).
+    pub data_interval: u64,
+    /// Interval `RetentionMonitor` menjalankan kebijakan retensi (detik).
+    pub retention_interval: u64,
+    /// Batas umur data records (jam) sebelum ditandai basi di `/status`.
+    pub record_stale_hours: i64,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            data_interval: 30 * 60,
+            retention_interval: 60 * 60,
+            record_stale_hours: 24,
+        }
+    }
+}
+
+/// Konfigurasi scheduler.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerConfig {
+    /// Override jadwal per nama job, eg: `purge_tokens = "0 3 * * *"`,
+    /// format lihat `scheduler::Schedule`.
+    pub schedules: BTreeMap<String, String>,
+}
+
+/// Konfigurasi log, level log diatur via env `RUST_LOG`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// Format output log.
+    pub format: LogFormat,
+}
+
+/// Format output log.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Text biasa, satu baris per log.
+    Text,
+    /// Satu object JSON per baris, berisi juga request id, endpoint & pelaku request.
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::Text
+    }
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<LogFormat, String> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("must be `text` or `json`".to_string()),
+        }
+    }
+}
+
+/// Kebijakan password, lihat `auth::PasswordPolicy`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordConfig {
+    /// Panjang minimal password.
+    pub min_length: usize,
+    /// Wajib mengandung huruf dan angka.
+    pub require_mixed: bool,
+    /// Wajib mengandung simbol.
+    pub require_symbol: bool,
+    /// Versi skema hash untuk password baru, lihat `crypto::PasshashScheme`.
+    pub hash_version: i32,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            require_mixed: true,
+            require_symbol: false,
+            hash_version: PasshashScheme::Argon2id.ver(),
+        }
+    }
+}
+
+/// Kebijakan autentikasi dua langkah admin.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TwoFactorConfig {
+    /// Daftar akses yang mewajibkan admin mengaktifkan 2FA, `*` untuk semua admin.
+    /// Via env var ditulis dipisah koma.
+    pub required_accesses: Vec<String>,
+}
+
+/// Konfigurasi keamanan data.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityConfig {
+    /// Master key dalam format hex (min 32 byte) untuk enkripsi data pribadi at rest.
+    pub data_key: Option<String>,
+    /// Wajibkan semua request ke endpoint `signed` ditandatangani client key.
+    pub require_signed_requests: bool,
+    /// Izinkan pendaftaran satgas menggunakan kode area kota (deprecated, gunakan undangan),
+    /// hanya untuk masa transisi aplikasi lama.
+    pub allow_area_code: bool,
+}
+
+/// Kebijakan retensi data dalam satuan hari, 0 = simpan selamanya.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Anonimisasi sub report yang telah selesai setelah N hari sejak update terakhir.
+    pub sub_report_days: u32,
+    /// Hapus lokasi terakhir device & user setelah N hari.
+    pub device_location_days: u32,
+    /// Hapus flag gejala setelah N hari.
+    pub symptom_days: u32,
+}
+
+/// Konfigurasi pengiriman email.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    /// Host SMTP (STARTTLS, port 587), kosongkan untuk tidak menggunakan SMTP.
+    pub smtp_host: Option<String>,
+    /// Username SMTP.
+    pub smtp_username: String,
+    /// Password SMTP.
+    pub smtp_password: String,
+    /// Apabila SMTP tidak diset, email ditulis sebagai file di direktori ini.
+    pub dir: Option<String>,
+    /// Alamat pengirim.
+    pub from: String,
+    /// Bahasa default email, `id` atau `en`.
+    pub lang: String,
+    /// Base url dashboard untuk link di dalam email & undangan.
+    pub web_url: Option<String>,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            smtp_host: None,
+            smtp_username: String::new(),
+            smtp_password: String::new(),
+            dir: None,
+            from: "noreply@pandemia.local".to_string(),
+            lang: "id".to_string(),
+            web_url: None,
+        }
+    }
+}
+
+/// Konfigurasi pengiriman SMS.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmsConfig {
+    /// Endpoint HTTP gateway SMS, apabila tidak diset SMS hanya ditulis ke log.
+    pub api_url: Option<String>,
+    /// API key gateway SMS, dikirim sebagai header `Authorization: Bearer <key>`.
+    pub api_key: String,
+}
+
+/// Konfigurasi rate limiter.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Override aturan per nama endpoint, eg: `admin_authorize = "5,300,60,3600"`,
+    /// format lihat `rate_limit::Rule`.
+    pub rules: BTreeMap<String, String>,
+}
+
+fn parse<T>(name: &'static str, value: &str) -> Result<T, ConfigError>
+where
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e| ConfigError::Invalid(name, format!("`{}`: {}", value, e)))
+}
+
+/// Parse flag dari env var, menerima `true`/`false` dan `1`/`0`.
+fn parse_flag(name: &'static str, value: &str) -> Result<bool, ConfigError> {
+    match value.trim() {
+        "1" => Ok(true),
+        "0" => Ok(false),
+        _ => parse(name, value),
+    }
+}
+
+fn check_address(name: &'static str, address: &str) -> Result<(), ConfigError> {
+    match address.rsplitn(2, ':').collect::<Vec<_>>().as_slice() {
+        [port, host] if !host.is_empty() && port.parse::<u16>().is_ok() => Ok(()),
+        _ => Err(ConfigError::Invalid(
+            name,
+            format!("`{}` is not a valid listen address, eg: 127.0.0.1:8080", address),
+        )),
+    }
+}
+
+impl Config {
+    /// Load konfigurasi dari file dan env var, kemudian validasi.
+    pub fn load() -> Result<Config, ConfigError> {
+        let mut config = match env::var("PANDEMIA_CONFIG") {
+            Ok(path) => Self::from_file(&path)?,
+            Err(_) if Path::new(DEFAULT_PATH).exists() => Self::from_file(DEFAULT_PATH)?,
+            Err(_) => Config::default(),
+        };
+        config.apply_vars(|key| env::var(key).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Baca konfigurasi dari file TOML, tanpa override env var & validasi.
+    pub fn from_file(path: &str) -> Result<Config, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_string(), e))?;
+        toml::from_str(&content).map_err(|e| ConfigError::Parse(path.to_string(), e))
+    }
+
+    /// Override konfigurasi menggunakan nilai dari `var`, lihat [ENV_VARS].
+    pub fn apply_vars<F>(&mut self, var: F) -> Result<(), ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        for &(key, name) in ENV_VARS {
+            let value = match var(key) {
+                Some(value) => value,
+                None => continue,
+            };
+            match key {
+                "DATABASE_URL" => self.database.url = value,
+                "DB_POOL_SIZE" => self.database.pool_size = parse(name, &value)?,
+                "DB_POOL_TIMEOUT" => self.database.pool_timeout = parse(name, &value)?,
+                "PANDEMIA_PUBLIC_LISTENING" => self.api.public_listening = value,
+                "PANDEMIA_PRIVATE_LISTENING" => self.api.private_listening = value,
+                "API_BLOCKING_THREADS" => self.api.blocking_threads = Some(parse(name, &value)?),
+                "API_MAX_PENDING" => self.api.max_pending = Some(parse(name, &value)?),
+                "API_SHUTDOWN_TIMEOUT" => self.api.shutdown_timeout = parse(name, &value)?,
+                "API_TRUSTED_PROXIES" => {
+                    self.api.trusted_proxies = value
+                        .split(',')
+                        .map(|a| a.trim().to_string())
+                        .filter(|a| !a.is_empty())
+                        .collect()
+                }
+                "FCM_SERVER_KEY" => self.fcm.server_key = value,
+                "GEOLOCATOR_API_KEY" => self.geolocator.api_key = Some(value),
+                "DATA_MONITOR_INTERVAL" => self.monitor.data_interval = parse(name, &value)?,
+                "RETENTION_MONITOR_INTERVAL" => self.monitor.retention_interval = parse(name, &value)?,
+                "RECORD_STALE_HOURS" => self.monitor.record_stale_hours = parse(name, &value)?,
+                "LOG_FORMAT" => self.log.format = parse(name, &value)?,
+                "PASSWORD_MIN_LENGTH" => self.password.min_length = parse(name, &value)?,
+                "PASSWORD_REQUIRE_MIXED" => self.password.require_mixed = parse_flag(name, &value)?,
+                "PASSWORD_REQUIRE_SYMBOL" => self.password.require_symbol = parse_flag(name, &value)?,
+                "PASSWORD_HASH_VERSION" => self.password.hash_version = parse(name, &value)?,
+                "ADMIN_2FA_REQUIRED_ACCESSES" => {
+                    self.two_factor.required_accesses = value
+                        .split(',')
+                        .map(|a| a.trim().to_string())
+                        .filter(|a| !a.is_empty())
+                        .collect()
+                }
+                "PANDEMIA_DATA_KEY" => self.security.data_key = Some(value),
+                "REQUIRE_SIGNED_REQUESTS" => {
+                    self.security.require_signed_requests = parse_flag(name, &value)?
+                }
+                "SATGAS_ALLOW_AREA_CODE" => self.security.allow_area_code = parse_flag(name, &value)?,
+                "RETENTION_SUB_REPORT_DAYS" => self.retention.sub_report_days = parse(name, &value)?,
+                "RETENTION_DEVICE_LOCATION_DAYS" => {
+                    self.retention.device_location_days = parse(name, &value)?
+                }
+                "RETENTION_SYMPTOM_DAYS" => self.retention.symptom_days = parse(name, &value)?,
+                "SMTP_HOST" => self.mail.smtp_host = Some(value),
+                "SMTP_USERNAME" => self.mail.smtp_username = value,
+                "SMTP_PASSWORD" => self.mail.smtp_password = value,
+                "MAIL_DIR" => self.mail.dir = Some(value),
+                "MAIL_FROM" => self.mail.from = value,
+                "MAIL_LANG" => self.mail.lang = value,
+                "PANDEMIA_WEB_URL" => self.mail.web_url = Some(value),
+                "SMS_API_URL" => self.sms.api_url = Some(value),
+                "SMS_API_KEY" => self.sms.api_key = value,
+                _ if key.starts_with("RATE_LIMIT_") => {
+                    let endpoint = name.trim_start_matches("rate_limit.rules.");
+                    self.rate_limit.rules.insert(endpoint.to_string(), value);
+                }
+                _ => unreachable!("unhandled config env var {}", key),
+            }
+        }
+        Ok(())
+    }
+
+    /// Validasi konfigurasi.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.database.url.trim().is_empty() {
+            return Err(ConfigError::Invalid(
+                "database.url",
+                "must be set (or use DATABASE_URL env var)".to_string(),
+            ));
+        }
+        let positive = [
+            ("database.pool_size", u64::from(self.database.pool_size)),
+            ("database.pool_timeout", self.database.pool_timeout),
+            (
+                "api.blocking_threads",
+                self.api.blocking_threads.unwrap_or(1) as u64,
+            ),
+            ("api.max_pending", self.api.max_pending.unwrap_or(1) as u64),
+            ("api.shutdown_timeout", self.api.shutdown_timeout),
+            ("monitor.data_interval", self.monitor.data_interval),
+            ("monitor.retention_interval", self.monitor.retention_interval),
+            (
+                "monitor.record_stale_hours",
+                self.monitor.record_stale_hours.max(0) as u64,
+            ),
+        ];
+        for &(name, value) in positive.iter() {
+            if value == 0 {
+                return Err(ConfigError::Invalid(name, "must be greater than 0".to_string()));
+            }
+        }
+        check_address("api.public_listening", &self.api.public_listening)?;
+        check_address("api.private_listening", &self.api.private_listening)?;
+        for proxy in &self.api.trusted_proxies {
+            if proxy.trim().parse::<IpAddr>().is_err() {
+                return Err(ConfigError::Invalid(
+                    "api.trusted_proxies",
+                    format!("invalid IP address `{}`", proxy),
+                ));
+            }
+        }
+        for (job, expr) in &self.scheduler.schedules {
+            expr.parse::<Schedule>()
+                .map_err(|e| ConfigError::Invalid("scheduler.schedules", format!("{}: {}", job, e)))?;
+        }
+        if self.password.min_length == 0 {
+            return Err(ConfigError::Invalid(
+                "password.min_length",
+                "must be greater than 0".to_string(),
+            ));
+        }
+        if PasshashScheme::from_ver(self.password.hash_version).is_none() {
+            return Err(ConfigError::Invalid(
+                "password.hash_version",
+                format!("unknown version {}", self.password.hash_version),
+            ));
+        }
+        if let Some(key) = self.security.data_key.as_ref() {
+            match hex::decode(key.trim()) {
+                Ok(key) if key.len() >= DATA_KEY_LENGTH => (),
+                _ => {
+                    return Err(ConfigError::Invalid(
+                        "security.data_key",
+                        format!("must be a hex string of at least {} bytes", DATA_KEY_LENGTH),
+                    ))
+                }
+            }
+        }
+        for (endpoint, rule) in &self.rate_limit.rules {
+            if !Endpoint::all().iter().any(|a| a.name() == endpoint) {
+                return Err(ConfigError::Invalid(
+                    "rate_limit.rules",
+                    format!("unknown endpoint `{}`", endpoint),
+                ));
+            }
+            rule.parse::<Rule>()
+                .map_err(|e| ConfigError::Invalid("rate_limit.rules", format!("{}: {}", endpoint, e)))?;
+        }
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref CURRENT: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+}
+
+/// Pasang konfigurasi yang digunakan oleh aplikasi, dipanggil sekali ketika startup
+/// (atau oleh test yang membutuhkan konfigurasi khusus).
+pub fn init(config: Config) -> Arc<Config> {
+    let config = Arc::new(config);
+    *CURRENT.write().unwrap() = Some(config.clone());
+    config
+}
+
+/// Konfigurasi yang dipasang oleh [init], panic apabila [init] belum dipanggil.
+/// Komponen sebaiknya menerima `&Config` (atau `AppState::config`) secara eksplisit.
+pub fn get() -> Arc<Config> {
+    CURRENT
+        .read()
+        .unwrap()
+        .clone()
+        .expect("Configuration not initialized, call config::init first")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+            [database]
+            url = "postgresql://localhost/pandemia"
+            pool_size = 20
+
+            [monitor]
+            data_interval = 600
+
+            [scheduler.schedules]
+            purge_tokens = "30 2 * * *"
+
+            [log]
+            format = "json"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.database.pool_size, 20);
+        assert_eq!(config.database.pool_timeout, 5);
+        assert_eq!(config.monitor.data_interval, 600);
+        assert_eq!(config.monitor.retention_interval, 3600);
+        assert_eq!(config.api.public_listening, "0.0.0.0:8080");
+        assert_eq!(config.scheduler.schedules["purge_tokens"], "30 2 * * *");
+        assert_eq!(config.log.format, LogFormat::Json);
+        assert!(config.validate().is_ok());
+
+        assert!(toml::from_str::<Config>("[database]\nurll = \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_config_env_override() {
+        let mut config = Config::default();
+        config
+            .apply_vars(|key| match key {
+                "DATABASE_URL" => Some("postgresql://localhost/test".to_string()),
+                "DB_POOL_SIZE" => Some("4".to_string()),
+                "API_MAX_PENDING" => Some("100".to_string()),
+                "LOG_FORMAT" => Some("json".to_string()),
+                "PASSWORD_REQUIRE_MIXED" => Some("0".to_string()),
+                "RETENTION_SYMPTOM_DAYS" => Some("30".to_string()),
+                "REQUIRE_SIGNED_REQUESTS" => Some("true".to_string()),
+                "SATGAS_ALLOW_AREA_CODE" => Some("1".to_string()),
+                "RATE_LIMIT_PHONE_OTP" => Some("3,3600,3600".to_string()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(config.database.url, "postgresql://localhost/test");
+        assert!(!config.password.require_mixed);
+        assert_eq!(config.retention.symptom_days, 30);
+        assert!(config.security.require_signed_requests);
+        assert!(config.security.allow_area_code);
+        assert_eq!(config.rate_limit.rules["phone_otp"], "3,3600,3600");
+        assert_eq!(config.database.pool_size, 4);
+        assert_eq!(config.api.blocking_threads(&config.database), 4);
+        assert_eq!(config.api.max_pending(&config.database), 100);
+        assert_eq!(config.log.format, LogFormat::Json);
+
+        let err = config
+            .apply_vars(|key| {
+                if key == "DB_POOL_SIZE" {
+                    Some("banyak".to_string())
+                } else {
+                    None
+                }
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("database.pool_size"));
+    }
+
+    #[test]
+    fn test_config_validate() {
+        let mut config = Config::default();
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("database.url"));
+
+        config.database.url = "postgresql://localhost/pandemia".to_string();
+        config.api.private_listening = "localhost".to_string();
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("api.private_listening"));
+
+        config.api.private_listening = "localhost:9090".to_string();
+        config.api.trusted_proxies = vec!["10.0.0.1".to_string(), "proxy.local".to_string()];
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("api.trusted_proxies"));
+
+        config.api.trusted_proxies = vec!["10.0.0.1".to_string(), "::1".to_string()];
+        config.database.pool_size = 0;
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("database.pool_size"));
+
+        config.database.pool_size = 10;
+        config
+            .scheduler
+            .schedules
+            .insert("purge_tokens".to_string(), "0 25 * * *".to_string());
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("scheduler.schedules"));
+
+        config.scheduler.schedules.clear();
+        config.security.data_key = Some("abcd".to_string());
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("security.data_key"));
+
+        config.security.data_key = Some("ab".repeat(DATA_KEY_LENGTH));
+        config
+            .rate_limit
+            .rules
+            .insert("admin_login".to_string(), "5,300,60".to_string());
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("rate_limit.rules"));
+
+        config.rate_limit.rules.clear();
+        config.password.hash_version = 9;
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("password.hash_version"));
+    }
+}
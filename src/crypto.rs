@@ -3,12 +3,27 @@
 //! menggenerasikan pasangan kunci (keypair) asimetris,
 //! melakukan signing pada data, dll.
 
+use argon2;
 use bcrypt;
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    XChaCha20Poly1305,
+};
 use ed25519_dalek::Keypair;
 use hex;
-use rand::thread_rng;
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, RngCore};
+use sha1::Sha1;
 use sha2::{Digest, Sha256, Sha512};
 
+use crate::{
+    config::{PasswordConfig, SecurityConfig},
+    result::Result,
+    token,
+};
+
+use std::sync::{Arc, RwLock};
+
 /// Number of bytes in a public key.
 pub const PUBLIC_KEY_LENGTH: usize = ed25519_dalek::PUBLIC_KEY_LENGTH;
 /// Number of bytes in a secret key.
@@ -20,6 +35,12 @@ pub const SIGNATURE_LENGTH: usize = ed25519_dalek::SIGNATURE_LENGTH;
 
 const DEFAULT_BCRYPT_COST: u32 = 5;
 
+/// Panjang minimal master key untuk enkripsi data.
+pub const DATA_KEY_LENGTH: usize = 32;
+/// Prefix ciphertext beserta versi formatnya (XChaCha20-Poly1305).
+pub const CIPHERTEXT_PREFIX: &str = "v1:";
+const XNONCE_LENGTH: usize = 24;
+
 // Buatkan wrapper untuk object-object internal dari crypto_impl
 // agar lebih flexibel kita bisa menambahkan implementasi sendiri.
 
@@ -65,16 +86,111 @@ impl<'a> std::convert::Into<ed25519_dalek::Signature> for &'a Signature {
     }
 }
 
-/// Mendapatkan passhash dari sebuah password.
-/// Algo menggunakan Bcrypt.
-pub fn get_passhash(password: &str) -> String {
-    bcrypt::hash(password, DEFAULT_BCRYPT_COST)
-        .unwrap_or_else(|_| panic!("Cannot bcrypt password `{}`", password))
+/// Skema hashing password, nomor versinya disimpan pada kolom `ver` di tabel passhash
+/// sehingga hash lama tetap bisa diverifikasi setelah skema default diganti.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PasshashScheme {
+    /// Bcrypt dengan cost 5, skema awal.
+    Bcrypt5 = 1,
+    /// Bcrypt dengan cost 12.
+    Bcrypt12 = 2,
+    /// Argon2id.
+    Argon2id = 3,
+}
+
+impl PasshashScheme {
+    /// Dapatkan skema berdasarkan nomor versi.
+    pub fn from_ver(ver: i32) -> Option<Self> {
+        match ver {
+            1 => Some(PasshashScheme::Bcrypt5),
+            2 => Some(PasshashScheme::Bcrypt12),
+            3 => Some(PasshashScheme::Argon2id),
+            _ => None,
+        }
+    }
+
+    /// Skema yang digunakan untuk hash baru, diatur melalui konfigurasi `password.hash_version`.
+    pub fn current(config: &PasswordConfig) -> Self {
+        Self::from_ver(config.hash_version).unwrap_or(PasshashScheme::Argon2id)
+    }
+
+    /// Deteksi skema dari format hash, untuk hash yang versinya tidak diketahui.
+    pub fn detect(hashed: &str) -> Option<Self> {
+        if hashed.starts_with("$argon2id$") {
+            Some(PasshashScheme::Argon2id)
+        } else if hashed.starts_with("$2") {
+            match hashed.split('$').nth(2) {
+                Some("12") => Some(PasshashScheme::Bcrypt12),
+                _ => Some(PasshashScheme::Bcrypt5),
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Nomor versi skema.
+    pub fn ver(self) -> i32 {
+        self as i32
+    }
+
+    /// Hash password menggunakan skema ini.
+    pub fn hash(self, password: &str) -> String {
+        match self {
+            PasshashScheme::Bcrypt5 | PasshashScheme::Bcrypt12 => {
+                let cost = if self == PasshashScheme::Bcrypt12 {
+                    12
+                } else {
+                    DEFAULT_BCRYPT_COST
+                };
+                bcrypt::hash(password, cost).unwrap_or_else(|e| panic!("Cannot bcrypt password. {}", e))
+            }
+            PasshashScheme::Argon2id => {
+                argon2::hash_encoded(password.as_bytes(), &token::rand_bytes(16), &argon2_config())
+                    .unwrap_or_else(|e| panic!("Cannot hash password using argon2id. {}", e))
+            }
+        }
+    }
+
+    /// Verifikasi password terhadap hash yang dibuat menggunakan skema ini.
+    pub fn verify(self, password: &str, hashed: &str) -> bool {
+        match self {
+            PasshashScheme::Bcrypt5 | PasshashScheme::Bcrypt12 => {
+                bcrypt::verify(password, hashed).unwrap_or(false)
+            }
+            PasshashScheme::Argon2id => argon2::verify_encoded(hashed, password.as_bytes()).unwrap_or(false),
+        }
+    }
+}
+
+fn argon2_config<'a>() -> argon2::Config<'a> {
+    argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        mem_cost: 19_456,
+        time_cost: 2,
+        lanes: 1,
+        ..Default::default()
+    }
+}
+
+/// Mendapatkan passhash dari sebuah password menggunakan skema terbaru, lihat [PasshashScheme::current].
+pub fn get_passhash(config: &PasswordConfig, password: &str) -> String {
+    PasshashScheme::current(config).hash(password)
 }
 
 /// Memverifikasi apakah password match (verified) dengan hash-nya?
+/// Skema dideteksi dari format hash.
 pub fn password_match(password: &str, hashed: &str) -> bool {
-    bcrypt::verify(password, hashed).expect("Cannot verify bcrypt hash")
+    PasshashScheme::detect(hashed)
+        .map(|a| a.verify(password, hashed))
+        .unwrap_or(false)
+}
+
+/// Memverifikasi password berdasarkan versi skema yang tersimpan.
+pub fn password_match_ver(password: &str, hashed: &str, ver: i32) -> bool {
+    match PasshashScheme::from_ver(ver) {
+        Some(scheme) => scheme.verify(password, hashed),
+        None => password_match(password, hashed),
+    }
 }
 
 /// Generate key pair
@@ -149,25 +265,173 @@ pub fn is_verified(message: &[u8], signature: &Signature, pub_key: &PublicKey) -
     pub_key.valid(message, signature)
 }
 
+/// Sama seperti `is_verified` tapi tidak panic apabila public key atau signature tidak valid,
+/// gunakan ini untuk memverifikasi data yang berasal dari luar (mis. signed request).
+pub fn verify_signature(message: &[u8], signature: &[u8], pub_key: &[u8]) -> bool {
+    match (
+        ed25519_dalek::PublicKey::from_bytes(pub_key),
+        ed25519_dalek::Signature::from_bytes(signature),
+    ) {
+        (Ok(pub_key), Ok(signature)) => pub_key.verify::<Sha512>(message, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+/// Check apakah bytes merupakan public key ed25519 yang valid.
+pub fn is_valid_public_key(bytes: &[u8]) -> bool {
+    ed25519_dalek::PublicKey::from_bytes(bytes).is_ok()
+}
+
+/// HMAC menggunakan SHA-256.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; HASH_SIZE] {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts keys of any length");
+    mac.input(data);
+    let mut fixed: [u8; HASH_SIZE] = Default::default();
+    fixed.copy_from_slice(mac.result().code().as_slice());
+    fixed
+}
+
+/// HMAC menggunakan SHA-1, hanya untuk kompatibilitas (mis. TOTP pada aplikasi authenticator).
+pub fn hmac_sha1(key: &[u8], data: &[u8]) -> [u8; 20] {
+    let mut mac = Hmac::<Sha1>::new_varkey(key).expect("HMAC accepts keys of any length");
+    mac.input(data);
+    let mut fixed = [0u8; 20];
+    fixed.copy_from_slice(mac.result().code().as_slice());
+    fixed
+}
+
+/// Kunci untuk enkripsi data at rest (field-level encryption).
+///
+/// Data dienkripsi menggunakan XChaCha20-Poly1305 dengan nonce acak, ciphertext disimpan
+/// dengan prefix versi format ([CIPHERTEXT_PREFIX]).
+///
+/// Dari satu master key diturunkan kunci untuk enkripsi dan untuk blind index.
+pub struct DataKey {
+    aead: XChaCha20Poly1305,
+    index_key: [u8; HASH_SIZE],
+}
+
+impl DataKey {
+    /// Buat data key dari master key.
+    pub fn new(master_key: &[u8]) -> Self {
+        Self {
+            aead: XChaCha20Poly1305::new_varkey(&hmac_sha256(master_key, b"pandemia.aead"))
+                .expect("invalid data key length"),
+            index_key: hmac_sha256(master_key, b"pandemia.bidx"),
+        }
+    }
+
+    /// Load master key dalam format hex dari konfigurasi `security.data_key`,
+    /// `None` apabila tidak diset.
+    pub fn from_config(config: &SecurityConfig) -> Option<Self> {
+        let master_key = hex::decode(config.data_key.as_ref()?.trim()).ok()?;
+        if master_key.len() < DATA_KEY_LENGTH {
+            error!("security.data_key must be at least {} bytes", DATA_KEY_LENGTH);
+            return None;
+        }
+        Some(Self::new(&master_key))
+    }
+
+    /// Enkripsi data, hasilnya dalam format `v1:` + hex `nonce || ciphertext || tag`.
+    pub fn encrypt(&self, plaintext: &[u8]) -> String {
+        let mut nonce = [0u8; XNONCE_LENGTH];
+        thread_rng().fill_bytes(&mut nonce);
+
+        let mut data = nonce.to_vec();
+        data.extend(
+            self.aead
+                .encrypt((&nonce[..]).into(), plaintext)
+                .expect("cannot encrypt data"),
+        );
+
+        format!("{}{}", CIPHERTEXT_PREFIX, hex::encode(data))
+    }
+
+    /// Dekripsi data hasil dari [DataKey::encrypt],
+    /// mengembalikan `None` apabila data tidak valid atau telah diubah.
+    pub fn decrypt(&self, ciphertext: &str) -> Option<Vec<u8>> {
+        if !Self::is_encrypted(ciphertext) {
+            return None;
+        }
+        let data = hex::decode(&ciphertext[CIPHERTEXT_PREFIX.len()..]).ok()?;
+        if data.len() < XNONCE_LENGTH {
+            return None;
+        }
+        let (nonce, ciphertext) = data.split_at(XNONCE_LENGTH);
+        self.aead.decrypt(nonce.into(), ciphertext).ok()
+    }
+
+    /// Apakah text merupakan ciphertext hasil [DataKey::encrypt].
+    pub fn is_encrypted(text: &str) -> bool {
+        text.starts_with(CIPHERTEXT_PREFIX)
+    }
+
+    /// Blind index untuk pencarian exact-match terhadap data terenkripsi,
+    /// text dinormalisasi menjadi lowercase terlebih dahulu.
+    pub fn blind_index(&self, text: &str) -> String {
+        hex::encode(hmac_sha256(&self.index_key, text.trim().to_lowercase().as_bytes()))
+    }
+}
+
+lazy_static! {
+    static ref DATA_KEY: RwLock<Option<Arc<DataKey>>> = RwLock::new(None);
+}
+
+/// Pasang data key global dari konfigurasi `security.data_key`,
+/// dipanggil ketika startup (atau oleh test yang membutuhkan data key khusus).
+pub fn init(config: &SecurityConfig) {
+    *DATA_KEY.write().unwrap() = DataKey::from_config(config).map(Arc::new);
+}
+
+/// Data key global yang dipasang oleh [init],
+/// digunakan untuk data pribadi sub report dan secret 2FA admin.
+pub fn data_key() -> Result<Arc<DataKey>> {
+    match DATA_KEY.read().unwrap().as_ref() {
+        Some(key) => Ok(key.clone()),
+        None => fail!("security.data_key not set, cannot process encrypted data"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{PublicKey, SecretKey, Signature};
 
     #[test]
     fn test_get_passhash() {
-        let passhash = super::get_passhash("123");
+        let passhash = super::get_passhash(&Default::default(), "123");
         assert_ne!(passhash, "");
         assert!(passhash.len() > 10);
     }
 
     #[test]
     fn test_verify_passhash() {
-        let passhash = super::get_passhash("123");
+        let passhash = super::get_passhash(&Default::default(), "123");
         assert_eq!(super::password_match("123", &passhash), true);
         assert_eq!(super::password_match("1234", &passhash), false);
         assert_eq!(super::password_match(" 123 ", &passhash), false);
     }
 
+    #[test]
+    fn test_passhash_schemes() {
+        use super::PasshashScheme;
+
+        for scheme in &[PasshashScheme::Bcrypt5, PasshashScheme::Argon2id] {
+            let passhash = scheme.hash("rahasia123");
+            assert_eq!(PasshashScheme::detect(&passhash), Some(*scheme));
+            assert_eq!(PasshashScheme::from_ver(scheme.ver()), Some(*scheme));
+            assert!(super::password_match_ver("rahasia123", &passhash, scheme.ver()));
+            assert!(!super::password_match_ver("rahasia124", &passhash, scheme.ver()));
+            assert!(super::password_match("rahasia123", &passhash));
+        }
+
+        assert_eq!(
+            PasshashScheme::detect("$2y$12$QjSH496pcT5CEbzjD/vtVeH03tfHKFy36d4J0Ltp3lRtee9HDxY3K"),
+            Some(PasshashScheme::Bcrypt12)
+        );
+        assert_eq!(PasshashScheme::detect("plain"), None);
+        assert!(!super::password_match("plain", "plain"));
+    }
+
     #[test]
     fn test_gen_keyppair() {
         let (p, s) = super::gen_keypair();
@@ -225,4 +489,56 @@ mod tests {
 
         assert!(super::is_verified(DATA, &signature, &p));
     }
+
+    #[test]
+    fn test_verify_signature_bytes() {
+        let (p, _) = get_preset_keypair();
+        let signature = create_signature();
+
+        assert!(super::verify_signature(DATA, &signature.0, &p.0));
+        assert!(!super::verify_signature(b"Zufar!", &signature.0, &p.0));
+        assert!(!super::verify_signature(DATA, &[0xffu8; 64], &p.0));
+        assert!(!super::verify_signature(DATA, &signature.0, &[1u8; 3]));
+        assert!(super::is_valid_public_key(&p.0));
+        assert!(!super::is_valid_public_key(&[1u8; 3]));
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hex::encode(super::hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_data_key_encrypt_decrypt() {
+        let key = super::DataKey::new(&[7u8; 32]);
+        let text = "Zufar, Jl. Kenanga 12 RT 01/02 ".repeat(5);
+        let encrypted = key.encrypt(text.as_bytes());
+        assert!(!encrypted.contains("Zufar"));
+        assert!(super::DataKey::is_encrypted(&encrypted));
+        assert_ne!(encrypted, key.encrypt(text.as_bytes()));
+        assert_eq!(key.decrypt(&encrypted), Some(text.as_bytes().to_vec()));
+
+        // salah kunci
+        assert_eq!(super::DataKey::new(&[8u8; 32]).decrypt(&encrypted), None);
+
+        // ciphertext diubah
+        let mut tampered = encrypted.clone().into_bytes();
+        tampered[40] = if tampered[40] == b'0' { b'1' } else { b'0' };
+        assert_eq!(key.decrypt(&String::from_utf8(tampered).unwrap()), None);
+    }
+
+    #[test]
+    fn test_data_key_blind_index() {
+        let key = super::DataKey::new(&[7u8; 32]);
+        assert_eq!(key.blind_index("Zufar"), key.blind_index(" zufar "));
+        assert_ne!(key.blind_index("Zufar"), key.blind_index("Zufa"));
+        assert_ne!(
+            key.blind_index("Zufar"),
+            super::DataKey::new(&[8u8; 32]).blind_index("Zufar")
+        );
+    }
 }
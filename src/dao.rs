@@ -3,7 +3,10 @@
 use diesel::sql_types;
 
 pub use crate::admin_dao::AdminDao;
+pub use crate::admin_two_factor_dao::AdminTwoFactorDao;
+pub use crate::app_release_dao::AppReleaseDao;
 pub use crate::auth::AuthDao;
+pub use crate::boundary_dao::BoundaryDao;
 pub use crate::city_dao::CityDao;
 pub use crate::dao::journal::Logs;
 pub use crate::district_dao::DistrictDao;
@@ -11,8 +14,11 @@ pub use crate::district_data_dao::DistrictDataDao;
 pub use crate::feed_dao::FeedDao;
 pub use crate::map_marker_dao::MapMarkerDao;
 pub use crate::notif_dao::NotifDao;
+pub use crate::phone_verification_dao::PhoneVerificationDao;
 pub use crate::record_dao::RecordDao;
 pub use crate::report_note_dao::ReportNoteDao;
+pub use crate::satgas_invitation_dao::SatgasInvitationDao;
+pub use crate::scheduled_job_dao::ScheduledJobDao;
 pub use crate::sub_report_dao::SubReportDao;
 pub use crate::user_dao::UserDao;
 pub use crate::village_dao::VillageDao;
@@ -4,27 +4,37 @@ use diesel::{
     r2d2::{self, ConnectionManager},
 };
 
-use std::env;
+use crate::config::DatabaseConfig;
+
+use std::{sync::RwLock, time::Duration};
 
 pub type DbConnMan = r2d2::Pool<ConnectionManager<PgConnection>>;
 pub type DbConn = r2d2::PooledConnection<ConnectionManager<PgConnection>>;
 
 lazy_static! {
-    pub static ref DB_CONN_POOL: r2d2::Pool<ConnectionManager<PgConnection>> = {
-        let conn_man = ConnectionManager::<PgConnection>::new(
-            env::var("DATABASE_URL").expect("no DATABASE_URL env var"),
-        );
-        r2d2::Pool::builder()
-            .max_size(2)
-            .build(conn_man)
-            .expect("Cannot build DB connection poll")
-    };
+    static ref DB_CONN_POOL: RwLock<Option<DbConnMan>> = RwLock::new(None);
+}
+
+/// Buat pool koneksi DB sesuai konfigurasi `[database]`, dipanggil sekali ketika startup.
+pub fn init(config: &DatabaseConfig) {
+    let conn_man = ConnectionManager::<PgConnection>::new(config.url.as_str());
+    let pool = r2d2::Pool::builder()
+        .max_size(config.pool_size)
+        .connection_timeout(Duration::from_secs(config.pool_timeout))
+        .build(conn_man)
+        .expect("Cannot build DB connection poll");
+    *DB_CONN_POOL.write().unwrap() = Some(pool);
 }
 
 pub fn connect(db_url: &str) -> PgConnection {
     PgConnection::establish(db_url).unwrap_or_else(|_| panic!("Cannot connect to `{}`", db_url))
 }
 
+/// Pool koneksi DB yang dibuat oleh [init].
 pub fn clone() -> DbConnMan {
-    DB_CONN_POOL.clone()
+    DB_CONN_POOL
+        .read()
+        .unwrap()
+        .clone()
+        .expect("DB connection pool not initialized, call db::init first")
 }
@@ -46,6 +46,27 @@ impl<'a> DistrictDao<'a> {
             .map_err(From::from)
     }
 
+    /// Mendapatkan district berdasarkan kode wilayah resmi (Kemendagri).
+    pub fn get_by_code(&self, code: &str) -> Result<Option<District>> {
+        use crate::schema::districts::{self, dsl};
+
+        dsl::districts
+            .filter(dsl::code.eq(code))
+            .first(self.db)
+            .optional()
+            .map_err(From::from)
+    }
+
+    /// Set kode wilayah resmi untuk district.
+    pub fn set_code(&self, id: ID, code: &str) -> Result<()> {
+        use crate::schema::districts::{self, dsl};
+
+        diesel::update(dsl::districts.filter(dsl::id.eq(id)))
+            .set(dsl::code.eq(code))
+            .execute(self.db)?;
+        Ok(())
+    }
+
     /// Search for specific districts
     pub fn search(
         &self,
@@ -218,6 +218,23 @@ impl<'a> DistrictDataDao<'a> {
         ))
     }
 
+    /// Rekalkulasi semua data district yang memiliki data village,
+    /// mengembalikan jumlah district yang dihitung ulang.
+    pub fn recalculate_all(&self, updater_id: ID) -> Result<usize> {
+        use crate::schema::village_data::dsl;
+
+        let districts: Vec<(ID, ID)> = dsl::village_data
+            .select((dsl::city_id, dsl::district_id))
+            .distinct()
+            .load(self.db)?;
+
+        for &(city_id, district_id) in &districts {
+            self.recalculate(city_id, district_id, 0, updater_id)?;
+        }
+
+        Ok(districts.len())
+    }
+
     /// Update recalculate
     pub fn recalculate(&self, city_id: ID, district_id: ID, village_id: ID, updater_id: ID) -> Result<()> {
         use crate::schema::district_data::{self, dsl};
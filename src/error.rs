@@ -4,6 +4,8 @@ use diesel;
 use failure;
 use std::io;
 
+use crate::i18n::Lang;
+
 /// Listing dari jenis error yang mungkin muncul pada sistem internal
 #[derive(Fail, Debug)]
 pub enum Error {
@@ -53,12 +55,27 @@ pub enum Error {
     Unauthorized,
 }
 
-/// Definisi kode kesalahan
+/// Definisi kode kesalahan.
+///
+/// Kode-kode ini adalah katalog error yang stabil, client menggunakannya untuk
+/// menentukan penanganan error, jadi jangan mengubah nilai kode yang telah ada.
+/// Deskripsi untuk masing-masing kode ada di [ErrorCode::message].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorCode {
     /// Sukses atau tidak terjadi error.
     NoError = 0,
+    /// Object unik yang akan dibuat telah ada (kode lama, dipertahankan untuk kompatibilitas client).
+    AlreadyExists = 304,
     /// Unauthorized
     Unauthorized = 3000,
+    /// Akun membutuhkan kode verifikasi dua langkah (TOTP atau recovery code).
+    TwoFactorRequired = 3001,
+    /// Akun wajib mengaktifkan verifikasi dua langkah sebelum bisa melakukan operasi ini.
+    TwoFactorSetupRequired = 3002,
+    /// Akun telah diblokir.
+    AccountBlocked = 3003,
+    /// Tidak memiliki hak akses untuk resource yang dimaksud.
+    AccessDenied = 3004,
 
     /// Kegagalan yang berkaitan dengan proses serialize/deserialize data.
     SerializeDeserializeError = 4001,
@@ -70,16 +87,209 @@ pub enum ErrorCode {
     NoLoginInfo = 4004,
     /// Pengirim dan penerima alamatnya sama.
     FromAndToTargetIsSame = 4005,
+    /// Signature request tidak valid, kadaluarsa, atau nonce telah digunakan.
+    InvalidSignature = 4006,
+    /// Terlalu banyak percobaan, identitas sedang dikunci sementara.
+    TooManyAttempts = 4007,
+    /// Konfirmasi password tidak cocok.
+    PasswordMismatch = 4010,
+    /// Kode verifikasi tidak benar.
+    InvalidVerificationCode = 4011,
+    /// Public key tidak valid.
+    InvalidPublicKey = 4012,
+    /// Web token satgas tidak valid.
+    InvalidWebToken = 4013,
+    /// Job terjadwal sedang berjalan.
+    JobAlreadyRunning = 4014,
+
+    /// Kode area kota/kabupaten tidak dikenal.
+    InvalidAreaCode = 4101,
+    /// Kota/kabupaten tidak ditemukan.
+    CityNotFound = 4102,
+    /// Kecamatan tidak ditemukan.
+    DistrictNotFound = 4103,
+    /// Desa tidak ditemukan.
+    VillageNotFound = 4104,
+    /// Desa tidak terdaftar di kota milik pengguna.
+    VillageNotInCity = 4105,
+    /// Nama desa belum diset.
+    VillageNotSet = 4106,
+    /// Nama kecamatan belum diset.
+    DistrictNotSet = 4107,
+    /// Kota/kabupaten belum diset.
+    CityNotSet = 4108,
+    /// Pengguna tidak terdaftar pada area manapun.
+    NoArea = 4109,
+    /// Pengguna tidak terdaftar pada area yang dimaksud.
+    NotInArea = 4110,
+    /// Tidak memiliki akses untuk kota/kabupaten yang dimaksud.
+    NoAccessToCity = 4111,
+    /// Tidak memiliki akses untuk kecamatan yang dimaksud.
+    NoAccessToDistrict = 4112,
+    /// Pengguna tidak terdaftar sebagai satgas.
+    NotSatgas = 4113,
+    /// Pengguna sudah terdaftar sebagai satgas.
+    AlreadySatgas = 4114,
+    /// Jumlah satgas untuk desa telah mencapai batas.
+    SatgasLimitReached = 4115,
+    /// Pengguna tidak dapat menambahkan data.
+    CannotAddData = 4116,
+    /// Status tidak valid.
+    InvalidStatus = 4117,
+    /// Pendaftaran satgas menggunakan kode area telah dinonaktifkan.
+    AreaCodeDisabled = 4118,
+
+    /// Nomor telepon sudah terverifikasi.
+    PhoneAlreadyVerified = 4201,
+    /// Nomor telepon telah digunakan oleh akun lain.
+    PhoneAlreadyUsed = 4202,
+    /// Akun dengan nomor telepon ini tidak bisa digunakan.
+    PhoneAccountUnusable = 4203,
 
     /// Kegagalan yang tidak diketahui penyebabnya.
     UnknownError = 5001,
+    /// Server sedang sibuk (antrian request penuh atau koneksi database habis),
+    /// client bisa mencoba kembali beberapa saat lagi.
+    ServiceBusy = 5002,
 
     /// Kegagalan pada database internal apabila terjadi error.
     DatabaseError = 6001,
     /// Kegagalan pada database yang berkaitan dengan
     /// ketidakditemukannya record/data di dalam database.
     DatabaseRecordNotFoundError = 6002,
-    // Tambahkan definisi kode error mu sendiri di sini.
+    // Tambahkan definisi kode error mu sendiri di sini,
+    // beserta deskripsinya di [ErrorCode::message].
+}
+
+impl ErrorCode {
+    /// Deskripsi error dalam bahasa `lang`,
+    /// `{}` diganti dengan argumen error secara berurutan.
+    pub fn message(self, lang: Lang) -> &'static str {
+        use self::ErrorCode::*;
+
+        let (id, en) = match self {
+            NoError => ("", ""),
+            Unauthorized => ("Akses tidak sah, silahkan login kembali", "Unauthorized"),
+            TwoFactorRequired => (
+                "Kode verifikasi dua langkah dibutuhkan",
+                "Verification code required",
+            ),
+            TwoFactorSetupRequired => (
+                "Silahkan aktifkan verifikasi dua langkah terlebih dahulu",
+                "Please enable two factor authentication first",
+            ),
+            AccountBlocked => ("Akun telah diblokir", "Account blocked"),
+            AccessDenied => (
+                "Anda tidak memiliki akses untuk data ini",
+                "You have no access to this resource",
+            ),
+            SerializeDeserializeError => ("Data tidak valid", "Invalid data"),
+            InvalidParameter => ("Parameter tidak valid", "Invalid parameter"),
+            MessageHasNoSign => ("Pesan tidak memiliki signature", "Message has no signature"),
+            NoLoginInfo => ("Informasi login tidak ada", "No login information"),
+            FromAndToTargetIsSame => ("Pengirim dan penerima sama", "Sender and receiver are the same"),
+            InvalidSignature => ("Signature request tidak valid", "Invalid request signature"),
+            TooManyAttempts => (
+                "Terlalu banyak percobaan, silahkan coba lagi dalam {} detik",
+                "Too many attempts, please try again in {} seconds",
+            ),
+            AlreadyExists => ("Data sudah ada", "Already exists"),
+            PasswordMismatch => (
+                "Konfirmasi password tidak cocok",
+                "Password verification didn't match",
+            ),
+            InvalidVerificationCode => ("Kode verifikasi tidak benar", "Invalid verification code"),
+            InvalidPublicKey => ("Public key tidak valid", "Invalid public key"),
+            InvalidWebToken => ("Web token tidak valid", "Invalid web token"),
+            JobAlreadyRunning => ("Job sedang berjalan", "Job is already running"),
+            InvalidAreaCode => (
+                "Kode area tidak benar, mohon periksa kembali.",
+                "Invalid area code, please check again.",
+            ),
+            CityNotFound => ("Tidak ada kota/kab dengan nama {}", "No city named {}"),
+            DistrictNotFound => ("Tidak ada kecamatan dengan nama {}", "No district named {}"),
+            VillageNotFound => (
+                "Tidak dapat menemukan data untuk desa {}",
+                "No data found for village {}",
+            ),
+            VillageNotInCity => (
+                "Desa {} tidak terdaftar di dalam kota Anda",
+                "Village {} is not registered in your city",
+            ),
+            VillageNotSet => ("Nama desa belum diset", "Village name is not set"),
+            DistrictNotSet => ("Nama kecamatan belum diset", "District name is not set"),
+            CityNotSet => ("Kota/kab belum diset", "City is not set"),
+            NoArea => (
+                "Anda tidak terdaftar pada area manapun",
+                "You are not registered in any area",
+            ),
+            NotInArea => (
+                "Anda tidak terdaftar pada area yang dimaksud",
+                "You are not registered in this area",
+            ),
+            NoAccessToCity => (
+                "Anda tidak memiliki akses untuk kab/kota ini",
+                "You have no access to this city",
+            ),
+            NoAccessToDistrict => (
+                "Anda tidak memiliki akses untuk kecamatan {}",
+                "You have no access to district {}",
+            ),
+            NotSatgas => (
+                "Anda tidak terdaftar sebagai satgas",
+                "You are not registered as satgas",
+            ),
+            AlreadySatgas => (
+                "Anda sudah terdaftar sebagai satgas",
+                "You are already registered as satgas",
+            ),
+            SatgasLimitReached => ("Maksimal {} satgas per desa", "At most {} satgas per village"),
+            CannotAddData => (
+                "Anda tidak dapat menambahkan data",
+                "You are not allowed to add data",
+            ),
+            InvalidStatus => ("Status tidak valid", "Invalid status"),
+            AreaCodeDisabled => (
+                "Kode area tidak lagi digunakan, silahkan gunakan undangan satgas",
+                "Area codes are no longer supported, please use a satgas invitation",
+            ),
+            PhoneAlreadyVerified => (
+                "Nomor telepon sudah terverifikasi",
+                "Phone number already verified",
+            ),
+            PhoneAlreadyUsed => (
+                "Nomor telepon sudah digunakan oleh akun lain",
+                "Phone number already used by another account",
+            ),
+            PhoneAccountUnusable => (
+                "Akun dengan nomor telepon ini tidak dapat digunakan",
+                "Account with this phone number cannot be used",
+            ),
+            UnknownError => ("Terjadi kesalahan yang tidak diketahui", "Unknown error"),
+            ServiceBusy => (
+                "Server sedang sibuk, silahkan coba beberapa saat lagi",
+                "Service is busy, please try again later",
+            ),
+            DatabaseError => ("Terjadi kesalahan internal", "Internal error"),
+            DatabaseRecordNotFoundError => ("Data tidak ditemukan", "Not found"),
+        };
+
+        match lang {
+            Lang::Id => id,
+            Lang::En => en,
+        }
+    }
+
+    /// Deskripsi error dalam bahasa `lang` dengan argumen `args`.
+    pub fn describe<T: AsRef<str>>(self, lang: Lang, args: &[T]) -> String {
+        let mut parts = self.message(lang).split("{}");
+        let mut result = parts.next().unwrap_or("").to_string();
+        for (i, part) in parts.enumerate() {
+            result.push_str(args.get(i).map(|a| a.as_ref()).unwrap_or(""));
+            result.push_str(part);
+        }
+        result
+    }
 }
 
 // semua error yang berasal dari diesel akan dipropagasi ke sistem error [Error::Storage]
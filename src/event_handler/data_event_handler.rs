@@ -4,8 +4,8 @@ use diesel::prelude::*;
 
 use crate::{
     api::types,
-    dao::{FeedDao, MapMarkerDao, NotifDao},
-    event_handler::FCM,
+    dao::{BoundaryDao, FeedDao, MapMarkerDao, NotifDao},
+    event_handler,
     eventstream::{self, Event::*},
     geolocator,
     models::{Record, User},
@@ -29,14 +29,15 @@ pub fn new_record_update(
     }
 
     // update map marker
-    if let Err(e) = update_map_marker(old_record, new_record, conn) {
+    if let Err(e) = update_map_marker(new_record, conn) {
         error!("Cannot update map marker. {}", e);
     }
 
     Ok(())
 }
 
-fn update_map_marker(old_record: &Option<Record>, new_record: &Record, conn: &PgConnection) -> Result<()> {
+/// Buat atau update map marker untuk lokasi `new_record`.
+pub fn update_map_marker(new_record: &Record, conn: &PgConnection) -> Result<()> {
     let dao = MapMarkerDao::new(conn);
 
     let scope_meta = new_record
@@ -84,6 +85,14 @@ fn update_feed_and_push_notif(
 ) -> Result<()> {
     let feed_dao = FeedDao::new(conn);
     if let Some(old_record) = old_record {
+        let fcm = event_handler::fcm()?;
+        let loc_id = BoundaryDao::new(conn)
+            .area_id_by_path(new_record.loc_kind.into(), &new_record.loc_path)
+            .unwrap_or_else(|e| {
+                error!("Cannot get area id of `{}`. {}", new_record.loc_path, e);
+                None
+            });
+
         let diff = new_record.diff(old_record);
         debug!("diff: {:?}", diff);
         let title = if new_record.loc != "Indonesia" {
@@ -108,11 +117,12 @@ fn update_feed_and_push_notif(
             }
 
             // Send push notification
-            if let Err(e) = FCM.push(
+            if let Err(e) = fcm.push(
                 "fcm",
                 &FCMPayloadData {
                     receiver_loc: &new_record.loc,
                     receiver_loc_kind: new_record.loc_kind.into(),
+                    receiver_loc_id: loc_id,
                     target_id: 0,
                     kind: NotifKind::NewCases,
                     title: &title,
@@ -145,11 +155,12 @@ fn update_feed_and_push_notif(
             }
 
             // Send push notification
-            if let Err(e) = FCM.push(
+            if let Err(e) = fcm.push(
                 "fcm",
                 &FCMPayloadData {
                     receiver_loc: &new_record.loc,
                     receiver_loc_kind: new_record.loc_kind.into(),
+                    receiver_loc_id: loc_id,
                     target_id: 0,
                     kind: NotifKind::NewDeaths,
                     title: &title,
@@ -182,11 +193,12 @@ fn update_feed_and_push_notif(
             }
 
             // Send push notification
-            if let Err(e) = FCM.push(
+            if let Err(e) = fcm.push(
                 "fcm",
                 &FCMPayloadData {
                     receiver_loc: &new_record.loc,
                     receiver_loc_kind: new_record.loc_kind.into(),
+                    receiver_loc_id: loc_id,
                     target_id: 0,
                     kind: NotifKind::NewRecovered,
                     title: &title,
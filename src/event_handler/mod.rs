@@ -2,16 +2,28 @@
 use chrono::prelude::*;
 use diesel::prelude::*;
 
-use crate::{api::types, models, result::Result, token, util, ID};
+use crate::{api::types, config::FcmConfig, models, result::Result, token, util, ID};
 
 mod data_event_handler;
 
 pub use crate::push_notif_handler::{FCMHandler, FCMPayloadData};
 pub use data_event_handler::*;
 
+use std::sync::{Arc, RwLock};
+
 lazy_static! {
-    /// FCM push handler
-    pub static ref FCM:FCMHandler = {
-        FCMHandler::new()
-    };
+    static ref FCM: RwLock<Option<Arc<FCMHandler>>> = RwLock::new(None);
+}
+
+/// Pasang FCM push handler sesuai konfigurasi `fcm`, dipanggil sekali ketika startup.
+pub fn init(config: &FcmConfig) {
+    *FCM.write().unwrap() = Some(Arc::new(FCMHandler::new(&config.server_key)));
+}
+
+/// FCM push handler yang dipasang oleh [init].
+pub fn fcm() -> Result<Arc<FCMHandler>> {
+    match FCM.read().unwrap().as_ref() {
+        Some(handler) => Ok(handler.clone()),
+        None => fail!("FCM handler not initialized, call event_handler::init first"),
+    }
 }
@@ -1,4 +1,7 @@
 //! Event stream and listener implementation for Pandemia
+//!
+//! Event dikirim bersama trace context (request id) dari request yang meng-emit-nya,
+//! sehingga log event handler & push notif bisa dikaitkan dengan request tersebut.
 
 extern crate event_stream;
 
@@ -6,9 +9,17 @@ use diesel::prelude::*;
 
 use self::event_stream::{EventDispatcher, EventDispatcherBuilder, EventListener};
 use crate::event_handler;
-use crate::{chrono, db, models::Record};
-
-use std::{env, sync::Arc, thread::sleep, time::Duration};
+use crate::{chrono, db, models::Record, trace, util};
+
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::sleep,
+    time::Duration,
+};
 
 /// Detax internal events
 #[derive(Debug, Clone)]
@@ -21,6 +32,15 @@ pub enum Event {
     NewRecordUpdate(Option<Record>, Record), // @TODO(*): Add more events here
 }
 
+/// Event beserta trace context dari request yang meng-emit-nya.
+#[derive(Debug, Clone)]
+pub struct TracedEvent {
+    /// Event yang di-emit.
+    pub event: Event,
+    /// Trace context, `None` apabila event di-emit di luar request API (eg: oleh monitor).
+    pub trace: Option<trace::Context>,
+}
+
 /// Pandemia event listener implemetation
 #[derive(Clone)]
 struct PandemiaEventListener {
@@ -52,8 +72,26 @@ macro_rules! handle_event {
     };
 }
 
-impl EventListener<Event> for PandemiaEventListener {
-    fn dispatch(&self, event: &Event) {
+/// Menandai satu event yang sedang diproses, [PENDING] dikurangi ketika di-drop
+/// sehingga tetap berkurang walaupun handler panic.
+struct Pending;
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        PENDING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl EventListener<TracedEvent> for PandemiaEventListener {
+    fn dispatch(&self, traced: &TracedEvent) {
+        let _pending = Pending;
+
+        trace::scope(traced.trace.clone(), || self.handle(&traced.event));
+    }
+}
+
+impl PandemiaEventListener {
+    fn handle(&self, event: &Event) {
         use self::Event::*;
 
         debug!("{:?} got event: {:?}", self, event);
@@ -75,20 +113,45 @@ impl std::fmt::Debug for PandemiaEventListener {
     }
 }
 
+static DISPATCHER_RUNNING: AtomicBool = AtomicBool::new(false);
+static PENDING: AtomicUsize = AtomicUsize::new(0);
+
 lazy_static! {
 
     /// Event dispatcher global var
-    pub static ref EVENT_DISPATCHER:EventDispatcher<Event> = {
+    pub static ref EVENT_DISPATCHER:EventDispatcher<TracedEvent> = {
         let event_dispatcher = EventDispatcherBuilder::new()
         .add_listener(PandemiaEventListener::new())
         .build();
 
         event_dispatcher.start();
+        DISPATCHER_RUNNING.store(true, Ordering::SeqCst);
         event_dispatcher
     };
 }
 
-/// Emit event to the stream
+/// Apakah event dispatcher sudah berjalan, digunakan oleh readiness check.
+pub fn is_running() -> bool {
+    DISPATCHER_RUNNING.load(Ordering::SeqCst)
+}
+
+/// Emit event to the stream, trace context thread ini ikut dikirim bersama event.
 pub fn emit(event: Event) {
-    EVENT_DISPATCHER.emit(event)
+    PENDING.fetch_add(1, Ordering::SeqCst);
+    EVENT_DISPATCHER.emit(TracedEvent {
+        event,
+        trace: trace::current(),
+    })
+}
+
+/// Jumlah event yang belum selesai diproses.
+pub fn pending() -> usize {
+    PENDING.load(Ordering::SeqCst)
+}
+
+/// Tunggu sampai semua event selesai diproses, berguna untuk perintah CLI
+/// yang prosesnya langsung selesai setelah meng-emit event.
+/// Mengembalikan `false` apabila sampai `timeout` masih ada event yang belum selesai.
+pub fn wait_idle(timeout: Duration) -> bool {
+    util::wait_until(timeout, || pending() == 0)
 }
@@ -5,9 +5,18 @@ use diesel::prelude::*;
 use reqwest;
 use serde_json;
 
-use crate::{error::Error, result::Result, schema::geoloc_cache, sqlutil::lower, ID};
-
-use std::env;
+use crate::{
+    config::GeolocatorConfig,
+    dao::{BoundaryDao, CityDao},
+    error::Error,
+    models::Village,
+    result::Result,
+    schema::geoloc_cache,
+    sqlutil::lower,
+    ID,
+};
+
+use std::sync::{Arc, RwLock};
 
 /// Latitude longitude representation struct
 #[derive(Deserialize, Copy, Clone, Debug)]
@@ -118,92 +127,342 @@ pub struct LocInfo {
     pub subdistrict: Option<String>,
 }
 
-/// Get location address from lat long
-pub fn ll_to_address(lat: f64, lng: f64, conn: &PgConnection) -> Result<LocInfo> {
-    let url_query = format!("https://reverse.geocoder.ls.hereapi.com/6.2/reversegeocode.json?prox={},{}&mode=retrieveAddresses&maxResults=1&gen=1&apiKey={}",
-    lat,lng,
-    env::var("GEOLOCATOR_API_KEY").expect("GEOLOCATOR_API_KEY env not set"));
-    let mut resp = reqwest::get(&url_query)?;
+/// Interface untuk geocoder, bisa berupa layanan eksternal (HERE)
+/// maupun data lokal (gazetteer).
+pub trait Geocoder: Send + Sync {
+    /// Nama geocoder, digunakan untuk logging.
+    fn name(&self) -> &'static str;
+
+    /// Get location address from lat long
+    fn ll_to_address(&self, lat: f64, lng: f64, conn: &PgConnection) -> Result<LocInfo>;
+
+    /// Get latitude longitude from query location path,
+    /// eg: `/Indonesia/Jawa Tengah/Wonosobo`.
+    fn address_to_ll(&self, query: &str, conn: &PgConnection) -> Result<LatLong>;
+}
+
+/// Geocoder menggunakan HERE API.
+pub struct HereGeocoder {
+    api_key: String,
+}
+
+impl HereGeocoder {
+    /// Create new HERE geocoder.
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+        }
+    }
+
+    /// Create HERE geocoder dari konfigurasi `geolocator.api_key`,
+    /// returns `None` apabila api key tidak diset.
+    pub fn from_config(config: &GeolocatorConfig) -> Option<Self> {
+        match config.api_key {
+            Some(ref key) if !key.trim().is_empty() => Some(Self::new(key.trim())),
+            _ => None,
+        }
+    }
+}
+
+impl Geocoder for HereGeocoder {
+    fn name(&self) -> &'static str {
+        "here"
+    }
+
+    fn ll_to_address(&self, lat: f64, lng: f64, _conn: &PgConnection) -> Result<LocInfo> {
+        let url_query = format!("https://reverse.geocoder.ls.hereapi.com/6.2/reversegeocode.json?prox={},{}&mode=retrieveAddresses&maxResults=1&gen=1&apiKey={}",
+        lat,lng,
+        self.api_key);
+        let mut resp = reqwest::get(&url_query)?;
+
+        let resp_text: String = resp.text()?;
+
+        let mut item: GeocoderResponseWrapper = serde_json::from_str(&resp_text)?;
+        if item.response.view.is_empty() || item.response.view[0].result.is_empty() {
+            error!("in getting geo locator data {:?}", item);
+            return Err(Error::NotFound("geo locator data not found".to_string()));
+        }
+
+        let loc = item.response.view[0]
+            .result
+            .pop()
+            .expect("ll_to_loc cannot get result");
+
+        loc.location
+            .address
+            .ok_or_else(|| Error::NotFound("geo locator address not found".to_string()))
+    }
+
+    fn address_to_ll(&self, query: &str, conn: &PgConnection) -> Result<LatLong> {
+        if let Some(latlong) = cached_ll(query, conn) {
+            return Ok(latlong);
+        }
+
+        let query = normalize_query(query.to_lowercase());
 
-    let resp_text: String = resp.text()?;
+        let (country, province, city) = split_loc_path(&query);
+
+        let url_query = format!(
+            "https://geocoder.ls.hereapi.com/6.2/geocode.json?apiKey={}&country={}&county={}&city={}",
+            self.api_key, country, province, city
+        );
+        let mut resp = reqwest::get(&url_query)?;
+        let resp_text = resp.text()?;
+        let item: GeocoderResponseWrapper = serde_json::from_str(&resp_text)?;
+        if item.response.view.is_empty() || item.response.view[0].result.is_empty() {
+            error!("in getting geo locator data {:?}", item);
+            return Err(Error::NotFound("geo locator data not found".to_string()));
+        }
+        let latlong = item.response.view[0].result[0].location.display_position;
+
+        // simpan dalam cache
+        {
+            if let Err(e) = diesel::insert_into(geoloc_cache::table)
+                .values(&NewGeolocCache {
+                    name: &query,
+                    latitude: latlong.latitude,
+                    longitude: latlong.longitude,
+                })
+                .execute(conn)
+            {
+                error!("Cannot insert new cache for location query {}", query);
+            }
+        }
 
-    let mut item: GeocoderResponseWrapper = serde_json::from_str(&resp_text)?;
-    if item.response.view.is_empty() || item.response.view[0].result.is_empty() {
-        error!("in getting geo locator data {:?}", item);
-        error!("url_query: {}", url_query);
-        return Err(Error::NotFound("geo locator data not found".to_string()));
+        Ok(latlong)
+    }
+}
+
+/// Geocoder offline menggunakan data lokal: `geoloc_cache`, `cities`,
+/// `villages` dan `boundaries`.
+pub struct GazetteerGeocoder;
+
+impl Geocoder for GazetteerGeocoder {
+    fn name(&self) -> &'static str {
+        "gazetteer"
     }
 
-    let loc = item.response.view[0]
-        .result
-        .pop()
-        .expect("ll_to_loc cannot get result");
+    fn ll_to_address(&self, lat: f64, lng: f64, conn: &PgConnection) -> Result<LocInfo> {
+        use crate::schema::villages::{self, dsl};
+
+        let area = BoundaryDao::new(conn).resolve(lat, lng)?;
+
+        if let Some(city) = area.city {
+            let district = area.district.map(|a| a.name);
+            let subdistrict = area.village.map(|a| a.name);
+            return Ok(LocInfo {
+                label: area_label(&city.province, &city.name, &district, &subdistrict),
+                country_code: city.country_code,
+                province: city.province,
+                city: Some(city.name),
+                district,
+                subdistrict,
+            });
+        }
 
-    Ok(loc.location.address.expect("ll_to_loc cannot get address"))
+        // tidak ada boundary, gunakan desa terdekat
+        let village = villages::table
+            .filter(
+                dsl::latitude
+                    .between(lat - NEAREST_VILLAGE_RADIUS, lat + NEAREST_VILLAGE_RADIUS)
+                    .and(dsl::longitude.between(lng - NEAREST_VILLAGE_RADIUS, lng + NEAREST_VILLAGE_RADIUS)),
+            )
+            .load::<Village>(conn)?
+            .into_iter()
+            .min_by(|a, b| {
+                let da = (a.latitude - lat).powi(2) + (a.longitude - lng).powi(2);
+                let db = (b.latitude - lat).powi(2) + (b.longitude - lng).powi(2);
+                da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .ok_or_else(|| Error::NotFound("geo locator data not found".to_string()))?;
+
+        let district = Some(village.district_name);
+        let subdistrict = Some(village.name);
+        Ok(LocInfo {
+            label: area_label(&village.province, &village.city, &district, &subdistrict),
+            country_code: "ID".to_string(),
+            province: village.province,
+            city: Some(village.city),
+            district,
+            subdistrict,
+        })
+    }
+
+    fn address_to_ll(&self, query: &str, conn: &PgConnection) -> Result<LatLong> {
+        use crate::schema::villages::{self, dsl as dsl_v};
+
+        let normalized = normalize_query(query.to_lowercase());
+
+        if let Some(latlong) = cached_ll(query, conn) {
+            return Ok(latlong);
+        }
+
+        // hanya level kota/kab yang bisa dijawab dari data lokal,
+        // menggunakan titik tengah dari desa-desa yang ada di kota tersebut.
+        let (_, province, city) = split_loc_path(&normalized);
+        if city.is_empty() {
+            return Err(Error::NotFound("geo locator data not found".to_string()));
+        }
+
+        let city = CityDao::new(conn).get_by_name(province, city).map_err(|_| {
+            Error::NotFound(format!("city `{}` not found in gazetteer", city))
+        })?;
+
+        let coords = villages::table
+            .filter(
+                dsl_v::city_id
+                    .eq(city.id)
+                    .and(dsl_v::latitude.ne(0.0).or(dsl_v::longitude.ne(0.0))),
+            )
+            .select((dsl_v::latitude, dsl_v::longitude))
+            .load::<(f64, f64)>(conn)?;
+
+        if coords.is_empty() {
+            return Err(Error::NotFound("geo locator data not found".to_string()));
+        }
+
+        let count = coords.len() as f64;
+        Ok(LatLong {
+            latitude: coords.iter().map(|a| a.0).sum::<f64>() / count,
+            longitude: coords.iter().map(|a| a.1).sum::<f64>() / count,
+        })
+    }
+}
+
+/// Geocoder yang mencoba geocoder satu per satu sesuai urutan,
+/// apabila gagal maka dilanjutkan ke geocoder berikutnya.
+pub struct ChainGeocoder {
+    geocoders: Vec<Box<dyn Geocoder>>,
+}
+
+impl ChainGeocoder {
+    /// Create new chain geocoder.
+    pub fn new(geocoders: Vec<Box<dyn Geocoder>>) -> Self {
+        Self { geocoders }
+    }
+
+    /// Chain default: HERE apabila `geolocator.api_key` diset,
+    /// kemudian gazetteer lokal sebagai fallback.
+    pub fn from_config(config: &GeolocatorConfig) -> Self {
+        let mut geocoders: Vec<Box<dyn Geocoder>> = vec![];
+        match HereGeocoder::from_config(config) {
+            Some(here) => geocoders.push(Box::new(here)),
+            None => warn!("geolocator.api_key not set, only using local gazetteer"),
+        }
+        geocoders.push(Box::new(GazetteerGeocoder));
+        Self::new(geocoders)
+    }
+
+    /// Nama geocoder di dalam chain sesuai urutan.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.geocoders.iter().map(|geocoder| geocoder.name()).collect()
+    }
+}
+
+impl Geocoder for ChainGeocoder {
+    fn name(&self) -> &'static str {
+        "chain"
+    }
+
+    fn ll_to_address(&self, lat: f64, lng: f64, conn: &PgConnection) -> Result<LocInfo> {
+        for geocoder in &self.geocoders {
+            match geocoder.ll_to_address(lat, lng, conn) {
+                Ok(loc_info) => return Ok(loc_info),
+                Err(e) => debug!("geocoder {} cannot resolve {},{}: {}", geocoder.name(), lat, lng, e),
+            }
+        }
+        Err(Error::NotFound("geo locator data not found".to_string()))
+    }
+
+    fn address_to_ll(&self, query: &str, conn: &PgConnection) -> Result<LatLong> {
+        for geocoder in &self.geocoders {
+            match geocoder.address_to_ll(query, conn) {
+                Ok(latlong) => return Ok(latlong),
+                Err(e) => debug!("geocoder {} cannot resolve `{}`: {}", geocoder.name(), query, e),
+            }
+        }
+        Err(Error::NotFound("geo locator data not found".to_string()))
+    }
+}
+
+/// Radius pencarian desa terdekat dalam derajat (~5.5km).
+const NEAREST_VILLAGE_RADIUS: f64 = 0.05;
+
+lazy_static! {
+    static ref CURRENT: RwLock<Option<Arc<dyn Geocoder>>> = RwLock::new(None);
+}
+
+/// Pasang geocoder default yang digunakan oleh [ll_to_address], [address_to_ll]
+/// dan `AppState`, ketika startup menggunakan [ChainGeocoder::from_config],
+/// berguna juga untuk test yang membutuhkan geocoder khusus.
+pub fn init(geocoder: Box<dyn Geocoder>) -> Arc<dyn Geocoder> {
+    let geocoder: Arc<dyn Geocoder> = Arc::from(geocoder);
+    *CURRENT.write().unwrap() = Some(geocoder.clone());
+    geocoder
+}
+
+/// Geocoder default yang dipasang oleh [init].
+pub fn get() -> Arc<dyn Geocoder> {
+    CURRENT
+        .read()
+        .unwrap()
+        .clone()
+        .expect("Geocoder not initialized, call geolocator::init first")
+}
+
+/// Get location address from lat long
+pub fn ll_to_address(lat: f64, lng: f64, conn: &PgConnection) -> Result<LocInfo> {
+    get().ll_to_address(lat, lng, conn)
 }
 
 /// Get latitude longitude from query location name like city etc.
 pub fn address_to_ll(query: &str, conn: &PgConnection) -> Result<LatLong> {
-    use crate::schema::geoloc_cache::{self, dsl};
+    get().address_to_ll(query, conn)
+}
 
-    // coba ambil dulu dari cache apabila ada
-    if let Ok(geoloc) = geoloc_cache::table
-        .filter(lower(dsl::name).eq(query.to_lowercase()))
+/// Ambil lat long dari `geoloc_cache` apabila query pernah di-resolve sebelumnya.
+fn cached_ll(query: &str, conn: &PgConnection) -> Option<LatLong> {
+    use crate::schema::geoloc_cache::dsl;
+
+    let normalized = normalize_query(query.to_lowercase());
+    geoloc_cache::table
+        .filter(
+            lower(dsl::name)
+                .eq(query.to_lowercase())
+                .or(lower(dsl::name).eq(&normalized)),
+        )
         .select((dsl::latitude, dsl::longitude))
         .first::<(f64, f64)>(conn)
-    {
-        return Ok(LatLong {
-            latitude: geoloc.0,
-            longitude: geoloc.1,
-        });
-    }
-
-    // tidak ada di cache, ambil dari source luar
-    let query = normalize_query(query.to_lowercase());
-
-    let (country, province, city) = {
-        let mut s: Vec<&str> = query.split('/').collect();
-        s = s.into_iter().filter(|a| a.trim().len() > 0).collect();
-        match &s[0..] {
-            &[a] => (a, "", ""),
-            &[a, b] => (a, b, ""),
-            &[a, b, c] => (a, b, c),
-            _ => ("", "", ""),
-        }
-    };
-
-    let url_query = format!(
-        "https://geocoder.ls.hereapi.com/6.2/geocode.json?apiKey={}&country={}&county={}&city={}",
-        env::var("GEOLOCATOR_API_KEY").expect("GEOLOCATOR_API_KEY env not set"),
-        country.trim(),
-        province.trim(),
-        city.trim()
-    );
-    let mut resp = reqwest::get(&url_query)?;
-    let resp_text = resp.text()?;
-    let item: GeocoderResponseWrapper = serde_json::from_str(&resp_text)?;
-    if item.response.view.is_empty() || item.response.view[0].result.is_empty() {
-        error!("in getting geo locator data {:?}", item);
-        error!("url_query: {}", url_query);
-        return Err(Error::NotFound("geo locator data not found".to_string()));
-    }
-    let latlong = item.response.view[0].result[0].location.display_position;
-
-    // simpan dalam cache
-    {
-        if let Err(e) = diesel::insert_into(geoloc_cache::table)
-            .values(&NewGeolocCache {
-                name: &query,
-                latitude: latlong.latitude,
-                longitude: latlong.longitude,
-            })
-            .execute(conn)
-        {
-            error!("Cannot insert new cache for location query {}", query);
-        }
+        .ok()
+        .map(|(latitude, longitude)| LatLong { latitude, longitude })
+}
+
+/// Pecah location path menjadi (country, province, city).
+fn split_loc_path(query: &str) -> (&str, &str, &str) {
+    let s: Vec<&str> = query
+        .split('/')
+        .map(|a| a.trim())
+        .filter(|a| !a.is_empty())
+        .collect();
+    match &s[0..] {
+        &[a] => (a, "", ""),
+        &[a, b] => (a, b, ""),
+        &[a, b, c] => (a, b, c),
+        _ => ("", "", ""),
     }
+}
 
-    Ok(latlong)
+fn area_label(province: &str, city: &str, district: &Option<String>, subdistrict: &Option<String>) -> String {
+    let mut parts: Vec<&str> = vec![];
+    if let Some(a) = subdistrict.as_ref() {
+        parts.push(a);
+    }
+    if let Some(a) = district.as_ref() {
+        parts.push(a);
+    }
+    parts.push(city);
+    parts.push(province);
+    parts.join(", ")
 }
 
 /// Normalize location name
@@ -228,4 +487,35 @@ mod tests {
         assert_eq!(normalize_query("kabupaten wonosobo"), "wonosobo");
         assert_eq!(normalize_query("provinsi kalimantan utara"), "kalimantan utara");
     }
+
+    #[test]
+    fn test_split_loc_path() {
+        assert_eq!(split_loc_path("/indonesia/jawa tengah/wonosobo"), ("indonesia", "jawa tengah", "wonosobo"));
+        assert_eq!(split_loc_path("indonesia/ jawa tengah"), ("indonesia", "jawa tengah", ""));
+        assert_eq!(split_loc_path(""), ("", "", ""));
+    }
+
+    #[test]
+    fn test_here_geocoder_without_api_key() {
+        assert!(HereGeocoder::from_config(&GeolocatorConfig::default()).is_none());
+        assert!(HereGeocoder::from_config(&GeolocatorConfig {
+            api_key: Some(" ".to_string())
+        })
+        .is_none());
+    }
+
+    #[test]
+    fn test_chain_order() {
+        assert_eq!(
+            ChainGeocoder::from_config(&GeolocatorConfig::default()).names(),
+            vec!["gazetteer"]
+        );
+        assert_eq!(
+            ChainGeocoder::from_config(&GeolocatorConfig {
+                api_key: Some("xxx".to_string())
+            })
+            .names(),
+            vec!["here", "gazetteer"]
+        );
+    }
 }
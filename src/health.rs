@@ -0,0 +1,125 @@
+//! Health check untuk orchestrator.
+//!
+//! * `/health` - liveness, selalu sukses selama proses masih bisa melayani request.
+//! * `/ready` - readiness, DB bisa diakses, migration sudah diterapkan dan event dispatcher berjalan.
+//! * `/status` - (private) status setiap job terjadwal dan umur data `records` per loc_path.
+//!
+//! Data dianggap basi apabila tidak ada update lebih dari `monitor.record_stale_hours` jam.
+
+use chrono::prelude::*;
+use diesel::{prelude::*, sql_query, sql_types};
+
+use crate::{
+    config::Config, dao::RecordDao, db, error::Error, eventstream, models::ScheduledJob, result::Result,
+    scheduler,
+};
+
+/// Versi migration terakhir yang dibundel bersama binary, lihat `build.rs`.
+pub const LATEST_MIGRATION: &str = env!("PANDEMIA_LATEST_MIGRATION");
+
+/// Hasil readiness check.
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    /// Semua check sukses.
+    pub ready: bool,
+    /// DB bisa diakses.
+    pub database: bool,
+    /// Migration terakhir ([LATEST_MIGRATION]) sudah diterapkan.
+    pub migrations: bool,
+    /// Event dispatcher berjalan.
+    pub event_dispatcher: bool,
+}
+
+/// Umur data terakhir untuk satu loc_path.
+#[derive(Debug, Serialize)]
+pub struct RecordFreshness {
+    /// Path lokasi, eg: `/Indonesia/Jawa Tengah`.
+    pub loc_path: String,
+    /// Waktu update terakhir.
+    pub last_updated: NaiveDateTime,
+    /// Umur data dalam detik.
+    pub age_secs: i64,
+    /// Data sudah melewati batas `monitor.record_stale_hours`.
+    pub stale: bool,
+}
+
+/// Status detail server.
+#[derive(Debug, Serialize)]
+pub struct Status {
+    /// Hasil readiness check.
+    pub readiness: Readiness,
+    /// Hasil jalan terakhir & jadwal berikutnya setiap job.
+    pub jobs: Vec<ScheduledJob>,
+    /// Umur data per loc_path.
+    pub records: Vec<RecordFreshness>,
+    /// Jumlah loc_path yang datanya basi.
+    pub stale_records: usize,
+}
+
+#[derive(QueryableByName)]
+struct MigrationVersion {
+    #[sql_type = "sql_types::Text"]
+    version: String,
+}
+
+fn migrations_applied(conn: &PgConnection) -> bool {
+    sql_query("SELECT version FROM __diesel_schema_migrations WHERE version = $1")
+        .bind::<sql_types::Text, _>(LATEST_MIGRATION)
+        .load::<MigrationVersion>(conn)
+        .map(|versions| !versions.is_empty())
+        .unwrap_or_else(|e| {
+            warn!("cannot check migrations: {}", e);
+            false
+        })
+}
+
+/// Jalankan readiness check.
+pub fn readiness() -> Readiness {
+    let (database, migrations) = match db::clone().get() {
+        Ok(conn) => (
+            sql_query("SELECT 1").execute(&conn).is_ok(),
+            migrations_applied(&conn),
+        ),
+        Err(e) => {
+            warn!("readiness: cannot get DB connection: {}", e);
+            (false, false)
+        }
+    };
+    let event_dispatcher = eventstream::is_running();
+
+    Readiness {
+        ready: database && migrations && event_dispatcher,
+        database,
+        migrations,
+        event_dispatcher,
+    }
+}
+
+/// Kumpulkan status detail server.
+pub fn status(config: &Config) -> Result<Status> {
+    let readiness = readiness();
+    let conn = db::clone().get().map_err(|e| Error::InternalError(e.into()))?;
+    let now = Utc::now().naive_utc();
+    let max_age = config.monitor.record_stale_hours * 3600;
+
+    let records: Vec<RecordFreshness> = RecordDao::new(&conn)
+        .get_last_updates()?
+        .into_iter()
+        .map(|(loc_path, last_updated)| {
+            let age_secs = now.signed_duration_since(last_updated).num_seconds();
+            RecordFreshness {
+                loc_path,
+                last_updated,
+                age_secs,
+                stale: age_secs > max_age,
+            }
+        })
+        .collect();
+
+    Ok(Status {
+        readiness,
+        jobs: scheduler::jobs(&conn)?,
+        stale_records: records.iter().filter(|a| a.stale).count(),
+        records,
+    })
+}
@@ -0,0 +1,100 @@
+//! Dukungan bahasa (localisation) untuk pesan yang dikirim ke pengguna,
+//! digunakan oleh template email dan katalog error API.
+
+/// Bahasa yang didukung.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Lang {
+    /// Bahasa Indonesia
+    Id,
+    /// English
+    En,
+}
+
+impl Lang {
+    /// Dapatkan bahasa dari kode bahasa, eg: `id`, `en`, `en-US`.
+    pub fn from_code(code: &str) -> Lang {
+        Self::parse_code(code).unwrap_or(Lang::Id)
+    }
+
+    fn parse_code(code: &str) -> Option<Lang> {
+        let code = code.trim().to_lowercase();
+        let primary = code.split(|c| c == '-' || c == '_').next().unwrap_or("");
+        match primary {
+            "en" => Some(Lang::En),
+            "id" | "in" => Some(Lang::Id),
+            _ => None,
+        }
+    }
+
+    /// Pilih bahasa dari nilai header `Accept-Language`, eg: `en-US,en;q=0.9,id;q=0.8`.
+    /// Bahasa yang didukung dengan bobot `q` tertinggi yang dipilih,
+    /// mengembalikan `None` apabila tidak ada bahasa yang didukung.
+    pub fn from_accept_language(value: &str) -> Option<Lang> {
+        let mut best: Option<(Lang, f32)> = None;
+
+        for item in value.split(',') {
+            let mut parts = item.split(';');
+            let lang = match parts.next().and_then(Self::parse_code) {
+                Some(lang) => lang,
+                None => continue,
+            };
+            let q = parts
+                .filter_map(|p| {
+                    let p = p.trim();
+                    if p.starts_with("q=") {
+                        p[2..].trim().parse::<f32>().ok()
+                    } else {
+                        None
+                    }
+                })
+                .next()
+                .unwrap_or(1.0);
+
+            if q <= 0.0 {
+                continue;
+            }
+
+            match best {
+                Some((_, best_q)) if best_q >= q => (),
+                _ => best = Some((lang, q)),
+            }
+        }
+
+        best.map(|(lang, _)| lang)
+    }
+
+    /// Kode bahasa, eg: `id`, `en`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::Id => "id",
+            Lang::En => "en",
+        }
+    }
+}
+
+impl Default for Lang {
+    fn default() -> Self {
+        Lang::Id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_language() {
+        assert_eq!(
+            Lang::from_accept_language("en-US,en;q=0.9,id;q=0.8"),
+            Some(Lang::En)
+        );
+        assert_eq!(
+            Lang::from_accept_language("fr-FR, id;q=0.5, en;q=0.4"),
+            Some(Lang::Id)
+        );
+        assert_eq!(Lang::from_accept_language("en;q=0.3, id-ID"), Some(Lang::Id));
+        assert_eq!(Lang::from_accept_language("en;q=0, fr"), None);
+        assert_eq!(Lang::from_accept_language(""), None);
+        assert_eq!(Lang::from_accept_language("*"), None);
+    }
+}
@@ -17,6 +17,7 @@ extern crate r2d2;
 #[macro_use]
 extern crate diesel;
 extern crate futures;
+extern crate futures_cpupool;
 #[macro_use]
 extern crate failure;
 #[macro_use]
@@ -33,27 +34,40 @@ extern crate regex;
 
 #[macro_use]
 extern crate pandemia_proc_macro;
+extern crate pandemia_client;
+extern crate pandemia_types;
 
 extern crate byteorder;
 extern crate ed25519_dalek;
 extern crate hex;
 extern crate rand;
 extern crate rsnowflake;
+extern crate sha1;
 extern crate sha2;
 #[macro_use]
 extern crate lazy_static;
+extern crate argon2;
 extern crate bcrypt;
+extern crate lettre;
+extern crate lettre_email;
+#[macro_use]
+extern crate prometheus;
 #[macro_use]
 extern crate validator_derive;
 extern crate select;
+extern crate toml;
 extern crate validator;
 
 #[macro_use]
 mod macros;
 pub mod admin_dao;
+pub mod admin_two_factor_dao;
 pub mod api;
+pub mod app_release_dao;
 pub mod auth;
+pub mod boundary_dao;
 pub mod city_dao;
+pub mod config;
 pub mod crypto;
 pub mod dao;
 mod db;
@@ -64,21 +78,34 @@ pub mod event_handler;
 pub mod eventstream;
 pub mod feed_dao;
 pub mod geolocator;
+pub mod health;
+pub mod i18n;
 pub mod kvstore;
+pub mod mailer;
 pub mod map_marker_dao;
+pub mod metrics;
 pub mod models;
 pub mod monitor;
 pub mod notif_dao;
 pub mod notif_sender;
+pub mod phone_verification_dao;
 pub mod push_notif_handler;
+pub mod rate_limit;
 pub mod record_dao;
+pub mod region_import;
 pub mod report_note_dao;
 mod result;
+pub mod satgas_invitation_dao;
+pub mod scheduled_job_dao;
+pub mod scheduler;
 mod schema;
 pub mod service;
+pub mod sms;
 mod sqlutil;
 pub mod sub_report_dao;
 pub mod token;
+pub mod totp;
+pub mod trace;
 pub mod types;
 pub mod user_dao;
 pub mod util;
@@ -90,6 +117,21 @@ pub mod web;
 /// Type alias for ID in integer
 pub type ID = i64;
 
+/// Inisialisasi komponen global (DB pool, data key, mailer, SMS, geocoder, FCM,
+/// scheduler dan blocking pool API) sesuai `config`, dipanggil sekali ketika startup
+/// sebelum komponen tersebut digunakan.
+pub fn init(config: &config::Config) {
+    db::init(&config.database);
+    crypto::init(&config.security);
+    mailer::init(&config.mail);
+    sms::init(&config.sms);
+    let geocoder = geolocator::ChainGeocoder::from_config(&config.geolocator);
+    geolocator::init(Box::new(geocoder));
+    event_handler::init(&config.fcm);
+    scheduler::init(config);
+    api::executor::init(config);
+}
+
 /// Common use (prelude) exports.
 #[doc(hidden)]
 pub mod prelude {
@@ -0,0 +1,489 @@
+//! Pengiriman email keluar (reset password, undangan admin, notifikasi perubahan akun).
+//!
+//! Email tidak dikirim langsung di dalam request, melainkan dimasukkan ke antrian
+//! dan dikirim oleh worker thread, sehingga SMTP yang lambat tidak memblokir request.
+//!
+//! Konfigurasi melalui section `mail` (lihat [MailConfig]) atau env var:
+//!
+//! * `SMTP_HOST`, `SMTP_USERNAME`, `SMTP_PASSWORD` - kirim melalui SMTP (STARTTLS, port 587).
+//! * `MAIL_DIR` - apabila SMTP tidak diset, email ditulis sebagai file di direktori ini,
+//!   apabila keduanya tidak diset email tidak dikirim dan hanya dicatat di log.
+//! * `MAIL_FROM` - alamat pengirim, default `noreply@pandemia.local`.
+//! * `MAIL_LANG` - bahasa default email, `id` atau `en`.
+//! * `PANDEMIA_WEB_URL` - base url dashboard untuk link di dalam email.
+
+use lettre::{smtp::authentication::Credentials, SmtpClient, Transport};
+use lettre_email::EmailBuilder;
+
+pub use crate::i18n::Lang;
+use crate::{config::MailConfig, result::Result, util};
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+};
+
+/// Berapa kali pengiriman dicoba sebelum email dibuang.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Email yang akan dikirim.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    /// Alamat tujuan.
+    pub to: String,
+    /// Subject email.
+    pub subject: String,
+    /// Isi email dalam format plain text.
+    pub body: String,
+}
+
+/// Abstraksi pengirim email.
+pub trait Mailer: Send + Sync {
+    /// Nama implementasi mailer, untuk keperluan logging.
+    fn name(&self) -> &'static str;
+
+    /// Kirim email.
+    fn send(&self, email: &Email) -> Result<()>;
+}
+
+/// Mailer yang mengirim email melalui SMTP.
+pub struct SmtpMailer {
+    host: String,
+    username: String,
+    password: String,
+    from: String,
+}
+
+impl SmtpMailer {
+    /// Buat SMTP mailer baru.
+    pub fn new(host: &str, username: &str, password: &str, from: &str) -> Self {
+        Self {
+            host: host.to_owned(),
+            username: username.to_owned(),
+            password: password.to_owned(),
+            from: from.to_owned(),
+        }
+    }
+
+    /// Buat SMTP mailer dari konfigurasi, `None` apabila `mail.smtp_host` tidak diset.
+    pub fn from_config(config: &MailConfig) -> Option<Self> {
+        let host = config.smtp_host.as_ref().filter(|a| !a.trim().is_empty())?;
+        Some(Self::new(
+            host.trim(),
+            &config.smtp_username,
+            &config.smtp_password,
+            &config.from,
+        ))
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    fn send(&self, email: &Email) -> Result<()> {
+        let message = EmailBuilder::new()
+            .to(email.to.as_str())
+            .from(self.from.as_str())
+            .subject(email.subject.as_str())
+            .text(email.body.as_str())
+            .build()
+            .map_err(|e| param_err!(format!("Cannot build email: {}", e)))?;
+
+        let mut client = SmtpClient::new_simple(&self.host)
+            .map_err(|e| crate::error::Error::InternalError(format_err!("Cannot connect to SMTP: {}", e)))?;
+
+        if !self.username.is_empty() {
+            client = client.credentials(Credentials::new(self.username.clone(), self.password.clone()));
+        }
+
+        client
+            .transport()
+            .send(message.into())
+            .map_err(|e| crate::error::Error::InternalError(format_err!("Cannot send email: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Mailer yang menulis setiap email sebagai file di direktori tertentu,
+/// berguna untuk development.
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    /// Buat file mailer baru.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn send(&self, email: &Email) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            util::current_time_millis(),
+            util::random_string(6)
+        ));
+        fs::write(
+            path,
+            format!("To: {}\nSubject: {}\n\n{}\n", email.to, email.subject, email.body),
+        )?;
+        Ok(())
+    }
+}
+
+/// Mailer yang tidak mengirim email dan hanya mencatatnya di log,
+/// digunakan apabila SMTP maupun direktori mail tidak dikonfigurasi.
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn send(&self, email: &Email) -> Result<()> {
+        // isi email tidak dicatat karena bisa berisi token
+        warn!(
+            "Email `{}` to {} not delivered, no mailer configured",
+            email.subject, email.to
+        );
+        Ok(())
+    }
+}
+
+/// Antrian email, email dikirim oleh worker thread secara berurutan.
+pub struct MailQueue {
+    tx: Mutex<Sender<Email>>,
+}
+
+impl MailQueue {
+    /// Jalankan worker pengirim email dan kembalikan antriannya.
+    pub fn start(mailer: Box<dyn Mailer>) -> Self {
+        let (tx, rx) = channel::<Email>();
+
+        thread::spawn(move || {
+            for email in rx {
+                for attempt in 1..=MAX_ATTEMPTS {
+                    match mailer.send(&email) {
+                        Ok(_) => {
+                            debug!(
+                                "[{}] email `{}` sent to {}",
+                                mailer.name(),
+                                email.subject,
+                                email.to
+                            );
+                            break;
+                        }
+                        Err(e) if attempt < MAX_ATTEMPTS => {
+                            warn!(
+                                "[{}] cannot send email to {}, retrying. {}",
+                                mailer.name(),
+                                email.to,
+                                e
+                            );
+                            util::sleep(1000 * u64::from(attempt));
+                        }
+                        Err(e) => error!("[{}] cannot send email to {}. {}", mailer.name(), email.to, e),
+                    }
+                }
+            }
+        });
+
+        Self { tx: Mutex::new(tx) }
+    }
+
+    /// Masukkan email ke antrian.
+    pub fn push(&self, email: Email) {
+        if let Err(e) = self.tx.lock().unwrap().send(email) {
+            error!("Cannot queue email. {}", e);
+        }
+    }
+}
+
+fn mailer_from_config(config: &MailConfig) -> Box<dyn Mailer> {
+    if let Some(mailer) = SmtpMailer::from_config(config) {
+        return Box::new(mailer);
+    }
+    match config.dir.as_ref().filter(|a| !a.trim().is_empty()) {
+        Some(dir) => Box::new(FileMailer::new(dir)),
+        None => {
+            warn!("No mail.smtp_host or mail.dir set, emails will not be delivered");
+            Box::new(LogMailer)
+        }
+    }
+}
+
+/// Base URL web admin/landing page dari konfigurasi `mail.web_url`.
+pub(crate) fn web_url(config: &MailConfig) -> Option<String> {
+    config
+        .web_url
+        .as_ref()
+        .map(|a| a.trim().trim_end_matches('/').to_string())
+        .filter(|a| !a.is_empty())
+}
+
+/// Antrian email global beserta bahasa & base url untuk render template.
+struct Outbox {
+    queue: MailQueue,
+    lang: Lang,
+    web_url: Option<String>,
+}
+
+lazy_static! {
+    static ref OUTBOX: RwLock<Option<Arc<Outbox>>> = RwLock::new(None);
+}
+
+/// Jalankan antrian email global sesuai konfigurasi `mail`, dipanggil sekali ketika startup.
+pub fn init(config: &MailConfig) {
+    *OUTBOX.write().unwrap() = Some(Arc::new(Outbox {
+        queue: MailQueue::start(mailer_from_config(config)),
+        lang: Lang::from_code(&config.lang),
+        web_url: web_url(config),
+    }));
+}
+
+/// Kirim email menggunakan antrian global yang dijalankan oleh [init].
+pub fn send(to: &str, message: &Message) {
+    let outbox = match OUTBOX.read().unwrap().as_ref() {
+        Some(outbox) => outbox.clone(),
+        None => {
+            error!("Mailer not initialized, email to {} dropped", to);
+            return;
+        }
+    };
+    let (subject, body) = message.render(outbox.lang, outbox.web_url.as_ref().map(|a| a.as_str()));
+    outbox.queue.push(Email {
+        to: to.to_owned(),
+        subject,
+        body,
+    });
+}
+
+/// Jenis perubahan akun yang perlu diberitahukan ke pemilik akun.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AccountChange {
+    /// Password diubah.
+    PasswordChanged,
+    /// Hak akses diubah.
+    AccessesChanged,
+}
+
+/// Template pesan email.
+pub enum Message<'a> {
+    /// Token untuk reset password admin.
+    ResetPassword {
+        /// Nama penerima.
+        name: &'a str,
+        /// Email penerima.
+        email: &'a str,
+        /// Token reset password.
+        token: &'a str,
+    },
+    /// Undangan untuk admin yang baru ditambahkan.
+    AdminInvitation {
+        /// Nama penerima.
+        name: &'a str,
+        /// Email penerima.
+        email: &'a str,
+        /// Nama admin yang mengundang.
+        inviter: &'a str,
+    },
+    /// Pemberitahuan perubahan akun.
+    AccountChanged {
+        /// Nama penerima.
+        name: &'a str,
+        /// Jenis perubahan.
+        change: AccountChange,
+    },
+}
+
+impl<'a> Message<'a> {
+    /// Render template menjadi subject & body.
+    pub fn render(&self, lang: Lang, web_url: Option<&str>) -> (String, String) {
+        match (self, lang) {
+            (Message::ResetPassword { name, email, token }, Lang::Id) => (
+                "Reset password Pandemia".to_string(),
+                format!(
+                    "Halo {},\n\nKami menerima permintaan untuk reset password akun Anda.\n\
+                     Kode reset password: {}\n{}\n\
+                     Kode ini berlaku selama 24 jam. Abaikan email ini apabila Anda tidak merasa memintanya.",
+                    name,
+                    token,
+                    reset_link(web_url, email, token)
+                ),
+            ),
+            (Message::ResetPassword { name, email, token }, Lang::En) => (
+                "Pandemia password reset".to_string(),
+                format!(
+                    "Hi {},\n\nWe received a request to reset your account password.\n\
+                     Reset code: {}\n{}\n\
+                     This code is valid for 24 hours. Ignore this email if you did not request it.",
+                    name,
+                    token,
+                    reset_link(web_url, email, token)
+                ),
+            ),
+            (Message::AdminInvitation { name, email, inviter }, Lang::Id) => (
+                "Undangan admin Pandemia".to_string(),
+                format!(
+                    "Halo {},\n\n{} telah menambahkan Anda sebagai admin Pandemia dengan email {}.\n{}",
+                    name,
+                    inviter,
+                    email,
+                    login_link(web_url, "Silahkan login di")
+                ),
+            ),
+            (Message::AdminInvitation { name, email, inviter }, Lang::En) => (
+                "Pandemia admin invitation".to_string(),
+                format!(
+                    "Hi {},\n\n{} has added you as a Pandemia admin with email {}.\n{}",
+                    name,
+                    inviter,
+                    email,
+                    login_link(web_url, "Please login at")
+                ),
+            ),
+            (Message::AccountChanged { name, change }, Lang::Id) => (
+                "Perubahan akun Pandemia".to_string(),
+                format!(
+                    "Halo {},\n\n{} akun Anda telah diubah.\n\
+                     Apabila Anda tidak merasa melakukan perubahan ini, segera hubungi administrator.",
+                    name,
+                    match change {
+                        AccountChange::PasswordChanged => "Password",
+                        AccountChange::AccessesChanged => "Hak akses",
+                    }
+                ),
+            ),
+            (Message::AccountChanged { name, change }, Lang::En) => (
+                "Pandemia account changed".to_string(),
+                format!(
+                    "Hi {},\n\nYour account {} has been changed.\n\
+                     If you did not make this change, please contact the administrator immediately.",
+                    name,
+                    match change {
+                        AccountChange::PasswordChanged => "password",
+                        AccountChange::AccessesChanged => "accesses",
+                    }
+                ),
+            ),
+        }
+    }
+}
+
+fn reset_link(web_url: Option<&str>, email: &str, token: &str) -> String {
+    web_url
+        .map(|url| {
+            let query = serde_urlencoded::to_string(&[("email", email), ("token", token)])
+                .expect("Cannot encode reset password query");
+            format!("{}/reset-password?{}\n", url, query)
+        })
+        .unwrap_or_default()
+}
+
+fn login_link(web_url: Option<&str>, text: &str) -> String {
+    web_url
+        .map(|url| format!("{} {}\n", text, url))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Mailer yang hanya menyimpan email di memory.
+    #[derive(Clone, Default)]
+    pub struct MemoryMailer {
+        sent: Arc<Mutex<Vec<Email>>>,
+    }
+
+    impl MemoryMailer {
+        /// Buat memory mailer baru.
+        pub fn new() -> Self {
+            Default::default()
+        }
+
+        /// Email yang telah terkirim.
+        pub fn sent(&self) -> Vec<Email> {
+            self.sent.lock().unwrap().clone()
+        }
+    }
+
+    impl Mailer for MemoryMailer {
+        fn name(&self) -> &'static str {
+            "memory"
+        }
+
+        fn send(&self, email: &Email) -> Result<()> {
+            self.sent.lock().unwrap().push(email.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_render_reset_password() {
+        let message = Message::ResetPassword {
+            name: "Robin",
+            email: "robin@mail.com",
+            token: "abc123",
+        };
+
+        let (subject, body) = message.render(Lang::Id, Some("https://pandemia.example"));
+        assert_eq!(subject, "Reset password Pandemia");
+        assert!(body.contains("abc123"));
+        assert!(body.contains("https://pandemia.example/reset-password?email=robin%40mail.com&token=abc123"));
+
+        let message = Message::ResetPassword {
+            name: "Robin",
+            email: "robin+1@mail.com",
+            token: "a&b=c",
+        };
+        let (_, body) = message.render(Lang::Id, Some("https://pandemia.example"));
+        assert!(body.contains("reset-password?email=robin%2B1%40mail.com&token=a%26b%3Dc"));
+
+        let (subject, body) = message.render(Lang::En, None);
+        assert_eq!(subject, "Pandemia password reset");
+        assert!(!body.contains("reset-password?"));
+    }
+
+    #[test]
+    fn test_lang_from_code() {
+        assert_eq!(Lang::from_code("en-US"), Lang::En);
+        assert_eq!(Lang::from_code("id"), Lang::Id);
+        assert_eq!(Lang::from_code(""), Lang::Id);
+    }
+
+    #[test]
+    fn test_mail_queue_delivery() {
+        let mailer = MemoryMailer::new();
+        let queue = MailQueue::start(Box::new(mailer.clone()));
+        let email = Email {
+            to: "robin@mail.com".to_string(),
+            subject: "test".to_string(),
+            body: "hello".to_string(),
+        };
+        queue.push(email.clone());
+
+        for _ in 0..100 {
+            if !mailer.sent().is_empty() {
+                break;
+            }
+            util::sleep(10);
+        }
+        assert_eq!(mailer.sent(), vec![email]);
+    }
+}
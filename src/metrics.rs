@@ -0,0 +1,140 @@
+//! Metrics dalam format Prometheus, di-expose pada `/metrics` di listener private.
+//!
+//! Metrics yang tersedia:
+//!
+//! * `pandemia_api_requests_total` & `pandemia_api_request_duration_seconds` - per endpoint.
+//! * `pandemia_db_pool_*` - penggunaan pool koneksi DB.
+//! * `pandemia_api_pending_handlers` - handler yang sedang berjalan/mengantri di blocking pool.
+//! * `pandemia_monitor_run_duration_seconds` - lama satu kali jalan job terjadwal, eg: `data_monitor`.
+//! * `pandemia_scraper_results_total` - hasil pengambilan data per sumber.
+//! * `pandemia_event_queue_depth` - jumlah event di `eventstream` yang belum diproses.
+//! * `pandemia_push_notif_total` - hasil pengiriman push notif FCM per device.
+
+use prometheus::{Encoder, HistogramVec, IntCounterVec, IntGauge, TextEncoder};
+
+use crate::{api, db, eventstream};
+
+use std::time::Duration;
+
+lazy_static! {
+    static ref API_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "pandemia_api_requests_total",
+        "Jumlah request API per endpoint",
+        &["endpoint", "method", "status"]
+    )
+    .expect("cannot register metric");
+    static ref API_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "pandemia_api_request_duration_seconds",
+        "Lama waktu pemrosesan request API per endpoint",
+        &["endpoint", "method"]
+    )
+    .expect("cannot register metric");
+    static ref API_PENDING: IntGauge = register_int_gauge!(
+        "pandemia_api_pending_handlers",
+        "Jumlah handler yang sedang berjalan/mengantri di blocking pool"
+    )
+    .expect("cannot register metric");
+    static ref DB_POOL_SIZE: IntGauge =
+        register_int_gauge!("pandemia_db_pool_size", "Jumlah koneksi maksimal di pool DB")
+            .expect("cannot register metric");
+    static ref DB_POOL_CONNECTIONS: IntGauge = register_int_gauge!(
+        "pandemia_db_pool_connections",
+        "Jumlah koneksi yang sedang terbuka di pool DB"
+    )
+    .expect("cannot register metric");
+    static ref DB_POOL_IDLE: IntGauge = register_int_gauge!(
+        "pandemia_db_pool_idle_connections",
+        "Jumlah koneksi yang tidak sedang digunakan di pool DB"
+    )
+    .expect("cannot register metric");
+    static ref MONITOR_RUN_DURATION: HistogramVec = register_histogram_vec!(
+        "pandemia_monitor_run_duration_seconds",
+        "Lama waktu satu kali jalan monitor",
+        &["monitor"],
+        vec![1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0]
+    )
+    .expect("cannot register metric");
+    static ref SCRAPER_RESULTS: IntCounterVec = register_int_counter_vec!(
+        "pandemia_scraper_results_total",
+        "Hasil pengambilan data dari sumber remote",
+        &["source", "result"]
+    )
+    .expect("cannot register metric");
+    static ref EVENT_QUEUE_DEPTH: IntGauge = register_int_gauge!(
+        "pandemia_event_queue_depth",
+        "Jumlah event yang belum diproses oleh event listener"
+    )
+    .expect("cannot register metric");
+    static ref PUSH_NOTIF: IntCounterVec = register_int_counter_vec!(
+        "pandemia_push_notif_total",
+        "Hasil pengiriman push notif FCM per device",
+        &["result"]
+    )
+    .expect("cannot register metric");
+}
+
+fn secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + f64::from(d.subsec_nanos()) / 1e9
+}
+
+fn result_label(ok: bool) -> &'static str {
+    if ok {
+        "success"
+    } else {
+        "failure"
+    }
+}
+
+/// Catat satu request API yang telah selesai diproses.
+pub fn observe_request(endpoint: &str, method: &str, status: u16, elapsed: Duration) {
+    API_REQUESTS
+        .with_label_values(&[endpoint, method, &status.to_string()])
+        .inc();
+    API_REQUEST_DURATION
+        .with_label_values(&[endpoint, method])
+        .observe(secs(elapsed));
+}
+
+/// Catat lama satu kali jalan job terjadwal `monitor`.
+pub fn observe_monitor_run(monitor: &str, elapsed: Duration) {
+    MONITOR_RUN_DURATION
+        .with_label_values(&[monitor])
+        .observe(secs(elapsed));
+}
+
+/// Catat hasil pengambilan data dari `source`, mengembalikan `result` apa adanya.
+pub fn scraped<T, E>(source: &str, result: Result<T, E>) -> Result<T, E> {
+    SCRAPER_RESULTS
+        .with_label_values(&[source, result_label(result.is_ok())])
+        .inc();
+    result
+}
+
+/// Catat hasil pengiriman push notif, dihitung per device tujuan.
+pub fn push_delivered(success: u64, failure: u64) {
+    PUSH_NOTIF.with_label_values(&["success"]).inc_by(success as i64);
+    PUSH_NOTIF.with_label_values(&["failure"]).inc_by(failure as i64);
+}
+
+/// Render semua metrics dalam format text Prometheus.
+pub fn render() -> String {
+    let pool = db::clone();
+    let state = pool.state();
+    DB_POOL_SIZE.set(i64::from(pool.max_size()));
+    DB_POOL_CONNECTIONS.set(i64::from(state.connections));
+    DB_POOL_IDLE.set(i64::from(state.idle_connections));
+    API_PENDING.set(api::executor::pending() as i64);
+    EVENT_QUEUE_DEPTH.set(eventstream::pending() as i64);
+
+    let mut buffer = vec![];
+    let encoder = TextEncoder::new();
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        error!("cannot encode metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Content type untuk response `/metrics`.
+pub fn content_type() -> String {
+    TextEncoder::new().format_type().to_string()
+}
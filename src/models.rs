@@ -1,6 +1,6 @@
 //! Definisi struct untuk model-model yang ada di dalam database.
 
-use crate::{result::Result, schema::user_settings, types::RecordDiff};
+use crate::{result::Result, schema::user_settings, types::RecordDiff, util};
 use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*;
 use serde::Serialize;
@@ -17,7 +17,7 @@ pub trait HasID {
 }
 
 /// Bentuk model akun di dalam database.
-#[derive(Queryable, Clone, Serialize, PartialEq)]
+#[derive(Queryable, Clone, Serialize, Deserialize, PartialEq, ApiSchema)]
 pub struct User {
     /// ID dari akun.
     pub id: i64,
@@ -95,6 +95,17 @@ impl User {
     pub fn is_medic(&self) -> bool {
         list_has_flag!(self.meta, "medic")
     }
+
+    /// Check apakah akun ini adalah akun device (anonim) yang dibuat otomatis
+    /// oleh `/auth/v1/device/authorize` dan belum diverifikasi nomor telp-nya.
+    pub fn is_device_account(&self) -> bool {
+        self.full_name.starts_with("gen__") && !self.is_phone_verified()
+    }
+
+    /// Check apakah nomor telepon akun ini telah diverifikasi menggunakan OTP.
+    pub fn is_phone_verified(&self) -> bool {
+        list_has_flag!(self.meta, "phone_verified")
+    }
 }
 
 /// Bentuk model dari alamat untuk akun.
@@ -144,12 +155,26 @@ pub struct RegisterUser {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize, PartialEq, Debug)]
+#[derive(Queryable, Serialize, PartialEq, Debug, ApiSchema)]
 pub struct AccessToken {
     pub token: String,
     pub user_id: i64,
     pub created: NaiveDateTime,
     pub valid_thru: NaiveDateTime,
+    #[serde(skip_serializing)]
+    pub id: ID,
+    #[serde(skip_serializing)]
+    pub device_id: String,
+    #[serde(skip_serializing)]
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub platform: String,
+    #[serde(skip_serializing)]
+    pub ip_address: String,
+    #[serde(skip_serializing)]
+    pub last_used: NaiveDateTime,
+    pub refresh_token: String,
+    pub refresh_valid_thru: NaiveDateTime,
 }
 
 #[doc(hidden)]
@@ -171,6 +196,7 @@ pub struct UserKey {
     pub secret_key: String,
     pub created: NaiveDateTime,
     pub active: bool,
+    pub client_key: bool,
 }
 
 impl fmt::Display for User {
@@ -186,7 +212,7 @@ impl fmt::Display for UserKey {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct Admin {
     pub id: ID,
     pub name: String,
@@ -205,7 +231,7 @@ impl Admin {
 
     /// Check whether user has access to some resource
     pub fn has_access(&self, access_name: &str) -> bool {
-        if self.id == 1 {
+        if self.is_super_admin() {
             // selalu true untuk super admin
             return true;
         }
@@ -217,21 +243,35 @@ impl Admin {
 
     /// Check whether this is super admin
     pub fn is_super_admin(&self) -> bool {
-        self.id == 1
+        self.id == crate::admin_dao::SUPER_ADMIN_ID
     }
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, ApiSchema)]
 pub struct AdminAccessToken {
     pub token: String,
     pub admin_id: ID,
     pub created: NaiveDateTime,
     pub valid_thru: NaiveDateTime,
+    #[serde(skip_serializing)]
+    pub id: ID,
+    #[serde(skip_serializing)]
+    pub device_id: String,
+    #[serde(skip_serializing)]
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub platform: String,
+    #[serde(skip_serializing)]
+    pub ip_address: String,
+    #[serde(skip_serializing)]
+    pub last_used: NaiveDateTime,
+    pub refresh_token: String,
+    pub refresh_valid_thru: NaiveDateTime,
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, ApiSchema)]
 pub struct ResetPasswordAdmin {
     pub admin_id: ID,
     pub token: String,
@@ -240,7 +280,7 @@ pub struct ResetPasswordAdmin {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize, Clone, Debug)]
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug, ApiSchema)]
 pub struct Record {
     pub id: ID,
     pub loc: String,
@@ -284,7 +324,7 @@ impl Record {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, ApiSchema)]
 pub struct Notif {
     pub id: ID,
     pub kind: i16,
@@ -298,7 +338,7 @@ pub struct Notif {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct Feed {
     pub id: ID,
     pub creator_id: ID,
@@ -312,12 +352,14 @@ pub struct Feed {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, ApiSchema)]
 pub struct UserSetting {
     pub id: ID,
     pub user_id: ID,
     pub s_key: String,
     pub s_value: String,
+    #[serde(skip_serializing)]
+    pub ts: NaiveDateTime,
 }
 
 #[derive(Insertable)]
@@ -329,7 +371,7 @@ struct NewUserSetting<'a> {
 }
 
 #[doc(hidden)]
-#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, ApiSchema)]
 pub struct LatLong {
     /// The latitude
     pub lat: f64,
@@ -350,7 +392,7 @@ impl User {
                 > 0;
             if already_exists {
                 diesel::update(dsl::user_settings.filter(dsl::user_id.eq(self.id).and(dsl::s_key.eq(key))))
-                    .set(dsl::s_value.eq(&value))
+                    .set((dsl::s_value.eq(&value), dsl::ts.eq(util::now())))
                     .execute(conn)?;
             } else {
                 diesel::insert_into(user_settings::table)
@@ -420,7 +462,7 @@ impl User {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, ApiSchema)]
 pub struct GeolocCache {
     pub id: ID,
     pub name: String,
@@ -430,7 +472,7 @@ pub struct GeolocCache {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct MapMarker {
     pub id: ID,
     pub name: String,
@@ -464,7 +506,7 @@ impl MapMarker {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct Log {
     pub id: ID,
     pub activity: String,
@@ -474,7 +516,7 @@ pub struct Log {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, ApiSchema)]
 pub struct SubReport {
     pub id: ID,
     pub creator_id: ID,
@@ -493,10 +535,19 @@ pub struct SubReport {
     pub city_id: ID,
     pub district_id: ID,
     pub village_id: ID,
+    #[serde(skip_serializing)]
+    pub pii: String,
+    #[serde(skip_serializing)]
+    pub full_name_bidx: Vec<String>,
+    #[serde(skip_serializing)]
+    pub age_bidx: String,
+    #[serde(skip_serializing)]
+    pub residence_address_bidx: String,
+    pub last_updated: NaiveDateTime,
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct Village {
     pub id: ID,
     pub name: String,
@@ -509,10 +560,11 @@ pub struct Village {
     pub ts: NaiveDateTime,
     pub city_id: ID,
     pub district_id: ID,
+    pub code: String,
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct City {
     pub id: ID,
     pub name: String,
@@ -520,10 +572,11 @@ pub struct City {
     pub country_code: String,
     pub area_code: String,
     pub ts: NaiveDateTime,
+    pub code: String,
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct VillageData {
     pub id: ID,
     pub village_id: ID,
@@ -547,7 +600,7 @@ pub struct VillageData {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct ReportNote {
     pub id: ID,
     pub title: String,
@@ -561,7 +614,7 @@ pub struct ReportNote {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, ApiSchema)]
 pub struct KvStore {
     pub id: ID,
     pub a_key: String,
@@ -569,16 +622,17 @@ pub struct KvStore {
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct District {
     pub id: ID,
     pub name: String,
     pub city_id: ID,
     pub meta: Vec<String>,
+    pub code: String,
 }
 
 #[doc(hidden)]
-#[derive(Queryable, Serialize)]
+#[derive(Queryable, Serialize, Deserialize, ApiSchema)]
 pub struct DistrictData {
     pub id: ID,
     pub district_id: ID,
@@ -600,3 +654,161 @@ pub struct DistrictData {
     pub pdpm: i32,
     pub otg: i32,
 }
+
+/// Batas wilayah administratif (kota, kecamatan, desa) dalam bentuk polygon.
+#[derive(Queryable, Serialize, ApiSchema)]
+pub struct Boundary {
+    /// ID dari boundary.
+    pub id: ID,
+    /// Jenis wilayah, lihat `LocKind`.
+    pub kind: i16,
+    /// ID dari city/district/village yang direferensikan.
+    pub ref_id: ID,
+    /// Nama wilayah.
+    pub name: String,
+    /// Geometry dalam format GeoJSON (Polygon atau MultiPolygon).
+    #[serde(skip_serializing)]
+    pub geometry: String,
+    /// Bounding box: latitude minimal.
+    pub min_lat: f64,
+    /// Bounding box: longitude minimal.
+    pub min_lng: f64,
+    /// Bounding box: latitude maksimal.
+    pub max_lat: f64,
+    /// Bounding box: longitude maksimal.
+    pub max_lng: f64,
+    /// Metadata.
+    pub meta: Vec<String>,
+    /// Waktu pembuatan/pembaharuan.
+    pub ts: NaiveDateTime,
+}
+
+/// Undangan untuk bergabung sebagai satgas di desa tertentu.
+#[derive(Queryable, Serialize, Deserialize, PartialEq, ApiSchema)]
+pub struct SatgasInvitation {
+    /// ID dari undangan.
+    pub id: ID,
+    /// Token undangan, bersifat rahasia dan hanya bisa digunakan sekali.
+    pub token: String,
+    /// ID kota/kab.
+    pub city_id: ID,
+    /// ID kecamatan.
+    pub district_id: ID,
+    /// ID desa.
+    pub village_id: ID,
+    /// Nama desa.
+    pub village_name: String,
+    /// Nama kecamatan.
+    pub district_name: String,
+    /// Apakah satgas yang diundang adalah tenaga medis.
+    pub is_medic: bool,
+    /// Nama orang yang diundang (catatan untuk admin).
+    pub invitee_name: String,
+    /// Nomor telepon orang yang diundang (catatan untuk admin).
+    pub invitee_phone: String,
+    /// ID admin pembuat undangan.
+    pub creator_id: ID,
+    /// Nama admin pembuat undangan.
+    pub creator_name: String,
+    /// ID user yang menerima undangan, 0 apabila belum diterima.
+    pub accepted_by: ID,
+    /// Nama user yang menerima undangan.
+    pub accepted_by_name: String,
+    /// Waktu undangan diterima.
+    pub accepted_at: Option<NaiveDateTime>,
+    /// Apakah undangan telah dibatalkan.
+    pub revoked: bool,
+    /// Batas waktu undangan bisa digunakan.
+    pub expiration: NaiveDateTime,
+    /// Waktu pembuatan.
+    pub ts: NaiveDateTime,
+}
+
+impl SatgasInvitation {
+    /// Status undangan: `accepted`, `revoked`, `expired` atau `pending`.
+    pub fn status(&self) -> &'static str {
+        if self.accepted_by != 0 {
+            "accepted"
+        } else if self.revoked {
+            "revoked"
+        } else if self.expiration <= util::now() {
+            "expired"
+        } else {
+            "pending"
+        }
+    }
+
+    /// Check apakah undangan masih bisa digunakan.
+    pub fn is_usable(&self) -> bool {
+        self.status() == "pending"
+    }
+}
+
+/// Konfigurasi TOTP (autentikasi dua langkah) milik admin,
+/// tidak di-serialize karena berisi secret.
+#[doc(hidden)]
+#[derive(Queryable)]
+pub struct AdminTwoFactor {
+    pub id: ID,
+    pub admin_id: ID,
+    pub secret: String,
+    pub enabled: bool,
+    pub recovery_codes: Vec<String>,
+    pub last_step: i64,
+    pub ts: NaiveDateTime,
+}
+
+/// Verifikasi nomor telepon (OTP) yang sedang berjalan,
+/// tidak di-serialize karena berisi hash kode OTP.
+#[doc(hidden)]
+#[derive(Queryable)]
+pub struct PhoneVerification {
+    pub id: ID,
+    pub user_id: ID,
+    pub phone_num: String,
+    pub code_hash: String,
+    pub attempts: i32,
+    pub expiration: NaiveDateTime,
+    pub ts: NaiveDateTime,
+}
+
+/// Rilis aplikasi mobile yang terdaftar, digunakan untuk pengecekan update oleh client.
+#[doc(hidden)]
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug, PartialEq, ApiSchema)]
+pub struct AppRelease {
+    pub id: ID,
+    pub platform: String,
+    pub version: String,
+    pub min_supported_version: String,
+    pub notes: String,
+    pub download_url: String,
+    pub force_update: bool,
+    pub ts: NaiveDateTime,
+}
+
+/// Job terjadwal beserta hasil jalan terakhirnya, lihat `scheduler`.
+#[doc(hidden)]
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug, PartialEq, ApiSchema)]
+pub struct ScheduledJob {
+    pub id: ID,
+    pub name: String,
+    pub schedule: String,
+    pub last_run: Option<NaiveDateTime>,
+    pub last_success: Option<bool>,
+    pub last_error: Option<String>,
+    pub next_run: Option<NaiveDateTime>,
+    pub ts: NaiveDateTime,
+}
+
+/// Riwayat satu kali jalan job terjadwal.
+#[doc(hidden)]
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug, PartialEq, ApiSchema)]
+pub struct JobRun {
+    pub id: ID,
+    pub job_id: ID,
+    pub started: NaiveDateTime,
+    pub finished: Option<NaiveDateTime>,
+    pub success: Option<bool>,
+    pub error: Option<String>,
+    pub manual: bool,
+}
@@ -10,31 +10,21 @@ use select::predicate::{Attr, Class, Name};
 use std::{fs::File, io::BufReader};
 
 use crate::{
+    config::Config,
     dao::RecordDao,
-    db,
     error::Error,
     eventstream::{self, Event::NewRecordUpdate},
+    metrics,
     // event_handler::FCM,
     // models::{User, Comment, HasID, MonitoredData},
-    monitor::{Monitor, PandemiaMonitor},
     record_dao::MutateRecord,
     // push_notif_handler::{FCMHandler, FCMPayloadData},
     result::Result,
+    scheduler::{Job, Schedule},
     types::LocKind,
-    util,
     ID,
 };
 
-use std::{
-    fmt,
-    sync::{
-        mpsc::{channel, Receiver, Sender},
-        Arc, Mutex,
-    },
-    thread,
-    time::Duration,
-};
-
 /// Untuk serialize json dari server
 #[derive(Debug, Serialize, Deserialize)]
 struct ResultItem {
@@ -66,42 +56,32 @@ struct ResultObject {
     pub attributes: ResultItem,
 }
 
-/// Data monitoring
-pub struct DataMonitor {
-    _started: bool,
-    _tx: Option<Sender<bool>>,
-}
-
-unsafe impl Sync for DataMonitor {}
-unsafe impl Send for DataMonitor {}
-
-impl DataMonitor {
-    /// Create DataMonitor new instance
-    pub fn new() -> PandemiaMonitor {
-        Mutex::new(Box::new(Self {
-            _started: false,
-            _tx: None,
-        }))
-    }
-}
-
-impl fmt::Display for DataMonitor {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "DataMonitor")
-    }
-}
+/// Data monitoring, dijalankan oleh scheduler sebagai job `data_monitor`.
+pub struct DataMonitor;
 
 impl DataMonitor {
     /// Datas checker
     pub fn check_data(conn: &PgConnection) -> Result<()> {
-        if let Err(e) = DataMonitor::check_worldometers(conn) {
+        let mut failed = vec![];
+
+        if let Err(e) = metrics::scraped("worldometers", DataMonitor::check_worldometers(conn)) {
             error!("check_worldometers. e {}", e);
+            failed.push("worldometers");
         }
 
-        if let Err(e) = DataMonitor::check_indonesian_provinces(conn) {
+        if let Err(e) = metrics::scraped("kawalcorona", DataMonitor::check_indonesian_provinces(conn)) {
             error!("check kawalcorona.com, e {}", e);
+            failed.push("kawalcorona");
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InternalError(format_err!(
+                "failed sources: {}",
+                failed.join(", ")
+            )))
         }
-        Ok(())
     }
 
     /// Check data from https://api.kawalcorona.com/indonesia/provinsi/
@@ -462,39 +442,16 @@ impl DataMonitor {
     }
 }
 
-impl Monitor for DataMonitor {
-    fn start(&mut self) {
-        let (tx, rx) = channel();
-        self._tx = Some(tx);
-        self._started = true;
-        thread::spawn(move || loop {
-            for i in 0..(60 * 30) {
-                // setiap setengah jam
-                util::sleep(1000);
-            }
-            // util::sleep(1000);
-            // debug!("[DataMonitor] monitor checking...");
-
-            let th = thread::spawn(move || {
-                let cm = db::clone();
-                let conn = cm.get().unwrap();
-
-                if let Err(e) = DataMonitor::check_data(&conn) {
-                    error!("Data monitor check_data error: {}", e);
-                }
-            });
-
-            let _ = th.join();
+impl Job for DataMonitor {
+    fn name(&self) -> &'static str {
+        "data_monitor"
+    }
 
-            if rx.try_recv().ok() == Some(true) {
-                debug!("[DataMonitor] down.");
-                break;
-            }
-        });
+    fn default_schedule(&self, config: &Config) -> Schedule {
+        Schedule::every(config.monitor.data_interval)
     }
 
-    fn stop(&mut self) {
-        self._started = false;
-        self._tx.as_ref().map(|tx| tx.send(true));
+    fn run(&self, conn: &PgConnection) -> Result<()> {
+        DataMonitor::check_data(conn)
     }
 }
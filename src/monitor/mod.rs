@@ -2,26 +2,24 @@
 //! monitor ini jalan di thread lain atau terpisah dengan thread utama
 //! karena akan melakukan pengecheckan secara berkala.
 //!
-//!
-
-use chrono::prelude::*;
-use diesel::prelude::*;
-use diesel::{dsl::not, expression::dsl::sql, sql_types};
+//! Pengecekan berkala (eg: [DataMonitor], [RetentionMonitor]) diimplementasikan
+//! sebagai job yang dijalankan oleh [Scheduler], lihat module `scheduler`.
 
-use crate::{db, models, result::Result, util};
+use crate::{config::Config, scheduler::Scheduler};
 
 use std::{
     fmt,
     sync::{
-        mpsc::{channel, Receiver, Sender},
-        Arc, Mutex,
+        mpsc::{Receiver, RecvTimeoutError},
+        Mutex,
     },
-    thread,
     time::Duration,
 };
 
 pub mod data_monitor;
+pub mod retention_monitor;
 pub use data_monitor::DataMonitor;
+pub use retention_monitor::{RetentionMonitor, RetentionPolicy};
 
 /// Base type for PandemiaMonitor
 pub type PandemiaMonitor = Mutex<Box<dyn Monitor>>;
@@ -29,7 +27,7 @@ pub type PandemiaMonitor = Mutex<Box<dyn Monitor>>;
 /// Abstraksi untuk sistem monitor
 pub trait Monitor: Send + Sync + fmt::Display {
     /// Jalankan monitor
-    fn start(&mut self);
+    fn start(&mut self, config: &Config);
 
     /// Berhentikan monitor
     fn stop(&mut self);
@@ -38,11 +36,21 @@ pub trait Monitor: Send + Sync + fmt::Display {
 // ------------ MONITOR CONTROLLER ---------------
 
 lazy_static! {
-    static ref MONITORS: Vec<PandemiaMonitor> = vec![DataMonitor::new()];
+    static ref MONITORS: Vec<PandemiaMonitor> = vec![Scheduler::new()];
+}
+
+/// Tunggu selama `secs` detik atau sampai monitor diperintahkan berhenti,
+/// mengembalikan `true` apabila monitor harus berhenti.
+pub(crate) fn wait_stop(rx: &Receiver<bool>, secs: u64) -> bool {
+    match rx.recv_timeout(Duration::from_secs(secs)) {
+        Ok(stop) => stop,
+        Err(RecvTimeoutError::Timeout) => false,
+        Err(RecvTimeoutError::Disconnected) => true,
+    }
 }
 
 /// Run all monitors
-pub fn start_monitors() {
+pub fn start_monitors(config: &Config) {
     debug!("Starting monitors...");
 
     // {
@@ -57,14 +65,15 @@ pub fn start_monitors() {
     for monitor in MONITORS.iter() {
         let mut monitor = monitor.lock().unwrap();
         debug!("Starting `{}`...", monitor);
-        monitor.start();
+        monitor.start(config);
     }
 }
 
-/// Stop all monitors
+/// Stop all monitors, menunggu monitor yang sedang berjalan selesai.
 pub fn stop_monitors() {
     for monitor in MONITORS.iter() {
         let mut monitor = monitor.lock().unwrap();
+        debug!("Stopping `{}`...", monitor);
         monitor.stop();
     }
 }
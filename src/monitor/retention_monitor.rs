@@ -0,0 +1,272 @@
+//! Retention monitor, menjalankan kebijakan retensi data pribadi & lokasi secara berkala.
+//!
+//! Aturan retensi diatur melalui konfigurasi `retention` (dalam satuan hari, 0 = simpan selamanya):
+//!
+//! * `sub_report_days` (`RETENTION_SUB_REPORT_DAYS`) - anonimisasi sub report yang telah selesai
+//!   (sembuh, selesai pemantauan, meninggal) setelah N hari sejak update terakhir.
+//! * `device_location_days` (`RETENTION_DEVICE_LOCATION_DAYS`) - hapus lokasi terakhir device
+//!   (`user_connect.latest_loc_*` & ID wilayah) yang lebih lama dari N hari, beserta
+//!   `loc_name=`/`loc_path=` pada `users.meta` apabila user tidak memiliki device dengan
+//!   lokasi yang lebih baru.
+//! * `symptom_days` (`RETENTION_SYMPTOM_DAYS`) - hapus flag gejala pada `user_settings`
+//!   yang lebih lama dari N hari.
+//!
+//! Data agregat di `village_data` dan `district_data` tidak diubah, sub report yang dianonimisasi
+//! juga tidak dihapus sehingga jumlah per status tetap sama.
+
+use chrono::{Duration as ChronoDuration, NaiveDateTime};
+use diesel::prelude::*;
+
+use crate::{
+    config::{Config, RetentionConfig},
+    models::User,
+    result::Result,
+    scheduler::{Job, Schedule},
+    sqlutil::array_to_string,
+    types::SubReportStatus,
+    user_dao::UserDao,
+    util, ID,
+};
+
+/// Status sub report yang dianggap telah selesai dan boleh dianonimisasi.
+const CLOSED_STATUSES: &[SubReportStatus] = &[
+    SubReportStatus::Recovered,
+    SubReportStatus::Death,
+    SubReportStatus::ODPSP,
+    SubReportStatus::PDPS,
+    SubReportStatus::PDPM,
+];
+
+/// Prefix item `users.meta` yang berisi lokasi terakhir, lihat [UserDao::update_user_location].
+const LOCATION_META_PREFIXES: &[&str] = &["loc_name=", "loc_path="];
+
+/// Key pada `user_settings` yang berisi flag gejala.
+const SYMPTOM_KEYS: &[&str] = &["has_cough", "has_fever", "has_cold", "has_headache"];
+
+/// Kebijakan retensi data, nilai dalam satuan hari.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RetentionPolicy {
+    /// Anonimisasi sub report yang telah selesai setelah N hari.
+    pub sub_report_days: Option<i64>,
+    /// Hapus lokasi device setelah N hari.
+    pub device_location_days: Option<i64>,
+    /// Hapus flag gejala setelah N hari.
+    pub symptom_days: Option<i64>,
+}
+
+/// Hasil dari satu kali eksekusi kebijakan retensi.
+#[derive(Debug, Default, Serialize)]
+pub struct RetentionReport {
+    /// Jumlah sub report yang dianonimisasi.
+    pub anonymized_sub_reports: usize,
+    /// Jumlah device yang lokasinya dihapus.
+    pub cleared_device_locations: usize,
+    /// Jumlah user yang lokasi pada meta-nya dihapus.
+    pub cleared_user_locations: usize,
+    /// Jumlah flag gejala yang dihapus.
+    pub removed_symptom_flags: usize,
+}
+
+impl RetentionPolicy {
+    /// Kebijakan retensi dari konfigurasi.
+    pub fn from_config(config: &RetentionConfig) -> Self {
+        let days = |days: u32| Some(i64::from(days)).filter(|days| *days > 0);
+        Self {
+            sub_report_days: days(config.sub_report_days),
+            device_location_days: days(config.device_location_days),
+            symptom_days: days(config.symptom_days),
+        }
+    }
+
+    /// Apakah ada aturan retensi yang aktif.
+    pub fn is_enabled(&self) -> bool {
+        self.sub_report_days.is_some() || self.device_location_days.is_some() || self.symptom_days.is_some()
+    }
+
+    /// Jalankan semua aturan retensi yang aktif.
+    pub fn apply(&self, conn: &PgConnection) -> Result<RetentionReport> {
+        let now = util::now();
+        let mut report = RetentionReport::default();
+
+        if let Some(days) = self.sub_report_days {
+            report.anonymized_sub_reports = anonymize_sub_reports(conn, cutoff(now, days))?;
+        }
+        if let Some(days) = self.device_location_days {
+            let before = cutoff(now, days);
+            report.cleared_device_locations = clear_device_locations(conn, before)?;
+            report.cleared_user_locations = clear_user_locations(conn, before)?;
+        }
+        if let Some(days) = self.symptom_days {
+            report.removed_symptom_flags = remove_symptom_flags(conn, cutoff(now, days))?;
+        }
+
+        Ok(report)
+    }
+}
+
+fn cutoff(now: NaiveDateTime, days: i64) -> NaiveDateTime {
+    now - ChronoDuration::days(days)
+}
+
+/// Kosongkan data identitas pada sub report yang telah selesai,
+/// status, wilayah, gender dan tanggal tetap disimpan untuk keperluan statistik.
+fn anonymize_sub_reports(conn: &PgConnection, before: NaiveDateTime) -> Result<usize> {
+    use crate::schema::sub_reports::dsl;
+
+    let statuses: Vec<i32> = CLOSED_STATUSES.iter().map(|a| *a as i32).collect();
+
+    diesel::update(
+        dsl::sub_reports.filter(
+            dsl::status
+                .eq_any(statuses)
+                .and(dsl::last_updated.lt(before))
+                .and(dsl::pii.ne("").or(dsl::full_name.ne(""))),
+        ),
+    )
+    .set((
+        dsl::full_name.eq(""),
+        dsl::age.eq(0),
+        dsl::residence_address.eq(""),
+        dsl::coming_from.eq(""),
+        dsl::notes.eq(""),
+        dsl::pii.eq(""),
+        dsl::full_name_bidx.eq(Vec::<String>::new()),
+        dsl::age_bidx.eq(""),
+        dsl::residence_address_bidx.eq(""),
+    ))
+    .execute(conn)
+    .map_err(From::from)
+}
+
+fn clear_device_locations(conn: &PgConnection, before: NaiveDateTime) -> Result<usize> {
+    use crate::schema::user_connect::dsl;
+
+    diesel::update(
+        dsl::user_connect.filter(
+            dsl::latest_loc_ts.lt(before).and(
+                dsl::latest_loc
+                    .ne("")
+                    .or(dsl::latest_loc_lat.ne(0.0))
+                    .or(dsl::latest_loc_long.ne(0.0))
+                    .or(dsl::latest_city_id.is_not_null()),
+            ),
+        ),
+    )
+    .set((
+        dsl::latest_loc.eq(""),
+        dsl::latest_loc_full.eq(""),
+        dsl::latest_loc_lat.eq(0.0),
+        dsl::latest_loc_long.eq(0.0),
+        dsl::latest_city_id.eq(None::<ID>),
+        dsl::latest_district_id.eq(None::<ID>),
+        dsl::latest_village_id.eq(None::<ID>),
+    ))
+    .execute(conn)
+    .map_err(From::from)
+}
+
+/// Hapus lokasi pada `users.meta` milik user yang semua device-nya memiliki lokasi
+/// lebih lama dari `before`.
+fn clear_user_locations(conn: &PgConnection, before: NaiveDateTime) -> Result<usize> {
+    use crate::schema::{user_connect::dsl as uc_dsl, users::dsl};
+
+    let users = dsl::users
+        .filter(
+            dsl::id
+                .eq_any(uc_dsl::user_connect.select(uc_dsl::user_id))
+                .and(diesel::dsl::not(
+                    dsl::id.eq_any(
+                        uc_dsl::user_connect
+                            .filter(uc_dsl::latest_loc_ts.ge(before))
+                            .select(uc_dsl::user_id),
+                    ),
+                ))
+                .and(
+                    array_to_string(dsl::meta, "\n")
+                        .like("%loc_name=%")
+                        .or(array_to_string(dsl::meta, "\n").like("%loc_path=%")),
+                ),
+        )
+        .load::<User>(conn)?;
+
+    let mut count = 0;
+    for user in users {
+        let meta: Vec<String> = user
+            .meta
+            .iter()
+            .filter(|a| !LOCATION_META_PREFIXES.iter().any(|prefix| a.starts_with(prefix)))
+            .cloned()
+            .collect();
+        if meta.len() != user.meta.len() {
+            UserDao::new(conn).update_meta(user.id, &meta)?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+fn remove_symptom_flags(conn: &PgConnection, before: NaiveDateTime) -> Result<usize> {
+    use crate::schema::user_settings::dsl;
+
+    diesel::delete(dsl::user_settings.filter(dsl::s_key.eq_any(SYMPTOM_KEYS).and(dsl::ts.lt(before))))
+        .execute(conn)
+        .map_err(From::from)
+}
+
+/// Menjalankan kebijakan retensi, dijalankan oleh scheduler sebagai job `retention_monitor`.
+pub struct RetentionMonitor {
+    policy: RetentionPolicy,
+}
+
+impl RetentionMonitor {
+    /// Buat retention monitor dengan kebijakan `policy`.
+    pub fn new(policy: RetentionPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+impl Job for RetentionMonitor {
+    fn name(&self) -> &'static str {
+        "retention_monitor"
+    }
+
+    fn default_schedule(&self, config: &Config) -> Schedule {
+        Schedule::every(config.monitor.retention_interval)
+    }
+
+    fn run(&self, conn: &PgConnection) -> Result<()> {
+        if !self.policy.is_enabled() {
+            debug!("[RetentionMonitor] no retention policy configured, skipped.");
+            return Ok(());
+        }
+
+        let report = self.policy.apply(conn)?;
+        info!("[RetentionMonitor] {:?}", report);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_from_config() {
+        let policy = RetentionPolicy::from_config(&RetentionConfig {
+            sub_report_days: 30,
+            device_location_days: 0,
+            symptom_days: 0,
+        });
+        assert_eq!(
+            policy,
+            RetentionPolicy {
+                sub_report_days: Some(30),
+                device_location_days: None,
+                symptom_days: None,
+            }
+        );
+        assert!(policy.is_enabled());
+        assert!(!RetentionPolicy::from_config(&RetentionConfig::default()).is_enabled());
+    }
+}
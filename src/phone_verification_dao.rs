@@ -0,0 +1,104 @@
+//! Dao implementation for PhoneVerification
+//!
+//! Verifikasi nomor telepon menggunakan kode OTP yang dikirim via SMS,
+//! kode hanya disimpan dalam bentuk hash dan hanya berlaku untuk beberapa kali percobaan.
+
+use chrono::Duration;
+use diesel::prelude::*;
+
+use crate::{
+    crypto, models::PhoneVerification, result::Result, schema::phone_verifications, token, util, ID,
+};
+
+/// Masa berlaku kode OTP dalam detik.
+pub const OTP_VALIDITY_SECS: i64 = 5 * 60;
+/// Jumlah percobaan verifikasi yang gagal sebelum kode tidak bisa digunakan lagi.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+#[derive(Insertable, AsChangeset)]
+#[table_name = "phone_verifications"]
+struct NewPhoneVerification<'a> {
+    pub user_id: ID,
+    pub phone_num: &'a str,
+    pub code_hash: String,
+    pub attempts: i32,
+    pub expiration: chrono::NaiveDateTime,
+}
+
+fn hash_code(code: &str) -> String {
+    crypto::sha256_hash(code.trim().as_bytes()).to_hex()
+}
+
+/// Data Access Object for PhoneVerification
+#[derive(Dao)]
+#[table_name = "phone_verifications"]
+pub struct PhoneVerificationDao<'a> {
+    db: &'a PgConnection,
+}
+
+impl<'a> PhoneVerificationDao<'a> {
+    /// Get verifikasi yang sedang berjalan milik user.
+    pub fn get_by_user_id(&self, user_id: ID) -> Result<Option<PhoneVerification>> {
+        use crate::schema::phone_verifications::dsl;
+
+        dsl::phone_verifications
+            .filter(dsl::user_id.eq(user_id))
+            .first(self.db)
+            .optional()
+            .map_err(From::from)
+    }
+
+    /// Buat kode OTP baru untuk nomor telepon, menggantikan kode sebelumnya apabila ada.
+    /// Mengembalikan kode OTP dalam bentuk plain untuk dikirim via SMS.
+    pub fn create(&self, user_id: ID, phone_num: &str) -> Result<String> {
+        use crate::schema::phone_verifications::dsl;
+
+        let code = token::generate_activation_code();
+
+        let entry = NewPhoneVerification {
+            user_id,
+            phone_num,
+            code_hash: hash_code(&code),
+            attempts: 0,
+            expiration: util::now() + Duration::seconds(OTP_VALIDITY_SECS),
+        };
+
+        diesel::insert_into(phone_verifications::table)
+            .values(&entry)
+            .on_conflict(dsl::user_id)
+            .do_update()
+            .set((&entry, dsl::ts.eq(util::now())))
+            .execute(self.db)?;
+
+        Ok(code)
+    }
+
+    /// Verifikasi kode OTP, kode yang berhasil diverifikasi tidak bisa digunakan lagi.
+    pub fn verify(&self, user_id: ID, phone_num: &str, code: &str) -> Result<bool> {
+        use crate::schema::phone_verifications::dsl;
+
+        let pv = match self.get_by_user_id(user_id)? {
+            Some(pv) => pv,
+            None => return Err(param_err!("No pending phone verification")),
+        };
+
+        if pv.phone_num != phone_num {
+            return Err(param_err!("Phone number doesn't match with the requested one"));
+        }
+
+        if pv.expiration <= util::now() || pv.attempts >= MAX_ATTEMPTS {
+            return Err(param_err!("Verification code expired, please request a new one"));
+        }
+
+        if hash_code(code) != pv.code_hash {
+            diesel::update(dsl::phone_verifications.filter(dsl::id.eq(pv.id)))
+                .set(dsl::attempts.eq(dsl::attempts + 1))
+                .execute(self.db)?;
+            return Ok(false);
+        }
+
+        diesel::delete(dsl::phone_verifications.filter(dsl::id.eq(pv.id))).execute(self.db)?;
+
+        Ok(true)
+    }
+}
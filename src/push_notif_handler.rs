@@ -6,6 +6,7 @@ use diesel::prelude::*;
 use diesel::sql_types;
 
 use crate::{
+    metrics,
     result::Result,
     sqlutil::lower,
     types::{LocKind, NotifKind},
@@ -16,14 +17,14 @@ use fcm::{Client, MessageBuilder, NotificationBuilder, Priority};
 use futures::{future::lazy, Future};
 use tokio_core::reactor::Core;
 
-use std::env;
-
 /// FCM payload data.
 pub struct FCMPayloadData<'a> {
     /// Receiver location.
     pub receiver_loc: &'a str,
     /// Receiver location kind.
     pub receiver_loc_kind: LocKind,
+    /// ID kecamatan/desa penerima, wajib untuk `LocKind::District` & `LocKind::Village`.
+    pub receiver_loc_id: Option<ID>,
     /// Target id.
     pub target_id: ID,
     /// Target item
@@ -87,10 +88,10 @@ pub struct FCMHandler {
 // }
 
 impl FCMHandler {
-    /// Add push notification handler.
-    pub fn new() -> FCMHandler {
+    /// Add push notification handler, push notif tidak dikirim apabila `server_key` kosong.
+    pub fn new(server_key: &str) -> FCMHandler {
         FCMHandler {
-            server_key: env::var("FCM_SERVER_KEY").expect("No FCM_SERVER_KEY env var"),
+            server_key: server_key.to_string(),
             client: fcm::Client::new().unwrap(),
         }
     }
@@ -111,6 +112,7 @@ impl FCMHandler {
         conn: &PgConnection,
         location: &str,
         loc_kind: LocKind,
+        loc_id: Option<ID>,
     ) -> Result<Vec<String>> {
         use crate::schema::user_connect::{self, dsl as dsl_uc};
         use crate::schema::user_settings::{self, dsl as dsl_us};
@@ -143,6 +145,22 @@ impl FCMHandler {
             }
         }
 
+        // kecamatan & desa ditarget berdasarkan ID wilayah hasil resolve batas wilayah,
+        // lihat `UserDao::update_user_location_ll`.
+        match (loc_kind, loc_id) {
+            (LocKind::District, Some(id)) => {
+                filterer = Box::new(filterer.and(dsl_uc::latest_district_id.eq(id)));
+            }
+            (LocKind::Village, Some(id)) => {
+                filterer = Box::new(filterer.and(dsl_uc::latest_village_id.eq(id)));
+            }
+            (LocKind::District, None) | (LocKind::Village, None) => {
+                warn!("No area id for push notif target `{}`", location);
+                return Ok(vec![]);
+            }
+            _ => (),
+        }
+
         user_connect::table
             .filter(filterer)
             .select(dsl_uc::app_id)
@@ -159,8 +177,12 @@ impl FCMHandler {
     ) -> Result<()> {
         if !self.server_key.is_empty() {
             // if let Ok(app_id) = self.get_user_app_id(payload.receiver_loc, conn) {
-            if let Ok(app_ids) = self.get_user_app_ids(conn, payload.receiver_loc, payload.receiver_loc_kind)
-            {
+            if let Ok(app_ids) = self.get_user_app_ids(
+                conn,
+                payload.receiver_loc,
+                payload.receiver_loc_kind,
+                payload.receiver_loc_id,
+            ) {
                 if app_ids.len() == 0 {
                     debug!("No target to send notification");
                     return Ok(());
@@ -198,11 +220,16 @@ impl FCMHandler {
 
                 let mut core = Core::new().expect("cannot get new Core");
 
-                let rv = core
-                    .run(lazy(move || sending))
-                    .expect("Cannot run reactor for sending push notif");
-
-                debug!("Send push notification: {:?}", rv);
+                match core.run(lazy(move || sending)) {
+                    Ok(rv) => {
+                        debug!("Send push notification: {:?}", rv);
+                        metrics::push_delivered(rv.success.unwrap_or(0), rv.failure.unwrap_or(0));
+                    }
+                    Err(e) => {
+                        error!("Cannot send push notification: {:?}", e);
+                        metrics::push_delivered(0, app_ids.len() as u64);
+                    }
+                }
             }
         } else {
             debug!("FCM server key not set");
@@ -0,0 +1,435 @@
+//! Rate limiter untuk melindungi endpoint otorisasi dari brute-force dan penyalahgunaan.
+//!
+//! Setiap percobaan dicatat per identitas (IP, akun, atau device) dan per endpoint,
+//! apabila jumlah percobaan dalam satu window mencapai batas maka identitas tersebut dikunci.
+//! Lama penguncian bertambah dua kali lipat setiap kali identitas yang sama terkunci lagi
+//! (progressive lockout), dan kembali ke awal setelah [LOCKOUT_DECAY] detik tanpa penguncian.
+//!
+//! Batas per endpoint bisa diatur melalui konfigurasi `rate_limit.rules` (atau env var
+//! `RATE_LIMIT_<ENDPOINT>`) dengan format
+//! `<max_attempts>,<window_secs>,<lockout_secs>,<max_lockout_secs>`, contoh:
+//! `RATE_LIMIT_ADMIN_AUTHORIZE=5,300,60,3600`.
+//!
+//! Data disimpan di memory, sehingga apabila server dijalankan lebih dari satu instance
+//! batas berlaku per instance.
+
+use chrono::NaiveDateTime;
+
+use crate::{config::RateLimitConfig, util};
+
+use std::{collections::HashMap, fmt, str::FromStr, sync::Mutex};
+
+/// Waktu (dalam detik) tanpa penguncian sebelum hitungan progressive lockout di-reset.
+pub const LOCKOUT_DECAY: u64 = 24 * 60 * 60;
+
+/// Jumlah entry maksimal sebelum entry yang sudah tidak relevan dibersihkan.
+const PURGE_THRESHOLD: usize = 10_000;
+
+/// Endpoint yang dilindungi oleh rate limiter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ApiSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Endpoint {
+    /// `/auth/v1/admin/authorize`
+    AdminAuthorize,
+    /// `/auth/v1/satgas/authorize`
+    SatgasAuthorize,
+    /// `/auth/v1/device/authorize`
+    DeviceAuthorize,
+    /// `/user/v1/me/phone/request_otp`
+    PhoneOtp,
+    /// Verifikasi 2FA step-up (header `X-Totp-Code`) pada endpoint admin yang sensitif.
+    AdminTwoFactor,
+}
+
+impl Endpoint {
+    /// Semua endpoint yang dilindungi.
+    pub fn all() -> &'static [Endpoint] {
+        &[
+            Endpoint::AdminAuthorize,
+            Endpoint::SatgasAuthorize,
+            Endpoint::DeviceAuthorize,
+            Endpoint::PhoneOtp,
+            Endpoint::AdminTwoFactor,
+        ]
+    }
+
+    /// Nama endpoint, digunakan juga sebagai key di `rate_limit.rules`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Endpoint::AdminAuthorize => "admin_authorize",
+            Endpoint::SatgasAuthorize => "satgas_authorize",
+            Endpoint::DeviceAuthorize => "device_authorize",
+            Endpoint::PhoneOtp => "phone_otp",
+            Endpoint::AdminTwoFactor => "admin_two_factor",
+        }
+    }
+
+    fn default_rule(self) -> Rule {
+        match self {
+            Endpoint::AdminAuthorize => Rule::new(5, 300, 60, 3600),
+            Endpoint::SatgasAuthorize => Rule::new(10, 300, 300, 3600),
+            Endpoint::DeviceAuthorize => Rule::new(20, 3600, 600, 24 * 3600),
+            Endpoint::PhoneOtp => Rule::new(5, 3600, 3600, 24 * 3600),
+            Endpoint::AdminTwoFactor => Rule::new(5, 300, 300, 3600),
+        }
+    }
+
+    /// Aturan untuk endpoint ini, dari konfigurasi `rate_limit.rules` apabila ada.
+    pub fn rule(self, config: &RateLimitConfig) -> Rule {
+        config
+            .rules
+            .get(self.name())
+            .and_then(|a| Rule::parse(a))
+            .unwrap_or_else(|| self.default_rule())
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Aturan pembatasan untuk satu endpoint.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rule {
+    /// Jumlah percobaan maksimal dalam satu window.
+    pub max_attempts: u32,
+    /// Panjang window dalam detik.
+    pub window_secs: u64,
+    /// Lama penguncian pertama dalam detik.
+    pub lockout_secs: u64,
+    /// Lama penguncian maksimal dalam detik.
+    pub max_lockout_secs: u64,
+}
+
+impl Rule {
+    /// Buat aturan baru.
+    pub fn new(max_attempts: u32, window_secs: u64, lockout_secs: u64, max_lockout_secs: u64) -> Self {
+        Self {
+            max_attempts,
+            window_secs,
+            lockout_secs,
+            max_lockout_secs,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let s: Vec<u64> = value
+            .split(',')
+            .map(|a| a.trim().parse::<u64>())
+            .collect::<std::result::Result<_, _>>()
+            .ok()?;
+        let rule = match s.as_slice() {
+            [max, window, lockout] => Self::new(*max as u32, *window, *lockout, *lockout),
+            [max, window, lockout, max_lockout] => {
+                Self::new(*max as u32, *window, *lockout, (*max_lockout).max(*lockout))
+            }
+            _ => return None,
+        };
+        Some(rule).filter(|a| a.max_attempts > 0 && a.window_secs > 0)
+    }
+
+    /// Lama penguncian untuk penguncian ke-`n` (dimulai dari 1).
+    fn lockout_for(&self, n: u32) -> u64 {
+        let factor = 1u64.checked_shl(n.saturating_sub(1)).unwrap_or(u64::max_value());
+        self.lockout_secs
+            .saturating_mul(factor)
+            .min(self.max_lockout_secs)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Rule, String> {
+        Rule::parse(s).ok_or_else(|| {
+            format!(
+                "`{}` must be `<max_attempts>,<window_secs>,<lockout_secs>[,<max_lockout_secs>]`",
+                s
+            )
+        })
+    }
+}
+
+/// Jenis identitas yang dibatasi.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ApiSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Subject {
+    /// Alamat IP client.
+    Ip,
+    /// Akun (email/nomor telp).
+    Account,
+    /// ID device.
+    Device,
+}
+
+/// Identitas yang dibatasi.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Identity {
+    /// Jenis identitas.
+    pub subject: Subject,
+    /// Nilai identitas, misalnya alamat IP atau email.
+    pub value: String,
+}
+
+impl Identity {
+    /// Identitas berdasarkan alamat IP.
+    pub fn ip(value: &str) -> Self {
+        Self::new(Subject::Ip, value)
+    }
+
+    /// Identitas berdasarkan akun, tidak case sensitive.
+    pub fn account(value: &str) -> Self {
+        Self::new(Subject::Account, &value.to_lowercase())
+    }
+
+    /// Identitas berdasarkan ID device.
+    pub fn device(value: &str) -> Self {
+        Self::new(Subject::Device, value)
+    }
+
+    /// Buat identitas baru.
+    pub fn new(subject: Subject, value: &str) -> Self {
+        Self {
+            subject,
+            value: value.trim().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Entry {
+    window_start: u64,
+    window_secs: u64,
+    attempts: u32,
+    lockouts: u32,
+    last_lockout: u64,
+    locked_until: u64,
+}
+
+/// Informasi identitas yang sedang terkunci.
+#[derive(Debug, Clone, Serialize, Deserialize, ApiSchema)]
+pub struct LockedIdentity {
+    /// Endpoint tempat identitas terkunci.
+    pub endpoint: Endpoint,
+    /// Jenis identitas.
+    pub subject: Subject,
+    /// Nilai identitas.
+    pub value: String,
+    /// Jumlah penguncian berturut-turut.
+    pub lockouts: u32,
+    /// Waktu kunci dibuka.
+    pub locked_until: NaiveDateTime,
+}
+
+/// Penyimpanan hitungan percobaan.
+#[derive(Default)]
+pub struct RateLimiter {
+    entries: Mutex<HashMap<(Endpoint, Identity), Entry>>,
+}
+
+impl RateLimiter {
+    /// Buat rate limiter baru.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check apakah salah satu identitas sedang terkunci,
+    /// mengembalikan sisa waktu penguncian terlama dalam detik.
+    pub fn check(&self, endpoint: Endpoint, identities: &[Identity], now: u64) -> Option<u64> {
+        let entries = self.entries.lock().unwrap();
+        identities
+            .iter()
+            .filter_map(|id| entries.get(&(endpoint, id.clone())))
+            .filter(|e| e.locked_until > now)
+            .map(|e| e.locked_until - now)
+            .max()
+    }
+
+    /// Catat satu percobaan untuk semua identitas,
+    /// mengembalikan lama penguncian terlama apabila ada identitas yang menjadi terkunci.
+    pub fn record(&self, endpoint: Endpoint, rule: &Rule, identities: &[Identity], now: u64) -> Option<u64> {
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.len() > PURGE_THRESHOLD {
+            entries.retain(|_, e| !is_stale(e, now));
+        }
+
+        let mut rv = None;
+
+        for id in identities.iter().filter(|a| !a.value.is_empty()) {
+            let entry = entries.entry((endpoint, id.clone())).or_default();
+
+            if entry.locked_until > now {
+                continue;
+            }
+
+            if now.saturating_sub(entry.window_start) >= rule.window_secs {
+                entry.window_start = now;
+                entry.attempts = 0;
+            }
+            entry.window_secs = rule.window_secs;
+
+            entry.attempts += 1;
+
+            if entry.attempts >= rule.max_attempts {
+                if now.saturating_sub(entry.last_lockout) > LOCKOUT_DECAY {
+                    entry.lockouts = 0;
+                }
+                entry.lockouts += 1;
+                entry.last_lockout = now;
+                entry.attempts = 0;
+                entry.window_start = now;
+
+                let lockout = rule.lockout_for(entry.lockouts);
+                entry.locked_until = now + lockout;
+
+                warn!(
+                    "{} `{:?}:{}` locked for {} seconds ({} lockouts)",
+                    endpoint, id.subject, id.value, lockout, entry.lockouts
+                );
+
+                rv = rv.max(Some(lockout));
+            }
+        }
+
+        rv
+    }
+
+    /// Reset hitungan percobaan identitas, misalnya setelah otorisasi berhasil.
+    /// Hitungan progressive lockout tetap disimpan.
+    pub fn reset(&self, endpoint: Endpoint, identity: &Identity) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get_mut(&(endpoint, identity.clone())) {
+            entry.attempts = 0;
+        }
+    }
+
+    /// Buka kunci identitas secara manual, mengembalikan `false` apabila tidak ditemukan.
+    pub fn unlock(&self, endpoint: Endpoint, identity: &Identity) -> bool {
+        self.entries
+            .lock()
+            .unwrap()
+            .remove(&(endpoint, identity.clone()))
+            .is_some()
+    }
+
+    /// Daftar identitas yang sedang terkunci.
+    pub fn locked(&self, now: u64) -> Vec<LockedIdentity> {
+        let entries = self.entries.lock().unwrap();
+        let mut rv: Vec<LockedIdentity> = entries
+            .iter()
+            .filter(|(_, e)| e.locked_until > now)
+            .map(|((endpoint, id), e)| LockedIdentity {
+                endpoint: *endpoint,
+                subject: id.subject,
+                value: id.value.clone(),
+                lockouts: e.lockouts,
+                locked_until: NaiveDateTime::from_timestamp(e.locked_until as i64, 0),
+            })
+            .collect();
+        rv.sort_by(|a, b| b.locked_until.cmp(&a.locked_until));
+        rv
+    }
+}
+
+fn is_stale(entry: &Entry, now: u64) -> bool {
+    entry.locked_until <= now
+        && now.saturating_sub(entry.window_start) >= entry.window_secs
+        && now.saturating_sub(entry.last_lockout) > LOCKOUT_DECAY
+}
+
+lazy_static! {
+    static ref LIMITER: RateLimiter = RateLimiter::new();
+}
+
+fn now() -> u64 {
+    util::current_time().as_secs()
+}
+
+/// Check apakah salah satu identitas sedang terkunci pada endpoint,
+/// mengembalikan sisa waktu penguncian dalam detik.
+pub fn check(endpoint: Endpoint, identities: &[Identity]) -> Option<u64> {
+    LIMITER.check(endpoint, identities, now())
+}
+
+/// Catat satu percobaan (gagal) pada endpoint, batasnya sesuai konfigurasi `config`.
+pub fn record(config: &RateLimitConfig, endpoint: Endpoint, identities: &[Identity]) -> Option<u64> {
+    LIMITER.record(endpoint, &endpoint.rule(config), identities, now())
+}
+
+/// Reset hitungan percobaan identitas pada endpoint.
+pub fn reset(endpoint: Endpoint, identity: &Identity) {
+    LIMITER.reset(endpoint, identity)
+}
+
+/// Buka kunci identitas pada endpoint.
+pub fn unlock(endpoint: Endpoint, identity: &Identity) -> bool {
+    LIMITER.unlock(endpoint, identity)
+}
+
+/// Daftar identitas yang sedang terkunci.
+pub fn locked() -> Vec<LockedIdentity> {
+    LIMITER.locked(now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_parse() {
+        assert_eq!(Rule::parse("5,300,60"), Some(Rule::new(5, 300, 60, 60)));
+        assert_eq!(Rule::parse("5, 300, 60, 3600"), Some(Rule::new(5, 300, 60, 3600)));
+        assert_eq!(Rule::parse("0,300,60"), None);
+        assert_eq!(Rule::parse("5,300"), None);
+        assert_eq!(Rule::parse("abc"), None);
+
+        let rule = Rule::new(5, 300, 60, 300);
+        assert_eq!(rule.lockout_for(1), 60);
+        assert_eq!(rule.lockout_for(2), 120);
+        assert_eq!(rule.lockout_for(3), 240);
+        assert_eq!(rule.lockout_for(4), 300);
+        assert_eq!(rule.lockout_for(100), 300);
+    }
+
+    #[test]
+    fn test_progressive_lockout() {
+        let limiter = RateLimiter::new();
+        let rule = Rule::new(3, 60, 10, 1000);
+        let ep = Endpoint::AdminAuthorize;
+        let ids = [Identity::ip("10.0.0.1"), Identity::account("Admin@Pandemia.net")];
+
+        assert_eq!(limiter.record(ep, &rule, &ids, 100), None);
+        assert_eq!(limiter.record(ep, &rule, &ids, 101), None);
+        assert_eq!(limiter.check(ep, &ids, 101), None);
+        assert_eq!(limiter.record(ep, &rule, &ids, 102), Some(10));
+        assert_eq!(limiter.check(ep, &ids, 105), Some(7));
+        assert_eq!(
+            limiter.check(ep, &[Identity::account("admin@pandemia.net")], 105),
+            Some(7)
+        );
+        assert_eq!(limiter.check(Endpoint::SatgasAuthorize, &ids, 105), None);
+        assert_eq!(limiter.locked(105).len(), 2);
+
+        // percobaan selama terkunci tidak dihitung
+        assert_eq!(limiter.record(ep, &rule, &ids, 106), None);
+        assert_eq!(limiter.check(ep, &ids, 112), None);
+
+        for ts in 112..114 {
+            assert_eq!(limiter.record(ep, &rule, &ids, ts), None);
+        }
+        assert_eq!(limiter.record(ep, &rule, &ids, 114), Some(20));
+
+        // window yang sudah lewat tidak dihitung
+        let other = [Identity::device("device-1")];
+        limiter.record(ep, &rule, &other, 100);
+        limiter.record(ep, &rule, &other, 101);
+        assert_eq!(limiter.record(ep, &rule, &other, 200), None);
+
+        assert!(limiter.unlock(ep, &ids[0]));
+        assert_eq!(limiter.check(ep, &ids[..1], 115), None);
+        assert_eq!(limiter.check(ep, &ids, 115), Some(19));
+    }
+}
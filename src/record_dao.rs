@@ -133,6 +133,29 @@ impl<'a> RecordDao<'a> {
             .map_err(From::from)
     }
 
+    /// Semua record terbaru untuk setiap loc_path.
+    pub fn get_all_latest(&self) -> Result<Vec<Record>> {
+        use crate::schema::records::dsl;
+
+        dsl::records
+            .filter(dsl::latest.eq(true))
+            .order(dsl::loc_path.asc())
+            .load(self.db)
+            .map_err(From::from)
+    }
+
+    /// Waktu update terakhir untuk setiap loc_path yang dimonitor.
+    pub fn get_last_updates(&self) -> Result<Vec<(String, NaiveDateTime)>> {
+        use crate::schema::records::dsl;
+
+        dsl::records
+            .filter(dsl::latest.eq(true))
+            .select((dsl::loc_path, dsl::last_updated))
+            .order(dsl::loc_path.asc())
+            .load(self.db)
+            .map_err(From::from)
+    }
+
     /// Get latest records, ini akan mencari records dengan parameter
     /// loc_paths lebih dari satu
     /// Untuk mendapatkan yang exact satu record gunakan get_latest_record_one.
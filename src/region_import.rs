@@ -0,0 +1,447 @@
+//! Importer untuk data kode wilayah resmi Kemendagri
+//! (provinsi, kota/kab, kecamatan, desa/kelurahan).
+//!
+//! Format file adalah CSV dengan 2 kolom: `kode,nama`, contoh:
+//!
+//! ```text
+//! 33,JAWA TENGAH
+//! 33.07,KABUPATEN WONOSOBO
+//! 33.07.01,Wadaslintang
+//! 33.07.01.2001,Plunjaran
+//! ```
+//!
+//! Kode boleh ditulis tanpa titik (`3307012001`), level wilayah
+//! ditentukan dari panjang kode.
+
+use diesel::prelude::*;
+
+use crate::{
+    dao::{CityDao, DistrictDao, VillageDao},
+    error::Error,
+    result::Result,
+    sqlutil::lower,
+    util, ID,
+};
+
+use std::collections::HashMap;
+
+/// Level wilayah administratif.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ApiSchema)]
+pub enum RegionLevel {
+    /// Provinsi
+    Province,
+    /// Kota/kabupaten
+    City,
+    /// Kecamatan
+    District,
+    /// Desa/kelurahan
+    Village,
+}
+
+/// Satu baris data wilayah.
+#[derive(Debug, PartialEq)]
+pub struct RegionEntry {
+    /// Kode wilayah dalam format bertitik, eg: `33.07.01`.
+    pub code: String,
+    /// Nama resmi wilayah.
+    pub name: String,
+    /// Level wilayah.
+    pub level: RegionLevel,
+}
+
+impl RegionEntry {
+    /// Kode wilayah induk, eg: `33.07` untuk `33.07.01`.
+    pub fn parent_code(&self) -> &str {
+        match self.code.rfind('.') {
+            Some(i) => &self.code[..i],
+            None => "",
+        }
+    }
+}
+
+/// Perbedaan nama antara data resmi dengan data yang sudah ada.
+#[derive(Serialize, Deserialize, PartialEq, ApiSchema)]
+pub struct NameMismatch {
+    /// Level wilayah.
+    pub level: RegionLevel,
+    /// Kode wilayah resmi.
+    pub code: String,
+    /// Nama menurut data resmi.
+    pub official_name: String,
+    /// ID record yang sudah ada.
+    pub existing_id: ID,
+    /// Nama pada record yang sudah ada.
+    pub existing_name: String,
+}
+
+/// Laporan hasil import.
+#[derive(Serialize, Deserialize, PartialEq, Default, ApiSchema)]
+pub struct ImportReport {
+    /// Apabila true maka tidak ada perubahan yang disimpan.
+    pub dry_run: bool,
+    /// Jumlah record baru.
+    pub created: usize,
+    /// Jumlah record lama yang diberi kode wilayah.
+    pub linked: usize,
+    /// Jumlah record yang sudah memiliki kode dan tidak berubah.
+    pub unchanged: usize,
+    /// Daftar nama yang tidak sama untuk direkonsiliasi.
+    pub mismatches: Vec<NameMismatch>,
+    /// Baris yang dilewati beserta alasannya.
+    pub skipped: Vec<String>,
+}
+
+/// Parse isi file kode wilayah.
+pub fn parse_regions(content: &str) -> Vec<std::result::Result<RegionEntry, String>> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|(no, line)| {
+            let mut cols = line.splitn(2, ',');
+            let code = cols.next().unwrap_or("").trim().trim_matches('"');
+            let name = cols.next().unwrap_or("").trim().trim_matches('"');
+
+            // lewati header
+            if no == 1 && code.parse::<u64>().is_err() && !code.contains('.') {
+                return None;
+            }
+
+            Some(match normalize_code(code) {
+                Some((code, level)) if !name.is_empty() => Ok(RegionEntry {
+                    code,
+                    name: name.to_string(),
+                    level,
+                }),
+                _ => Err(format!("line {}: invalid entry `{}`", no, line)),
+            })
+        })
+        .collect()
+}
+
+/// Normalisasi kode wilayah ke format bertitik beserta level-nya.
+pub fn normalize_code(code: &str) -> Option<(String, RegionLevel)> {
+    let digits: String = code.chars().filter(|c| *c != '.').collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (level, parts) = match digits.len() {
+        2 => (RegionLevel::Province, vec![&digits[..2]]),
+        4 => (RegionLevel::City, vec![&digits[..2], &digits[2..4]]),
+        6 => (RegionLevel::District, vec![&digits[..2], &digits[2..4], &digits[4..6]]),
+        10 => (
+            RegionLevel::Village,
+            vec![&digits[..2], &digits[2..4], &digits[4..6], &digits[6..10]],
+        ),
+        _ => return None,
+    };
+    Some((parts.join("."), level))
+}
+
+/// Kunci untuk membandingkan nama wilayah, eg: `KAB. WONOSOBO` -> `wonosobo`.
+fn name_key(name: &str) -> String {
+    let name = name.to_lowercase();
+    let name = name.trim();
+    ["kabupaten ", "kab. ", "kab ", "kota administrasi ", "kota "]
+        .iter()
+        .find(|prefix| name.starts_with(*prefix))
+        .map(|prefix| &name[prefix.len()..])
+        .unwrap_or(name)
+        .replace("-", " ")
+        .trim()
+        .to_string()
+}
+
+/// Nama kota/kab dalam format yang digunakan di tabel `cities`,
+/// eg: `KABUPATEN PULAU TALIABU` -> `Kabupaten Pulau Taliabu`.
+fn city_display_name(name: &str) -> String {
+    let name = util::title_case(name);
+    if name.starts_with("Kab. ") {
+        name.replacen("Kab. ", "Kabupaten ", 1)
+    } else {
+        name
+    }
+}
+
+/// Import data kode wilayah ke tabel `cities`, `districts` dan `villages`.
+///
+/// Record yang sudah ada dicocokkan berdasarkan kode, apabila belum punya kode
+/// dicocokkan berdasarkan nama. Nama record lama tidak diubah, perbedaan nama
+/// dilaporkan pada `mismatches` untuk direkonsiliasi secara manual.
+pub fn import_regions(content: &str, dry_run: bool, conn: &PgConnection) -> Result<ImportReport> {
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    let mut entries = vec![];
+    for entry in parse_regions(content) {
+        match entry {
+            Ok(entry) => entries.push(entry),
+            Err(e) => report.skipped.push(e),
+        }
+    }
+
+    let rv = conn.build_transaction().read_write().run::<_, Error, _>(|| {
+        import_entries(&entries, &mut report, conn)?;
+        if dry_run {
+            return Err(diesel::result::Error::RollbackTransaction.into());
+        }
+        Ok(())
+    });
+
+    match rv {
+        Ok(()) | Err(Error::Storage(diesel::result::Error::RollbackTransaction)) => Ok(report),
+        Err(e) => Err(e),
+    }
+}
+
+/// Nama provinsi yang digunakan untuk `entry`, apabila provinsi sudah ada di tabel `cities`
+/// maka gunakan nama yang sudah ada (dicocokkan case-insensitive, eg: `DKI JAKARTA` -> `DKI Jakarta`)
+/// agar tetap sama dengan `province` dan `loc_path` yang sudah ada.
+fn province_name(entry: &RegionEntry, conn: &PgConnection) -> Result<String> {
+    use crate::schema::cities::{self, dsl};
+
+    let existing = cities::table
+        .filter(lower(dsl::province).eq(entry.name.trim().to_lowercase()))
+        .select(dsl::province)
+        .first::<String>(conn)
+        .optional()?;
+
+    Ok(existing.unwrap_or_else(|| util::title_case(&entry.name)))
+}
+
+fn import_entries(entries: &[RegionEntry], report: &mut ImportReport, conn: &PgConnection) -> Result<()> {
+    let mut provinces: HashMap<&str, String> = HashMap::new();
+    for entry in entries.iter().filter(|a| a.level == RegionLevel::Province) {
+        provinces.insert(entry.code.as_str(), province_name(entry, conn)?);
+    }
+
+    // kode -> id
+    let mut cities: HashMap<&str, ID> = HashMap::new();
+    // kode -> (id, city_id)
+    let mut districts: HashMap<&str, (ID, ID)> = HashMap::new();
+
+    for entry in entries.iter().filter(|a| a.level == RegionLevel::City) {
+        let province = match provinces.get(entry.parent_code()) {
+            Some(province) => province,
+            None => {
+                report
+                    .skipped
+                    .push(format!("{}: unknown province {}", entry.code, entry.parent_code()));
+                continue;
+            }
+        };
+        let id = import_city(entry, province, report, conn)?;
+        cities.insert(entry.code.as_str(), id);
+    }
+
+    for entry in entries.iter().filter(|a| a.level == RegionLevel::District) {
+        let city_id = match cities.get(entry.parent_code()) {
+            Some(id) => *id,
+            None => {
+                report
+                    .skipped
+                    .push(format!("{}: unknown city {}", entry.code, entry.parent_code()));
+                continue;
+            }
+        };
+        let id = import_district(entry, city_id, report, conn)?;
+        districts.insert(entry.code.as_str(), (id, city_id));
+    }
+
+    for entry in entries.iter().filter(|a| a.level == RegionLevel::Village) {
+        let (district_id, city_id) = match districts.get(entry.parent_code()) {
+            Some(ids) => *ids,
+            None => {
+                report
+                    .skipped
+                    .push(format!("{}: unknown district {}", entry.code, entry.parent_code()));
+                continue;
+            }
+        };
+        import_village(entry, city_id, district_id, report, conn)?;
+    }
+
+    Ok(())
+}
+
+fn check_name(
+    level: RegionLevel,
+    entry: &RegionEntry,
+    existing_id: ID,
+    existing_name: &str,
+    report: &mut ImportReport,
+) {
+    if name_key(&entry.name) != name_key(existing_name) {
+        report.mismatches.push(NameMismatch {
+            level,
+            code: entry.code.to_owned(),
+            official_name: entry.name.to_owned(),
+            existing_id,
+            existing_name: existing_name.to_owned(),
+        });
+    }
+}
+
+fn import_city(entry: &RegionEntry, province: &str, report: &mut ImportReport, conn: &PgConnection) -> Result<ID> {
+    use crate::schema::cities::{self, dsl};
+
+    let dao = CityDao::new(conn);
+
+    if let Some(city) = dao.get_by_code(&entry.code)? {
+        check_name(RegionLevel::City, entry, city.id, &city.name, report);
+        report.unchanged += 1;
+        return Ok(city.id);
+    }
+
+    let name = city_display_name(&entry.name);
+
+    // cari berdasarkan nama yang belum memiliki kode
+    let candidates: Vec<(ID, String)> = cities::table
+        .filter(dsl::code.eq("").and(dsl::province.eq(province)))
+        .select((dsl::id, dsl::name))
+        .load(conn)?;
+    let existing = candidates
+        .iter()
+        .find(|(_, n)| n.to_lowercase() == name.to_lowercase())
+        .or_else(|| {
+            let key = name_key(&name);
+            let mut found = candidates.iter().filter(|(_, n)| name_key(n) == key);
+            match (found.next(), found.next()) {
+                (Some(a), None) => Some(a),
+                _ => None,
+            }
+        });
+
+    if let Some((id, existing_name)) = existing {
+        check_name(RegionLevel::City, entry, *id, existing_name, report);
+        dao.set_code(*id, &entry.code)?;
+        report.linked += 1;
+        return Ok(*id);
+    }
+
+    // area code digunakan untuk registrasi satgas, generasikan yang unik
+    let area_code = loop {
+        let code = util::random_string(4).to_uppercase();
+        if dao.get_by_area_code(&code)?.is_none() {
+            break code;
+        }
+    };
+
+    let city = dao.create(&name, province, "Indonesia", &area_code)?;
+    dao.set_code(city.id, &entry.code)?;
+    report.created += 1;
+    Ok(city.id)
+}
+
+fn import_district(entry: &RegionEntry, city_id: ID, report: &mut ImportReport, conn: &PgConnection) -> Result<ID> {
+    let dao = DistrictDao::new(conn);
+
+    if let Some(district) = dao.get_by_code(&entry.code)? {
+        check_name(RegionLevel::District, entry, district.id, &district.name, report);
+        report.unchanged += 1;
+        return Ok(district.id);
+    }
+
+    let name = util::title_case(&entry.name);
+
+    if let Ok(district) = dao.get_by_name(city_id, &name) {
+        if district.code.is_empty() {
+            dao.set_code(district.id, &entry.code)?;
+            report.linked += 1;
+            return Ok(district.id);
+        }
+    }
+
+    let city = CityDao::new(conn).get_by_id(city_id)?;
+    let meta = vec![format!("city={}", city.name), format!("province={}", city.province)];
+
+    let district = dao.create(&name, city_id, &meta.iter().map(|a| a.as_str()).collect())?;
+    dao.set_code(district.id, &entry.code)?;
+    report.created += 1;
+    Ok(district.id)
+}
+
+fn import_village(
+    entry: &RegionEntry,
+    city_id: ID,
+    district_id: ID,
+    report: &mut ImportReport,
+    conn: &PgConnection,
+) -> Result<ID> {
+    let dao = VillageDao::new(conn);
+
+    if let Some(village) = dao.get_by_code(&entry.code)? {
+        check_name(RegionLevel::Village, entry, village.id, &village.name, report);
+        report.unchanged += 1;
+        return Ok(village.id);
+    }
+
+    let name = util::title_case(&entry.name);
+
+    if let Ok(village) = dao.get_by_name_id(city_id, district_id, &name) {
+        if village.code.is_empty() {
+            dao.set_code(village.id, &entry.code)?;
+            report.linked += 1;
+            return Ok(village.id);
+        }
+    }
+
+    let city = CityDao::new(conn).get_by_id(city_id)?;
+    let district = DistrictDao::new(conn).get_by_id(district_id)?;
+
+    // data resmi tidak memiliki koordinat, diisi 0 sampai diperbaharui
+    let village = dao.create(
+        &name,
+        &district.name,
+        &city.name,
+        &city.province,
+        0.0,
+        0.0,
+        &vec![],
+        city_id,
+        district_id,
+    )?;
+    dao.set_code(village.id, &entry.code)?;
+    report.created += 1;
+    Ok(village.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_code() {
+        assert_eq!(normalize_code("33"), Some(("33".to_string(), RegionLevel::Province)));
+        assert_eq!(normalize_code("3307"), Some(("33.07".to_string(), RegionLevel::City)));
+        assert_eq!(
+            normalize_code("33.07.01"),
+            Some(("33.07.01".to_string(), RegionLevel::District))
+        );
+        assert_eq!(
+            normalize_code("3307012001"),
+            Some(("33.07.01.2001".to_string(), RegionLevel::Village))
+        );
+        assert_eq!(normalize_code("330"), None);
+        assert_eq!(normalize_code("ab"), None);
+    }
+
+    #[test]
+    fn test_parse_regions() {
+        let rv = parse_regions("kode,nama\n33,JAWA TENGAH\n\n33.07,KABUPATEN WONOSOBO\nxx,bad\n");
+        assert_eq!(rv.len(), 3);
+        assert_eq!(rv[1].as_ref().unwrap().parent_code(), "33");
+        assert!(rv[2].is_err());
+    }
+
+    #[test]
+    fn test_name_key() {
+        assert_eq!(name_key("KABUPATEN WONOSOBO"), "wonosobo");
+        assert_eq!(name_key("Kab. Wonosobo"), "wonosobo");
+        assert_eq!(name_key("KOTA TANJUNG BALAI"), "tanjung balai");
+        assert_eq!(name_key("Tanjung-Balai"), "tanjung balai");
+    }
+}
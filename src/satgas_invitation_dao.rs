@@ -0,0 +1,229 @@
+//! Dao implementation for SatgasInvitation
+//!
+
+use chrono::prelude::*;
+use diesel::prelude::*;
+use diesel::sql_types;
+
+use crate::{
+    error::Error as PandemiaError,
+    models::{SatgasInvitation, User, Village},
+    result::Result,
+    schema::satgas_invitations,
+    token,
+    types::EntriesResult,
+    util, ID,
+};
+
+#[derive(Insertable)]
+#[table_name = "satgas_invitations"]
+struct NewSatgasInvitation<'a> {
+    pub token: &'a str,
+    pub city_id: ID,
+    pub district_id: ID,
+    pub village_id: ID,
+    pub village_name: &'a str,
+    pub district_name: &'a str,
+    pub is_medic: bool,
+    pub invitee_name: &'a str,
+    pub invitee_phone: &'a str,
+    pub creator_id: ID,
+    pub creator_name: &'a str,
+    pub expiration: NaiveDateTime,
+}
+
+/// Status undangan, digunakan untuk filter pencarian.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InvitationState {
+    /// Semua undangan.
+    All,
+    /// Belum diterima, belum dibatalkan dan belum expired.
+    Pending,
+    /// Sudah diterima.
+    Accepted,
+    /// Dibatalkan oleh admin.
+    Revoked,
+    /// Sudah melewati batas waktu tanpa diterima.
+    Expired,
+}
+
+impl From<&str> for InvitationState {
+    fn from(a: &str) -> Self {
+        match a {
+            "pending" => InvitationState::Pending,
+            "accepted" => InvitationState::Accepted,
+            "revoked" => InvitationState::Revoked,
+            "expired" => InvitationState::Expired,
+            _ => InvitationState::All,
+        }
+    }
+}
+
+/// Data Access Object for SatgasInvitation
+#[derive(Dao)]
+#[table_name = "satgas_invitations"]
+pub struct SatgasInvitationDao<'a> {
+    db: &'a PgConnection,
+}
+
+impl<'a> SatgasInvitationDao<'a> {
+    /// Buat undangan satgas baru untuk desa tertentu.
+    pub fn create(
+        &self,
+        village: &Village,
+        is_medic: bool,
+        invitee_name: &str,
+        invitee_phone: &str,
+        creator_id: ID,
+        creator_name: &str,
+        valid_days: i64,
+    ) -> Result<SatgasInvitation> {
+        diesel::insert_into(satgas_invitations::table)
+            .values(&NewSatgasInvitation {
+                token: &token::generate_access_token(),
+                city_id: village.city_id,
+                district_id: village.district_id,
+                village_id: village.id,
+                village_name: &village.name,
+                district_name: &village.district_name,
+                is_medic,
+                invitee_name,
+                invitee_phone,
+                creator_id,
+                creator_name,
+                expiration: util::now() + chrono::Duration::days(valid_days),
+            })
+            .get_result(self.db)
+            .map_err(From::from)
+    }
+
+    /// Get invitation by token
+    pub fn get_by_token(&self, token: &str) -> Result<SatgasInvitation> {
+        use crate::schema::satgas_invitations::dsl;
+
+        dsl::satgas_invitations
+            .filter(dsl::token.eq(token))
+            .first(self.db)
+            .map_err(|_| PandemiaError::NotFound("Undangan tidak ditemukan".to_string()))
+    }
+
+    /// Tandai undangan telah diterima oleh user,
+    /// gagal apabila undangan sudah digunakan, dibatalkan atau expired.
+    pub fn accept(&self, token: &str, user: &User) -> Result<SatgasInvitation> {
+        use crate::schema::satgas_invitations::dsl;
+
+        let now = util::now();
+
+        diesel::update(
+            dsl::satgas_invitations.filter(
+                dsl::token
+                    .eq(token)
+                    .and(dsl::accepted_by.eq(0))
+                    .and(dsl::revoked.eq(false))
+                    .and(dsl::expiration.gt(now)),
+            ),
+        )
+        .set((
+            dsl::accepted_by.eq(user.id),
+            dsl::accepted_by_name.eq(&user.full_name),
+            dsl::accepted_at.eq(Some(now)),
+        ))
+        .get_result(self.db)
+        .map_err(|_| {
+            PandemiaError::InvalidParameter(
+                "Undangan tidak valid, sudah digunakan atau kadaluarsa".to_string(),
+            )
+        })
+    }
+
+    /// Batalkan undangan yang belum diterima.
+    pub fn revoke(&self, id: ID) -> Result<()> {
+        use crate::schema::satgas_invitations::dsl;
+
+        let updated =
+            diesel::update(dsl::satgas_invitations.filter(dsl::id.eq(id).and(dsl::accepted_by.eq(0))))
+                .set(dsl::revoked.eq(true))
+                .execute(self.db)?;
+
+        if updated == 0 {
+            return Err(PandemiaError::InvalidParameter(
+                "Undangan sudah diterima atau tidak ditemukan".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Search for specific invitations
+    pub fn search(
+        &self,
+        city_id: Option<ID>,
+        state: InvitationState,
+        query: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<EntriesResult<SatgasInvitation>> {
+        use crate::schema::satgas_invitations::dsl;
+
+        let now = util::now();
+
+        let mut filterer: Box<
+            dyn BoxableExpression<satgas_invitations::table, _, SqlType = sql_types::Bool>,
+        > = Box::new(dsl::id.ne(0));
+
+        if let Some(city_id) = city_id {
+            filterer = Box::new(filterer.and(dsl::city_id.eq(city_id)));
+        }
+
+        let query = query.trim();
+        if query != "" {
+            let like_clause = format!("%{}%", query);
+            filterer = Box::new(
+                filterer.and(
+                    dsl::village_name
+                        .ilike(like_clause.to_owned())
+                        .or(dsl::invitee_name.ilike(like_clause.to_owned()))
+                        .or(dsl::accepted_by_name.ilike(like_clause)),
+                ),
+            );
+        }
+
+        match state {
+            InvitationState::All => (),
+            InvitationState::Pending => {
+                filterer = Box::new(
+                    filterer.and(
+                        dsl::accepted_by
+                            .eq(0)
+                            .and(dsl::revoked.eq(false))
+                            .and(dsl::expiration.gt(now)),
+                    ),
+                )
+            }
+            InvitationState::Accepted => filterer = Box::new(filterer.and(dsl::accepted_by.ne(0))),
+            InvitationState::Revoked => filterer = Box::new(filterer.and(dsl::revoked.eq(true))),
+            InvitationState::Expired => {
+                filterer = Box::new(
+                    filterer.and(
+                        dsl::accepted_by
+                            .eq(0)
+                            .and(dsl::revoked.eq(false))
+                            .and(dsl::expiration.le(now)),
+                    ),
+                )
+            }
+        }
+
+        Ok(EntriesResult::new(
+            dsl::satgas_invitations
+                .filter(&filterer)
+                .offset(offset)
+                .limit(limit)
+                .order(dsl::ts.desc())
+                .load::<SatgasInvitation>(self.db)?,
+            dsl::satgas_invitations
+                .filter(filterer)
+                .select(diesel::dsl::count(dsl::id))
+                .first(self.db)?,
+        ))
+    }
+}
@@ -0,0 +1,206 @@
+//! Dao implementation for ScheduledJob
+//!
+//! Menyimpan jadwal & hasil jalan job terjadwal beserta riwayatnya,
+//! termasuk lock (PostgreSQL advisory lock) agar satu job tidak dijalankan
+//! bersamaan oleh lebih dari satu instance server.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::{
+    models::{JobRun, ScheduledJob},
+    result::Result,
+    schema::{job_runs, scheduled_jobs},
+    sqlutil::{pg_advisory_unlock, pg_try_advisory_lock},
+    util, ID,
+};
+
+/// Jumlah riwayat jalan yang disimpan per job.
+pub const HISTORY_LIMIT: i64 = 100;
+
+/// Namespace key advisory lock untuk job, key kedua adalah ID job.
+const LOCK_NAMESPACE: i32 = 0x4a4f_4253; // "JOBS"
+
+#[derive(Insertable)]
+#[table_name = "scheduled_jobs"]
+struct NewScheduledJob<'a> {
+    pub name: &'a str,
+    pub schedule: &'a str,
+    pub next_run: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[table_name = "job_runs"]
+struct NewJobRun {
+    pub job_id: ID,
+    pub started: NaiveDateTime,
+    pub manual: bool,
+}
+
+/// Data Access Object for ScheduledJob
+#[derive(Dao)]
+#[table_name = "scheduled_jobs"]
+pub struct ScheduledJobDao<'a> {
+    db: &'a PgConnection,
+}
+
+impl<'a> ScheduledJobDao<'a> {
+    /// Daftarkan job apabila belum ada, `next_run` diset ulang apabila
+    /// jadwalnya berubah atau belum pernah diset.
+    pub fn register(
+        &self,
+        name: &str,
+        schedule: &str,
+        next_run: Option<NaiveDateTime>,
+    ) -> Result<ScheduledJob> {
+        use crate::schema::scheduled_jobs::dsl;
+
+        self.db.build_transaction().read_write().run(|| {
+            diesel::insert_into(dsl::scheduled_jobs)
+                .values(&NewScheduledJob {
+                    name,
+                    schedule,
+                    next_run,
+                })
+                .on_conflict(dsl::name)
+                .do_nothing()
+                .execute(self.db)?;
+
+            let job = self.get_by_name(name)?;
+            if job.schedule == schedule && job.next_run.is_some() {
+                return Ok(job);
+            }
+
+            diesel::update(dsl::scheduled_jobs.filter(dsl::id.eq(job.id)))
+                .set((dsl::schedule.eq(schedule), dsl::next_run.eq(next_run)))
+                .get_result(self.db)
+                .map_err(From::from)
+        })
+    }
+
+    /// Get job berdasarkan nama.
+    pub fn get_by_name(&self, name: &str) -> Result<ScheduledJob> {
+        use crate::schema::scheduled_jobs::dsl;
+
+        dsl::scheduled_jobs
+            .filter(dsl::name.eq(name))
+            .first(self.db)
+            .map_err(From::from)
+    }
+
+    /// Get semua job berdasarkan nama.
+    pub fn get_by_names(&self, names: &[&str]) -> Result<Vec<ScheduledJob>> {
+        use crate::schema::scheduled_jobs::dsl;
+
+        dsl::scheduled_jobs
+            .filter(dsl::name.eq_any(names))
+            .order(dsl::name.asc())
+            .load(self.db)
+            .map_err(From::from)
+    }
+
+    /// Update jadwal jalan berikutnya.
+    pub fn set_next_run(&self, job_id: ID, next_run: Option<NaiveDateTime>) -> Result<()> {
+        use crate::schema::scheduled_jobs::dsl;
+
+        diesel::update(dsl::scheduled_jobs.filter(dsl::id.eq(job_id)))
+            .set(dsl::next_run.eq(next_run))
+            .execute(self.db)?;
+        Ok(())
+    }
+
+    /// Coba ambil lock untuk job, mengembalikan `false` apabila job sedang dijalankan
+    /// oleh koneksi (instance) lain. Lock terikat dengan koneksi, lepaskan menggunakan
+    /// [ScheduledJobDao::unlock] pada koneksi yang sama.
+    pub fn try_lock(&self, job_id: ID) -> Result<bool> {
+        diesel::select(pg_try_advisory_lock(LOCK_NAMESPACE, job_id as i32))
+            .get_result(self.db)
+            .map_err(From::from)
+    }
+
+    /// Lepaskan lock job.
+    pub fn unlock(&self, job_id: ID) -> Result<()> {
+        diesel::select(pg_advisory_unlock(LOCK_NAMESPACE, job_id as i32)).execute(self.db)?;
+        Ok(())
+    }
+
+    /// Catat job mulai berjalan.
+    pub fn start_run(&self, job_id: ID, manual: bool) -> Result<JobRun> {
+        use crate::schema::job_runs::dsl;
+
+        diesel::insert_into(dsl::job_runs)
+            .values(&NewJobRun {
+                job_id,
+                started: util::now(),
+                manual,
+            })
+            .get_result(self.db)
+            .map_err(From::from)
+    }
+
+    /// Catat hasil jalan job beserta jadwal berikutnya,
+    /// riwayat yang lebih lama dari [HISTORY_LIMIT] dihapus.
+    pub fn finish_run(
+        &self,
+        run: &JobRun,
+        error: Option<&str>,
+        next_run: Option<NaiveDateTime>,
+    ) -> Result<()> {
+        use crate::schema::{job_runs::dsl as run_dsl, scheduled_jobs::dsl};
+
+        let success = error.is_none();
+
+        self.db.build_transaction().read_write().run(|| {
+            diesel::update(run_dsl::job_runs.filter(run_dsl::id.eq(run.id)))
+                .set((
+                    run_dsl::finished.eq(util::now()),
+                    run_dsl::success.eq(success),
+                    run_dsl::error.eq(error),
+                ))
+                .execute(self.db)?;
+
+            diesel::update(dsl::scheduled_jobs.filter(dsl::id.eq(run.job_id)))
+                .set((
+                    dsl::last_run.eq(run.started),
+                    dsl::last_success.eq(success),
+                    dsl::last_error.eq(error),
+                    dsl::next_run.eq(next_run),
+                ))
+                .execute(self.db)?;
+
+            let keep = run_dsl::job_runs
+                .select(run_dsl::id)
+                .filter(run_dsl::job_id.eq(run.job_id))
+                .order(run_dsl::id.desc())
+                .limit(HISTORY_LIMIT);
+
+            diesel::delete(
+                run_dsl::job_runs
+                    .filter(run_dsl::job_id.eq(run.job_id))
+                    .filter(run_dsl::id.ne_all(keep)),
+            )
+            .execute(self.db)?;
+
+            Ok(())
+        })
+    }
+
+    /// Get riwayat jalan job, terbaru lebih dulu.
+    pub fn get_runs(&self, job_id: ID, offset: i64, limit: i64) -> Result<(Vec<JobRun>, i64)> {
+        use crate::schema::job_runs::dsl;
+
+        let entries = dsl::job_runs
+            .filter(dsl::job_id.eq(job_id))
+            .order(dsl::id.desc())
+            .offset(offset)
+            .limit(limit)
+            .load(self.db)?;
+
+        let count = dsl::job_runs
+            .filter(dsl::job_id.eq(job_id))
+            .select(diesel::dsl::count(dsl::id))
+            .first(self.db)?;
+
+        Ok((entries, count))
+    }
+}
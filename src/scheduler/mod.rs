@@ -0,0 +1,341 @@
+//! Scheduler untuk menjalankan job secara berkala.
+//!
+//! Setiap job mengimplementasikan trait [Job] dan didaftarkan oleh [init], jadwalnya
+//! bisa di-override via konfigurasi `[scheduler.schedules]`, lihat [Schedule] untuk formatnya.
+//! Jadwal berikutnya beserta hasil jalan terakhir disimpan di tabel `scheduled_jobs`
+//! dan riwayatnya di `job_runs`, sehingga jadwal tetap konsisten walaupun server di-restart.
+//!
+//! Apabila server berjalan lebih dari satu instance, job hanya akan dijalankan oleh
+//! satu instance dalam satu waktu menggunakan PostgreSQL advisory lock.
+//! Scheduler sendiri berjalan sebagai [Monitor] sehingga ikut dijalankan
+//! dan dihentikan bersama monitor lainnya.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::{
+    config::Config,
+    dao::{AuthDao, ScheduledJobDao},
+    db::{self, DbConn},
+    error::{Error, ErrorCode},
+    metrics,
+    models::ScheduledJob,
+    monitor::{self, DataMonitor, Monitor, PandemiaMonitor, RetentionMonitor, RetentionPolicy},
+    result::Result,
+    util,
+};
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Sender},
+        Arc, Mutex, RwLock,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+mod schedule;
+
+pub use self::schedule::Schedule;
+
+/// Interval pengecekan jadwal dalam detik.
+const TICK_SECS: u64 = 10;
+
+/// Diset ketika scheduler dihentikan, job baru tidak boleh dijalankan lagi.
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+/// Job yang dijalankan secara terjadwal oleh scheduler.
+pub trait Job: Send + Sync {
+    /// Nama unik job, eg: `data_monitor`, digunakan di konfigurasi, DB dan API.
+    fn name(&self) -> &'static str;
+
+    /// Jadwal default apabila tidak diset di `scheduler.schedules`.
+    fn default_schedule(&self, config: &Config) -> Schedule;
+
+    /// Jalankan job sekali.
+    fn run(&self, conn: &PgConnection) -> Result<()>;
+}
+
+/// Job yang terdaftar di scheduler beserta jadwalnya.
+struct Entry {
+    job: Box<dyn Job>,
+    schedule: Schedule,
+    next_run: Mutex<Option<NaiveDateTime>>,
+    running: AtomicBool,
+}
+
+impl Entry {
+    fn new(job: Box<dyn Job>, config: &Config) -> Entry {
+        let schedule = config
+            .scheduler
+            .schedules
+            .get(job.name())
+            .and_then(|expr| expr.parse().ok())
+            .unwrap_or_else(|| job.default_schedule(config));
+        Entry {
+            job,
+            schedule,
+            next_run: Mutex::new(None),
+            running: AtomicBool::new(false),
+        }
+    }
+
+    /// Jadwal berikutnya belum diketahui (belum di-load dari DB) atau sudah lewat.
+    fn is_due(&self, now: NaiveDateTime) -> bool {
+        self.next_run.lock().unwrap().map(|t| t <= now).unwrap_or(true)
+    }
+
+    fn register(&self, conn: &PgConnection) -> Result<ScheduledJob> {
+        let next_run = self.schedule.next_after(util::now());
+        ScheduledJobDao::new(conn).register(self.job.name(), &self.schedule.to_string(), next_run)
+    }
+}
+
+lazy_static! {
+    /// Daftar semua job.
+    static ref JOBS: RwLock<Vec<Arc<Entry>>> = RwLock::new(vec![]);
+}
+
+/// Daftarkan semua job beserta jadwalnya sesuai konfigurasi `config`,
+/// dipanggil sekali ketika startup.
+pub fn init(config: &Config) {
+    let retention = RetentionPolicy::from_config(&config.retention);
+    let jobs: Vec<Box<dyn Job>> = vec![
+        Box::new(DataMonitor),
+        Box::new(RetentionMonitor::new(retention)),
+        Box::new(PurgeTokens),
+    ];
+    *JOBS.write().unwrap() = jobs
+        .into_iter()
+        .map(|job| Arc::new(Entry::new(job, config)))
+        .collect();
+}
+
+fn entries() -> Vec<Arc<Entry>> {
+    let entries = JOBS.read().unwrap();
+    if entries.is_empty() {
+        panic!("Scheduler not initialized, call scheduler::init first");
+    }
+    entries.clone()
+}
+
+/// Nama semua job yang terdaftar.
+pub fn job_names() -> Vec<&'static str> {
+    entries().iter().map(|entry| entry.job.name()).collect()
+}
+
+/// Daftar semua job beserta hasil jalan terakhir dan jadwal berikutnya.
+pub fn jobs(conn: &PgConnection) -> Result<Vec<ScheduledJob>> {
+    entries().iter().map(|entry| entry.register(conn)).collect()
+}
+
+/// Jalankan job `name` sekarang juga di luar jadwal, job berjalan di background.
+/// Ditolak apabila scheduler sedang dihentikan.
+pub fn trigger(name: &str) -> Result<()> {
+    let entry = entries()
+        .into_iter()
+        .find(|entry| entry.job.name() == name)
+        .ok_or_else(|| {
+            Error::BadRequest(
+                ErrorCode::InvalidParameter as i32,
+                format!("Unknown job `{}`", name),
+            )
+        })?;
+
+    if launch(entry, true)? {
+        Ok(())
+    } else {
+        Err(Error::BadRequest(
+            ErrorCode::JobAlreadyRunning as i32,
+            format!("Job `{}` is already running", name),
+        ))
+    }
+}
+
+/// Ambil lock lalu jalankan job di thread terpisah, mengembalikan `false`
+/// apabila job sedang berjalan atau (untuk jalan terjadwal) sudah dijalankan instance lain.
+fn launch(entry: Arc<Entry>, manual: bool) -> Result<bool> {
+    if entry.running.swap(true, Ordering::SeqCst) {
+        return Ok(false);
+    }
+
+    // dicek setelah `running` diset, sehingga `Scheduler::stop` pasti menunggu job ini
+    // atau job ini pasti melihat scheduler sedang berhenti.
+    if STOPPING.load(Ordering::SeqCst) {
+        entry.running.store(false, Ordering::SeqCst);
+        return Err(Error::BadRequest(
+            ErrorCode::ServiceBusy as i32,
+            "Scheduler is stopping".to_string(),
+        ));
+    }
+
+    match acquire(&entry, manual) {
+        Ok(Some((conn, job))) => {
+            thread::spawn(move || run_locked(&entry, &conn, &job, manual));
+            Ok(true)
+        }
+        Ok(None) => {
+            entry.running.store(false, Ordering::SeqCst);
+            Ok(false)
+        }
+        Err(e) => {
+            entry.running.store(false, Ordering::SeqCst);
+            Err(e)
+        }
+    }
+}
+
+fn acquire(entry: &Entry, manual: bool) -> Result<Option<(DbConn, ScheduledJob)>> {
+    let conn = db::clone().get().map_err(|e| Error::InternalError(e.into()))?;
+    let job = entry.register(&conn)?;
+    let dao = ScheduledJobDao::new(&conn);
+
+    if !dao.try_lock(job.id)? {
+        debug!("[Scheduler] job `{}` is locked by another instance", job.name);
+        return Ok(None);
+    }
+
+    // cek ulang jadwal dari DB, bisa jadi baru saja dijalankan oleh instance lain
+    if !manual && job.next_run.map(|t| t > util::now()).unwrap_or(false) {
+        *entry.next_run.lock().unwrap() = job.next_run;
+        dao.unlock(job.id)?;
+        return Ok(None);
+    }
+
+    Ok(Some((conn, job)))
+}
+
+fn run_locked(entry: &Entry, conn: &PgConnection, job: &ScheduledJob, manual: bool) {
+    let dao = ScheduledJobDao::new(conn);
+    let name = entry.job.name();
+
+    info!(
+        "[Scheduler] running job `{}`{}",
+        name,
+        if manual { " (manual)" } else { "" }
+    );
+    let run = dao.start_run(job.id, manual);
+
+    let start = Instant::now();
+    let result = entry.job.run(conn);
+    metrics::observe_monitor_run(name, start.elapsed());
+
+    let error = result.err().map(|e| e.to_string());
+    match &error {
+        Some(e) => error!("[Scheduler] job `{}` failed: {}", name, e),
+        None => debug!("[Scheduler] job `{}` done in {:?}", name, start.elapsed()),
+    }
+
+    let next_run = entry.schedule.next_after(util::now());
+    *entry.next_run.lock().unwrap() = next_run;
+
+    let saved = run.and_then(|run| dao.finish_run(&run, error.as_ref().map(String::as_str), next_run));
+    if let Err(e) = saved {
+        error!("[Scheduler] cannot save result of job `{}`: {}", name, e);
+    }
+    if let Err(e) = dao.unlock(job.id) {
+        error!("[Scheduler] cannot unlock job `{}`: {}", name, e);
+    }
+
+    entry.running.store(false, Ordering::SeqCst);
+}
+
+/// Scheduler, berjalan sebagai [Monitor].
+pub struct Scheduler {
+    _tx: Option<Sender<bool>>,
+    _handle: Option<JoinHandle<()>>,
+    shutdown_timeout: u64,
+}
+
+impl Scheduler {
+    /// Create Scheduler new instance
+    pub fn new() -> PandemiaMonitor {
+        Mutex::new(Box::new(Self {
+            _tx: None,
+            _handle: None,
+            shutdown_timeout: 0,
+        }))
+    }
+}
+
+impl std::fmt::Display for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Scheduler")
+    }
+}
+
+impl Monitor for Scheduler {
+    fn start(&mut self, config: &Config) {
+        let (tx, rx) = channel();
+        self._tx = Some(tx);
+        self.shutdown_timeout = config.api.shutdown_timeout;
+        STOPPING.store(false, Ordering::SeqCst);
+
+        for name in config.scheduler.schedules.keys() {
+            if !job_names().contains(&name.as_str()) {
+                warn!("[Scheduler] unknown job `{}` in scheduler.schedules", name);
+            }
+        }
+        let entries = entries();
+        for entry in &entries {
+            info!(
+                "[Scheduler] job `{}` scheduled at `{}`",
+                entry.job.name(),
+                entry.schedule
+            );
+        }
+
+        self._handle = Some(thread::spawn(move || loop {
+            let now = util::now();
+            for entry in entries
+                .iter()
+                .filter(|entry| entry.is_due(now) && !STOPPING.load(Ordering::SeqCst))
+            {
+                if let Err(e) = launch(entry.clone(), false) {
+                    error!("[Scheduler] cannot launch job `{}`: {}", entry.job.name(), e);
+                }
+            }
+
+            if monitor::wait_stop(&rx, TICK_SECS) {
+                debug!("[Scheduler] down.");
+                break;
+            }
+        }));
+    }
+
+    fn stop(&mut self) {
+        STOPPING.store(true, Ordering::SeqCst);
+        self._tx.as_ref().map(|tx| tx.send(true));
+        if let Some(handle) = self._handle.take() {
+            let _ = handle.join();
+        }
+        // tunggu job yang sedang berjalan selesai
+        let timeout = Duration::from_secs(self.shutdown_timeout);
+        let entries = entries();
+        if !util::wait_until(timeout, || {
+            entries.iter().all(|entry| !entry.running.load(Ordering::SeqCst))
+        }) {
+            warn!("[Scheduler] some jobs are still running");
+        }
+    }
+}
+
+/// Job untuk menghapus access token yang sudah expired, default setiap hari jam 3 pagi (UTC).
+pub struct PurgeTokens;
+
+impl Job for PurgeTokens {
+    fn name(&self) -> &'static str {
+        "purge_tokens"
+    }
+
+    fn default_schedule(&self, _config: &Config) -> Schedule {
+        "0 3 * * *".parse().expect("invalid schedule")
+    }
+
+    fn run(&self, conn: &PgConnection) -> Result<()> {
+        let count = AuthDao::new(conn).purge_expired_tokens()?;
+        info!("[PurgeTokens] {} expired tokens purged", count);
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+//! Jadwal job, mendukung format:
+//!
+//! * cron 5 field `menit jam tanggal bulan hari` (UTC), eg: `0 3 * * *`, `*/15 * * * *`, `0 0 * * 1-5`.
+//!   Setiap field mendukung `*`, angka, range `a-b`, step `*/n` atau `a-b/n` dan list `a,b,c`,
+//!   hari 0 dan 7 adalah Minggu.
+//! * alias `@hourly`, `@daily`, `@weekly` dan `@monthly`.
+//! * interval `@every <n>[s|m|h|d]`, eg: `@every 30m`, dihitung sejak jalan terakhir.
+
+use chrono::{prelude::*, Duration};
+
+use std::{fmt, str::FromStr};
+
+/// Jadwal job, dibuat dari string menggunakan `parse()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    expr: String,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Every(i64),
+    Cron(Cron),
+}
+
+/// Field cron dalam bentuk bitmask.
+#[derive(Debug, Clone, PartialEq)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Schedule {
+    /// Jadwal yang berjalan setiap `secs` detik.
+    pub fn every(secs: u64) -> Schedule {
+        Schedule {
+            expr: format!("@every {}s", secs),
+            kind: Kind::Every(secs as i64),
+        }
+    }
+
+    /// Waktu jalan berikutnya setelah `time`,
+    /// `None` apabila tidak ada jadwal yang cocok dalam 5 tahun ke depan.
+    pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        match &self.kind {
+            Kind::Every(secs) => Some(time + Duration::seconds(*secs)),
+            Kind::Cron(cron) => cron.next_after(time),
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(expr: &str) -> Result<Schedule, String> {
+        let expr = expr.trim();
+        let cron = match expr {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            _ if expr.starts_with("@every ") => {
+                return parse_interval(expr["@every ".len()..].trim()).map(|secs| Schedule {
+                    expr: expr.to_string(),
+                    kind: Kind::Every(secs),
+                });
+            }
+            _ => expr,
+        };
+
+        let fields: Vec<&str> = cron.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "`{}`: expected 5 fields (minute hour day month weekday)",
+                expr
+            ));
+        }
+
+        let field = |i: usize, name: &str, min: u32, max: u32| {
+            parse_field(fields[i], min, max).map_err(|e| format!("`{}`: invalid {} field, {}", expr, name, e))
+        };
+
+        let mut weekdays = field(4, "weekday", 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+
+        let cron = Cron {
+            minutes: field(0, "minute", 0, 59)?,
+            hours: field(1, "hour", 0, 23)?,
+            days: field(2, "day", 1, 31)?,
+            months: field(3, "month", 1, 12)?,
+            weekdays,
+            any_day: fields[2].starts_with('*'),
+            any_weekday: fields[4].starts_with('*'),
+        };
+
+        if cron
+            .next_after(NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0))
+            .is_none()
+        {
+            return Err(format!("`{}`: never runs", expr));
+        }
+
+        Ok(Schedule {
+            expr: expr.to_string(),
+            kind: Kind::Cron(cron),
+        })
+    }
+}
+
+fn parse_interval(s: &str) -> Result<i64, String> {
+    let (num, unit) = match s.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((i, _)) => (&s[..i], &s[i..]),
+        None => (s, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => {
+            return Err(format!(
+                "`@every {}`: unknown unit `{}`, use s, m, h or d",
+                s, unit
+            ))
+        }
+    };
+    match num.parse::<i64>() {
+        Ok(n) if n > 0 => Ok(n * multiplier),
+        _ => Err(format!("`@every {}`: interval must be greater than 0", s)),
+    }
+}
+
+fn parse_field(spec: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.find('/') {
+            Some(i) => (&part[..i], Some(&part[i + 1..])),
+            None => (part, None),
+        };
+        let step = match step {
+            Some(step) => match step.parse::<u32>() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(format!("invalid step `{}`", step)),
+            },
+            None => 1,
+        };
+        let num = |s: &str| match s.parse::<u32>() {
+            Ok(n) if n >= min && n <= max => Ok(n),
+            _ => Err(format!("`{}` is out of range {}-{}", s, min, max)),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some(i) = range.find('-') {
+            (num(&range[..i])?, num(&range[i + 1..])?)
+        } else if step > 1 {
+            (num(range)?, max)
+        } else {
+            let n = num(range)?;
+            (n, n)
+        };
+        if start > end {
+            return Err(format!("invalid range `{}`", range));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            mask |= 1 << n;
+        }
+    }
+    Ok(mask)
+}
+
+fn has(mask: u64, n: u32) -> bool {
+    mask & (1 << n) != 0
+}
+
+impl Cron {
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        // sama seperti cron, apabila tanggal & hari sama-sama dibatasi cukup salah satu yang cocok
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut t = time.date().and_hms(time.hour(), time.minute(), 0) + Duration::minutes(1);
+        let limit = t.year() + 5;
+
+        while t.year() <= limit {
+            if !has(self.months, t.month()) {
+                let (year, month) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = NaiveDate::from_ymd(year, month, 1).and_hms(0, 0, 0);
+            } else if !self.day_matches(t.date()) {
+                t = (t.date() + Duration::days(1)).and_hms(0, 0, 0);
+            } else if !has(self.hours, t.hour()) {
+                t = t.date().and_hms(t.hour(), 0, 0) + Duration::hours(1);
+            } else if !has(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(expr: &str, time: &str) -> NaiveDateTime {
+        expr.parse::<Schedule>().unwrap().next_after(at(time)).unwrap()
+    }
+
+    #[test]
+    fn test_cron_next() {
+        assert_eq!(next("*/15 * * * *", "2020-05-02 10:07"), at("2020-05-02 10:15"));
+        assert_eq!(next("*/15 * * * *", "2020-05-02 10:45"), at("2020-05-02 11:00"));
+        assert_eq!(next("0 3 * * *", "2020-05-02 03:00"), at("2020-05-03 03:00"));
+        assert_eq!(next("30 8 1 * *", "2020-12-02 00:00"), at("2021-01-01 08:30"));
+        // 2020-05-02 adalah hari Sabtu
+        assert_eq!(next("0 0 * * 1-5", "2020-05-02 12:00"), at("2020-05-04 00:00"));
+        assert_eq!(next("0 0 * * 7", "2020-05-02 12:00"), at("2020-05-03 00:00"));
+        assert_eq!(next("0 0 29 2 *", "2020-03-01 00:00"), at("2024-02-29 00:00"));
+        assert_eq!(next("@hourly", "2020-05-02 10:07"), at("2020-05-02 11:00"));
+    }
+
+    #[test]
+    fn test_every() {
+        assert_eq!(next("@every 30m", "2020-05-02 10:07"), at("2020-05-02 10:37"));
+        assert_eq!(Schedule::every(90).to_string(), "@every 90s");
+        assert_eq!(
+            Schedule::every(90).next_after(at("2020-05-02 10:07")),
+            Some(at("2020-05-02 10:08") + Duration::seconds(30))
+        );
+    }
+
+    #[test]
+    fn test_invalid_schedule() {
+        for expr in &[
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "0 0 30 2 *",
+            "@every 0m",
+            "@every 5x",
+            "@yearly",
+        ] {
+            assert!(expr.parse::<Schedule>().is_err(), "{} should be invalid", expr);
+        }
+    }
+}
@@ -4,6 +4,14 @@ table! {
         user_id -> Int8,
         created -> Timestamp,
         valid_thru -> Timestamp,
+        id -> Int8,
+        device_id -> Text,
+        name -> Text,
+        platform -> Text,
+        ip_address -> Text,
+        last_used -> Timestamp,
+        refresh_token -> Text,
+        refresh_valid_thru -> Timestamp,
     }
 }
 
@@ -28,6 +36,14 @@ table! {
         admin_id -> Int8,
         created -> Timestamp,
         valid_thru -> Timestamp,
+        id -> Int8,
+        device_id -> Text,
+        name -> Text,
+        platform -> Text,
+        ip_address -> Text,
+        last_used -> Timestamp,
+        refresh_token -> Text,
+        refresh_valid_thru -> Timestamp,
     }
 }
 
@@ -42,6 +58,18 @@ table! {
     }
 }
 
+table! {
+    admin_two_factors (id) {
+        id -> Int8,
+        admin_id -> Int8,
+        secret -> Text,
+        enabled -> Bool,
+        recovery_codes -> Array<Text>,
+        last_step -> Int8,
+        ts -> Timestamp,
+    }
+}
+
 table! {
     admins (id) {
         id -> Int8,
@@ -54,6 +82,35 @@ table! {
     }
 }
 
+table! {
+    app_releases (id) {
+        id -> Int8,
+        platform -> Varchar,
+        version -> Varchar,
+        min_supported_version -> Varchar,
+        notes -> Text,
+        download_url -> Text,
+        force_update -> Bool,
+        ts -> Timestamp,
+    }
+}
+
+table! {
+    boundaries (id) {
+        id -> Int8,
+        kind -> Int2,
+        ref_id -> Int8,
+        name -> Text,
+        geometry -> Text,
+        min_lat -> Float8,
+        min_lng -> Float8,
+        max_lat -> Float8,
+        max_lng -> Float8,
+        meta -> Array<Text>,
+        ts -> Timestamp,
+    }
+}
+
 table! {
     cities (id) {
         id -> Int8,
@@ -62,6 +119,7 @@ table! {
         country_code -> Text,
         area_code -> Varchar,
         ts -> Timestamp,
+        code -> Text,
     }
 }
 
@@ -94,6 +152,7 @@ table! {
         name -> Text,
         city_id -> Int8,
         meta -> Array<Text>,
+        code -> Text,
     }
 }
 
@@ -121,6 +180,18 @@ table! {
     }
 }
 
+table! {
+    job_runs (id) {
+        id -> Int8,
+        job_id -> Int8,
+        started -> Timestamp,
+        finished -> Nullable<Timestamp>,
+        success -> Nullable<Bool>,
+        error -> Nullable<Text>,
+        manual -> Bool,
+    }
+}
+
 table! {
     kv_store (id) {
         id -> Int8,
@@ -166,6 +237,18 @@ table! {
     }
 }
 
+table! {
+    phone_verifications (id) {
+        id -> Int8,
+        user_id -> Int8,
+        phone_num -> Varchar,
+        code_hash -> Text,
+        attempts -> Int4,
+        expiration -> Timestamp,
+        ts -> Timestamp,
+    }
+}
+
 table! {
     records (id) {
         id -> Int8,
@@ -216,6 +299,14 @@ table! {
     }
 }
 
+table! {
+    request_nonces (user_id, nonce) {
+        user_id -> Int8,
+        nonce -> Text,
+        ts -> Timestamp,
+    }
+}
+
 table! {
     reset_password_admins (admin_id) {
         admin_id -> Int8,
@@ -225,6 +316,42 @@ table! {
     }
 }
 
+table! {
+    satgas_invitations (id) {
+        id -> Int8,
+        token -> Text,
+        city_id -> Int8,
+        district_id -> Int8,
+        village_id -> Int8,
+        village_name -> Text,
+        district_name -> Text,
+        is_medic -> Bool,
+        invitee_name -> Text,
+        invitee_phone -> Text,
+        creator_id -> Int8,
+        creator_name -> Text,
+        accepted_by -> Int8,
+        accepted_by_name -> Text,
+        accepted_at -> Nullable<Timestamp>,
+        revoked -> Bool,
+        expiration -> Timestamp,
+        ts -> Timestamp,
+    }
+}
+
+table! {
+    scheduled_jobs (id) {
+        id -> Int8,
+        name -> Varchar,
+        schedule -> Varchar,
+        last_run -> Nullable<Timestamp>,
+        last_success -> Nullable<Bool>,
+        last_error -> Nullable<Text>,
+        next_run -> Nullable<Timestamp>,
+        ts -> Timestamp,
+    }
+}
+
 table! {
     sub_reports (id) {
         id -> Int8,
@@ -244,6 +371,11 @@ table! {
         city_id -> Int8,
         district_id -> Int8,
         village_id -> Int8,
+        pii -> Text,
+        full_name_bidx -> Array<Text>,
+        age_bidx -> Text,
+        residence_address_bidx -> Text,
+        last_updated -> Timestamp,
     }
 }
 
@@ -258,6 +390,10 @@ table! {
         latest_loc_full -> Text,
         latest_loc_long -> Float8,
         latest_loc_lat -> Float8,
+        latest_loc_ts -> Timestamp,
+        latest_city_id -> Nullable<Int8>,
+        latest_district_id -> Nullable<Int8>,
+        latest_village_id -> Nullable<Int8>,
     }
 }
 
@@ -269,6 +405,7 @@ table! {
         secret_key -> Text,
         created -> Timestamp,
         active -> Bool,
+        client_key -> Bool,
     }
 }
 
@@ -288,6 +425,7 @@ table! {
         user_id -> Int8,
         s_key -> Text,
         s_value -> Text,
+        ts -> Timestamp,
     }
 }
 
@@ -342,6 +480,7 @@ table! {
         ts -> Timestamp,
         city_id -> Int8,
         district_id -> Int8,
+        code -> Text,
     }
 }
 
@@ -349,14 +488,21 @@ joinable!(access_tokens -> users (user_id));
 joinable!(addresses -> users (user_id));
 joinable!(admin_access_tokens -> admins (admin_id));
 joinable!(admin_passhash -> admins (admin_id));
+joinable!(admin_two_factors -> admins (admin_id));
 joinable!(district_data -> districts (district_id));
 joinable!(districts -> cities (city_id));
 joinable!(feeds -> users (creator_id));
+joinable!(job_runs -> scheduled_jobs (job_id));
 joinable!(logs -> users (initiator_id));
 joinable!(notifs -> users (receiver_id));
+joinable!(phone_verifications -> users (user_id));
 joinable!(report_notes -> cities (city_id));
 joinable!(report_notes -> users (creator_id));
+joinable!(request_nonces -> users (user_id));
 joinable!(reset_password_admins -> admins (admin_id));
+joinable!(satgas_invitations -> cities (city_id));
+joinable!(satgas_invitations -> districts (district_id));
+joinable!(satgas_invitations -> villages (village_id));
 joinable!(sub_reports -> cities (city_id));
 joinable!(sub_reports -> users (creator_id));
 joinable!(user_connect -> users (user_id));
@@ -372,20 +518,28 @@ allow_tables_to_appear_in_same_query!(
     addresses,
     admin_access_tokens,
     admin_passhash,
+    admin_two_factors,
     admins,
+    app_releases,
+    boundaries,
     cities,
     district_data,
     districts,
     feeds,
     geoloc_cache,
+    job_runs,
     kv_store,
     logs,
     map_markers,
     notifs,
+    phone_verifications,
     records,
     register_users,
     report_notes,
+    request_nonces,
     reset_password_admins,
+    satgas_invitations,
+    scheduled_jobs,
     sub_reports,
     user_connect,
     user_keys,
@@ -8,15 +8,17 @@ use serde::Serialize;
 use serde_json::Value as JsonValue;
 use validator::Validate;
 
-use crate::crypto::{self, SecretKey};
+use crate::crypto::{self, PasshashScheme, SecretKey};
 use crate::{
-    api::{error::*, types::*, ApiResult, Error as ApiError, ErrorCode},
-    auth::AuthDao,
-    dao::{AdminDao, CityDao, Logs},
+    admin_two_factor_dao::TwoFactorPolicy,
+    api::{self, client_ip, error::*, types::*, ApiResult, Error as ApiError, ErrorCode},
+    auth::{AuthDao, SessionInfo},
+    dao::{AdminDao, AdminTwoFactorDao, CityDao, Logs},
     error::Error,
     kvstore::KvStore,
     models,
     prelude::*,
+    rate_limit::{self, Endpoint, Identity},
     types::AccountKind,
     user_dao::{NewUser, NewUserConnect, UserDao},
     util, ID,
@@ -45,14 +47,16 @@ impl Service for AuthService {
 
 use crate::models::AccessToken;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ApiSchema)]
 pub struct Authorize {
     pub email: Option<String>,
     pub phone: Option<String>,
     pub password: String,
+    /// Kode TOTP atau recovery code, wajib apabila admin telah mengaktifkan 2FA.
+    pub totp_code: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ApiSchema)]
 pub struct DeviceAuthorize {
     #[validate(length(min = 5, max = 1000))]
     pub device_id: String,
@@ -69,15 +73,93 @@ pub struct DeviceAuthorize {
     pub loc_path: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct AccessTokenQuery {
     pub token: String,
 }
 
-#[derive(Serialize, Validate)]
+#[derive(Serialize, Validate, ApiSchema)]
 pub struct AuthorizeResult<T> {
     pub access_token: models::AccessToken,
     pub user: Option<T>,
+    /// Akun wajib mengaktifkan 2FA, sampai 2FA aktif sesi ini hanya bisa
+    /// mengakses endpoint enrollment 2FA.
+    pub two_factor_setup_required: bool,
+}
+
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct RefreshSession {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct RenameSession {
+    pub id: ID,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+}
+
+/// Informasi sesi (access token) yang aktif.
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct Session {
+    pub id: ID,
+    pub name: String,
+    pub device_id: String,
+    pub platform: String,
+    pub ip_address: String,
+    pub created: NaiveDateTime,
+    pub last_used: NaiveDateTime,
+    pub valid_thru: NaiveDateTime,
+    /// Apakah sesi ini adalah sesi yang sedang digunakan untuk request.
+    pub current: bool,
+}
+
+impl Session {
+    fn from_user_token(a: models::AccessToken, current_token: &str) -> Self {
+        Session {
+            current: a.token == current_token,
+            id: a.id,
+            name: a.name,
+            device_id: a.device_id,
+            platform: a.platform,
+            ip_address: a.ip_address,
+            created: a.created,
+            last_used: a.last_used,
+            valid_thru: a.valid_thru,
+        }
+    }
+
+    fn from_admin_token(a: models::AdminAccessToken, current_token: &str) -> Self {
+        Session {
+            current: a.token == current_token,
+            id: a.id,
+            name: a.name,
+            device_id: a.device_id,
+            platform: a.platform,
+            ip_address: a.ip_address,
+            created: a.created,
+            last_used: a.last_used,
+            valid_thru: a.valid_thru,
+        }
+    }
+}
+
+fn user_agent(req: &api::HttpRequest) -> String {
+    req.headers()
+        .get("User-Agent")
+        .and_then(|a| a.to_str().ok())
+        .unwrap_or("")
+        .chars()
+        .take(200)
+        .collect()
+}
+
+fn current_token(req: &api::HttpRequest) -> String {
+    req.headers()
+        .get("X-Access-Token")
+        .and_then(|a| a.to_str().ok())
+        .unwrap_or("")
+        .to_string()
 }
 
 struct PrivateApi;
@@ -87,7 +169,7 @@ impl PrivateApi {
     /// Menghapus akses token
     #[api_endpoint(path = "/remove_access_token", auth = "required", mutable)]
     pub fn remove_access_token(query: AccessTokenQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AuthDao::new(&conn);
         dao.remove_access_token(&query.token)?;
 
@@ -97,7 +179,7 @@ impl PrivateApi {
     /// Unauthorize user, this will invalidate all valid access tokens.
     #[api_endpoint(path = "/unauthorize", auth = "required", mutable)]
     pub fn unauthorize(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AuthDao::new(&conn);
 
         dao.clear_access_token_by_user_id(query.id)?;
@@ -108,7 +190,7 @@ impl PrivateApi {
     /// Unauthorize user, this will invalidate all valid access tokens.
     #[api_endpoint(path = "/admin/unauthorize", auth = "required", mutable)]
     pub fn admin_unauthorize(query: IdQuery) -> ApiResult<()> {
-        let conn = state.db();
+        let conn = state.db()?;
         let dao = AuthDao::new(&conn);
 
         dao.clear_access_token_by_admin_id(query.id)?;
@@ -129,12 +211,26 @@ impl PublicApi {
     pub fn authorize_device(query: DeviceAuthorize) -> ApiResult<AccessToken> {
         query.validate()?;
 
-        let conn = state.db();
+        let conn = state.db()?;
 
         let dao = UserDao::new(&conn);
 
         let kv = KvStore::new(&conn);
 
+        let ip_address = client_ip(req);
+
+        // setiap device yang melakukan otorisasi dihitung, device baru juga dihitung per IP
+        // karena akan membuat user baru.
+        throttle(
+            Endpoint::DeviceAuthorize,
+            &[Identity::ip(ip_address), Identity::device(&query.device_id)],
+        )?;
+        rate_limit::record(
+            &state.config().rate_limit,
+            Endpoint::DeviceAuthorize,
+            &[Identity::device(&query.device_id)],
+        );
+
         let user_device_key = format!("user-device.{}", query.device_id);
 
         // check apabila sudah terdaftar dan kemungkinan akses token expired
@@ -147,13 +243,22 @@ impl PublicApi {
                 };
                 if let Ok(user_id) = user_id.parse::<i64>() {
                     if let Ok(user) = dao.get_by_id(user_id) {
-                        let token = AuthDao::new(&conn).generate_access_token(user.id)?;
+                        let token = AuthDao::new(&conn).create_session(
+                            user.id,
+                            &SessionInfo {
+                                device_id: &query.device_id,
+                                platform: &query.platform,
+                                ip_address,
+                            },
+                        )?;
                         // .map_err(From::from)?;
                         // .map(ApiResult::success);
 
-                        dao.update_user_location(
+                        dao.update_user_location_ll(
                             &user,
                             &query.device_id,
+                            query.loc_lat,
+                            query.loc_long,
                             &query.loc_name,
                             &query.loc_path.unwrap_or(query.loc_name_full),
                         )?;
@@ -164,6 +269,12 @@ impl PublicApi {
             }
         }
 
+        rate_limit::record(
+            &state.config().rate_limit,
+            Endpoint::DeviceAuthorize,
+            &[Identity::ip(ip_address)],
+        );
+
         // gunakan semuanya random hanya untuk memudahkan push notif saja
         let gen_name = format!("gen__{}_{}", util::random_string(20), util::random_number());
 
@@ -194,9 +305,11 @@ impl PublicApi {
             }),
         )?;
 
-        dao.update_user_location(
+        dao.update_user_location_ll(
             &user,
             &query.device_id,
+            query.loc_lat,
+            query.loc_long,
             &query.loc_name,
             &query.loc_path.unwrap_or(query.loc_name_full),
         )?;
@@ -208,9 +321,16 @@ impl PublicApi {
 
         let dao = AuthDao::new(&conn);
 
-        dao.generate_access_token(user.id)
-            .map_err(From::from)
-            .map(ApiResult::success)
+        dao.create_session(
+            user.id,
+            &SessionInfo {
+                device_id: &query.device_id,
+                platform: &query.platform,
+                ip_address,
+            },
+        )
+        .map_err(From::from)
+        .map(ApiResult::success)
     }
 
     /// Unauthorize current user session, this will invalidate all valid access tokens.
@@ -226,50 +346,94 @@ impl PublicApi {
     /// Admin bisa melakukan otorisasi menggunakan email / nomor telp.
     #[api_endpoint(path = "/admin/authorize", auth = "none", mutable)]
     pub fn admin_authorize(state: &mut AppState, query: Authorize) -> ApiResult<AuthorizeResult<Admin>> {
-        let conn = state.db();
-        let user = {
-            let dao = AdminDao::new(&conn);
-            if let Some(email) = query.email {
-                dao.get_by_email(&email)?
-            } else {
-                Err(ApiError::InvalidParameter(
+        let conn = state.db()?;
+        let email = match query.email.as_ref().map(|a| a.trim()).filter(|a| !a.is_empty()) {
+            Some(email) => email,
+            None => {
+                return Err(ApiError::InvalidParameter(
                     ErrorCode::NoLoginInfo as i32,
                     "No email parameter".to_string(),
-                ))?
+                ))
+            }
+        };
+
+        let identities = [Identity::ip(client_ip(req)), Identity::account(email)];
+        throttle(Endpoint::AdminAuthorize, &identities)?;
+
+        let user = match AdminDao::new(&conn).get_by_email(email) {
+            Ok(user) => user,
+            Err(e) => {
+                rate_limit::record(&state.config().rate_limit, Endpoint::AdminAuthorize, &identities);
+                return Err(e.into());
             }
         };
 
         if !user.active {
-            return Err(ApiError::InvalidParameter(
-                ErrorCode::Unauthorized as i32,
-                "Account blocked".to_string(),
-            ));
+            return coded_error(ErrorCode::AccountBlocked);
         }
 
         let dao = AuthDao::new(&conn);
 
-        let user_passhash = dao.get_passhash(AccountKind::Admin, user.id)?;
-        if !crypto::password_match(&query.password, &user_passhash) {
+        let scheme = PasshashScheme::current(&state.config().password);
+        if !dao.verify_password(AccountKind::Admin, user.id, &query.password, scheme)? {
             warn!("user `{}` try to authorize using wrong password", &user.id);
+            rate_limit::record(&state.config().rate_limit, Endpoint::AdminAuthorize, &identities);
             Err(ApiError::Unauthorized)?
         }
 
-        let access_token = dao.generate_admin_access_token(user.id)?;
+        let tf_dao = AdminTwoFactorDao::new(&conn);
+        let two_factor_enabled = tf_dao.is_enabled(user.id)?;
+        if two_factor_enabled {
+            match query
+                .totp_code
+                .as_ref()
+                .map(|a| a.trim())
+                .filter(|a| !a.is_empty())
+            {
+                Some(code) => {
+                    if !tf_dao.verify(user.id, code)? {
+                        warn!("user `{}` try to authorize using wrong two factor code", &user.id);
+                        rate_limit::record(&state.config().rate_limit, Endpoint::AdminAuthorize, &identities);
+                        Err(ApiError::Unauthorized)?
+                    }
+                }
+                None => return coded_error(ErrorCode::TwoFactorRequired),
+            }
+        }
+
+        let access_token = dao.create_admin_session(
+            user.id,
+            &SessionInfo {
+                device_id: "",
+                platform: &user_agent(req),
+                ip_address: client_ip(req),
+            },
+        )?;
+
+        rate_limit::reset(Endpoint::AdminAuthorize, &identities[1]);
 
         Logs::new(&conn).write(&format!("{} logged in", user.name), user.id);
 
         Ok(ApiResult::success(AuthorizeResult {
             access_token,
+            two_factor_setup_required: !two_factor_enabled
+                && TwoFactorPolicy::from_config(&state.config().two_factor).requires(&user),
             user: Some(user.to_api_type(&conn)),
         }))
     }
 
     /// Unauthorize current user session, this will invalidate all valid access tokens.
-    #[api_endpoint(path = "/admin/unauthorize", auth = "optional", mutable, accessor = "admin")]
+    #[api_endpoint(
+        path = "/admin/unauthorize",
+        auth = "optional",
+        mutable,
+        accessor = "admin",
+        two_factor_setup
+    )]
     pub fn admin_unauthorize(query: ()) -> ApiResult<()> {
         match current_admin {
             Some(current_admin) => {
-                let conn = state.db();
+                let conn = state.db()?;
 
                 let rv = PrivateApi::admin_unauthorize(state, IdQuery { id: current_admin.id }, req);
 
@@ -284,16 +448,23 @@ impl PublicApi {
     /// Authorize satgas.
     #[api_endpoint(path = "/satgas/authorize", auth = "none", mutable)]
     pub fn satgas_authorize(query: SatgasAuthorize) -> ApiResult<SatgasAuthorizeResult> {
-        let conn = state.db();
+        let conn = state.db()?;
 
         let kv = KvStore::new(&conn);
 
+        let identities = [Identity::ip(client_ip(req))];
+        throttle(Endpoint::SatgasAuthorize, &identities)?;
+
         let entry_key = format!("web-token.{}", query.token.trim());
 
-        let user_id = kv
-            .get(&entry_key)?
-            .ok_or(ApiError::NotFound(404, "Web token tidak valid".to_string()))?
-            .parse::<i64>()?;
+        let user_id = match kv.get(&entry_key) {
+            Ok(Some(user_id)) => user_id.parse::<i64>()?,
+            Err(Error::Storage(diesel::result::Error::NotFound)) | Ok(None) => {
+                rate_limit::record(&state.config().rate_limit, Endpoint::SatgasAuthorize, &identities);
+                return coded_error(ErrorCode::InvalidWebToken);
+            }
+            Err(e) => return Err(e.into()),
+        };
 
         let user_dao = UserDao::new(&conn);
 
@@ -313,7 +484,14 @@ impl PublicApi {
         kv.delete(&entry_key)?;
 
         AuthDao::new(&conn)
-            .generate_access_token(user.id)
+            .create_session(
+                user.id,
+                &SessionInfo {
+                    device_id: "",
+                    platform: &user_agent(req),
+                    ip_address: client_ip(req),
+                },
+            )
             .map_err(From::from)
             .map(|at| {
                 ApiResult::success(SatgasAuthorizeResult {
@@ -323,10 +501,117 @@ impl PublicApi {
             })
     }
 
+    /// Perbarui access token menggunakan refresh token,
+    /// refresh token lama tidak bisa digunakan lagi setelah ini.
+    #[api_endpoint(path = "/refresh", auth = "none", mutable)]
+    pub fn refresh_session(query: RefreshSession) -> ApiResult<AccessToken> {
+        let conn = state.db()?;
+
+        AuthDao::new(&conn)
+            .refresh_session(&query.refresh_token, client_ip(req))
+            .map_err(From::from)
+            .map(ApiResult::success)
+    }
+
+    /// Perbarui access token admin menggunakan refresh token.
+    #[api_endpoint(path = "/admin/refresh", auth = "none", mutable)]
+    pub fn refresh_admin_session(query: RefreshSession) -> ApiResult<AccessToken> {
+        let conn = state.db()?;
+
+        AuthDao::new(&conn)
+            .refresh_admin_session(&query.refresh_token, client_ip(req))
+            .map_err(From::from)
+            .map(ApiResult::success)
+    }
+
+    /// Mendapatkan daftar sesi aktif milik current user.
+    #[api_endpoint(path = "/sessions", auth = "required", accessor = "user")]
+    pub fn list_sessions(query: ()) -> ApiResult<Vec<Session>> {
+        let conn = state.db()?;
+        let token = current_token(req);
+
+        let sessions = AuthDao::new(&conn).get_sessions(current_user.id)?;
+
+        Ok(ApiResult::success(
+            sessions
+                .into_iter()
+                .map(|a| Session::from_user_token(a, &token))
+                .collect(),
+        ))
+    }
+
+    /// Beri nama pada sesi milik current user.
+    #[api_endpoint(path = "/session/rename", auth = "required", mutable, accessor = "user")]
+    pub fn rename_session(query: RenameSession) -> ApiResult<()> {
+        query.validate()?;
+        let conn = state.db()?;
+
+        AuthDao::new(&conn).rename_session(current_user.id, query.id, &query.name)?;
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Cabut sesi milik current user.
+    #[api_endpoint(path = "/session/revoke", auth = "required", mutable, accessor = "user")]
+    pub fn revoke_session(query: IdQuery) -> ApiResult<()> {
+        let conn = state.db()?;
+
+        AuthDao::new(&conn).revoke_session(current_user.id, query.id)?;
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Mendapatkan daftar sesi aktif milik current admin.
+    #[api_endpoint(path = "/admin/sessions", auth = "required", accessor = "admin")]
+    pub fn list_admin_sessions(query: ()) -> ApiResult<Vec<Session>> {
+        let conn = state.db()?;
+        let token = current_token(req);
+
+        let sessions = AuthDao::new(&conn).get_admin_sessions(current_admin.id)?;
+
+        Ok(ApiResult::success(
+            sessions
+                .into_iter()
+                .map(|a| Session::from_admin_token(a, &token))
+                .collect(),
+        ))
+    }
+
+    /// Beri nama pada sesi milik current admin.
+    #[api_endpoint(
+        path = "/admin/session/rename",
+        auth = "required",
+        mutable,
+        accessor = "admin"
+    )]
+    pub fn rename_admin_session(query: RenameSession) -> ApiResult<()> {
+        query.validate()?;
+        let conn = state.db()?;
+
+        AuthDao::new(&conn).rename_admin_session(current_admin.id, query.id, &query.name)?;
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Cabut sesi milik current admin.
+    #[api_endpoint(
+        path = "/admin/session/revoke",
+        auth = "required",
+        mutable,
+        accessor = "admin"
+    )]
+    pub fn revoke_admin_session(query: IdQuery) -> ApiResult<()> {
+        let conn = state.db()?;
+
+        AuthDao::new(&conn).revoke_admin_session(current_admin.id, query.id)?;
+
+        Ok(ApiResult::success(()))
+    }
+
     /// Generate web token for login.
     #[api_endpoint(path = "/satgas/get_web_token", auth = "required", mutable, accessor = "user")]
     pub fn get_web_token(query: IdQuery) -> ApiResult<String> {
-        let conn = state.db();
+        let conn = state.db()?;
 
         if !current_user.is_satgas() || current_user.is_blocked() || current_user.is_deleted() {
             return Err(ApiError::Unauthorized);
@@ -345,9 +630,14 @@ impl PublicApi {
     }
 
     /// Get city area code.
+    ///
+    /// Deprecated: gunakan undangan satgas, hanya aktif apabila `security.allow_area_code` diset.
     #[api_endpoint(path = "/get_area_code", auth = "required", accessor = "admin")]
     pub fn get_area_code(query: IdQuery) -> ApiResult<String> {
-        let conn = state.db();
+        if !state.config().security.allow_area_code {
+            return coded_error(ErrorCode::AreaCodeDisabled);
+        }
+        let conn = state.db()?;
 
         if query.id == 0 || query.id != current_admin.get_city_id().unwrap_or(0) {
             return unauthorized();
@@ -360,11 +650,16 @@ impl PublicApi {
     }
 
     /// reset area code.
+    ///
+    /// Deprecated: gunakan undangan satgas, hanya aktif apabila `security.allow_area_code` diset.
     #[api_endpoint(path = "/reset_area_code", auth = "required", mutable, accessor = "admin")]
     pub fn reset_area_code(query: IdQuery) -> ApiResult<String> {
         use crate::schema::cities::{self, dsl};
 
-        let conn = state.db();
+        if !state.config().security.allow_area_code {
+            return coded_error(ErrorCode::AreaCodeDisabled);
+        }
+        let conn = state.db()?;
 
         if !current_admin.has_access("reset_area_code") && current_admin.get_city_id() != Some(query.id) {
             return unauthorized();
@@ -381,7 +676,6 @@ impl PublicApi {
             util::random_number()
         );
 
-        let conn = state.db();
         diesel::update(dsl::cities.filter(dsl::id.eq(query.id)))
             .set(dsl::area_code.eq(&area_code))
             .execute(&conn)
@@ -397,12 +691,12 @@ impl PublicApi {
 //     pub area_code: String,
 // }
 
-#[derive(Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
 pub struct SatgasAuthorize {
     pub token: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct SatgasAuthorizeResult {
     pub token: String,
     pub user: Satgas,
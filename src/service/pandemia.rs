@@ -39,7 +39,7 @@ impl PublicApi1 {
         Ok(format!("Welcome {}! {}", info.1, info.0))
     }
 
-    fn resource_test(req: &api::HttpRequest) -> api::Result<String> {
+    fn resource_test(req: &api::RawRequest) -> api::Result<String> {
         Ok("resource_test".to_owned())
     }
 
@@ -76,6 +76,8 @@ impl_service!(AnalyticService, analytic);
 impl_service!(VillageService, village);
 impl_service!(CitiesService, cities);
 impl_service!(DistrictService, district);
+impl_service!(BoundaryService, boundary);
+impl_service!(InvitationService, invitation);
 
 /// Initialize and load services
 pub fn load_services() -> Vec<Box<dyn Service>> {
@@ -91,5 +93,7 @@ pub fn load_services() -> Vec<Box<dyn Service>> {
         VillageService::new(),
         CitiesService::new(),
         DistrictService::new(),
+        BoundaryService::new(),
+        InvitationService::new(),
     ]
 }
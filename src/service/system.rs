@@ -2,10 +2,16 @@
 
 use actix_web::{http::Method, App, AsyncResponder, Error, Path, Result};
 use serde_json::Value as JsonValue;
+use validator::Validate;
 
 use crate::api;
-use crate::api::*;
+use crate::api::{error::param_error, types::*, *};
 use crate::service::Service;
+use crate::{
+    app_release_dao,
+    dao::{AppReleaseDao, ScheduledJobDao},
+    models, scheduler, util,
+};
 
 /// Service contoh, kamu bisa mencontoh bagaimana caranya membuat service
 /// dengan melihat kode [SystemService] ini.
@@ -24,15 +30,64 @@ impl Service for SystemService {
     }
     fn wire_api(&self, builder: &mut ServiceApiBuilder) {
         builder.public_scope().link(PublicApi::wire);
+        builder.private_scope().link(PrivateApi::wire);
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct CheckVersion {
     pub version: String,
     pub platform: String,
 }
 
+/// Informasi update untuk client.
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct UpdateInfo {
+    /// Versi terbaru apabila tersedia update, kosong apabila client sudah menggunakan versi terbaru.
+    pub new_update: String,
+    pub notes: String,
+    pub download_url: String,
+    /// Client wajib update sebelum bisa melanjutkan.
+    pub force_update: bool,
+    pub min_supported_version: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct NewAppRelease {
+    #[validate(length(min = 1, max = 20))]
+    pub platform: String,
+    #[validate(length(min = 1, max = 30))]
+    pub version: String,
+    #[validate(length(min = 1, max = 30))]
+    pub min_supported_version: String,
+    #[validate(length(max = 5000))]
+    pub notes: Option<String>,
+    #[validate(url)]
+    pub download_url: Option<String>,
+    pub force_update: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct PlatformQuery {
+    pub platform: String,
+}
+
+#[derive(Serialize, Deserialize, ApiSchema)]
+pub struct JobQuery {
+    /// Nama job, eg: `data_monitor`.
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ApiSchema)]
+pub struct JobRunsQuery {
+    /// Nama job, eg: `data_monitor`.
+    pub name: String,
+    #[validate(range(min = 0, max = 1_000_000))]
+    pub offset: i64,
+    #[validate(range(min = 1, max = 1000))]
+    pub limit: i64,
+}
+
 /// Contoh API public untuk service contoh [[SystemService]].
 struct PublicApi {}
 
@@ -45,16 +100,124 @@ impl PublicApi {
                 "build": env!("BUILD_INFO"), "git": env!("GIT_REV") }))
     }
 
-    /// check for new version
+    /// Cek apakah tersedia versi baru untuk aplikasi client berdasarkan rilis yang terdaftar.
     #[api_endpoint(path = "/check_version", auth = "optional")]
-    pub fn check_version(state: &AppState, query: CheckVersion) -> JsonValue {
-        // @TODO(Rboin): code here
-        Ok(json!({
-            "code": 0,
-            "description": "",
-            "result": {
-                "new_update": "", "notes": ""
-            }
+    pub fn check_version(state: &AppState, query: CheckVersion) -> ApiResult<UpdateInfo> {
+        if util::parse_version(&query.version).is_none() {
+            return param_error("Invalid version format, expected eg: 1.2.3");
+        }
+
+        let conn = state.db()?;
+        let releases = AppReleaseDao::new(&conn).get_by_platform(&query.platform)?;
+
+        let info = match app_release_dao::check_update(&releases, &query.version) {
+            Some(update) => UpdateInfo {
+                new_update: update.latest.version.to_owned(),
+                notes: update.latest.notes.to_owned(),
+                download_url: update.latest.download_url.to_owned(),
+                force_update: update.force,
+                min_supported_version: update.latest.min_supported_version.to_owned(),
+            },
+            None => UpdateInfo {
+                new_update: "".to_string(),
+                notes: "".to_string(),
+                download_url: "".to_string(),
+                force_update: false,
+                min_supported_version: "".to_string(),
+            },
+        };
+
+        Ok(ApiResult::success(info))
+    }
+}
+
+/// API privat untuk mengelola registry rilis aplikasi mobile dan job terjadwal.
+struct PrivateApi {}
+
+#[api_group("System", "private", base = "/system/v1")]
+impl PrivateApi {
+    /// Daftarkan rilis aplikasi mobile baru.
+    #[api_endpoint(path = "/release/add", auth = "none", mutable)]
+    pub fn add_release(query: NewAppRelease) -> ApiResult<models::AppRelease> {
+        query.validate()?;
+
+        let conn = state.db()?;
+
+        let release = AppReleaseDao::new(&conn).create(
+            &query.platform,
+            &query.version,
+            &query.min_supported_version,
+            query.notes.as_ref().map(|a| a.as_str()).unwrap_or(""),
+            query.download_url.as_ref().map(|a| a.as_str()).unwrap_or(""),
+            query.force_update.unwrap_or(false),
+        )?;
+
+        info!(
+            "new {} release registered: {} (min {}, force: {})",
+            release.platform, release.version, release.min_supported_version, release.force_update
+        );
+
+        Ok(ApiResult::success(release))
+    }
+
+    /// Mendapatkan daftar rilis untuk platform.
+    #[api_endpoint(path = "/releases", auth = "none")]
+    pub fn list_releases(query: PlatformQuery) -> ApiResult<EntriesResult<models::AppRelease>> {
+        let conn = state.db()?;
+
+        let entries = AppReleaseDao::new(&conn).get_by_platform(&query.platform)?;
+
+        Ok(ApiResult::success(EntriesResult {
+            count: entries.len() as i64,
+            entries,
         }))
     }
+
+    /// Hapus rilis dari registry.
+    #[api_endpoint(path = "/release/delete", auth = "none", mutable)]
+    pub fn delete_release(query: IdQuery) -> ApiResult<()> {
+        let conn = state.db()?;
+
+        AppReleaseDao::new(&conn).delete_by_id(query.id)?;
+
+        Ok(ApiResult::success(()))
+    }
+
+    /// Mendapatkan daftar job terjadwal beserta hasil jalan terakhir dan jadwal berikutnya.
+    #[api_endpoint(path = "/jobs", auth = "none")]
+    pub fn list_jobs(state: &AppState, query: ()) -> ApiResult<EntriesResult<models::ScheduledJob>> {
+        let conn = state.db()?;
+
+        let entries = scheduler::jobs(&conn)?;
+
+        Ok(ApiResult::success(EntriesResult {
+            count: entries.len() as i64,
+            entries,
+        }))
+    }
+
+    /// Mendapatkan riwayat jalan job, terbaru lebih dulu.
+    #[api_endpoint(path = "/job/runs", auth = "none")]
+    pub fn job_runs(query: JobRunsQuery) -> ApiResult<EntriesResult<models::JobRun>> {
+        query.validate()?;
+
+        let conn = state.db()?;
+        let dao = ScheduledJobDao::new(&conn);
+
+        let job = dao.get_by_name(&query.name)?;
+        let (entries, count) = dao.get_runs(job.id, query.offset, query.limit)?;
+
+        Ok(ApiResult::success(EntriesResult { entries, count }))
+    }
+
+    /// Jalankan job sekarang juga di luar jadwal, job berjalan di background,
+    /// hasilnya bisa dilihat via `/job/runs`.
+    #[api_endpoint(path = "/job/trigger", auth = "none", mutable)]
+    pub fn trigger_job(query: JobQuery) -> ApiResult<()> {
+        scheduler::trigger(&query.name)?;
+
+        info!("job `{}` triggered manually", query.name);
+
+        Ok(ApiResult::success(()))
+    }
 }
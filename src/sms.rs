@@ -0,0 +1,214 @@
+//! Pengiriman SMS keluar, saat ini digunakan untuk kode OTP verifikasi nomor telepon.
+//!
+//! Seperti email, SMS dimasukkan ke antrian dan dikirim oleh worker thread.
+//!
+//! Konfigurasi melalui section `sms` atau env var:
+//!
+//! * `SMS_API_URL` - endpoint HTTP gateway SMS, dikirim sebagai POST JSON `{"to": "..", "text": ".."}`.
+//! * `SMS_API_KEY` - apabila diset dikirim sebagai header `Authorization: Bearer <key>`.
+//!
+//! Apabila `sms.api_url` tidak diset, SMS hanya ditulis ke log (stub untuk development).
+
+use crate::{config::SmsConfig, error::Error, result::Result, util};
+
+use std::{
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+};
+
+/// Berapa kali pengiriman dicoba sebelum SMS dibuang.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// SMS yang akan dikirim.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sms {
+    /// Nomor tujuan.
+    pub to: String,
+    /// Isi pesan.
+    pub text: String,
+}
+
+/// Abstraksi pengirim SMS.
+pub trait SmsSender: Send + Sync {
+    /// Nama implementasi, untuk keperluan logging.
+    fn name(&self) -> &'static str;
+
+    /// Kirim SMS.
+    fn send(&self, sms: &Sms) -> Result<()>;
+}
+
+/// Pengirim SMS melalui HTTP gateway.
+pub struct HttpSmsSender {
+    url: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl HttpSmsSender {
+    /// Buat HTTP SMS sender baru.
+    pub fn new(url: &str, api_key: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            api_key: api_key.to_owned(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Buat dari konfigurasi, `None` apabila `sms.api_url` tidak diset.
+    pub fn from_config(config: &SmsConfig) -> Option<Self> {
+        let url = config.api_url.as_ref().filter(|a| !a.trim().is_empty())?;
+        Some(Self::new(url.trim(), &config.api_key))
+    }
+}
+
+impl SmsSender for HttpSmsSender {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn send(&self, sms: &Sms) -> Result<()> {
+        let mut req = self.client.post(&self.url).json(&json!({
+            "to": sms.to,
+            "text": sms.text,
+        }));
+
+        if !self.api_key.is_empty() {
+            req = req.header("Authorization", format!("Bearer {}", self.api_key));
+        }
+
+        let resp = req.send()?;
+
+        if !resp.status().is_success() {
+            return Err(Error::InternalError(format_err!(
+                "SMS gateway returned status {}",
+                resp.status()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Stub yang hanya menulis SMS ke log, berguna untuk development.
+pub struct LogSmsSender;
+
+impl SmsSender for LogSmsSender {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn send(&self, sms: &Sms) -> Result<()> {
+        info!("[sms] to {}: {}", sms.to, sms.text);
+        Ok(())
+    }
+}
+
+/// Pengirim SMS yang hanya menyimpan SMS di memory, digunakan untuk testing.
+#[derive(Clone, Default)]
+pub struct MemorySmsSender {
+    sent: Arc<Mutex<Vec<Sms>>>,
+}
+
+impl MemorySmsSender {
+    /// Buat memory SMS sender baru.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// SMS yang telah terkirim.
+    pub fn sent(&self) -> Vec<Sms> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+impl SmsSender for MemorySmsSender {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn send(&self, sms: &Sms) -> Result<()> {
+        self.sent.lock().unwrap().push(sms.clone());
+        Ok(())
+    }
+}
+
+/// Antrian SMS, dikirim oleh worker thread secara berurutan.
+pub struct SmsQueue {
+    tx: Mutex<Sender<Sms>>,
+}
+
+impl SmsQueue {
+    /// Jalankan worker pengirim SMS dan kembalikan antriannya.
+    pub fn start(sender: Box<dyn SmsSender>) -> Self {
+        let (tx, rx) = channel::<Sms>();
+
+        thread::spawn(move || {
+            for sms in rx {
+                for attempt in 1..=MAX_ATTEMPTS {
+                    match sender.send(&sms) {
+                        Ok(_) => {
+                            debug!("[{}] sms sent to {}", sender.name(), sms.to);
+                            break;
+                        }
+                        Err(e) if attempt < MAX_ATTEMPTS => {
+                            warn!(
+                                "[{}] cannot send sms to {}, retrying. {}",
+                                sender.name(),
+                                sms.to,
+                                e
+                            );
+                            util::sleep(1000 * u64::from(attempt));
+                        }
+                        Err(e) => error!("[{}] cannot send sms to {}. {}", sender.name(), sms.to, e),
+                    }
+                }
+            }
+        });
+
+        Self { tx: Mutex::new(tx) }
+    }
+
+    /// Masukkan SMS ke antrian.
+    pub fn push(&self, sms: Sms) {
+        if let Err(e) = self.tx.lock().unwrap().send(sms) {
+            error!("Cannot queue sms. {}", e);
+        }
+    }
+}
+
+fn sender_from_config(config: &SmsConfig) -> Box<dyn SmsSender> {
+    match HttpSmsSender::from_config(config) {
+        Some(sender) => Box::new(sender),
+        None => {
+            warn!("No sms.api_url set, sms will only be written to log");
+            Box::new(LogSmsSender)
+        }
+    }
+}
+
+lazy_static! {
+    static ref SMS_QUEUE: RwLock<Option<Arc<SmsQueue>>> = RwLock::new(None);
+}
+
+/// Jalankan antrian SMS global sesuai konfigurasi `sms`, dipanggil sekali ketika startup.
+pub fn init(config: &SmsConfig) {
+    *SMS_QUEUE.write().unwrap() = Some(Arc::new(SmsQueue::start(sender_from_config(config))));
+}
+
+/// Kirim SMS menggunakan antrian global yang dijalankan oleh [init].
+pub fn send(to: &str, text: &str) {
+    let queue = match SMS_QUEUE.read().unwrap().as_ref() {
+        Some(queue) => queue.clone(),
+        None => {
+            error!("Sms sender not initialized, sms to {} dropped", to);
+            return;
+        }
+    };
+    queue.push(Sms {
+        to: to.to_owned(),
+        text: text.to_owned(),
+    });
+}
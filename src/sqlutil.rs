@@ -23,3 +23,18 @@ sql_function!(
     /// Extends array with other array in Postgres
     fn array_cat<T>(list: sql_types::Array<T>, item: sql_types::Array<T>) -> sql_types::Array<T>
 );
+
+sql_function!(
+    /// Gabungkan item array menjadi text di Postgres
+    fn array_to_string(list: sql_types::Array<sql_types::Text>, delimiter: sql_types::Text) -> sql_types::Text
+);
+
+sql_function!(
+    /// Coba ambil PostgreSQL advisory lock (level session) tanpa menunggu
+    fn pg_try_advisory_lock(key1: sql_types::Integer, key2: sql_types::Integer) -> sql_types::Bool
+);
+
+sql_function!(
+    /// Lepaskan PostgreSQL advisory lock (level session)
+    fn pg_advisory_unlock(key1: sql_types::Integer, key2: sql_types::Integer) -> sql_types::Bool
+);
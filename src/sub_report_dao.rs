@@ -2,6 +2,8 @@
 //!
 
 use crate::{
+    crypto::{data_key, DataKey},
+    dao::Logs,
     models::SubReport,
     result::Result,
     schema::sub_reports,
@@ -14,6 +16,57 @@ use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*;
 use diesel::{dsl::any, sql_types};
 
+/// Data pribadi pada sub report yang disimpan terenkripsi di kolom `pii`.
+#[derive(Serialize, Deserialize)]
+struct SubReportPii {
+    full_name: String,
+    age: i32,
+    residence_address: String,
+    notes: String,
+}
+
+/// Kolom-kolom terenkripsi beserta blind index-nya.
+struct EncryptedPii {
+    pii: String,
+    full_name_bidx: Vec<String>,
+    age_bidx: String,
+    residence_address_bidx: String,
+}
+
+impl SubReportPii {
+    fn encrypt(&self, key: &DataKey) -> Result<EncryptedPii> {
+        Ok(EncryptedPii {
+            pii: key.encrypt(serde_json::to_string(self)?.as_bytes()),
+            full_name_bidx: name_bidx(key, &self.full_name),
+            age_bidx: key.blind_index(&self.age.to_string()),
+            residence_address_bidx: key.blind_index(&self.residence_address),
+        })
+    }
+
+    fn decrypt(key: &DataKey, pii: &str) -> Result<Self> {
+        match key.decrypt(pii) {
+            Some(data) => Ok(serde_json::from_slice(&data)?),
+            None => fail!("Cannot decrypt sub report personal data"),
+        }
+    }
+
+    fn restore(self, mut sub_report: SubReport) -> SubReport {
+        sub_report.full_name = self.full_name;
+        sub_report.age = self.age;
+        sub_report.residence_address = self.residence_address;
+        sub_report.notes = self.notes;
+        sub_report
+    }
+}
+
+/// Blind index per kata dari nama, sehingga pencarian nama bisa dilakukan per kata.
+fn name_bidx(key: &DataKey, name: &str) -> Vec<String> {
+    let mut rv: Vec<String> = name.split_whitespace().map(|a| key.blind_index(a)).collect();
+    rv.sort();
+    rv.dedup();
+    rv
+}
+
 #[derive(Insertable)]
 #[table_name = "sub_reports"]
 struct NewSubReport<'a> {
@@ -33,6 +86,10 @@ struct NewSubReport<'a> {
     pub city_id: ID,
     pub district_id: ID,
     pub village_id: ID,
+    pub pii: &'a str,
+    pub full_name_bidx: &'a Vec<String>,
+    pub age_bidx: &'a str,
+    pub residence_address_bidx: &'a str,
 }
 
 #[doc(hidden)]
@@ -60,7 +117,8 @@ pub struct SubReportDao<'a> {
 }
 
 impl<'a> SubReportDao<'a> {
-    /// Create new SubReport
+    /// Create new SubReport,
+    /// data pribadi (nama, umur, alamat & catatan) disimpan dalam bentuk terenkripsi.
     pub fn create(
         &self,
         creator_id: i64,
@@ -82,27 +140,40 @@ impl<'a> SubReportDao<'a> {
     ) -> Result<SubReport> {
         use crate::schema::sub_reports::{self, dsl};
 
-        diesel::insert_into(sub_reports::table)
+        let pii = SubReportPii {
+            full_name: full_name.to_owned(),
+            age,
+            residence_address: residence_address.to_owned(),
+            notes: notes.to_owned(),
+        };
+        let enc = pii.encrypt(&data_key()?)?;
+
+        let sub_report = diesel::insert_into(sub_reports::table)
             .values(&NewSubReport {
                 creator_id,
                 creator_name,
-                full_name,
-                age,
-                residence_address,
+                full_name: "",
+                age: 0,
+                residence_address: "",
                 gender,
                 coming_from,
                 arrival_date: arrival_date.unwrap_or(util::now().date()),
                 healthy,
-                notes,
+                notes: "",
                 status,
                 meta,
                 ts: util::now(),
                 city_id,
                 village_id,
                 district_id,
+                pii: &enc.pii,
+                full_name_bidx: &enc.full_name_bidx,
+                age_bidx: &enc.age_bidx,
+                residence_address_bidx: &enc.residence_address_bidx,
             })
-            .get_result(self.db)
-            .map_err(From::from)
+            .get_result::<SubReport>(self.db)?;
+
+        Ok(pii.restore(sub_report))
     }
 
     /// Update
@@ -111,25 +182,118 @@ impl<'a> SubReportDao<'a> {
 
         let arrival_date = data.arrival_date.unwrap_or(util::now().date());
 
+        let pii = SubReportPii {
+            full_name: data.full_name.to_owned(),
+            age: data.age,
+            residence_address: data.residence_address.to_owned(),
+            notes: data.notes.to_owned(),
+        };
+        let enc = pii.encrypt(&data_key()?)?;
+
         let result = diesel::update(dsl::sub_reports.filter(dsl::id.eq(id)))
             .set((
-                dsl::full_name.eq(data.full_name),
-                dsl::age.eq(data.age),
-                dsl::residence_address.eq(data.residence_address),
+                dsl::full_name.eq(""),
+                dsl::age.eq(0),
+                dsl::residence_address.eq(""),
                 dsl::gender.eq(data.gender),
                 dsl::coming_from.eq(data.coming_from),
                 dsl::arrival_date.eq(arrival_date),
                 dsl::healthy.eq(data.healthy),
-                dsl::notes.eq(data.notes),
+                dsl::notes.eq(""),
                 dsl::status.eq(data.status),
                 dsl::meta.eq(data.meta),
+                dsl::pii.eq(&enc.pii),
+                dsl::full_name_bidx.eq(&enc.full_name_bidx),
+                dsl::age_bidx.eq(&enc.age_bidx),
+                dsl::residence_address_bidx.eq(&enc.residence_address_bidx),
+                dsl::last_updated.eq(util::now()),
             ))
             .get_result::<SubReport>(self.db)?;
 
-        Ok(result)
+        Ok(pii.restore(result))
+    }
+
+    /// Dekripsi data pribadi dari sub report untuk ditampilkan,
+    /// setiap pembacaan dicatat di log journal.
+    pub fn reveal(&self, entries: Vec<SubReport>, reader_id: ID, reader_name: &str) -> Result<Vec<SubReport>> {
+        let encrypted: Vec<ID> = entries.iter().filter(|a| a.pii != "").map(|a| a.id).collect();
+
+        if encrypted.is_empty() {
+            return Ok(entries);
+        }
+
+        let key = data_key()?;
+
+        let entries = entries
+            .into_iter()
+            .map(|a| {
+                if a.pii == "" {
+                    // data lama yang belum dienkripsi
+                    Ok(a)
+                } else {
+                    Ok(SubReportPii::decrypt(&key, &a.pii)?.restore(a))
+                }
+            })
+            .collect::<Result<Vec<SubReport>>>()?;
+
+        Logs::new(self.db).write(
+            &format!(
+                "{} membuka data pribadi sub report dengan id {:?}",
+                reader_name, encrypted
+            ),
+            reader_id,
+        );
+
+        Ok(entries)
+    }
+
+    /// Enkripsi data pribadi pada sub report lama yang masih tersimpan dalam bentuk plaintext.
+    /// Mengembalikan jumlah sub report yang dienkripsi.
+    pub fn encrypt_legacy(&self) -> Result<usize> {
+        use crate::schema::sub_reports::{self, dsl};
+
+        let key = data_key()?;
+
+        self.db.build_transaction().read_write().run::<_, crate::error::Error, _>(|| {
+            let entries = dsl::sub_reports
+                .filter(dsl::pii.eq(""))
+                .load::<SubReport>(self.db)?;
+
+            for entry in &entries {
+                let enc = SubReportPii {
+                    full_name: entry.full_name.to_owned(),
+                    age: entry.age,
+                    residence_address: entry.residence_address.to_owned(),
+                    notes: entry.notes.to_owned(),
+                }
+                .encrypt(&key)?;
+
+                diesel::update(dsl::sub_reports.filter(dsl::id.eq(entry.id)))
+                    .set((
+                        dsl::full_name.eq(""),
+                        dsl::age.eq(0),
+                        dsl::residence_address.eq(""),
+                        dsl::notes.eq(""),
+                        dsl::pii.eq(&enc.pii),
+                        dsl::full_name_bidx.eq(&enc.full_name_bidx),
+                        dsl::age_bidx.eq(&enc.age_bidx),
+                        dsl::residence_address_bidx.eq(&enc.residence_address_bidx),
+                    ))
+                    .execute(self.db)?;
+            }
+
+            Ok(entries.len())
+        })
     }
 
-    /// Search for specific sub report by creator
+    /// Search for specific sub report by creator.
+    ///
+    /// Karena data pribadi tersimpan terenkripsi, pencarian nama, umur dan alamat
+    /// dilakukan menggunakan blind index: nama dicocokkan per kata secara utuh
+    /// (case-insensitive), semua kata pada `query` harus ada, misal "budi" cocok
+    /// dengan "Budi Santoso" tapi "bud" tidak. Umur dan alamat harus sama persis.
+    /// Hanya filter tersebut yang membutuhkan `security.data_key`.
+    /// Hasil pencarian masih terenkripsi, gunakan [SubReportDao::reveal] untuk membukanya.
     pub fn search(
         &self,
         city_id: Option<ID>,
@@ -156,8 +320,14 @@ impl<'a> SubReportDao<'a> {
         let query = query.trim();
 
         if query != "" {
+            let key = data_key()?;
             let like_clause = format!("%{}%", query).to_lowercase();
-            filterer = Box::new(filterer.and(lower(dsl::full_name).like(like_clause)));
+            // `full_name` hanya berisi untuk data lama yang belum dienkripsi
+            filterer = Box::new(filterer.and(
+                dsl::full_name_bidx
+                    .contains(name_bidx(key, query))
+                    .or(lower(dsl::full_name).like(like_clause)),
+            ));
         }
 
         if let Some(village_name) = village_name {
@@ -173,7 +343,12 @@ impl<'a> SubReportDao<'a> {
         }
 
         if let Some(age) = age {
-            filterer = Box::new(filterer.and(dsl::age.eq(age as i32)));
+            let key = data_key()?;
+            filterer = Box::new(filterer.and(
+                dsl::age_bidx
+                    .eq(key.blind_index(&age.to_string()))
+                    .or(dsl::pii.eq("").and(dsl::age.eq(age as i32))),
+            ));
         }
 
         if let Some(status) = status {
@@ -182,8 +357,14 @@ impl<'a> SubReportDao<'a> {
         }
 
         if let Some(residence_address) = residence_address {
-            filterer =
-                Box::new(filterer.and(lower(dsl::residence_address).eq(residence_address.to_lowercase())));
+            let key = data_key()?;
+            filterer = Box::new(
+                filterer.and(
+                    dsl::residence_address_bidx
+                        .eq(key.blind_index(residence_address))
+                        .or(lower(dsl::residence_address).eq(residence_address.to_lowercase())),
+                ),
+            );
         }
 
         if let Some(come_from) = come_from {
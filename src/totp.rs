@@ -0,0 +1,157 @@
+//! Implementasi TOTP (RFC 6238) untuk autentikasi dua langkah,
+//! kompatibel dengan aplikasi authenticator pada umumnya (SHA-1, 6 digit, periode 30 detik).
+
+use crate::{crypto, token};
+
+/// Periode satu kode dalam detik.
+pub const PERIOD: u64 = 30;
+/// Jumlah digit kode.
+pub const DIGITS: u32 = 6;
+/// Jumlah periode sebelum/sesudah yang masih diterima untuk toleransi jam perangkat.
+const SKEW: i64 = 1;
+/// Ukuran secret dalam byte.
+const SECRET_SIZE: usize = 20;
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Generate secret baru dalam format base32.
+pub fn generate_secret() -> String {
+    base32_encode(&token::rand_bytes(SECRET_SIZE))
+}
+
+/// Encode bytes ke base32 (RFC 4648) tanpa padding.
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut rv = String::with_capacity((data.len() * 8 + 4) / 5);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for b in data {
+        buffer = (buffer << 8) | u32::from(*b);
+        bits += 8;
+        while bits >= 5 {
+            rv.push(BASE32_ALPHABET[((buffer >> (bits - 5)) & 0x1f) as usize] as char);
+            bits -= 5;
+        }
+    }
+    if bits > 0 {
+        rv.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    rv
+}
+
+/// Decode base32, spasi dan padding diabaikan, tidak case sensitive.
+pub fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut rv = Vec::with_capacity(text.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let c = c.to_ascii_uppercase() as u8;
+        let val = BASE32_ALPHABET.iter().position(|a| *a == c)? as u32;
+        buffer = (buffer << 5) | val;
+        bits += 5;
+        if bits >= 8 {
+            rv.push((buffer >> (bits - 8)) as u8);
+            bits -= 8;
+        }
+    }
+    Some(rv)
+}
+
+/// Nomor periode (time step) untuk waktu UNIX dalam detik.
+pub fn time_step(unix_secs: u64) -> i64 {
+    (unix_secs / PERIOD) as i64
+}
+
+/// Hitung kode HOTP untuk periode tertentu.
+pub fn code_at(secret: &[u8], step: i64) -> u32 {
+    let hash = crypto::hmac_sha1(secret, &(step as u64).to_be_bytes());
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let bin = (u32::from(hash[offset]) & 0x7f) << 24
+        | u32::from(hash[offset + 1]) << 16
+        | u32::from(hash[offset + 2]) << 8
+        | u32::from(hash[offset + 3]);
+    bin % 10u32.pow(DIGITS)
+}
+
+/// Verifikasi kode TOTP, mengembalikan nomor periode yang cocok.
+///
+/// Kode dari periode yang sama atau sebelum `last_step` ditolak
+/// agar kode yang sama tidak bisa digunakan dua kali.
+pub fn verify(secret_b32: &str, code: &str, unix_secs: u64, last_step: i64) -> Option<i64> {
+    let code = code.trim().replace(' ', "");
+    if code.len() != DIGITS as usize {
+        return None;
+    }
+    let code = code.parse::<u32>().ok()?;
+    let secret = base32_decode(secret_b32)?;
+    let now = time_step(unix_secs);
+
+    (now - SKEW..=now + SKEW)
+        .filter(|step| *step > last_step)
+        .find(|step| code_at(&secret, *step) == code)
+}
+
+/// Buat URI `otpauth://` untuk dijadikan QR code di aplikasi authenticator.
+pub fn provisioning_uri(secret_b32: &str, account: &str, issuer: &str) -> String {
+    let encode = |a: &str| {
+        a.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'@' => {
+                    (b as char).to_string()
+                }
+                _ => format!("%{:02X}", b),
+            })
+            .collect::<String>()
+    };
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        encode(issuer),
+        encode(account),
+        secret_b32,
+        encode(issuer),
+        DIGITS,
+        PERIOD
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base32() {
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("MZXW6YTBOI======"), Some(b"foobar".to_vec()));
+        assert_eq!(base32_decode("mzxw 6ytb oi"), Some(b"foobar".to_vec()));
+        assert_eq!(base32_decode("MZXW1"), None);
+
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(base32_decode(&secret).unwrap().len(), SECRET_SIZE);
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let secret = b"12345678901234567890";
+        assert_eq!(code_at(secret, time_step(59)), 287_082);
+        assert_eq!(code_at(secret, time_step(1_111_111_109)), 81_804);
+        assert_eq!(code_at(secret, time_step(1_234_567_890)), 5_924);
+        assert_eq!(code_at(secret, time_step(2_000_000_000)), 279_037);
+    }
+
+    #[test]
+    fn test_verify() {
+        let secret = base32_encode(b"12345678901234567890");
+        let now = 1_111_111_109;
+        let step = time_step(now);
+
+        assert_eq!(verify(&secret, "081804", now, 0), Some(step));
+        assert_eq!(verify(&secret, "081804", now + PERIOD, 0), Some(step));
+        assert_eq!(verify(&secret, "081804", now + PERIOD * 3, 0), None);
+        // kode yang sama tidak bisa digunakan lagi
+        assert_eq!(verify(&secret, "081804", now, step), None);
+        assert_eq!(verify(&secret, "81804", now, 0), None);
+        assert_eq!(verify(&secret, "abcdef", now, 0), None);
+    }
+}
@@ -0,0 +1,251 @@
+//! Request tracing & format log.
+//!
+//! Setiap request API mendapatkan request id (correlation id), diambil dari header
+//! `X-Request-Id` apabila dikirim oleh client/proxy atau dibuat baru, dan dikembalikan
+//! di header response yang sama. Request id beserta path endpoint & pelaku (user/admin)
+//! disimpan sebagai [Context] per thread sehingga ikut tercatat di setiap log handler,
+//! event di `eventstream` & push notif yang dipicu oleh request tersebut.
+//!
+//! Log bisa ditulis dalam format text atau JSON (satu object per baris),
+//! lihat konfigurasi `log.format`, level log tetap diatur via env `RUST_LOG`.
+
+use chrono::Utc;
+use env_logger::fmt::Formatter;
+use log::Record;
+use serde_json::Value;
+
+use crate::{
+    config::{LogConfig, LogFormat},
+    ID,
+};
+
+use std::{
+    cell::RefCell,
+    fmt,
+    io::{self, Write},
+};
+
+/// Nama header untuk request id.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Panjang maksimal request id yang diterima dari client.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Pelaku request, diset setelah access token divalidasi.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Actor {
+    /// Jenis pelaku, `user` atau `admin`.
+    pub kind: &'static str,
+    /// ID user/admin.
+    pub id: ID,
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.kind, self.id)
+    }
+}
+
+/// Trace context dari request yang sedang diproses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Context {
+    /// Request id.
+    pub request_id: String,
+    /// Path endpoint, eg: `/api/user/v1/me/info`.
+    pub endpoint: String,
+    /// Pelaku request, `None` apabila tanpa access token.
+    pub actor: Option<Actor>,
+}
+
+impl Context {
+    /// Buat context baru untuk request ke `endpoint`.
+    pub fn new(request_id: String, endpoint: &str) -> Context {
+        Context {
+            request_id,
+            endpoint: endpoint.to_string(),
+            actor: None,
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Context>> = RefCell::new(None);
+}
+
+/// Buat request id baru.
+pub fn generate_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// Cek request id dari client, hanya boleh berisi alfanumerik, `-`, `_` dan `.`
+/// agar aman ditulis ke log & header response.
+pub fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Trace context di thread ini.
+pub fn current() -> Option<Context> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Request id di thread ini.
+pub fn request_id() -> Option<String> {
+    current().map(|context| context.request_id)
+}
+
+/// Jalankan `f` dengan trace context `context`, context sebelumnya dikembalikan
+/// setelah `f` selesai (termasuk apabila panic).
+pub fn scope<F, R>(context: Option<Context>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    struct Restore(Option<Context>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            CURRENT.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(CURRENT.with(|current| current.replace(context)));
+    f()
+}
+
+/// Set pelaku request di trace context thread ini.
+pub fn set_actor(kind: &'static str, id: ID) {
+    CURRENT.with(|current| {
+        if let Some(context) = current.borrow_mut().as_mut() {
+            context.actor = Some(Actor { kind, id });
+        }
+    });
+}
+
+/// Pasang logger sesuai konfigurasi, dipanggil sekali ketika startup.
+pub fn init_logger(config: &LogConfig) {
+    let format = config.format;
+    let result = env_logger::Builder::from_default_env()
+        .format(move |buf, record| match format {
+            LogFormat::Text => write_text(buf, record),
+            LogFormat::Json => write_json(buf, record),
+        })
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("cannot init logger: {}", e);
+    }
+}
+
+fn write_text(buf: &mut Formatter, record: &Record<'_>) -> io::Result<()> {
+    let ts = Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+    match current() {
+        Some(Context {
+            request_id,
+            actor: Some(actor),
+            ..
+        }) => writeln!(
+            buf,
+            "[{} {:<5} {}] [{} {}] {}",
+            ts,
+            record.level(),
+            record.target(),
+            request_id,
+            actor,
+            record.args()
+        ),
+        Some(context) => writeln!(
+            buf,
+            "[{} {:<5} {}] [{}] {}",
+            ts,
+            record.level(),
+            record.target(),
+            context.request_id,
+            record.args()
+        ),
+        None => writeln!(
+            buf,
+            "[{} {:<5} {}] {}",
+            ts,
+            record.level(),
+            record.target(),
+            record.args()
+        ),
+    }
+}
+
+fn write_json(buf: &mut Formatter, record: &Record<'_>) -> io::Result<()> {
+    serde_json::to_writer(&mut *buf, &json_entry(record, current()))?;
+    writeln!(buf)
+}
+
+fn json_entry(record: &Record<'_>, context: Option<Context>) -> Value {
+    let mut entry = json!({
+        "ts": Utc::now().to_rfc3339(),
+        "level": record.level().to_string(),
+        "target": record.target(),
+        "msg": record.args().to_string(),
+    });
+    if let Some(context) = context {
+        entry["request_id"] = json!(context.request_id);
+        entry["endpoint"] = json!(context.endpoint);
+        if let Some(actor) = context.actor {
+            entry["actor_type"] = json!(actor.kind);
+            entry["actor_id"] = json!(actor.id);
+        }
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_id_validation() {
+        assert!(is_valid_id(&generate_id()));
+        assert!(is_valid_id("5f2b-1a.c_9"));
+        assert!(!is_valid_id(""));
+        assert!(!is_valid_id("abc def"));
+        assert!(!is_valid_id("abc\r\nX-Evil: 1"));
+        assert!(!is_valid_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+
+    #[test]
+    fn test_scope() {
+        let context = Context::new("abc".to_string(), "/api/user/v1/me/info");
+        assert_eq!(request_id(), None);
+        scope(Some(context.clone()), || {
+            set_actor("user", 12);
+            assert_eq!(request_id(), Some("abc".to_string()));
+            scope(None, || assert_eq!(current(), None));
+            assert_eq!(
+                current().and_then(|c| c.actor),
+                Some(Actor { kind: "user", id: 12 })
+            );
+        });
+        assert_eq!(current(), None);
+    }
+
+    #[test]
+    fn test_json_entry() {
+        let mut context = Context::new("abc".to_string(), "/api/feed/v1/query");
+        context.actor = Some(Actor { kind: "admin", id: 3 });
+        let entry = json_entry(
+            &Record::builder()
+                .args(format_args!("cannot create feed. {}", "error"))
+                .level(log::Level::Error)
+                .target("pandemia::event_handler")
+                .build(),
+            Some(context),
+        );
+        assert_eq!(entry["level"], "ERROR");
+        assert_eq!(entry["msg"], "cannot create feed. error");
+        assert_eq!(entry["request_id"], "abc");
+        assert_eq!(entry["endpoint"], "/api/feed/v1/query");
+        assert_eq!(entry["actor_type"], "admin");
+        assert_eq!(entry["actor_id"], 3);
+    }
+}
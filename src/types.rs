@@ -19,7 +19,7 @@ pub enum HealthyKind {
 }
 
 /// Notification kind or types
-#[derive(Serialize, Copy, Clone)]
+#[derive(Serialize, Copy, Clone, ApiSchema)]
 pub enum NotifKind {
     /// Merupakan notif yang memberikan informasi pengumuman secara global,
     /// notif ini tidak ada creatornya atau creatornya adalah system.
@@ -39,7 +39,7 @@ pub enum NotifKind {
 }
 
 /// Status sub reports
-#[derive(Serialize, Copy, Clone, PartialEq, Debug)]
+#[derive(Serialize, Copy, Clone, PartialEq, Debug, ApiSchema)]
 pub enum SubReportStatus {
     /// Orang Dalam Pemantauan
     ODP = 0,
@@ -198,6 +198,9 @@ pub enum LocKind {
     /// Code for District
     District = 5,
 
+    /// Code for Village
+    Village = 6,
+
     /// Unknown
     Unknown = 10,
 }
@@ -212,6 +215,7 @@ impl From<i16> for LocKind {
             3 => Province,
             4 => City,
             5 => District,
+            6 => Village,
             x => {
                 error!("Unknown loc kind code: {}", x);
                 Unknown
@@ -221,7 +225,7 @@ impl From<i16> for LocKind {
 }
 
 /// Entries result type
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ApiSchema)]
 pub struct EntriesResult<T> {
     /// list of entries
     pub entries: Vec<T>,
@@ -6,8 +6,10 @@ use diesel::prelude::*;
 use diesel::sql_types;
 
 use crate::{
-    crypto::{self, PublicKey, SecretKey},
+    boundary_dao::BoundaryDao,
+    crypto::{self, PasshashScheme, PublicKey, SecretKey},
     error::Error as PandemiaError,
+    kvstore::KvStore,
     models::*,
     result::Result,
     schema::*,
@@ -60,6 +62,14 @@ pub struct NewUserKey {
     pub pub_key: String,
     pub secret_key: String,
     pub active: bool,
+    pub client_key: bool,
+}
+
+#[derive(Insertable)]
+#[table_name = "request_nonces"]
+struct NewRequestNonce<'a> {
+    pub user_id: ID,
+    pub nonce: &'a str,
 }
 
 #[doc(hidden)]
@@ -127,14 +137,14 @@ impl<'a> UserDao<'a> {
             .map_err(From::from)
     }
 
-    /// Setting user's password
-    pub fn set_password(&self, user_id: ID, password: &str) -> Result<()> {
+    /// Setting user's password, di-hash menggunakan skema `scheme`.
+    pub fn set_password(&self, user_id: ID, password: &str, scheme: PasshashScheme) -> Result<()> {
         use crate::schema::user_passhash::dsl;
 
         let _ = self.get_by_id(user_id)?;
 
         self.db.build_transaction().read_write().run(|| {
-            let passhash = &crate::crypto::get_passhash(password);
+            let passhash = &scheme.hash(password);
 
             // dipresiasi password lama
             diesel::update(
@@ -150,7 +160,7 @@ impl<'a> UserDao<'a> {
                     user_id,
                     passhash,
                     deprecated: false,
-                    ver: 1,
+                    ver: scheme.ver(),
                 })
                 .execute(self.db)?;
             // .map_err(From::from)?;
@@ -259,6 +269,7 @@ impl<'a> UserDao<'a> {
                     pub_key: pub_key.to_hex(),
                     secret_key: secret_key.to_hex(),
                     active: true,
+                    client_key: false,
                 })
                 .execute(self.db)?;
 
@@ -275,11 +286,104 @@ impl<'a> UserDao<'a> {
         use crate::schema::users;
 
         ak_dsl::user_keys
-            .filter(ak_dsl::user_id.eq(user_id))
+            .filter(ak_dsl::user_id.eq(user_id).and(ak_dsl::client_key.eq(false)))
             .first(self.db)
             .map_err(From::from)
     }
 
+    /// Daftarkan public key milik client untuk signed request,
+    /// secret key-nya hanya disimpan di perangkat client.
+    pub fn register_client_key(&self, user_id: ID, pub_key: &PublicKey) -> Result<UserKey> {
+        use crate::schema::user_keys::{self, dsl};
+
+        if self.get_client_key(user_id, &pub_key.to_hex())?.is_some() {
+            return Err(PandemiaError::AlreadyExists);
+        }
+
+        diesel::insert_into(user_keys::table)
+            .values(&NewUserKey {
+                user_id,
+                pub_key: pub_key.to_hex(),
+                secret_key: "".to_string(),
+                active: true,
+                client_key: true,
+            })
+            .get_result(self.db)
+            .map_err(From::from)
+    }
+
+    /// Mendapatkan client key yang masih aktif.
+    pub fn get_client_key(&self, user_id: ID, pub_key: &str) -> Result<Option<UserKey>> {
+        use crate::schema::user_keys::dsl;
+
+        dsl::user_keys
+            .filter(
+                dsl::user_id
+                    .eq(user_id)
+                    .and(dsl::pub_key.eq(pub_key))
+                    .and(dsl::client_key.eq(true))
+                    .and(dsl::active.eq(true)),
+            )
+            .first(self.db)
+            .optional()
+            .map_err(From::from)
+    }
+
+    /// Check apakah user telah mendaftarkan client key,
+    /// apabila iya maka request ke endpoint yang ditandai `signed` wajib ditandatangani.
+    pub fn has_client_key(&self, user_id: ID) -> Result<bool> {
+        use crate::schema::user_keys::dsl;
+
+        dsl::user_keys
+            .filter(
+                dsl::user_id
+                    .eq(user_id)
+                    .and(dsl::client_key.eq(true))
+                    .and(dsl::active.eq(true)),
+            )
+            .select(diesel::dsl::count(dsl::id))
+            .first::<i64>(self.db)
+            .map(|a| a > 0)
+            .map_err(From::from)
+    }
+
+    /// Nonaktifkan client key.
+    pub fn revoke_client_key(&self, user_id: ID, pub_key: &str) -> Result<()> {
+        use crate::schema::user_keys::dsl;
+
+        let updated = diesel::update(
+            dsl::user_keys.filter(
+                dsl::user_id
+                    .eq(user_id)
+                    .and(dsl::pub_key.eq(pub_key))
+                    .and(dsl::client_key.eq(true)),
+            ),
+        )
+        .set(dsl::active.eq(false))
+        .execute(self.db)?;
+
+        if updated == 0 {
+            return Err(PandemiaError::NotFound("Key not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Catat nonce dari signed request, mengembalikan `false` apabila nonce sudah pernah digunakan.
+    /// Nonce milik user yang lebih lama dari `expired_before` sekaligus dihapus.
+    pub fn use_request_nonce(&self, user_id: ID, nonce: &str, expired_before: NaiveDateTime) -> Result<bool> {
+        use crate::schema::request_nonces::{self, dsl};
+
+        diesel::delete(dsl::request_nonces.filter(dsl::user_id.eq(user_id).and(dsl::ts.lt(expired_before))))
+            .execute(self.db)?;
+
+        diesel::insert_into(request_nonces::table)
+            .values(&NewRequestNonce { user_id, nonce })
+            .on_conflict_do_nothing()
+            .execute(self.db)
+            .map(|a| a == 1)
+            .map_err(From::from)
+    }
+
     /// Buat akun baru secara langsung.
     pub fn create_user(
         &self,
@@ -303,6 +407,7 @@ impl<'a> UserDao<'a> {
                         pub_key: keypair.0.to_hex(),
                         secret_key: keypair.1.to_hex(),
                         active: true,
+                        client_key: false,
                     })
                     .execute(self.db)?;
 
@@ -324,6 +429,93 @@ impl<'a> UserDao<'a> {
         })
     }
 
+    /// Upgrade akun dengan nomor telepon yang telah diverifikasi menggunakan OTP.
+    pub fn set_verified_phone(&self, user: &User, phone_num: &str, full_name: Option<&str>) -> Result<User> {
+        use crate::schema::users::dsl;
+
+        let mut meta = user.meta.clone();
+        if !user.is_phone_verified() {
+            meta.push(":phone_verified:".to_string());
+        }
+
+        diesel::update(dsl::users.filter(dsl::id.eq(user.id)))
+            .set((
+                dsl::phone_num.eq(phone_num),
+                dsl::full_name.eq(full_name.unwrap_or(&user.full_name)),
+                dsl::meta.eq(&meta),
+            ))
+            .get_result(self.db)
+            .map_err(From::from)
+    }
+
+    /// Gabungkan akun device `source` ke akun `target`.
+    ///
+    /// Semua device (user connect), access token, client key dan settings (termasuk data gejala)
+    /// dipindahkan ke akun target, setting yang ada di kedua akun menggunakan yang terbaru.
+    /// Akun source kemudian ditandai terhapus.
+    pub fn merge_into(&self, source: &User, target: &User) -> Result<()> {
+        use crate::schema::{access_tokens, user_connect, user_keys, user_settings, users};
+
+        if source.id == target.id {
+            return Err(param_err!("Cannot merge account into itself"));
+        }
+
+        self.db.build_transaction().read_write().run(|| {
+            let device_ids: Vec<String> = user_connect::table
+                .filter(user_connect::user_id.eq(source.id))
+                .select(user_connect::device_id)
+                .load(self.db)?;
+
+            diesel::update(user_connect::table.filter(user_connect::user_id.eq(source.id)))
+                .set(user_connect::user_id.eq(target.id))
+                .execute(self.db)?;
+
+            // agar otorisasi ulang dari device tersebut menggunakan akun target
+            let device_value = format!("{}|{}", target.id, target.full_name);
+            for device_id in &device_ids {
+                let key = format!("user-device.{}", device_id);
+                KvStore::new(self.db).set(&key, &device_value)?;
+            }
+
+            diesel::update(access_tokens::table.filter(access_tokens::user_id.eq(source.id)))
+                .set(access_tokens::user_id.eq(target.id))
+                .execute(self.db)?;
+
+            diesel::update(
+                user_keys::table.filter(
+                    user_keys::user_id
+                        .eq(source.id)
+                        .and(user_keys::client_key.eq(true)),
+                ),
+            )
+            .set(user_keys::user_id.eq(target.id))
+            .execute(self.db)?;
+
+            let target_settings = target.get_settings(self.db)?;
+            for setting in source.get_settings(self.db)? {
+                let newer = target_settings
+                    .iter()
+                    .find(|a| a.s_key == setting.s_key)
+                    .map(|a| a.ts < setting.ts)
+                    .unwrap_or(true);
+                if newer {
+                    target.set_setting(&setting.s_key, &setting.s_value, self.db)?;
+                }
+            }
+            diesel::delete(user_settings::table.filter(user_settings::user_id.eq(source.id)))
+                .execute(self.db)?;
+
+            let mut meta = source.meta.clone();
+            meta.push(":deleted:".to_string());
+            meta.push(format!("merged_into={}", target.id));
+            diesel::update(users::table.filter(users::id.eq(source.id)))
+                .set(users::meta.eq(&meta))
+                .execute(self.db)?;
+
+            Ok(())
+        })
+    }
+
     /// Clean up registered user by token
     pub fn cleanup_registered_user(&self, token: &str) -> Result<usize> {
         use crate::schema::register_users::dsl;
@@ -461,7 +653,8 @@ impl<'a> UserDao<'a> {
         Ok(())
     }
 
-    /// Update user location by device_id
+    /// Update user location by device_id,
+    /// ID wilayah hasil resolve sebelumnya dihapus karena lokasi hanya berupa nama.
     pub fn update_user_location(
         &self,
         user: &User,
@@ -478,6 +671,10 @@ impl<'a> UserDao<'a> {
                     .set((
                         dsl::latest_loc.eq(latest_loc),
                         dsl::latest_loc_full.eq(latest_loc_full),
+                        dsl::latest_loc_ts.eq(Utc::now().naive_utc()),
+                        dsl::latest_city_id.eq(None::<ID>),
+                        dsl::latest_district_id.eq(None::<ID>),
+                        dsl::latest_village_id.eq(None::<ID>),
                     ))
                     .execute(self.db)?;
 
@@ -496,6 +693,54 @@ impl<'a> UserDao<'a> {
         Ok(())
     }
 
+    /// Update user location berdasarkan koordinat, nama lokasi di-resolve dari
+    /// batas wilayah (boundaries), apabila koordinat tidak berada di wilayah manapun
+    /// maka menggunakan nama lokasi yang dikirim oleh client.
+    ///
+    /// ID kota, kecamatan & desa hasil resolve disimpan di `user_connect`
+    /// untuk target push notif.
+    pub fn update_user_location_ll(
+        &self,
+        user: &User,
+        device_id: &str,
+        latitude: f64,
+        longitude: f64,
+        latest_loc: &str,
+        latest_loc_full: &str,
+    ) -> Result<()> {
+        use crate::schema::user_connect::{self, dsl};
+
+        let area = BoundaryDao::new(self.db)
+            .resolve(latitude, longitude)
+            .unwrap_or_else(|e| {
+                error!("Cannot resolve boundary for {},{}. {}", latitude, longitude, e);
+                Default::default()
+            });
+
+        self.db
+            .build_transaction()
+            .read_write()
+            .run::<_, crate::error::Error, _>(|| {
+                if area.is_empty() {
+                    self.update_user_location(user, device_id, latest_loc, latest_loc_full)?;
+                } else {
+                    self.update_user_location(user, device_id, area.loc_name(), &area.loc_path())?;
+                }
+
+                diesel::update(dsl::user_connect.filter(dsl::device_id.eq(device_id)))
+                    .set((
+                        dsl::latest_loc_lat.eq(latitude),
+                        dsl::latest_loc_long.eq(longitude),
+                        dsl::latest_city_id.eq(area.city.as_ref().map(|a| a.id)),
+                        dsl::latest_district_id.eq(area.district.as_ref().map(|a| a.id)),
+                        dsl::latest_village_id.eq(area.village.as_ref().map(|a| a.id)),
+                    ))
+                    .execute(self.db)?;
+
+                Ok(())
+            })
+    }
+
     /// Remove user connect app id untuk spesifik user.
     pub fn remove_user_connect(&self, device_id: &str, provider_name: &str, app_id: &str) -> Result<()> {
         use crate::schema::user_connect::dsl;
@@ -4,8 +4,9 @@
 use chrono::{NaiveDateTime, Utc};
 use rand::{self, distributions::Alphanumeric, Rng};
 use std::{
+    cmp::Ordering,
     thread,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 /// Mendapatkan waktu saat ini dalam format milidetik sejak UNIX EPOCH.
@@ -53,6 +54,19 @@ pub fn sleep(millis: u64) {
     thread::sleep(Duration::from_millis(millis));
 }
 
+/// Tunggu sampai `done` bernilai `true`, dicek setiap 100ms.
+/// Mengembalikan `false` apabila sampai `timeout` masih belum terpenuhi.
+pub fn wait_until<F: Fn() -> bool>(timeout: Duration, done: F) -> bool {
+    let start = Instant::now();
+    while !done() {
+        if start.elapsed() > timeout {
+            return false;
+        }
+        sleep(100);
+    }
+    true
+}
+
 /// Convert any case to Title Case
 #[inline(always)]
 pub fn title_case(s: &str) -> String {
@@ -69,6 +83,33 @@ pub fn title_case(s: &str) -> String {
         .join(" ")
 }
 
+/// Parse versi aplikasi, eg: `1.2.3`, `v1.2`, `1.2.3+45` menjadi komponen angka.
+/// Suffix pre-release/build (`-beta`, `+45`) diabaikan dan `1.2` dianggap sama dengan `1.2.0`.
+pub fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches(|c| c == 'v' || c == 'V');
+    let core = version.split(|c| c == '-' || c == '+').next().unwrap_or("");
+
+    if core.is_empty() {
+        return None;
+    }
+
+    let mut parts = core
+        .split('.')
+        .map(|a| a.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+
+    while parts.len() > 1 && parts.last() == Some(&0) {
+        parts.pop();
+    }
+
+    Some(parts)
+}
+
+/// Bandingkan dua versi aplikasi, `None` apabila salah satu versi tidak valid.
+pub fn compare_version(a: &str, b: &str) -> Option<Ordering> {
+    Some(parse_version(a)?.cmp(&parse_version(b)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,4 +123,15 @@ mod tests {
             "Daerah Istimewa Yogyakarta"
         );
     }
+
+    #[test]
+    fn test_compare_version() {
+        assert_eq!(compare_version("1.2.0", "1.10.0"), Some(Ordering::Less));
+        assert_eq!(compare_version("v1.2", "1.2.0"), Some(Ordering::Equal));
+        assert_eq!(compare_version("1.3.0+45", "1.2.9"), Some(Ordering::Greater));
+        assert_eq!(compare_version("2.0.0-beta", "2.0.0"), Some(Ordering::Equal));
+        assert_eq!(compare_version("unknown", "1.0.0"), None);
+        assert_eq!(parse_version("1.0.1"), Some(vec![1, 0, 1]));
+        assert_eq!(parse_version(""), None);
+    }
 }
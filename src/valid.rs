@@ -1,6 +1,9 @@
-use chrono::Utc;
+use chrono::{Duration, NaiveDateTime, Utc};
 
-use crate::models::{AccessToken, AdminAccessToken};
+use crate::{
+    auth::SESSION_IDLE_DAYS,
+    models::{AccessToken, AdminAccessToken},
+};
 
 /// Trait untuk memastikan apakah suatu object
 /// bisa divalidasi atau tidak.
@@ -23,49 +26,103 @@ pub trait Expirable {
     fn expired(&self) -> bool;
 }
 
+/// Trait untuk sesi yang bisa diperbarui menggunakan refresh token.
+pub trait Refreshable {
+    /// Periksa apakah refresh token sudah expired atau sesi sudah terlalu lama tidak digunakan.
+    fn refresh_expired(&self) -> bool;
+}
+
+fn idle(last_used: NaiveDateTime) -> bool {
+    Utc::now().naive_utc() - last_used > Duration::days(SESSION_IDLE_DAYS)
+}
+
 impl Expirable for AccessToken {
     fn expired(&self) -> bool {
         let now = Utc::now().naive_utc();
-        now > self.valid_thru
+        now > self.valid_thru || idle(self.last_used)
     }
 }
 
 impl Expirable for AdminAccessToken {
     fn expired(&self) -> bool {
         let now = Utc::now().naive_utc();
-        now > self.valid_thru
+        now > self.valid_thru || idle(self.last_used)
+    }
+}
+
+impl Refreshable for AccessToken {
+    fn refresh_expired(&self) -> bool {
+        let now = Utc::now().naive_utc();
+        now > self.refresh_valid_thru || idle(self.last_used)
+    }
+}
+
+impl Refreshable for AdminAccessToken {
+    fn refresh_expired(&self) -> bool {
+        let now = Utc::now().naive_utc();
+        now > self.refresh_valid_thru || idle(self.last_used)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Expirable, Validable};
+    use super::{Expirable, Refreshable, Validable};
     use crate::models::AccessToken;
-    use chrono::{Duration, Utc};
+    use chrono::{Duration, NaiveDateTime, Utc};
     use std::{ops::Add, thread::sleep, time};
 
-    #[test]
-    fn test_access_token_valid() {
-        let access_token = AccessToken {
+    fn new_access_token(valid_thru: NaiveDateTime, last_used: NaiveDateTime) -> AccessToken {
+        AccessToken {
             token: "".to_owned(),
             user_id: 1,
             created: Utc::now().naive_utc(),
-            valid_thru: Utc::now().naive_utc().add(Duration::days(1)),
-        };
+            valid_thru,
+            id: 1,
+            device_id: "".to_owned(),
+            name: "".to_owned(),
+            platform: "".to_owned(),
+            ip_address: "".to_owned(),
+            last_used,
+            refresh_token: "".to_owned(),
+            refresh_valid_thru: Utc::now().naive_utc().add(Duration::days(60)),
+        }
+    }
+
+    #[test]
+    fn test_access_token_valid() {
+        let access_token = new_access_token(
+            Utc::now().naive_utc().add(Duration::days(1)),
+            Utc::now().naive_utc(),
+        );
         sleep(time::Duration::from_millis(1000));
         assert!(access_token.valid());
     }
 
     #[test]
     fn test_access_token_expire() {
-        let access_token = AccessToken {
-            token: "".to_owned(),
-            user_id: 1,
-            created: Utc::now().naive_utc(),
-            valid_thru: Utc::now().naive_utc().add(Duration::milliseconds(50)),
-        };
+        let access_token = new_access_token(
+            Utc::now().naive_utc().add(Duration::milliseconds(50)),
+            Utc::now().naive_utc(),
+        );
         sleep(time::Duration::from_millis(1000));
         assert!(!access_token.valid());
         assert!(access_token.expired());
     }
+
+    #[test]
+    fn test_access_token_idle_expire() {
+        let access_token = new_access_token(
+            Utc::now().naive_utc().add(Duration::days(1)),
+            Utc::now().naive_utc().add(Duration::days(-31)),
+        );
+        assert!(access_token.expired());
+        assert!(access_token.refresh_expired());
+
+        let access_token = new_access_token(
+            Utc::now().naive_utc().add(Duration::days(-1)),
+            Utc::now().naive_utc(),
+        );
+        assert!(access_token.expired());
+        assert!(!access_token.refresh_expired());
+    }
 }
@@ -88,6 +88,27 @@ impl<'a> VillageDao<'a> {
             .map_err(From::from)
     }
 
+    /// Mendapatkan village berdasarkan kode wilayah resmi (Kemendagri).
+    pub fn get_by_code(&self, code: &str) -> Result<Option<Village>> {
+        use crate::schema::villages::{self, dsl};
+
+        dsl::villages
+            .filter(dsl::code.eq(code))
+            .first(self.db)
+            .optional()
+            .map_err(From::from)
+    }
+
+    /// Set kode wilayah resmi untuk village.
+    pub fn set_code(&self, id: ID, code: &str) -> Result<()> {
+        use crate::schema::villages::{self, dsl};
+
+        diesel::update(dsl::villages.filter(dsl::id.eq(id)))
+            .set(dsl::code.eq(code))
+            .execute(self.db)?;
+        Ok(())
+    }
+
     /// Search for specific villages
     pub fn search(
         &self,
@@ -46,7 +46,8 @@ impl TestHelper {
         }
     }
 
-    fn get_db<'a>() -> MutexGuard<'a, PgConnection> {
+    /// Koneksi DB test, untuk test yang langsung menggunakan DAO.
+    pub fn get_db<'a>() -> MutexGuard<'a, PgConnection> {
         lazy_static! {
             static ref PG_CONN_FOR_TEST: Arc<Mutex<PgConnection>> = Arc::new(Mutex::new(
                 PgConnection::establish(
@@ -92,9 +93,10 @@ impl TestHelper {
         format!("{}@{}.com", util::random_string(10), util::random_string(5)).to_lowercase()
     }
 
+    /// Nomor telepon seluler acak yang lolos validasi `phone`.
     pub fn generate_phone_num(&self) -> String {
-        let nums: String = (0..10).map(|_| util::random_number().to_string()).collect();
-        format!("+628{}", nums)
+        let nums: String = (0..8).map(|_| util::random_number().to_string()).collect();
+        format!("+62812{}", nums)
     }
 
     /// Menggenerasikan beberapa akun sekaligus,
@@ -118,6 +120,24 @@ impl TestHelper {
         rv
     }
 
+    /// Menggenerasikan akun device seperti hasil `/auth/v1/device/authorize`,
+    /// ini tidak via rest API, tapi langsung ke database.
+    pub fn generate_device_user(&self) -> types::User {
+        let db = Self::get_db();
+        let gen_name = format!("gen__{}", util::random_string(20));
+        let new_user = NewUser {
+            full_name: &gen_name,
+            email: &format!("{}@pandemia.net", gen_name),
+            phone_num: &self.generate_phone_num(),
+            active: true,
+            register_time: util::now(),
+        };
+        let (user, _) = UserDao::new(&db)
+            .create_user(&new_user, None)
+            .expect("cannot create device user");
+        user.into()
+    }
+
     /// Menghapus akun berdasarkan ID.
     pub fn cleanup_user_by_id(&self, user_id: ID) {
         let db = Self::get_db();
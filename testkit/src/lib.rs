@@ -2,6 +2,8 @@
 
 extern crate actix_web;
 extern crate pandemia;
+extern crate pandemia_client;
+extern crate pandemia_types;
 extern crate reqwest;
 #[macro_use]
 extern crate log;
@@ -28,9 +30,12 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{env, fmt};
 
 use pandemia::{
-    api::{self, ApiAccess, ApiAggregator},
+    api::{self, ApiAggregator},
+    config::{self, Config},
     service, ID,
 };
+use pandemia_client::ApiClient;
+use pandemia_types::ApiAccess;
 
 pub use pandemia::api::{types::User, types::*};
 
@@ -109,6 +114,7 @@ pub struct TestKitApi {
     // test_server: TestServer,
     test_client: Client,
     test_server_url: String,
+    access_token: Option<String>,
 }
 
 impl TestKitApi {
@@ -117,6 +123,21 @@ impl TestKitApi {
             testkit: testkit.clone(),
             test_client: Client::new(),
             test_server_url,
+            access_token: None,
+        }
+    }
+
+    /// Typed client untuk test server, method-nya digenerasikan dari endpoint-endpoint
+    /// `api_group` (lihat `pandemia::api::client::TypedClient`),
+    /// eg: `api.client().public().system().info(&())`. Access token dari `authorize` ikut disertakan.
+    pub fn client(&self) -> ApiClient {
+        let client = ApiClient::new(
+            &format!("{}public", self.test_server_url),
+            &format!("{}private", self.test_server_url),
+        );
+        match self.access_token {
+            Some(ref token) => client.with_access_token(token),
+            None => client,
         }
     }
 
@@ -155,6 +176,7 @@ impl TestKitApi {
             .default_headers(headers)
             .build()
             .expect("Cannot build http client");
+        self.access_token = Some(token.token);
     }
 
     /// Cara pintas untuk meng-otorisasi User,
@@ -172,6 +194,7 @@ impl TestKitApi {
             .default_headers(headers)
             .build()
             .expect("Cannot build http client");
+        self.access_token = Some(token.token);
     }
 
     /// Assert json result from API,
@@ -338,12 +361,15 @@ where
     }
 }
 
-pub fn setup() {
-    let _ = env_logger::try_init();
-    env::set_var(
-        "DATABASE_URL",
-        env::var("DATABASE_TEST_URL").expect("No DATABASE_TEST_URL"),
-    );
+/// Konfigurasi untuk test, sama dengan konfigurasi dari env
+/// tetapi database menggunakan `DATABASE_TEST_URL`.
+pub fn test_config() -> Config {
+    let mut config = Config::default();
+    config
+        .apply_vars(|key| env::var(key).ok())
+        .expect("Invalid configuration");
+    config.database.url = env::var("DATABASE_TEST_URL").expect("No DATABASE_TEST_URL");
+    config
 }
 
 use std::sync::{mpsc::channel, Arc, Mutex};
@@ -351,10 +377,21 @@ use std::{thread, time::Duration};
 
 lazy_static! {
     static ref SERVER_URL: Arc<Mutex<String>> = Arc::new(Mutex::new("".to_string()));
+    /// Konfigurasi test, komponen global di-inisialisasi sekali untuk semua test.
+    static ref CONFIG: Arc<Config> = {
+        let config = config::init(test_config());
+        pandemia::init(&config);
+        config
+    };
+}
+
+pub fn setup() -> Arc<Config> {
+    let _ = env_logger::try_init();
+    CONFIG.clone()
 }
 
 pub fn create_test_server() {
-    setup();
+    let config = setup();
 
     let (tx, rx) = channel();
 
@@ -373,7 +410,7 @@ pub fn create_test_server() {
         let agg = ApiAggregator::new(services);
 
         let server = TestServer::with_factory(move || {
-            let state = api::AppState::new();
+            let state = api::AppState::with_config(config.clone());
             App::with_state(state.clone())
                 .scope("public/api", |scope| {
                     trace!("Create public API");
@@ -6,11 +6,7 @@ extern crate pandemia_testkit;
 #[macro_use]
 extern crate serde_json;
 
-use serde_json::Value as JsonValue;
-
-use pandemia_testkit::ApiKind;
-
-use pandemia::api::ErrorCode;
+use pandemia::api::{client::TypedClient, ErrorCode};
 
 mod common;
 
@@ -22,7 +18,7 @@ fn test_get_info() {
     let api = testkit.api();
 
     assert_eq!(
-        api.public(ApiKind::System).get::<JsonValue>("v1/info").unwrap(),
+        api.client().public().system().info(&()).unwrap(),
         json!({ "version": env!("CARGO_PKG_VERSION"),
                 "build": env!("BUILD_INFO"), "git": env!("GIT_REV") })
     );
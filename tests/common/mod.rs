@@ -1,15 +1,19 @@
 #![allow(dead_code, unused_variables)]
 
+use pandemia::config::Config;
 use pandemia_testkit::TestKit;
 
+use std::sync::Arc;
+
 pub mod prelude {
     pub use super::{create_testkit, setup};
     pub use pandemia::api::{ApiResult, ErrorCode};
     pub use pandemia_testkit::{TestHelper, TestKit, TestKitApi};
 }
 
-pub fn setup() {
-    let _ = env_logger::try_init();
+/// Inisialisasi logger, konfigurasi dan komponen global untuk test.
+pub fn setup() -> Arc<Config> {
+    pandemia_testkit::setup()
 }
 
 pub fn create_testkit() -> TestKit {
@@ -0,0 +1,129 @@
+extern crate pandemia_testkit;
+#[macro_use]
+extern crate serde_json;
+
+mod common;
+
+use pandemia::{
+    api::ErrorCode,
+    dao::{CityDao, DistrictDao, SatgasInvitationDao, VillageDao},
+    models::{City, District, Village},
+    util,
+};
+use pandemia_testkit::{ApiKind, TestHelper, TestKit, User};
+use serde_json::Value as JsonValue;
+
+use crate::common::create_testkit;
+
+fn create_village() -> (City, District, Village) {
+    let db = TestHelper::get_db();
+    let province = format!("Test {}", util::random_string(8));
+
+    let city = CityDao::new(&db)
+        .create("Wonosobo", &province, "ID", &util::random_string(10))
+        .unwrap();
+    let district = DistrictDao::new(&db).create("Kertek", city.id, &vec![]).unwrap();
+    let village = VillageDao::new(&db)
+        .create(
+            "Kapencar",
+            "Kertek",
+            "Wonosobo",
+            &province,
+            -7.35,
+            109.95,
+            &vec![],
+            city.id,
+            district.id,
+        )
+        .unwrap();
+
+    (city, district, village)
+}
+
+fn accept(testkit: &TestKit, user: &User, token: &str) -> JsonValue {
+    let mut api = testkit.api();
+    api.authorize(user.id);
+    api.public(ApiKind::Service("invitation"))
+        .query(&json!({
+            "token": token,
+            "full_name": user.full_name,
+            "phone_num": user.phone_num,
+            "latitude": -7.35,
+            "longitude": 109.95,
+        }))
+        .post("v1/accept")
+        .unwrap()
+}
+
+fn status(testkit: &TestKit, token: &str) -> JsonValue {
+    testkit
+        .api()
+        .public(ApiKind::Service("invitation"))
+        .query(&json!({ "token": token }))
+        .get::<JsonValue>("v1/info")
+        .unwrap()["result"]["status"]
+        .clone()
+}
+
+#[test]
+fn test_invitation_accept_is_single_use_and_expires() {
+    let testkit = create_testkit();
+    let helper = testkit.helper();
+    let mut users = helper.generate_users(2);
+    let other = users.pop().unwrap().user;
+    let user = users.pop().unwrap().user;
+    let (city, district, village) = create_village();
+
+    let (invitation, expired) = {
+        let db = TestHelper::get_db();
+        let dao = SatgasInvitationDao::new(&db);
+        (
+            dao.create(&village, true, "Satgas", "", 1, "admin", 1).unwrap(),
+            // masa berlaku 0 hari, langsung kadaluarsa
+            dao.create(&village, false, "Satgas", "", 1, "admin", 0).unwrap(),
+        )
+    };
+
+    assert_eq!(status(&testkit, &invitation.token), json!("pending"));
+    assert_eq!(accept(&testkit, &user, &invitation.token)["code"], json!(0));
+    assert_eq!(status(&testkit, &invitation.token), json!("accepted"));
+
+    let user = helper.get_user_by_id(user.id).unwrap();
+    assert!(user.is_satgas());
+    assert!(user.meta.contains(&format!("invitation_id={}", invitation.id)));
+    assert!(user.meta.contains(&format!("village_id={}", village.id)));
+
+    // undangan hanya bisa digunakan sekali
+    assert_ne!(accept(&testkit, &other, &invitation.token)["code"], json!(0));
+
+    assert_eq!(status(&testkit, &expired.token), json!("expired"));
+    assert_ne!(accept(&testkit, &other, &expired.token)["code"], json!(0));
+    assert!(!helper.get_user_by_id(other.id).unwrap().is_satgas());
+
+    // pendaftaran menggunakan area code kota hanya aktif apabila `security.allow_area_code` diset
+    if !common::setup().security.allow_area_code {
+        let mut api = testkit.api();
+        api.authorize(other.id);
+        let rv: JsonValue = api
+            .public(ApiKind::User)
+            .query(&json!({
+                "full_name": other.full_name,
+                "phone_num": other.phone_num,
+                "village": village.name,
+                "latitude": -7.35,
+                "longitude": 109.95,
+                "area_code": city.area_code,
+                "is_medic": false,
+            }))
+            .post("v1/me/update")
+            .unwrap();
+        assert_eq!(rv["code"], json!(ErrorCode::AreaCodeDisabled as i32));
+    }
+
+    helper.cleanup_users(vec![user.id, other.id]);
+
+    let db = TestHelper::get_db();
+    let _ = VillageDao::new(&db).delete_by_id(village.id);
+    let _ = DistrictDao::new(&db).delete_by_id(district.id);
+    let _ = CityDao::new(&db).delete_by_id(city.id);
+}
@@ -0,0 +1,139 @@
+extern crate pandemia_testkit;
+#[macro_use]
+extern crate serde_json;
+
+mod common;
+
+use pandemia::{
+    dao::{BoundaryDao, CityDao, DistrictDao, VillageDao},
+    types::LocKind,
+    user_dao::UserDao,
+    util,
+};
+use pandemia_testkit::TestHelper;
+
+use serde_json::Value as JsonValue;
+
+fn square(min_lat: f64, min_lng: f64, max_lat: f64, max_lng: f64) -> JsonValue {
+    json!({
+        "type": "Polygon",
+        "coordinates": [[
+            [min_lng, min_lat], [max_lng, min_lat], [max_lng, max_lat], [min_lng, max_lat], [min_lng, min_lat]
+        ]]
+    })
+}
+
+#[test]
+fn test_update_user_location_ll() {
+    let testkit = common::create_testkit();
+    let helper = TestHelper::new(&testkit);
+    let user = helper.generate_users(1).pop().unwrap().user;
+
+    let db = TestHelper::get_db();
+    let province = format!("Test {}", util::random_string(8));
+    let area_code = util::random_string(10);
+    let device_id = util::random_string(16);
+
+    let city = CityDao::new(&db)
+        .create("Wonosobo", &province, "ID", &area_code)
+        .unwrap();
+    let district = DistrictDao::new(&db).create("Kertek", city.id, &vec![]).unwrap();
+    let village = VillageDao::new(&db)
+        .create(
+            "Kapencar",
+            "Kertek",
+            "Wonosobo",
+            &province,
+            -50.35,
+            150.35,
+            &vec![],
+            city.id,
+            district.id,
+        )
+        .unwrap();
+
+    // koordinat di tengah laut agar tidak bentrok dengan boundary lain di DB test
+    let dao = BoundaryDao::new(&db);
+    let city_boundary = dao
+        .upsert(
+            LocKind::City,
+            city.id,
+            "Wonosobo",
+            &square(-51.0, 150.0, -50.0, 151.0),
+        )
+        .unwrap();
+    let village_boundary = dao
+        .upsert(
+            LocKind::Village,
+            village.id,
+            "Kapencar",
+            &square(-50.4, 150.3, -50.3, 150.4),
+        )
+        .unwrap();
+
+    // target push notif desa menggunakan ID, bukan nama lokasi dari geocoder
+    assert_eq!(
+        dao.area_id_by_path(
+            LocKind::Village,
+            &format!("/Indonesia/{}/Wonosobo/Kertek/Kapencar", province)
+        )
+        .unwrap(),
+        Some(village.id)
+    );
+
+    let user_dao = UserDao::new(&db);
+    let user = user_dao.get_by_id(user.id).unwrap();
+    user_dao
+        .create_user_connect(user.id, &device_id, "android", "app-id", "", "")
+        .unwrap();
+
+    let loc_path = |user_id| {
+        let user = user_dao.get_by_id(user_id).unwrap();
+        user.meta
+            .into_iter()
+            .filter(|a| a.starts_with("loc_path="))
+            .collect::<Vec<_>>()
+    };
+
+    // di dalam desa
+    user_dao
+        .update_user_location_ll(&user, &device_id, -50.35, 150.35, "Lain", "Indonesia/Lain/Lain")
+        .unwrap();
+    assert_eq!(
+        loc_path(user.id),
+        vec![format!(
+            "loc_path=/Indonesia/{}/Wonosobo/Kertek/Kapencar",
+            province
+        )]
+    );
+
+    // di dalam kota tapi di luar desa
+    let user = user_dao.get_by_id(user.id).unwrap();
+    user_dao
+        .update_user_location_ll(&user, &device_id, -50.8, 150.8, "Lain", "Indonesia/Lain/Lain")
+        .unwrap();
+    assert_eq!(
+        loc_path(user.id),
+        vec![format!("loc_path=/Indonesia/{}/Wonosobo", province)]
+    );
+
+    // di luar semua boundary, menggunakan lokasi dari client
+    let user = user_dao.get_by_id(user.id).unwrap();
+    user_dao
+        .update_user_location_ll(&user, &device_id, -52.0, 152.0, "Lain", "Indonesia/Lain/Lain")
+        .unwrap();
+    assert_eq!(
+        loc_path(user.id),
+        vec!["loc_path=/Indonesia/Lain/Lain".to_string()]
+    );
+
+    let _ = user_dao.remove_user_connect_by_id(&device_id);
+    let _ = dao.delete_by_id(village_boundary.id);
+    let _ = dao.delete_by_id(city_boundary.id);
+    let _ = VillageDao::new(&db).delete_by_id(village.id);
+    let _ = DistrictDao::new(&db).delete_by_id(district.id);
+    let _ = CityDao::new(&db).delete_by_id(city.id);
+
+    drop(db);
+    helper.cleanup_user_by_id(user.id);
+}
@@ -0,0 +1,57 @@
+extern crate pandemia_testkit;
+#[macro_use]
+extern crate serde_json;
+
+mod common;
+
+use pandemia::{api::ErrorCode, dao::PhoneVerificationDao, phone_verification_dao::MAX_ATTEMPTS, ID};
+use pandemia_testkit::{ApiKind, TestHelper};
+use serde_json::Value as JsonValue;
+
+use crate::common::create_testkit;
+
+fn create_otp(user_id: ID, phone_num: &str) -> String {
+    let db = TestHelper::get_db();
+    PhoneVerificationDao::new(&db).create(user_id, phone_num).unwrap()
+}
+
+#[test]
+fn test_verify_phone_attempts_and_merge_device_account() {
+    let testkit = create_testkit();
+    let helper = testkit.helper();
+    let target = helper.generate_users(1).pop().unwrap().user;
+    let device = helper.generate_device_user();
+
+    let mut api = testkit.api();
+    api.authorize(device.id);
+
+    let verify = |code: &str| {
+        api.public(ApiKind::User)
+            .query(&json!({ "phone_num": target.phone_num, "code": code }))
+            .post::<JsonValue>("v1/me/phone/verify")
+            .unwrap()
+    };
+
+    // kode salah, setelah MAX_ATTEMPTS kali kode yang benar pun tidak bisa digunakan lagi
+    let code = create_otp(device.id, &target.phone_num);
+    for _ in 0..MAX_ATTEMPTS {
+        assert_eq!(
+            verify("invalid")["code"],
+            json!(ErrorCode::InvalidVerificationCode as i32)
+        );
+    }
+    assert_ne!(verify(&code)["code"], json!(0));
+
+    // nomor sudah digunakan oleh akun lain, akun device digabungkan ke akun tersebut
+    let code = create_otp(device.id, &target.phone_num);
+    let rv = verify(&code);
+    assert_eq!(rv["code"], json!(0));
+    assert_eq!(rv["result"]["merged"], json!(true));
+    assert_eq!(rv["result"]["user"]["id"], json!(target.id));
+
+    let merged = helper.get_user_by_id(target.id).unwrap();
+    assert!(merged.is_phone_verified());
+    assert!(helper.get_user_by_id(device.id).unwrap().is_deleted());
+
+    helper.cleanup_users(vec![device.id, target.id]);
+}
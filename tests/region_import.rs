@@ -0,0 +1,36 @@
+extern crate pandemia_testkit;
+
+mod common;
+
+use pandemia::{dao::CityDao, region_import, util};
+use pandemia_testkit::TestHelper;
+
+#[test]
+fn test_import_matches_existing_province_case_insensitive() {
+    common::setup();
+    let db = TestHelper::get_db();
+    let province = format!("DKI Test{}", util::random_string(8).to_lowercase());
+    let area_code = util::random_string(10);
+
+    let city = CityDao::new(&db)
+        .create(
+            "Kota Administrasi Jakarta Pusat",
+            &province,
+            "Indonesia",
+            &area_code,
+        )
+        .unwrap();
+
+    let content = format!(
+        "99,{}\n99.98,KOTA ADMINISTRASI JAKARTA PUSAT\n",
+        province.to_uppercase()
+    );
+    let report = region_import::import_regions(&content, true, &db).unwrap();
+
+    // kota lama terhubung, bukan dibuat ulang dengan provinsi `Dki Test...`
+    assert_eq!(report.linked, 1);
+    assert_eq!(report.created, 0);
+    assert!(report.mismatches.is_empty());
+
+    let _ = CityDao::new(&db).delete_by_id(city.id);
+}
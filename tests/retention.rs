@@ -0,0 +1,58 @@
+extern crate pandemia_testkit;
+
+mod common;
+
+use pandemia::{monitor::RetentionPolicy, user_dao::UserDao, util};
+use pandemia_testkit::TestHelper;
+
+#[test]
+fn test_device_location_retention_clears_user_meta() {
+    let testkit = common::create_testkit();
+    let helper = TestHelper::new(&testkit);
+    let user = helper.generate_users(1).pop().unwrap().user;
+
+    let db = TestHelper::get_db();
+    let device_id = util::random_string(16);
+
+    let user_dao = UserDao::new(&db);
+    user_dao
+        .create_user_connect(user.id, &device_id, "android", "app-id", "", "")
+        .unwrap();
+    user_dao
+        .update_meta(user.id, &vec!["test=1".to_string()])
+        .unwrap();
+
+    let user = user_dao.get_by_id(user.id).unwrap();
+    user_dao
+        .update_user_location(
+            &user,
+            &device_id,
+            "Kertek",
+            "Indonesia/Jawa Tengah/Wonosobo/Kertek",
+        )
+        .unwrap();
+    assert!(user_dao
+        .get_by_id(user.id)
+        .unwrap()
+        .meta
+        .contains(&"loc_name=Kertek".to_string()));
+
+    // 0 hari, semua lokasi dianggap kadaluarsa
+    let policy = RetentionPolicy {
+        device_location_days: Some(0),
+        ..Default::default()
+    };
+    let report = policy.apply(&db).unwrap();
+    assert!(report.cleared_device_locations >= 1);
+    assert!(report.cleared_user_locations >= 1);
+
+    assert_eq!(
+        user_dao.get_by_id(user.id).unwrap().meta,
+        vec!["test=1".to_string()]
+    );
+
+    let _ = user_dao.remove_user_connect_by_id(&device_id);
+
+    drop(db);
+    helper.cleanup_user_by_id(user.id);
+}
@@ -0,0 +1,59 @@
+extern crate pandemia_testkit;
+
+mod common;
+
+use pandemia::{
+    api::ErrorCode,
+    dao::ScheduledJobDao,
+    error::Error,
+    models::{JobRun, ScheduledJob},
+    scheduler,
+};
+use pandemia_testkit::TestHelper;
+
+use std::{thread, time::Duration};
+
+const JOB_NAME: &str = "purge_tokens";
+
+fn latest_run(dao: &ScheduledJobDao, job: &ScheduledJob) -> Option<JobRun> {
+    dao.get_runs(job.id, 0, 1).unwrap().0.pop()
+}
+
+#[test]
+fn test_trigger_respects_job_lock() {
+    common::setup();
+    let db = TestHelper::get_db();
+    let dao = ScheduledJobDao::new(&db);
+
+    let job = scheduler::jobs(&db)
+        .unwrap()
+        .into_iter()
+        .find(|job| job.name == JOB_NAME)
+        .unwrap();
+    let before = latest_run(&dao, &job).map(|run| run.id).unwrap_or(0);
+
+    assert!(scheduler::trigger("no_such_job").is_err());
+
+    // lock dipegang oleh instance lain (koneksi ini), job tidak boleh dijalankan
+    assert!(dao.try_lock(job.id).unwrap());
+    match scheduler::trigger(JOB_NAME) {
+        Err(Error::BadRequest(code, _)) => assert_eq!(code, ErrorCode::JobAlreadyRunning as i32),
+        rv => panic!("job should not run while locked: {:?}", rv),
+    }
+    dao.unlock(job.id).unwrap();
+
+    scheduler::trigger(JOB_NAME).unwrap();
+
+    // job berjalan di background, tunggu hasilnya tercatat
+    let mut run = None;
+    for _ in 0..50 {
+        run = latest_run(&dao, &job).filter(|run| run.id > before && run.finished.is_some());
+        if run.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    let run = run.expect("triggered job run not recorded");
+    assert!(run.manual);
+    assert_eq!(run.success, Some(true));
+}
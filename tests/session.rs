@@ -0,0 +1,33 @@
+extern crate pandemia_testkit;
+
+mod common;
+
+use pandemia::auth::{AuthDao, SessionInfo};
+use pandemia_testkit::TestHelper;
+
+#[test]
+fn test_refresh_session_rotates_hashed_token() {
+    let testkit = common::create_testkit();
+    let helper = TestHelper::new(&testkit);
+    let user = helper.generate_users(1).pop().unwrap().user;
+
+    let db = TestHelper::get_db();
+    let dao = AuthDao::new(&db);
+    let session = dao.create_session(user.id, &SessionInfo::default()).unwrap();
+
+    // refresh token tidak disimpan dalam bentuk plaintext
+    let stored = dao.get_sessions(user.id).unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_ne!(stored[0].refresh_token, session.refresh_token);
+
+    let refreshed = dao.refresh_session(&session.refresh_token, "127.0.0.1").unwrap();
+    assert_ne!(refreshed.token, session.token);
+    assert_ne!(refreshed.refresh_token, session.refresh_token);
+
+    // refresh token lama tidak bisa digunakan lagi
+    assert!(dao.refresh_session(&session.refresh_token, "127.0.0.1").is_err());
+    assert!(dao.refresh_session(&refreshed.refresh_token, "127.0.0.1").is_ok());
+
+    drop(db);
+    helper.cleanup_user_by_id(user.id);
+}
@@ -0,0 +1,99 @@
+extern crate pandemia_testkit;
+#[macro_use]
+extern crate serde_json;
+
+mod common;
+
+use pandemia::{
+    api::ErrorCode,
+    crypto::{self, PublicKey, SecretKey},
+    util,
+};
+use pandemia_types::signature;
+use reqwest::Client;
+use serde_json::Value as JsonValue;
+
+use crate::common::create_testkit;
+
+const REGISTER_KEY_PATH: &str = "/public/api/user/v1/me/key/register";
+
+/// Kirim request register key, ditandatangani apabila `sign` berisi key dan nonce.
+fn register_key(
+    server_url: &str,
+    token: &str,
+    pub_key: &PublicKey,
+    sign: Option<(&PublicKey, &SecretKey, &str)>,
+) -> JsonValue {
+    let url = format!("{}{}", server_url.trim_end_matches('/'), REGISTER_KEY_PATH);
+    let body = serde_json::to_vec(&json!({ "pub_key": pub_key.to_hex() })).unwrap();
+    let mut builder = Client::new()
+        .post(&url)
+        .header("X-Access-Token", token)
+        .header("Content-Type", "application/json");
+
+    if let Some((sign_key, secret_key, nonce)) = sign {
+        let timestamp = util::current_time().as_secs() as i64;
+        let body_hash = crypto::sha256_hash(&body).to_hex();
+        let message = signature::canonical_message("POST", REGISTER_KEY_PATH, timestamp, nonce, &body_hash);
+        builder = builder
+            .header(signature::KEY_HEADER, sign_key.to_hex())
+            .header(signature::TIMESTAMP_HEADER, timestamp.to_string())
+            .header(signature::NONCE_HEADER, nonce)
+            .header(
+                signature::SIGNATURE_HEADER,
+                crypto::sign(message.as_bytes(), secret_key).to_hex(),
+            );
+    }
+
+    let mut resp = builder.body(body).send().unwrap();
+    serde_json::from_str(&resp.text().unwrap()).unwrap()
+}
+
+fn code(rv: &JsonValue) -> i64 {
+    rv["code"].as_i64().unwrap()
+}
+
+#[test]
+fn test_signed_request_rejects_bad_signature_and_replay() {
+    let testkit = create_testkit();
+    let helper = testkit.helper();
+    let user = helper.generate_users(1).pop().unwrap().user;
+    let token = helper.gen_access_token_for(user.id).unwrap().token;
+    let url = &testkit.test_server_url;
+
+    let (pub_key, secret_key) = crypto::gen_keypair();
+    let (other_pub_key, other_secret_key) = crypto::gen_keypair();
+
+    // key pertama belum ada key terdaftar, tidak perlu ditandatangani
+    assert_eq!(code(&register_key(url, &token, &pub_key, None)), 0);
+
+    // setelah ada key terdaftar request wajib ditandatangani
+    assert_eq!(
+        code(&register_key(url, &token, &other_pub_key, None)),
+        ErrorCode::MessageHasNoSign as i64
+    );
+
+    // ditandatangani menggunakan secret key yang tidak sesuai dengan key terdaftar
+    let nonce = util::random_string(32);
+    assert_eq!(
+        code(&register_key(
+            url,
+            &token,
+            &other_pub_key,
+            Some((&pub_key, &other_secret_key, &nonce))
+        )),
+        ErrorCode::InvalidSignature as i64
+    );
+
+    let nonce = util::random_string(32);
+    let sign = Some((&pub_key, &secret_key, nonce.as_str()));
+    assert_eq!(code(&register_key(url, &token, &other_pub_key, sign)), 0);
+
+    // nonce yang sama tidak bisa digunakan lagi
+    assert_eq!(
+        code(&register_key(url, &token, &other_pub_key, sign)),
+        ErrorCode::InvalidSignature as i64
+    );
+
+    helper.cleanup_user_by_id(user.id);
+}